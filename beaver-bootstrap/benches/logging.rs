@@ -0,0 +1,149 @@
+//! Throughput of the logging layers, in events per second.
+//!
+//! Save a baseline before a change and compare with it after, criterion reports the events
+//! per second lost or gained by every benchmark:
+//!
+//! ```sh
+//! cargo bench -p beaver-bootstrap --bench logging -- --save-baseline main
+//! cargo bench -p beaver-bootstrap --bench logging -- --baseline main
+//! ```
+
+use std::{
+    io,
+    sync::{Arc, Barrier},
+    thread,
+    time::{Duration, Instant},
+};
+
+use beaver_bootstrap::log::{
+    buffer::{DEFAULT_BUFFER_SIZE, OnFull},
+    format::{LogFormat, fmt_layer},
+    writer::{FlushOn, appender_writer},
+};
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use tracing_subscriber::{Registry, layer::SubscriberExt};
+
+/// time the non blocking appenders get to drain after a batch.
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(30);
+
+fn fields() -> Vec<(String, String)> {
+    vec![
+        ("service".to_string(), "billing".to_string()),
+        ("region".to_string(), "eu-west-1".to_string()),
+    ]
+}
+
+fn event(index: u64) {
+    tracing::info!(
+        target: "billing::invoice",
+        invoice = index,
+        customer = "c-42",
+        amount = 12.5,
+        "invoice sent"
+    );
+}
+
+/// an event through a text layer writing on the logging thread, like the console appender,
+/// and text, JSON and fast JSON layers handing events to a worker writing a file, like the
+/// file appenders.
+fn layers(c: &mut Criterion) {
+    let dir = std::env::temp_dir().join(format!("beaver-bench-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let mut group = c.benchmark_group("logging");
+    group.throughput(Throughput::Elements(1));
+    for (name, format, flush_on) in [
+        ("console_text", LogFormat::Text, FlushOn::Error),
+        ("file_text", LogFormat::Text, FlushOn::Never),
+        ("file_json", LogFormat::Json, FlushOn::Never),
+        ("file_fast_json", LogFormat::FastJson, FlushOn::Never),
+    ] {
+        let writer: Box<dyn io::Write + Send> = match flush_on {
+            FlushOn::Never => Box::new(std::fs::File::create(dir.join(name)).unwrap()),
+            _ => Box::new(io::sink()),
+        };
+        let (writer, guard) =
+            appender_writer(writer, name, DEFAULT_BUFFER_SIZE, OnFull::Block, flush_on);
+        let subscriber = Registry::default().with(fmt_layer(format, false, fields(), writer));
+        let flusher = guard.flusher();
+        tracing::subscriber::with_default(subscriber, || {
+            group.bench_function(name, |b| {
+                b.iter_custom(|iters| {
+                    let start = Instant::now();
+                    for index in 0..iters {
+                        event(index);
+                    }
+                    // the events count once written
+                    flusher.flush(DRAIN_TIMEOUT);
+                    start.elapsed()
+                })
+            });
+        });
+        drop(guard);
+    }
+    group.finish();
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+/// events logged by several threads at once through one non blocking appender, blocking or
+/// dropping on a full buffer.
+fn contention(c: &mut Criterion) {
+    let mut group = c.benchmark_group("non_blocking");
+    for on_full in [OnFull::Block, OnFull::Drop] {
+        for threads in [1, 4, 8] {
+            let (writer, guard) = appender_writer(
+                io::sink(),
+                "contention",
+                DEFAULT_BUFFER_SIZE,
+                on_full,
+                FlushOn::Never,
+            );
+            let subscriber = Arc::new(Registry::default().with(fmt_layer(
+                LogFormat::Json,
+                false,
+                fields(),
+                writer,
+            )));
+            let flusher = guard.flusher();
+            group.throughput(Throughput::Elements(threads));
+            let id = BenchmarkId::new(format!("{:?}", on_full).to_lowercase(), threads);
+            group.bench_with_input(id, &threads, |b, &threads| {
+                b.iter_custom(|iters| {
+                    let barrier = Arc::new(Barrier::new(threads as usize + 1));
+                    let handles: Vec<_> = (0..threads)
+                        .map(|_| {
+                            let subscriber = subscriber.clone();
+                            let barrier = barrier.clone();
+                            thread::spawn(move || {
+                                tracing::subscriber::with_default(subscriber, || {
+                                    barrier.wait();
+                                    for index in 0..iters {
+                                        event(index);
+                                    }
+                                })
+                            })
+                        })
+                        .collect();
+                    barrier.wait();
+                    let start = Instant::now();
+                    for handle in handles {
+                        handle.join().unwrap();
+                    }
+                    flusher.flush(DRAIN_TIMEOUT);
+                    start.elapsed()
+                })
+            });
+            let stats = flusher.stats().unwrap();
+            if stats.dropped > 0 {
+                println!(
+                    "{} threads dropped {} of {} events",
+                    threads, stats.dropped, stats.sent
+                );
+            }
+            drop(guard);
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, layers, contention);
+criterion_main!(benches);
@@ -0,0 +1,54 @@
+use beaver_bootstrap::config::{Config, PropertiesConfig};
+use config::{File, FileFormat};
+use criterion::{BenchmarkId, Criterion, black_box, criterion_group, criterion_main};
+
+/// a config of `sections` tables of 40 keys, with nested tables and arrays like a config
+/// layered from files and environment variables.
+fn config(sections: usize) -> Config {
+    let mut toml = String::new();
+    for section in 0..sections {
+        toml.push_str(&format!("[section_{}]\n", section));
+        for key in 0..30 {
+            toml.push_str(&format!("key_{} = \"value {}\"\n", key, key));
+        }
+        toml.push_str("port = 8080\nratio = 0.75\nenabled = true\n");
+        toml.push_str("hosts = [\"a.example.com\", \"b.example.com\", \"c.example.com\"]\n");
+        toml.push_str(&format!(
+            "[section_{}.pool]\nsize = 16\nidle = 4\n",
+            section
+        ));
+    }
+    let inner = config::Config::builder()
+        .add_source(File::from_str(&toml, FileFormat::Toml))
+        .build()
+        .unwrap();
+    Config::new(inner)
+}
+
+fn flatten(c: &mut Criterion) {
+    let mut group = c.benchmark_group("properties");
+    // 300 sections are about 12k keys
+    for sections in [25, 300] {
+        let config = config(sections);
+        let keys = config.to_properties().unwrap().get_properties().len();
+        group.bench_with_input(BenchmarkId::new("flatten", keys), &config, |b, config| {
+            b.iter(|| black_box(config.to_properties().unwrap()))
+        });
+        let joined = PropertiesConfig::default().with_array_split(false);
+        group.bench_with_input(
+            BenchmarkId::new("flatten_joined_arrays", keys),
+            &config,
+            |b, config| b.iter(|| black_box(config.to_properties_with(&joined).unwrap())),
+        );
+        group.bench_with_input(BenchmarkId::new("entries", keys), &config, |b, config| {
+            b.iter(|| {
+                let properties = config.to_properties().unwrap();
+                black_box(properties.entries().len())
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, flatten);
+criterion_main!(benches);
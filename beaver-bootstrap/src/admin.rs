@@ -0,0 +1,392 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::{
+        Arc, RwLock,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::{Config, ConfigPrefix},
+    error::BootstrapError,
+};
+
+/// longest request head read from an admin connection.
+const MAX_REQUEST_HEAD: usize = 16 * 1024;
+/// connections served at once, the next ones are answered 503.
+const MAX_CONNECTIONS: usize = 32;
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// AdminConfig configures the admin endpoint, see `[admin]`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AdminConfig {
+    enable: bool,
+    /// address the admin endpoint listens on.
+    bind: String,
+    /// bearer token required on every request, unauthenticated when absent.
+    token: Option<String>,
+}
+
+impl Default for AdminConfig {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            bind: "127.0.0.1:9901".to_string(),
+            token: None,
+        }
+    }
+}
+
+impl ConfigPrefix for AdminConfig {
+    const PREFIX: &'static str = "admin";
+}
+
+impl AdminConfig {
+    pub fn new(config: &Config) -> Result<Self, BootstrapError> {
+        config
+            .get::<AdminConfig>()
+            .map_err(BootstrapError::ConfigLoadError)
+    }
+
+    pub fn enable(&self) -> bool {
+        self.enable
+    }
+
+    pub fn bind(&self) -> Result<SocketAddr, BootstrapError> {
+        self.bind.parse().map_err(|e| {
+            BootstrapError::InvalidConfigValueError(format!("admin.bind={}: {}", self.bind, e))
+        })
+    }
+
+    pub fn token(&self) -> Option<&str> {
+        self.token.as_deref()
+    }
+}
+
+/// AdminRequest is the method, path, query and headers of an admin request.
+#[derive(Debug, Clone, Default)]
+pub struct AdminRequest {
+    method: String,
+    path: String,
+    query: HashMap<String, String>,
+    /// header names are lower case.
+    headers: HashMap<String, String>,
+}
+
+impl AdminRequest {
+    pub fn new(method: &str, target: &str) -> Self {
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        let query = query
+            .split('&')
+            .filter(|x| !x.is_empty())
+            .map(|x| {
+                let (k, v) = x.split_once('=').unwrap_or((x, ""));
+                (k.to_string(), v.to_string())
+            })
+            .collect();
+        Self {
+            method: method.to_ascii_uppercase(),
+            path: path.to_string(),
+            query,
+            headers: HashMap::new(),
+        }
+    }
+
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers
+            .insert(name.to_ascii_lowercase(), value.trim().to_string());
+        self
+    }
+
+    pub fn method(&self) -> &str {
+        self.method.as_str()
+    }
+
+    pub fn path(&self) -> &str {
+        self.path.as_str()
+    }
+
+    pub fn query(&self, key: &str) -> Option<&str> {
+        self.query.get(key).map(|x| x.as_str())
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .get(&name.to_ascii_lowercase())
+            .map(|x| x.as_str())
+    }
+}
+
+/// AdminResponse is the status and body answered to an admin request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AdminResponse {
+    status: u16,
+    content_type: String,
+    body: Vec<u8>,
+}
+
+impl AdminResponse {
+    pub fn new(status: u16, content_type: &str, body: Vec<u8>) -> Self {
+        Self {
+            status,
+            content_type: content_type.to_string(),
+            body,
+        }
+    }
+
+    pub fn text(status: u16, body: &str) -> Self {
+        Self::new(
+            status,
+            "text/plain; charset=utf-8",
+            body.as_bytes().to_vec(),
+        )
+    }
+
+    pub fn json<T: Serialize>(status: u16, body: &T) -> Self {
+        match serde_json::to_vec_pretty(body) {
+            Ok(body) => Self::new(status, "application/json", body),
+            Err(e) => Self::text(500, &e.to_string()),
+        }
+    }
+
+    pub fn status(&self) -> u16 {
+        self.status
+    }
+
+    pub fn content_type(&self) -> &str {
+        self.content_type.as_str()
+    }
+
+    pub fn body(&self) -> &[u8] {
+        &self.body
+    }
+}
+
+pub type AdminHandler = Arc<dyn Fn(&AdminRequest) -> AdminResponse + Send + Sync>;
+
+/// AdminRoutes maps admin paths to handlers, shared with the running admin endpoint so routes
+/// added after startup are served too.
+///
+/// # Example
+/// ```
+/// use beaver_bootstrap::admin::{AdminRequest, AdminResponse, AdminRoutes};
+/// let routes = AdminRoutes::default();
+/// routes.route("/ping", |_| AdminResponse::text(200, "pong"));
+/// let response = routes.handle(&AdminRequest::new("GET", "/ping"), None);
+/// assert_eq!(response.body(), b"pong");
+/// ```
+#[derive(Clone, Default)]
+pub struct AdminRoutes {
+    routes: Arc<RwLock<BTreeMap<String, AdminHandler>>>,
+}
+
+impl std::fmt::Debug for AdminRoutes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AdminRoutes")
+            .field("paths", &self.paths())
+            .finish()
+    }
+}
+
+impl AdminRoutes {
+    /// register `handler` for `path`, replacing the previous handler of the path.
+    pub fn route<F>(&self, path: &str, handler: F)
+    where
+        F: Fn(&AdminRequest) -> AdminResponse + Send + Sync + 'static,
+    {
+        if let Ok(mut routes) = self.routes.write() {
+            routes.insert(path.to_string(), Arc::new(handler));
+        }
+    }
+
+    pub fn paths(&self) -> Vec<String> {
+        self.routes
+            .read()
+            .map(|routes| routes.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// answer `request`, checking the bearer token first when one is given.
+    pub fn handle(&self, request: &AdminRequest, token: Option<&str>) -> AdminResponse {
+        if let Some(token) = token {
+            let authorized = request
+                .header("authorization")
+                .and_then(|x| x.strip_prefix("Bearer "))
+                .is_some_and(|x| constant_time_eq(x.trim().as_bytes(), token.as_bytes()));
+            if !authorized {
+                return AdminResponse::text(401, "unauthorized");
+            }
+        }
+        let handler = self
+            .routes
+            .read()
+            .ok()
+            .and_then(|routes| routes.get(request.path()).cloned());
+        match handler {
+            Some(handler) => handler(request),
+            None => AdminResponse::text(404, "not found"),
+        }
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// AdminServer serves [`AdminRoutes`] over plain HTTP/1.1 until dropped.
+///
+/// It is meant for low traffic operational endpoints: one thread per connection, up to
+/// 32 at once, no keep alive, request bodies are ignored.
+pub struct AdminServer {
+    local_addr: SocketAddr,
+    stopped: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl std::fmt::Debug for AdminServer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AdminServer")
+            .field("local_addr", &self.local_addr)
+            .finish()
+    }
+}
+
+impl AdminServer {
+    pub fn start(config: &AdminConfig, routes: AdminRoutes) -> Result<Self, BootstrapError> {
+        let addr = config.bind()?;
+        let listener = TcpListener::bind(addr).map_err(|e| {
+            BootstrapError::InvalidConfigValueError(format!("admin.bind={}: {}", addr, e))
+        })?;
+        let local_addr = listener.local_addr().unwrap_or(addr);
+        let stopped = Arc::new(AtomicBool::new(false));
+        let token = config.token().map(String::from);
+        let thread_stopped = stopped.clone();
+        let connections = Arc::new(AtomicUsize::new(0));
+        let thread = thread::Builder::new()
+            .name("beaver-admin".to_string())
+            .spawn(move || {
+                for stream in listener.incoming() {
+                    if thread_stopped.load(Ordering::Acquire) {
+                        break;
+                    }
+                    let Ok(stream) = stream else {
+                        continue;
+                    };
+                    if connections.fetch_add(1, Ordering::AcqRel) >= MAX_CONNECTIONS {
+                        connections.fetch_sub(1, Ordering::AcqRel);
+                        let _ = stream.set_write_timeout(Some(READ_TIMEOUT));
+                        let busy = AdminResponse::text(503, "too many admin connections");
+                        let _ = write_response(stream, &busy);
+                        continue;
+                    }
+                    let routes = routes.clone();
+                    let token = token.clone();
+                    let served = connections.clone();
+                    let spawned = thread::Builder::new()
+                        .name("beaver-admin-conn".to_string())
+                        .spawn(move || {
+                            if let Err(e) = serve(stream, &routes, token.as_deref()) {
+                                tracing::debug!("admin connection failed: {}", e);
+                            }
+                            served.fetch_sub(1, Ordering::AcqRel);
+                        });
+                    if spawned.is_err() {
+                        connections.fetch_sub(1, Ordering::AcqRel);
+                    }
+                }
+            })
+            .map_err(|e| BootstrapError::InvalidConfigValueError(format!("admin: {}", e)))?;
+        tracing::info!("admin endpoint listening on {}", local_addr);
+        Ok(Self {
+            local_addr,
+            stopped,
+            thread: Some(thread),
+        })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+}
+
+impl Drop for AdminServer {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Release);
+        // wake up the accept loop
+        let _ = TcpStream::connect_timeout(&self.local_addr, Duration::from_secs(1));
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn serve(stream: TcpStream, routes: &AdminRoutes, token: Option<&str>) -> io::Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    // bounds the memory of a connection, a head without newline included
+    let mut reader = BufReader::new(stream.try_clone()?).take(MAX_REQUEST_HEAD as u64);
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    if is_truncated(&line, &reader) {
+        return write_response(stream, &AdminResponse::text(431, "request head too large"));
+    }
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return write_response(stream, &AdminResponse::text(400, "bad request"));
+    };
+    let mut request = AdminRequest::new(method, target);
+    loop {
+        line.clear();
+        let n = reader.read_line(&mut line)?;
+        if is_truncated(&line, &reader) {
+            return write_response(stream, &AdminResponse::text(431, "request head too large"));
+        }
+        if n == 0 || line.trim_end().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            request = request.with_header(name, value);
+        }
+    }
+    let response = routes.handle(&request, token);
+    write_response(stream, &response)
+}
+
+/// whether `line` was cut by the [`MAX_REQUEST_HEAD`] limit of `reader`.
+fn is_truncated<R>(line: &str, reader: &io::Take<R>) -> bool {
+    reader.limit() == 0 && !line.ends_with('\n')
+}
+
+fn write_response(mut stream: TcpStream, response: &AdminResponse) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        response.status(),
+        reason(response.status()),
+        response.content_type(),
+        response.body().len()
+    )?;
+    stream.write_all(response.body())?;
+    stream.flush()
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        431 => "Request Header Fields Too Large",
+        500 => "Internal Server Error",
+        501 => "Not Implemented",
+        503 => "Service Unavailable",
+        _ => "",
+    }
+}
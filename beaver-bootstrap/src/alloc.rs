@@ -0,0 +1,90 @@
+use std::io;
+
+use serde::Serialize;
+
+use crate::admin::{AdminResponse, AdminRoutes};
+
+#[cfg(all(feature = "jemalloc", feature = "mimalloc"))]
+compile_error!("features `jemalloc` and `mimalloc` are mutually exclusive");
+
+#[cfg(feature = "jemalloc")]
+#[global_allocator]
+static GLOBAL: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
+
+#[cfg(all(feature = "mimalloc", not(feature = "jemalloc")))]
+#[global_allocator]
+static GLOBAL: mimalloc::MiMalloc = mimalloc::MiMalloc;
+
+/// name of the global allocator selected by the crate features.
+pub fn allocator_name() -> &'static str {
+    if cfg!(feature = "jemalloc") {
+        "jemalloc"
+    } else if cfg!(feature = "mimalloc") {
+        "mimalloc"
+    } else {
+        "system"
+    }
+}
+
+/// AllocatorStats is the heap usage reported by the global allocator.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct AllocatorStats {
+    pub allocator: &'static str,
+    /// bytes allocated by the application.
+    pub allocated_bytes: u64,
+    /// bytes in active pages, a multiple of the page size.
+    pub active_bytes: u64,
+    /// bytes in physically resident pages mapped by the allocator.
+    pub resident_bytes: u64,
+    /// bytes in chunks mapped by the allocator.
+    pub mapped_bytes: u64,
+    /// bytes retained by the allocator instead of being returned to the system.
+    pub retained_bytes: u64,
+    /// share of active bytes not allocated by the application.
+    pub fragmentation: f64,
+}
+
+/// current statistics of the global allocator, only jemalloc reports them.
+#[cfg(feature = "jemalloc")]
+pub fn allocator_stats() -> io::Result<AllocatorStats> {
+    use tikv_jemalloc_ctl::{epoch, stats};
+
+    // statistics are cached by jemalloc until the epoch is advanced
+    epoch::advance().map_err(ctl_error)?;
+    let allocated = stats::allocated::read().map_err(ctl_error)? as u64;
+    let active = stats::active::read().map_err(ctl_error)? as u64;
+    Ok(AllocatorStats {
+        allocator: allocator_name(),
+        allocated_bytes: allocated,
+        active_bytes: active,
+        resident_bytes: stats::resident::read().map_err(ctl_error)? as u64,
+        mapped_bytes: stats::mapped::read().map_err(ctl_error)? as u64,
+        retained_bytes: stats::retained::read().map_err(ctl_error)? as u64,
+        fragmentation: if active == 0 {
+            0.0
+        } else {
+            active.saturating_sub(allocated) as f64 / active as f64
+        },
+    })
+}
+
+#[cfg(feature = "jemalloc")]
+fn ctl_error(e: tikv_jemalloc_ctl::Error) -> io::Error {
+    io::Error::other(e.to_string())
+}
+
+#[cfg(not(feature = "jemalloc"))]
+pub fn allocator_stats() -> io::Result<AllocatorStats> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("{} allocator does not report statistics", allocator_name()),
+    ))
+}
+
+/// register `/debug/allocator` on the admin routes.
+pub fn register_routes(routes: &AdminRoutes) {
+    routes.route("/debug/allocator", |_| match allocator_stats() {
+        Ok(stats) => AdminResponse::json(200, &stats),
+        Err(e) => AdminResponse::text(501, &e.to_string()),
+    });
+}
@@ -1,40 +1,130 @@
 use std::{
-    cell::RefCell,
     collections::{HashMap, HashSet},
-    sync::RwLock,
+    future::Future,
+    io::Write,
+    path::PathBuf,
+    process::ExitCode,
+    sync::{
+        Arc, Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Duration, Instant, UNIX_EPOCH},
 };
 
+#[cfg(feature = "sentry")]
+use crate::crash::CrashReporter;
+#[cfg(feature = "fault-injection")]
+use crate::fault::module as injected_module;
 use crate::{
-    config::Config,
-    error::BootstrapError,
+    admin::{AdminConfig, AdminRoutes, AdminServer},
+    alloc,
+    clock::{Clock, SystemClock},
+    config::{
+        Config, ConfigDiff, ConfigFilePolicy, ConfigPrefix, PropertiesConfig, Redactor,
+        alias::KeyAliases,
+        default_config_folder,
+        environment::{EnvNaming, EnvVar, RequiredEnv},
+        export::{self, ConfigExportConfig, ConfigExportFormat},
+        history::{self, ConfigHistory, ConfigHistoryConfig},
+        k8s::{KubernetesConfig, VolumeWatcher},
+        migration::{ConfigMigration, INITIAL_CONFIG_VERSION},
+        module::MODULES_PREFIX,
+        reload::{ConfigSubscriber, LiveConfig},
+        secret::{ConfigCipher, EnvKeyProvider, SecretKeyProvider, SecretsConfig},
+        source::{self, ConfigSource, SourceChanged},
+        template::{self, ConfigSection},
+    },
+    context::{AppInfo, AppResult, BeaverContext},
+    crash::SentryConfig,
+    debug::{DebugConfig, profile},
+    dispose::Disposer,
+    env::RuntimeEnv,
+    error::{BootstrapError, EX_SOFTWARE},
+    event::{EventBus, LifecycleEvent},
+    fs::{Fs, OsFs, PidFile},
+    graph::ServiceGraph,
+    health::{
+        self, HealthCheck, HealthConfig, HealthMonitor, HealthRegistry, HealthStatus,
+        dependency::DependencyCheck,
+    },
+    heartbeat::{HeartbeatConfig, HeartbeatEmitter},
+    id::{IdGenerator, IdGeneratorConfig},
     log::{
         AllLogger, AppenderGuard, ConsoleAppenderConfig, FileAppenderConfig, Logger, LoggingConfig,
+        LoggingManager,
+        audit::AuditLogger,
+        batch::BatchWriter,
+        buffer::DroppedEvents,
+        capture::LogCapture,
+        early::{self, DEFAULT_EARLY_EVENTS, EarlyEvents, StartupLog},
+        encrypt::EncryptingWriter,
+        flush_on_panic,
+        format::{deterministic_fmt_layer, fmt_layer_with_clock},
+        location::LogFolderStrategy,
+        maintenance::{LogMaintenance, LogMaintenanceConfig, MaintainedFile, RotationMode},
+        monitor::{ErrorMonitor, ErrorMonitorLayer},
+        overlay::{DEFAULT_LOG_FILTER_ENV, TargetOverlay},
+        pattern::{deterministic_pattern_layer, pattern_layer_with_clock},
+        reload::{LoggingReloader, appender_filter, logger_targets},
+        reopen::{ReopenWatcher, ReopenableFile},
+        schema::{SchemaLayer, SchemaMode, SchemaValidator},
+        tenant::TenantFiles,
+        writer::{AppenderWriter, AppenderWriterGuard, FlushOn, SyncWriter, appender_writer},
     },
+    metrics::{Counter, MetricsRegistry},
+    preflight::{PreflightCheck, PreflightConfig, PreflightReport},
+    random::{RandomConfig, RngProvider, ThreadRngProvider},
+    request::RequestScope,
+    runtime::{InitFuture, ManagedRuntime, RuntimeConfig, run_initializers},
+    signal::{self, Signal, SignalBus, SignalConfig},
+    startup::StartupConfig,
 };
-use di::{Ref, ServiceCollection, singleton_as_self};
+use di::{Ref, ServiceCollection, ServiceProvider, singleton_factory, transient_factory};
+use tokio_util::sync::CancellationToken;
 use tracing::{Level, level_filters::LevelFilter};
-use tracing_appender::non_blocking::{NonBlocking, WorkerGuard};
-use tracing_rolling_file::RollingFileAppenderBase;
 use tracing_subscriber::{
-    EnvFilter, Layer,
-    filter::{Targets, targets},
-    fmt::writer::MakeWriterExt,
-    layer::SubscriberExt,
-    registry,
-    util::SubscriberInitExt,
+    Layer, Registry, filter::Targets, layer::SubscriberExt, reload, util::SubscriberInitExt,
 };
 use typed_builder::TypedBuilder;
 
+/// ReloadCounter counts the reloads applied to the live config, rollbacks included.
+struct ReloadCounter(Counter);
+
+impl ConfigSubscriber for ReloadCounter {
+    fn name(&self) -> String {
+        "config_reload_count".to_string()
+    }
+
+    fn apply(&self, _config: &Config, _diff: &ConfigDiff) {
+        self.0.inc();
+    }
+}
+
+/// step of the application run by [`Bootstrap::run`], handled on the thread of the bootstrap.
+enum RunStep {
+    SourceChanged(String),
+    Finished(AppResult),
+}
+
 /// Bootstrap is the entry point of the application.
 ///
 /// It is responsible for initializing the application, including loading the configuration,
 /// initializing the logging, and initializing the service collection.
 ///
+/// Bootstrap is `Send` and `Sync`, so it can be shared behind an `Arc` and driven from async
+/// code or other threads.
+///
 /// # Example
-/// ```
-/// use beaver_bootstrap::Bootstrap;
-/// let bootstrap = Bootstrap::builder().build();
-/// bootstrap.initialize().unwrap();
+/// ```no_run
+/// use std::sync::Arc;
+/// use beaver_bootstrap::bootstrap::Bootstrap;
+/// let bootstrap = Arc::new(Bootstrap::builder().build());
+/// let initializing = bootstrap.clone();
+/// std::thread::spawn(move || initializing.initialize())
+///     .join()
+///     .unwrap()
+///     .unwrap();
+/// assert!(bootstrap.service_provider().is_some());
 /// ```
 ///
 #[derive(TypedBuilder)]
@@ -45,6 +135,10 @@ pub struct Bootstrap {
     /// Whether need to print config.
     #[builder(default = false)]
     show_config: bool,
+    /// How the config is printed, with full float precision by default. Keys are always
+    /// printed sorted, so the output of two runs can be diffed.
+    #[builder(default)]
+    show_config_properties: PropertiesConfig,
 
     /// Prefix of environment variables to override config values.
     #[builder(default = Some("BEAVER_".to_string()))]
@@ -52,28 +146,196 @@ pub struct Bootstrap {
     /// Separator of environment variables to override config values.
     #[builder(default = "_".to_string())]
     env_config_split: String,
+    /// Whether the string values of environment variables are converted to the booleans,
+    /// numbers and nulls they spell, see [`Config::coerce_env_values`].
+    #[builder(default = true)]
+    coerce_env_values: bool,
+    /// Keys which must be set by environment variables, checked at startup.
+    #[builder(via_mutators, mutators(
+        /// require the keys of [`RequiredEnv::REQUIRED_ENV`] of `T`.
+        pub fn require_env<T: RequiredEnv>(&mut self) {
+            self.required_env
+                .extend(T::REQUIRED_ENV.iter().map(|x| format!("{}.{}", T::PREFIX, x)));
+        }
+        /// require the full config key `key`, e.g. `database.password`.
+        pub fn require_env_key(&mut self, key: impl Into<String>) {
+            self.required_env.push(key.into());
+        }
+    ))]
+    required_env: Vec<String>,
 
-    /// a collection of registered services.
-    ///
-    /// This field is initialized internally.
-    #[builder(default = RwLock::new(ServiceCollection::new()), setter(skip))]
-    service_collection: RwLock<ServiceCollection>,
+    /// Kubernetes mode: mounted ConfigMaps/Secrets and downward-API metadata layered into config,
+    /// reloaded when the volumes change while [`Bootstrap::run`] runs the application.
+    #[builder(default = None, setter(strip_option))]
+    kubernetes: Option<KubernetesConfig>,
+    /// Config systems layered above the config file, see [`ConfigSource`].
+    #[builder(via_mutators, mutators(
+        /// add `source` to the sources of config.
+        pub fn config_source(&mut self, source: Box<dyn ConfigSource>) {
+            self.config_sources.push(Arc::from(source));
+        }
+    ))]
+    config_sources: Vec<Arc<dyn ConfigSource>>,
+
+    /// Current layout version of the config file, see `config_version` key.
+    #[builder(default = INITIAL_CONFIG_VERSION)]
+    config_version: u32,
+    /// Migrations used to upgrade config files written for older layout versions.
+    #[builder(default = vec![])]
+    config_migrations: Vec<ConfigMigration>,
+    /// Alternative keys read as the keys of beaver, applied before the migrations.
+    #[builder(default = KeyAliases::default())]
+    config_aliases: KeyAliases,
+    /// Values forced by the application, layered above config files and environment variables.
+    #[builder(via_mutators, mutators(
+        /// override the config value at `key`, e.g. map a `--verbose` flag to
+        /// `logging.console_appender.level`.
+        pub fn override_value(&mut self, key: impl Into<String>, value: impl Into<config::Value>) {
+            self.config_overrides.push((key.into(), value.into()));
+        }
+    ))]
+    config_overrides: Vec<(String, config::Value)>,
+    /// Provider of the key used to decrypt `enc:` prefixed config values, the backend
+    /// selected by `[secrets]` by default, see [`SecretsConfig`].
+    #[builder(default = None, setter(strip_option))]
+    config_key_provider: Option<Box<dyn SecretKeyProvider>>,
+    /// Provider of the backend selected by `[secrets]` and the section it was built from,
+    /// built again when a reload changes the section.
+    #[builder(default, setter(skip))]
+    secrets_key_provider: RwLock<Option<(SecretsConfig, Arc<dyn SecretKeyProvider>)>>,
+    /// Checks of the environment run before modules are initialized, next to the built-in
+    /// checks declared in `[preflight]`.
+    #[builder(default = vec![])]
+    preflight_checks: Vec<Box<dyn PreflightCheck>>,
+    /// Checks of the health of the process, registered next to the ones of modules, see
+    /// [`HealthRegistry`].
+    #[builder(default = vec![])]
+    health_checks: Vec<Arc<dyn HealthCheck>>,
+    /// Warnings produced while loading config, reported once logging is initialized.
+    #[builder(default, setter(skip))]
+    config_warnings: Mutex<Vec<String>>,
+
+    /// Source of time of the background tasks, see [`ManualClock`](crate::clock::ManualClock)
+    /// for tests.
+    #[builder(default = Arc::new(SystemClock))]
+    clock: Arc<dyn Clock>,
+    /// Source of the timestamps of log lines, a [`ManualClock`](crate::clock::ManualClock)
+    /// makes log output reproducible for golden-file tests.
+    #[builder(default = Arc::new(SystemClock))]
+    log_clock: Arc<dyn Clock>,
+    /// Whether the appenders render the same lines on every run, for golden-file tests: fixed
+    /// timestamps, no thread ids nor ANSI colors, JSON members sorted by key, see
+    /// [`deterministic_fmt_layer`]. `log_clock` is then ignored.
+    #[builder(default = false)]
+    deterministic_logs: bool,
+    /// Layer recording every event once logging is initialized, to assert on the logs of
+    /// tests.
+    #[builder(default = None, setter(strip_option))]
+    log_capture: Option<LogCapture>,
+    /// Environment variable of target levels overlaid on the configured loggers at startup,
+    /// see [`TargetOverlay`]. `None` ignores the environment.
+    #[builder(default = Some(DEFAULT_LOG_FILTER_ENV.to_string()))]
+    log_filter_env: Option<String>,
+    /// Where the file appenders without `file_dir` write, next to the executable by default.
+    /// `logging.default_folder` wins over it.
+    #[builder(default)]
+    log_folder: LogFolderStrategy,
+    /// Events logged before logging is initialized kept for replay and for the startup log,
+    /// the oldest are dropped first.
+    #[builder(default = DEFAULT_EARLY_EVENTS)]
+    early_events: usize,
+    /// Events logged before logging was initialized, see [`Bootstrap::startup_log`].
+    #[builder(default, setter(skip))]
+    startup_log: StartupLog,
+    /// Time the bootstrap was created, origin of the uptime.
+    #[builder(default = clock.instant(), setter(skip))]
+    started_at: Instant,
+    /// Filesystem of config discovery, log directories and the PID file, see
+    /// [`MemoryFs`](crate::fs::MemoryFs) for tests.
+    #[builder(default = Arc::new(OsFs))]
+    fs: Arc<dyn Fs>,
+    /// Folder of config.toml, found by [`default_config_folder`] unless set.
+    #[builder(default = None, setter(strip_option, into))]
+    config_folder: Option<PathBuf>,
+    /// Whether a missing config.toml fails the startup, required by default.
+    #[builder(default)]
+    config_file: ConfigFilePolicy,
+    /// File the process id is written to, removed when the bootstrap is dropped.
+    #[builder(default = None, setter(strip_option))]
+    pid_file: Option<PathBuf>,
+
+    /// Metrics of the process.
+    #[builder(default, setter(skip))]
+    metrics: MetricsRegistry,
+    /// Name and version of the application, see [`AppInfo::new`].
+    #[builder(default)]
+    app_info: AppInfo,
+    /// Events of beaver and the application, see [`LifecycleEvent`].
+    #[builder(default, setter(skip))]
+    events: Ref<EventBus>,
+    /// Health checks of the process, their transitions published on `events`.
+    #[builder(default = Ref::new(HealthRegistry::default().with_events(Ref::<EventBus>::clone(&events))), setter(skip))]
+    health: Ref<HealthRegistry>,
+    /// Token cancelled once a shutdown signal is received by [`Bootstrap::run`].
+    #[builder(default, setter(skip))]
+    shutdown_token: CancellationToken,
+    /// Whether the [`ModuleConfig`](crate::config::module::ModuleConfig) views of
+    /// [`BeaverContext::config_for_module`] refuse reads outside of their namespace.
+    #[builder(default = false)]
+    strict_module_config: bool,
+
+    /// Disposable services constructed so far, disposed by [`Bootstrap::shutdown`].
+    #[builder(default, setter(skip))]
+    disposer: Disposer,
+    /// Whether the services were disposed.
+    #[builder(default, setter(skip))]
+    shut_down: AtomicBool,
+
+    /// Routes of the admin endpoint, served once `[admin]` is enabled.
+    #[builder(default, setter(skip))]
+    admin_routes: AdminRoutes,
+
+    /// provider built from the service collection once its graph is verified.
+    #[builder(default, setter(skip))]
+    service_provider: RwLock<Option<ServiceProvider>>,
 
     /// a collection of modules
     #[builder(default = vec![])]
     modules: Vec<Box<dyn Module>>,
 
     /// a collection of modules
-    #[builder(default = RefCell::new(BootstrapBaseModule::default()))]
-    base_modules: RefCell<BootstrapBaseModule>,
+    #[builder(default, setter(skip))]
+    base_modules: RwLock<BootstrapBaseModule>,
 }
 
 impl Bootstrap {
+    /// initialize every part of the application, each phase in a `bootstrap` span naming it.
+    ///
+    /// The events of the phases before logging, on this thread, are captured in a ring buffer
+    /// and replayed into the appenders once logging is initialized, see [`EarlyEvents`]. They
+    /// are kept as the [`Bootstrap::startup_log`] too, even when a phase fails.
     pub fn initialize(&self) -> Result<(), BootstrapError> {
-        // first we try to initialize config
-        self.initialize_config()?;
-        // then we try to initialize logging by logger config
-        self.initialize_logging()?;
+        let early = EarlyEvents::capture(self.early_events);
+        let result = self.initialize_before_logging();
+        self.startup_log.record(&early);
+        result?;
+        early.replay();
+        for warning in self.config_warnings().iter() {
+            tracing::warn!("{}", warning);
+        }
+        phase("preflight", || self.run_preflight_checks())?;
+        phase("metrics", || {
+            self.initialize_metrics();
+            Ok(())
+        })?;
+        phase("admin", || self.initialize_admin())?;
+        phase("heartbeat", || self.initialize_heartbeat())?;
+        phase("signals", || self.initialize_signals())?;
+        phase("runtime", || self.initialize_runtime())?;
+        phase("services", || self.initialize_services())?;
+        phase("wait_for", || self.wait_for_dependencies())?;
+        phase("modules", || self.initialize_modules())?;
+        phase("health", || self.initialize_health())?;
         if self.show_config {
             // after logging initialized, we show config if needed
             self.show_config()?;
@@ -81,134 +343,439 @@ impl Bootstrap {
         Ok(())
     }
 
+    /// the phases up to logging, their events captured by [`Bootstrap::initialize`].
+    fn initialize_before_logging(&self) -> Result<(), BootstrapError> {
+        // first we try to initialize config
+        let start = Instant::now();
+        phase("config", || self.initialize_config())?;
+        self.record_init_duration("config", start);
+        phase("pid_file", || self.initialize_pid_file())?;
+        phase("config_export", || self.export_config())?;
+        // then we try to initialize logging by logger config
+        let start = Instant::now();
+        phase("logging", || self.initialize_logging())?;
+        self.record_init_duration("logging", start);
+        Ok(())
+    }
+
+    /// Events logged by [`Bootstrap::initialize`] before logging was initialized, as lines of
+    /// text, for the post-mortem of a failed startup. Served at `/debug/startup-log` by the
+    /// admin endpoint.
+    pub fn startup_log(&self) -> Vec<String> {
+        self.startup_log.lines()
+    }
+
     pub fn initialize_config(&self) -> Result<(), BootstrapError> {
+        self.check_required_env()?;
+        let (config, warnings) = self.load_config()?;
+        self.config_warnings().extend(warnings);
+        let rng = RandomConfig::new(&config)?.provider();
+        let id_generator = IdGenerator::from_config(&config, rng.clone(), self.clock.clone())?;
+        let history = ConfigHistory::new(
+            ConfigHistoryConfig::new(&config)?.size(),
+            self.clock.clone(),
+        );
+        let config = Ref::new(config);
+        history.record(config.clone(), "startup");
+        let live_config = LiveConfig::new(config.clone()).with_history(Ref::new(history));
+        let mut base_modules = self.base_modules_mut();
+        let _ = base_modules.config.insert(config);
+        let _ = base_modules.live_config.insert(Ref::new(live_config));
+        let _ = base_modules.id_generator.insert(Ref::new(id_generator));
+        let _ = base_modules.rng.insert(rng);
+        Ok(())
+    }
+
+    /// load the config again and propose it to the subscribers of the [`LiveConfig`].
+    ///
+    /// Nothing is applied when a subscriber rejects it, see [`LiveConfig::propose`].
+    pub fn reload_config(&self) -> Result<ConfigDiff, BootstrapError> {
+        let live_config = self.base_modules().live_config.clone();
+        let Some(live_config) = live_config else {
+            return Err(BootstrapError::ConfigLoadError(
+                config::ConfigError::Message("config is not initialized".to_string()),
+            ));
+        };
+        let (config, warnings) = self.load_config()?;
+        for warning in warnings {
+            tracing::warn!("{}", warning);
+        }
+        live_config.propose(config)
+    }
+
+    /// load, decrypt, coerce environment values, rename aliased keys, migrate and override the
+    /// config.
+    fn load_config(&self) -> Result<(Config, Vec<String>), BootstrapError> {
         let env_config_prefix: Option<&str> = self.env_config_prefix.as_deref();
         let env_config_split: &str = self.env_config_split.as_str();
-        let config = Config::load(env_config_prefix, env_config_split)
-            .map_err(|e| BootstrapError::ConfigLoadError(e))?;
-        let _ = self
-            .base_modules
-            .borrow_mut()
-            .config
-            .insert(Ref::new(config));
+        let mut sources = self
+            .kubernetes
+            .as_ref()
+            .map(KubernetesConfig::sources)
+            .unwrap_or_default();
+        sources.extend(source::plugin_sources(&self.config_sources));
+        let config_folder = match &self.config_folder {
+            Some(folder) => folder.as_path(),
+            None => default_config_folder(),
+        };
+        let (config, warnings) = Config::from_folder_with_policy(
+            self.fs.as_ref(),
+            config_folder,
+            self.config_file,
+            env_config_prefix,
+            env_config_split,
+            sources,
+        )
+        .map_err(BootstrapError::ConfigLoadError)?;
+        let secrets_key_provider;
+        let key_provider: &dyn SecretKeyProvider = match &self.config_key_provider {
+            Some(provider) => provider.as_ref(),
+            None => {
+                secrets_key_provider = self.secrets_key_provider(&config)?;
+                secrets_key_provider.as_ref()
+            }
+        };
+        let (config, warnings) = config
+            .decrypt(key_provider)
+            .and_then(|config| {
+                let mut warnings = warnings;
+                let config = match self.coerce_env_values {
+                    true => config.coerce_env_values()?,
+                    false => config,
+                };
+                let (config, aliased) = config.with_aliases(&self.config_aliases)?;
+                warnings.extend(aliased);
+                Ok((config, warnings))
+            })
+            .and_then(|(config, mut warnings)| {
+                let (config, migrated) =
+                    config.migrate(self.config_version, &self.config_migrations)?;
+                warnings.extend(migrated);
+                Ok((config, warnings))
+            })
+            .and_then(|(config, warnings)| {
+                Ok((config.with_overrides(&self.config_overrides)?, warnings))
+            })
+            .map_err(BootstrapError::ConfigLoadError)?;
+        Ok((config, warnings))
+    }
+
+    /// the provider of the backend selected by the `[secrets]` of `config`, the one of the
+    /// last load while the section is unchanged, so its cached key is kept.
+    fn secrets_key_provider(
+        &self,
+        config: &Config,
+    ) -> Result<Arc<dyn SecretKeyProvider>, BootstrapError> {
+        let secrets_config = SecretsConfig::new(config)?;
+        let mut cached = self
+            .secrets_key_provider
+            .write()
+            .unwrap_or_else(|e| e.into_inner());
+        if let Some((built_from, provider)) = cached.as_ref()
+            && *built_from == secrets_config
+        {
+            return Ok(provider.clone());
+        }
+        let provider: Arc<dyn SecretKeyProvider> = Arc::from(secrets_config.key_provider()?);
+        *cached = Some((secrets_config, provider.clone()));
+        Ok(provider)
+    }
+
+    /// write the effective config when `config_export.enable` is set.
+    fn export_config(&self) -> Result<(), BootstrapError> {
+        let Some(config) = self.base_modules().config.clone() else {
+            return Ok(());
+        };
+        let export_config = ConfigExportConfig::new(&config)?;
+        if export_config.enable() {
+            export::export(self.fs.as_ref(), &config, &export_config)?;
+        }
+        Ok(())
+    }
+
+    fn initialize_pid_file(&self) -> Result<(), BootstrapError> {
+        if let Some(path) = &self.pid_file {
+            let pid_file = PidFile::create(self.fs.clone(), path)
+                .map_err(|e| BootstrapError::PidFileError(Box::new(e)))?;
+            let _ = self.base_modules_mut().pid_file.insert(Ref::new(pid_file));
+        }
         Ok(())
     }
 
     fn initialize_logging_config(&self) -> Result<(), BootstrapError> {
-        let config: Option<std::sync::Arc<Config>> = self.base_modules.borrow().config.clone();
+        let config: Option<std::sync::Arc<Config>> = self.base_modules().config.clone();
 
         let logging_config_result = match config {
-            Some(config) => LoggingConfig::new(&config),
+            Some(config) => {
+                LoggingConfig::new_with_folder(&config, self.fs.as_ref(), &self.log_folder)
+            }
             None => Err(BootstrapError::MissingConfigValueError(
                 "logging.logger_config is empty".to_string(),
             )),
         };
-        let logging_config = Ref::new(logging_config_result?);
+        let mut logging_config = logging_config_result?;
+        for line in logging_config.file_dir_report() {
+            tracing::info!("{}", line);
+        }
+        logging_config.merge_loggers(self.modules.iter().flat_map(|m| m.loggers()).collect());
+        let logging_config = Ref::new(logging_config);
         {
-            // limit the scope of borrow_mut
-            let mut base_modules = self.base_modules.borrow_mut();
+            // limit the scope of the write lock
+            let mut base_modules = self.base_modules_mut();
             let _ = base_modules.logging_config.insert(logging_config);
         }
-        return Ok(());
+        Ok(())
     }
     fn initialize_logging_loggers(&self) -> Result<(), BootstrapError> {
         let logging_config: Option<std::sync::Arc<LoggingConfig>> =
-            self.base_modules.borrow().logging_config.clone();
+            self.base_modules().logging_config.clone();
         if logging_config.is_none() {
             return Err(BootstrapError::MissingConfigValueError(
                 "logging.logger_config is empty".to_string(),
             ));
         }
         let binding: std::sync::Arc<LoggingConfig> = logging_config.unwrap();
+        let overlay = match &self.log_filter_env {
+            Some(var) => TargetOverlay::from_env(var)?,
+            None => None,
+        };
         let mut non_blocking_writers = Vec::new();
         let mut writer_guards = Vec::new();
+        let mut dropped_events = DroppedEvents::default();
+        let mut reopen_files = Vec::new();
+        let mut maintained_files = Vec::new();
+        let maintenance_config = binding.maintenance_config();
 
-        let all_logger = binding.logger_config().loggers();
-        let mut logger_map: HashMap<&str, &Logger> = HashMap::new();
-        all_logger.iter().cloned().for_each(|x| {
-            logger_map.insert(x.name(), x);
-        });
+        let all_logger = binding.logger_config();
         for file_config in binding.file_appender_config() {
             if file_config.enable() {
-                let (non_blocking_file_writer, targets, level, file_writer_guard) =
-                    self.initialize_logging_file_tracing(file_config, &logger_map)?;
-                non_blocking_writers.push((non_blocking_file_writer, targets, level));
+                let (non_blocking_file_writer, targets, level, file_writer_guard) = self
+                    .initialize_logging_file_tracing(
+                        file_config,
+                        all_logger,
+                        &maintenance_config,
+                        &mut reopen_files,
+                        &mut maintained_files,
+                    )?;
+                let fields = binding.appender_fields(file_config.fields());
+                if let Some(counter) = non_blocking_file_writer.error_counter() {
+                    dropped_events.add(file_config.name(), counter);
+                }
+                non_blocking_writers.push((
+                    file_config.name().to_string(),
+                    non_blocking_file_writer,
+                    targets,
+                    level,
+                    fields,
+                    file_config.format(),
+                    file_config.pattern().cloned(),
+                    file_config.filter().cloned(),
+                    file_config.dedup_window(),
+                ));
                 writer_guards.push(file_writer_guard);
             }
         }
         let mut console_writer = None;
-        let console_opt = binding.console_appender_config();
-        if console_opt.is_some() && console_opt.unwrap().enable() {
+        if let Some(console_config) = binding.console_appender_config()
+            && console_config.enable()
+        {
             let (non_blocking_console_writer, targets, level, console_writer_guard) =
-                self.initialize_logging_console_tracing(console_opt.unwrap(), &logger_map)?;
-            let _ = console_writer.insert((non_blocking_console_writer, targets, level));
+                self.initialize_logging_console_tracing(console_config, all_logger)?;
+            let fields = binding.appender_fields(console_config.fields());
+            if let Some(counter) = non_blocking_console_writer.error_counter() {
+                dropped_events.add(console_config.name(), counter);
+            }
+            let _ = console_writer.insert((
+                console_config.name().to_string(),
+                non_blocking_console_writer,
+                targets,
+                level,
+                fields,
+                console_config.format(),
+                console_config.pattern().cloned(),
+                console_config.filter().cloned(),
+                console_config.dedup_window(),
+            ));
             writer_guards.push(console_writer_guard);
         }
-        let mut layers = Vec::new();
-        for (non_blocking_file_writer, target, level) in non_blocking_writers {
-            let file_layer = tracing_subscriber::fmt::layer()
-                .with_ansi(false)
-                .with_writer(non_blocking_file_writer.with_max_level(level))
-                .with_filter(target);
+        let mut layers: Vec<Box<dyn Layer<Registry> + Send + Sync>> = Vec::new();
+        // filters are reloadable, the write level caps them rather than the writers
+        let mut filters = HashMap::new();
+        let mut reloadable_filter = |name: String, targets: Targets, level: Level| {
+            let filter = appender_filter(targets, overlay.as_ref(), LevelFilter::from_level(level));
+            let (filter, handle) = reload::Layer::new(filter);
+            filters.insert(name, handle);
+            filter
+        };
+        let log_clock = self.log_clock.clone();
+        let deterministic = self.deterministic_logs;
+        // text appenders with a pattern use it rather than the default layout
+        let fmt_layer =
+            |format, ansi, pattern, fields, dedup, writer| match (pattern, deterministic) {
+                (Some(pattern), true) => deterministic_pattern_layer(pattern, fields, writer),
+                (Some(pattern), false) => {
+                    pattern_layer_with_clock(pattern, fields, dedup, log_clock.clone(), writer)
+                }
+                (None, true) => deterministic_fmt_layer(format, fields, writer),
+                (None, false) => {
+                    fmt_layer_with_clock(format, ansi, fields, dedup, log_clock.clone(), writer)
+                }
+            };
+        for (name, writer, target, level, fields, format, pattern, filter, dedup) in
+            non_blocking_writers
+        {
+            let file_layer = fmt_layer(format, false, pattern, fields, dedup, writer)
+                .with_filter(reloadable_filter(name, target, level))
+                .with_filter(filter)
+                .boxed();
             layers.push(file_layer);
         }
-        let _console_layer = console_writer.is_some_and(|(x, y, z)| {
-            let layer = tracing_subscriber::fmt::layer()
-                .with_writer(x.with_max_level(z))
-                .with_filter(y);
+        if let Some((name, x, y, z, fields, format, pattern, filter, dedup)) = console_writer {
+            let layer = fmt_layer(format, true, pattern, fields, dedup, x)
+                .with_filter(reloadable_filter(name, y, z))
+                .with_filter(filter)
+                .boxed();
             layers.push(layer);
-            return true;
-        });
+        }
+        if let Some(monitor_config) = binding.error_monitor_config()
+            && monitor_config.enable()
+        {
+            let monitor = Ref::new(ErrorMonitor::from_config(
+                monitor_config,
+                self.clock.clone(),
+            ));
+            layers.push(ErrorMonitorLayer::new(monitor.clone()).boxed());
+            let _ = self.base_modules_mut().error_monitor.insert(monitor);
+        }
+        if let Some(schema_config) = binding.schema_config()
+            && schema_config.mode() != SchemaMode::Off
+        {
+            let schema = Ref::new(SchemaValidator::new(schema_config));
+            layers.push(SchemaLayer::new(schema.clone()).boxed());
+            let _ = self.base_modules_mut().schema.insert(schema);
+        }
+        if let Some(capture) = &self.log_capture {
+            layers.push(capture.clone().boxed());
+        }
+        let config = self.base_modules().config.clone();
+        if let Some(config) = config {
+            let debug_config = DebugConfig::new(&config)?;
+            let tokio_console = debug_config.tokio_console();
+            if tokio_console.enable() {
+                #[cfg(feature = "tokio-console")]
+                layers.push(tokio_console.layer()?);
+                #[cfg(not(feature = "tokio-console"))]
+                self.config_warnings().push(
+                    "debug.tokio_console.enable is ignored, build with the tokio-console feature"
+                        .to_string(),
+                );
+            }
+            let sentry_config = SentryConfig::new(&config)?;
+            if sentry_config.enable() {
+                #[cfg(feature = "sentry")]
+                {
+                    let reporter = CrashReporter::init(&sentry_config)?;
+                    layers.push(CrashReporter::layer());
+                    let _ = self
+                        .base_modules_mut()
+                        .crash_reporter
+                        .insert(Ref::new(reporter));
+                }
+                #[cfg(not(feature = "sentry"))]
+                self.config_warnings()
+                    .push("sentry.enable is ignored, build with the sentry feature".to_string());
+            }
+        }
         // save logger to keep guards active
         {
-            // limit the scope of borrow_mut
-            let mut base_modules = self.base_modules.borrow_mut();
-            let logger = AppenderGuard::new(writer_guards);
+            // limit the scope of the write lock
+            let mut base_modules = self.base_modules_mut();
+            let mut logger = AppenderGuard::new(writer_guards).with_dropped_events(dropped_events);
+            if let Some(reopen_signal) = binding.reopen_signal_config()
+                && reopen_signal.enable()
+            {
+                let watcher = ReopenWatcher::spawn(reopen_signal, reopen_files).map_err(|e| {
+                    BootstrapError::InvalidConfigValueError(format!(
+                        "logging.reopen_signal.signal={}: {}",
+                        reopen_signal.signal(),
+                        e
+                    ))
+                })?;
+                logger = logger.with_reopen_watcher(watcher);
+            }
+            let manager = logger.logging_manager();
+            let rng = base_modules
+                .rng
+                .clone()
+                .unwrap_or_else(|| Ref::new(ThreadRngProvider));
+            if let Some(maintenance) = LogMaintenance::start(
+                &maintenance_config,
+                maintained_files,
+                manager.clone(),
+                self.clock.clone(),
+                rng,
+            ) {
+                logger = logger.with_maintenance(maintenance);
+            }
+            flush_on_panic(manager.clone(), binding.flush_timeout());
+            let _ = base_modules.logging_manager.insert(Ref::new(manager));
             let _ = base_modules.logger.insert(Ref::new(logger));
         }
         let subscriber = tracing_subscriber::registry().with(layers);
         subscriber
             .try_init()
             .map_err(|e| BootstrapError::TracingSubscriberInitError(Box::new(e)))?;
-
+        if let (Some(var), Some(_)) = (&self.log_filter_env, &overlay) {
+            tracing::info!("log targets overlaid from {}", var);
+        }
+        // effective levels of the logger hierarchy
+        for logger in all_logger.loggers() {
+            let source = all_logger.level_source(logger);
+            let level = all_logger.effective_level(logger);
+            let target = match logger.target() {
+                "" => "default",
+                target => target,
+            };
+            match source.name() == logger.name() {
+                true => tracing::info!("logger {} ({}) at {}", logger.name(), target, level),
+                false => tracing::info!(
+                    "logger {} ({}) at {}, inherited from {}",
+                    logger.name(),
+                    target,
+                    level,
+                    source.name()
+                ),
+            }
+        }
+        if let Some(live_config) = self.base_modules().live_config.clone() {
+            let module_loggers = self.modules.iter().flat_map(|m| m.loggers()).collect();
+            let reloader = LoggingReloader::new(filters, overlay, module_loggers, self.fs.clone())
+                .with_default_folder(self.log_folder.clone());
+            live_config.subscribe(Ref::new(reloader));
+        }
         Ok(())
     }
     fn initialize_logging_console_tracing(
         &self,
         appender_config: &ConsoleAppenderConfig,
-        logger_map: &HashMap<&str, &Logger>,
-    ) -> Result<(NonBlocking, Targets, Level, WorkerGuard), BootstrapError> {
+        all_logger: &AllLogger,
+    ) -> Result<(AppenderWriter, Targets, Level, AppenderWriterGuard), BootstrapError> {
         // get write level from appender config
         let Some(level) = appender_config.write_level().as_tracing_level() else {
             return Err(BootstrapError::InvalidConfigValueError(format!(
-                "logging.console_appender[?].write_level={:?}",
+                "logging.console_appender.write_level={:?}",
                 appender_config.write_level()
             )));
         };
-        let targets: Vec<String> = appender_config
-            .logger_names()
-            .iter()
-            .cloned()
-            .collect::<HashSet<&str>>()
-            .into_iter()
-            .map(String::from)
-            .collect::<Vec<String>>();
-        let mut logger_target: Vec<&Logger> = Vec::new();
-        for target in targets {
-            // unwrap is safe, validate during logging config init
-            let value = logger_map.get(target.as_str()).unwrap();
-            logger_target.push(value);
-        }
-        let (non_blocking_file_writer, console_writer_guard) =
-            tracing_appender::non_blocking(std::io::stdout());
-        let target_builder: Targets = Targets::new();
-        let targets = logger_target.into_iter().fold(target_builder, |acc, item| {
-            if item.target().is_empty() {
-                acc.with_default(item.level().as_tracing_level_filter())
-            } else {
-                acc.with_target(item.target(), item.level().as_tracing_level_filter())
-            }
-        });
+        let (non_blocking_file_writer, console_writer_guard) = appender_writer(
+            std::io::stdout(),
+            appender_config.name(),
+            appender_config.buffer_size(),
+            appender_config.on_full(),
+            appender_config.flush_on(),
+        );
+        // logger names are validated during logging config init
+        let targets = logger_targets(all_logger, &appender_config.logger_names());
         Ok((
             non_blocking_file_writer,
             targets,
@@ -219,76 +786,979 @@ impl Bootstrap {
     fn initialize_logging_file_tracing(
         &self,
         appender_config: &FileAppenderConfig,
-        logger_map: &HashMap<&str, &Logger>,
-    ) -> Result<(NonBlocking, Targets, Level, WorkerGuard), BootstrapError> {
+        all_logger: &AllLogger,
+        maintenance: &LogMaintenanceConfig,
+        reopen_files: &mut Vec<ReopenableFile>,
+        maintained_files: &mut Vec<MaintainedFile>,
+    ) -> Result<(AppenderWriter, Targets, Level, AppenderWriterGuard), BootstrapError> {
         // get write level from appender config
         let Some(level) = appender_config.write_level().as_tracing_level() else {
             return Err(BootstrapError::InvalidConfigValueError(format!(
-                "logging.file_appenders[?].write_level={:?}",
+                "logging.file_appenders[{}].write_level={:?}",
+                appender_config.name(),
                 appender_config.write_level()
             )));
         };
-        // build file layer
-        let builder = RollingFileAppenderBase::builder();
-        let file_appender = builder
-            .filename(appender_config.file_path().to_str().unwrap().to_string())
-            .max_filecount(appender_config.file_max_count())
-            .condition_max_file_size(appender_config.file_max_size())
-            .condition_daily()
-            .build()
-            .map_err(|e| BootstrapError::LogFileCreationError(Box::new(e)))?;
-        let targets: Vec<String> = appender_config
-            .logger_names()
+        // logger names are validated during logging config init
+        let targets = logger_targets(all_logger, &appender_config.logger_names());
+        if appender_config.is_per_tenant() {
+            // the tenant of an event is known on the thread emitting it only
+            let files = TenantFiles::new(
+                appender_config.file_dir(),
+                appender_config.file_name(),
+                appender_config.tenant_field(),
+                appender_config.file_max_count(),
+                appender_config.file_max_size(),
+                appender_config.max_open_files(),
+            );
+            let writer = match appender_config.encrypt() {
+                true => SyncWriter::new(
+                    EncryptingWriter::new(files, self.log_cipher()?),
+                    appender_config.flush_on(),
+                ),
+                false => SyncWriter::new(files, appender_config.flush_on()),
+            };
+            return Ok((
+                AppenderWriter::Sync(writer.clone()),
+                targets,
+                level,
+                AppenderWriterGuard::Sync(writer),
+            ));
+        }
+        // build file layer, the maintenance thread rotating it by size in scheduled mode
+        let scheduled = maintenance.rotation() == RotationMode::Scheduled;
+        let file_appender = ReopenableFile::open(
+            appender_config.file_path(),
+            appender_config.file_max_count(),
+            match scheduled {
+                true => u64::MAX,
+                false => appender_config.file_max_size(),
+            },
+        )
+        .map_err(|e| BootstrapError::LogFileCreationError(Box::new(e)))?;
+        reopen_files.push(file_appender.clone());
+        let maintained = MaintainedFile::new(file_appender.clone(), appender_config.retention());
+        maintained_files.push(match scheduled {
+            true => maintained.with_scheduled_rotation(appender_config.file_max_size()),
+            false => maintained,
+        });
+        let name = appender_config.name();
+        let buffer_size = appender_config.buffer_size();
+        let on_full = appender_config.on_full();
+        let flush_on = appender_config.flush_on();
+        let mut writer: Box<dyn Write + Send> = Box::new(file_appender);
+        if let Some(batch) = appender_config.batch() {
+            // at most MAX_BATCH_SIZE, validated with the config
+            let size = batch.size() as usize;
+            writer = Box::new(BatchWriter::new(writer, name, size, batch.latency()));
+        }
+        // encrypted lines are batched rather than batches encrypted
+        if appender_config.encrypt() {
+            writer = Box::new(EncryptingWriter::new(writer, self.log_cipher()?));
+        }
+        if let Some(batch) = appender_config.batch() {
+            // events are copied to the batch on the logging threads, the batch thread writes
+            // the file
+            let flush_on = match batch.flush_on_error() {
+                true => FlushOn::Error,
+                false => FlushOn::Never,
+            };
+            let writer = SyncWriter::new(writer, flush_on);
+            return Ok((
+                AppenderWriter::Sync(writer.clone()),
+                targets,
+                level,
+                AppenderWriterGuard::Sync(writer),
+            ));
+        }
+        let (non_blocking_file_writer, file_writer_guard) =
+            appender_writer(writer, name, buffer_size, on_full, flush_on);
+        Ok((non_blocking_file_writer, targets, level, file_writer_guard))
+    }
+    fn initialize_logging_audit(&self) -> Result<(), BootstrapError> {
+        let logging_config: Option<std::sync::Arc<LoggingConfig>> =
+            self.base_modules().logging_config.clone();
+        let Some(audit_config) = logging_config
+            .as_ref()
+            .and_then(|config| config.audit_config())
+            .filter(|config| config.enable())
+        else {
+            return Ok(());
+        };
+        let audit_logger = AuditLogger::from_config(audit_config)?;
+        let _ = self
+            .base_modules_mut()
+            .audit_logger
+            .insert(Ref::new(audit_logger));
+        Ok(())
+    }
+    /// Routes of the admin endpoint, routes added after startup are served too.
+    pub fn admin_routes(&self) -> &AdminRoutes {
+        &self.admin_routes
+    }
+
+    fn initialize_admin(&self) -> Result<(), BootstrapError> {
+        let Some(config) = self.base_modules().config.clone() else {
+            return Ok(());
+        };
+        let admin_config = AdminConfig::new(&config)?;
+        self.metrics.register_routes(&self.admin_routes);
+        early::register_routes(&self.admin_routes, self.startup_log.clone());
+        health::register_routes(&self.admin_routes, self.health.clone());
+        if let Some(live_config) = self.base_modules().live_config.clone() {
+            // rolling back changes the process, so it is only served to authenticated callers
+            history::register_routes(
+                &self.admin_routes,
+                live_config.clone(),
+                admin_config.token().is_some(),
+            );
+            export::register_routes(
+                &self.admin_routes,
+                live_config,
+                admin_config.token().is_some(),
+            );
+        }
+        let debug_config = DebugConfig::new(&config)?;
+        if debug_config.profiling().enable() {
+            if admin_config.token().is_none() {
+                return Err(BootstrapError::MissingConfigValueError(
+                    "admin.token is required by debug.profiling".to_string(),
+                ));
+            }
+            profile::register_routes(&self.admin_routes, debug_config.profiling());
+            alloc::register_routes(&self.admin_routes);
+        }
+        let mut base_modules = self.base_modules_mut();
+        let _ = base_modules
+            .admin_routes
+            .insert(Ref::new(self.admin_routes.clone()));
+        if admin_config.enable() {
+            let server = AdminServer::start(&admin_config, self.admin_routes.clone())?;
+            let _ = base_modules.admin_server.insert(Ref::new(server));
+        }
+        Ok(())
+    }
+
+    fn run_preflight_checks(&self) -> Result<(), BootstrapError> {
+        let Some(config) = self.base_modules().config.clone() else {
+            return Ok(());
+        };
+        let preflight_config = PreflightConfig::new(&config)?;
+        let checks = preflight_config.checks(&self.fs, &self.clock);
+        let checks = checks.iter().chain(self.preflight_checks.iter());
+        PreflightReport::run(checks.map(|x| x.as_ref()), &preflight_config).into_result()?;
+        Ok(())
+    }
+
+    pub fn app_info(&self) -> &AppInfo {
+        &self.app_info
+    }
+
+    /// Names of the registered modules, in order of registration.
+    pub fn module_names(&self) -> Vec<String> {
+        self.modules.iter().map(|x| x.name()).collect()
+    }
+
+    /// load the config and validate the sections of beaver without starting anything,
+    /// returning the warnings of loading.
+    ///
+    /// Log directories are created to check that the log files are writable.
+    pub fn check_config(&self) -> Result<Vec<String>, BootstrapError> {
+        self.check_required_env()?;
+        let (config, warnings) = self.load_config()?;
+        RandomConfig::new(&config)?;
+        ConfigHistoryConfig::new(&config)?;
+        ConfigExportConfig::new(&config)?;
+        LoggingConfig::new_with_fs(&config, self.fs.as_ref())?;
+        DebugConfig::new(&config)?;
+        SentryConfig::new(&config)?;
+        AdminConfig::new(&config)?;
+        PreflightConfig::new(&config)?;
+        RuntimeConfig::new(&config)?;
+        HeartbeatConfig::new(&config)?;
+        SignalConfig::new(&config)?;
+        let mut warnings = warnings;
+        let namespaces: HashSet<String> =
+            self.modules.iter().map(|x| x.config_namespace()).collect();
+        for namespace in config.keys_with_prefix(MODULES_PREFIX) {
+            if !namespaces.contains(&namespace) {
+                warnings.push(format!(
+                    "{}.{} is not the namespace of a registered module",
+                    MODULES_PREFIX, namespace
+                ));
+            }
+        }
+        Ok(warnings)
+    }
+
+    /// sections of config.toml read by beaver and the modules.
+    pub fn config_sections(&self) -> Result<Vec<ConfigSection>, BootstrapError> {
+        let mut sections = vec![
+            ConfigSection::with_example(
+                LoggingConfig::PREFIX,
+                "loggers and appenders, at least one logger named after all_logger.default_name.",
+                LOGGING_EXAMPLE,
+            ),
+            ConfigSection::of::<RuntimeConfig>("managed tokio runtime.")?,
+            ConfigSection::of::<AdminConfig>("admin HTTP endpoint: health, metrics and config.")?,
+            ConfigSection::of::<HeartbeatConfig>("periodic liveness signal.")?,
+            ConfigSection::of::<HealthConfig>("health checks evaluated periodically.")?,
+            ConfigSection::of::<StartupConfig>("dependencies waited for before modules.")?,
+            ConfigSection::of::<PreflightConfig>("checks of the environment run at startup.")?,
+            ConfigSection::of::<SignalConfig>("signals kept from applications.")?,
+            ConfigSection::of::<IdGeneratorConfig>("request and entity ids.")?,
+            ConfigSection::of::<RandomConfig>("source of randomness.")?,
+            ConfigSection::of::<ConfigHistoryConfig>("config snapshots kept for rollback.")?,
+            ConfigSection::of::<ConfigExportConfig>("effective config written at startup.")?,
+            ConfigSection::of::<SentryConfig>("crash reporting, built with the sentry feature.")?,
+            ConfigSection::of::<DebugConfig>("diagnostics of a running process.")?,
+        ];
+        for module in &self.modules {
+            sections.extend(module.config_sections());
+        }
+        Ok(sections)
+    }
+
+    /// the environment variables overriding the keys of [`Bootstrap::config_sections`] and
+    /// the required keys, sorted by key.
+    pub fn env_vars(&self) -> Result<Vec<EnvVar>, BootstrapError> {
+        let naming = EnvNaming::new(
+            self.env_config_prefix.as_deref(),
+            self.env_config_split.as_str(),
+        );
+        let mut keys: Vec<String> = self
+            .config_sections()?
+            .iter()
+            .flat_map(ConfigSection::keys)
+            .chain(self.required_env.iter().cloned())
+            .collect();
+        keys.sort();
+        keys.dedup();
+        Ok(keys
+            .iter()
+            .map(|key| EnvVar::new(&naming, key, self.required_env.contains(key)))
+            .collect())
+    }
+
+    /// fail with every required environment variable which is not set.
+    fn check_required_env(&self) -> Result<(), BootstrapError> {
+        let naming = EnvNaming::new(
+            self.env_config_prefix.as_deref(),
+            self.env_config_split.as_str(),
+        );
+        let mut missing: Vec<String> = self
+            .required_env
             .iter()
-            .cloned()
-            .collect::<HashSet<&str>>()
+            .map(|key| EnvVar::new(&naming, key, true))
+            .filter(|x| !x.is_set())
+            .map(|x| match x.name() {
+                Some(name) => format!("{} ({})", name, x.key()),
+                None => format!(
+                    "{} (cannot be set with separator `{}`)",
+                    x.key(),
+                    self.env_config_split
+                ),
+            })
+            .collect();
+        if missing.is_empty() {
+            return Ok(());
+        }
+        missing.sort();
+        missing.dedup();
+        Err(BootstrapError::MissingEnvVarsError(missing.join(", ")))
+    }
+
+    /// a commented example config.toml of [`Bootstrap::config_sections`].
+    pub fn default_config(&self) -> Result<String, BootstrapError> {
+        Ok(template::render(&self.config_sections()?))
+    }
+
+    /// the loaded config in `format`, with the values of sensitive keys redacted.
+    pub fn effective_config(&self, format: ConfigExportFormat) -> Result<String, BootstrapError> {
+        let (config, _) = self.load_config()?;
+        export::render(&config, format, &Redactor::default())
+    }
+
+    /// Metrics of the process, served on `/metrics` by the admin endpoint.
+    pub fn metrics(&self) -> &MetricsRegistry {
+        &self.metrics
+    }
+
+    fn record_init_duration(&self, module: &str, start: Instant) {
+        self.metrics
+            .gauge_with_labels(
+                "module_init_duration_seconds",
+                "time spent initializing each module",
+                &[("module", module)],
+            )
+            .set(start.elapsed().as_secs_f64());
+    }
+
+    /// register the baseline metrics every application exposes.
+    fn initialize_metrics(&self) {
+        let start_time = self
+            .clock
+            .now()
+            .checked_sub(self.uptime())
+            .and_then(|x| x.duration_since(UNIX_EPOCH).ok())
+            .unwrap_or_default();
+        self.metrics
+            .gauge(
+                "process_start_time_seconds",
+                "start time of the process since unix epoch",
+            )
+            .set(start_time.as_secs_f64());
+        let (clock, started_at) = (self.clock.clone(), self.started_at);
+        self.metrics
+            .gauge_fn("uptime_seconds", "time elapsed since startup", move || {
+                clock
+                    .instant()
+                    .saturating_duration_since(started_at)
+                    .as_secs_f64()
+            });
+        self.metrics
+            .gauge_with_labels(
+                "beaver_build_info",
+                "version of beaver-bootstrap",
+                &[("version", env!("CARGO_PKG_VERSION"))],
+            )
+            .set(1.0);
+        let reloads = self.metrics.counter(
+            "config_reload_count",
+            "config reloads applied since startup",
+        );
+        if let Some(live_config) = self.base_modules().live_config.clone() {
+            live_config.subscribe(Arc::new(ReloadCounter(reloads)));
+        }
+        if let Some(logger) = self.base_modules().logger.clone() {
+            self.metrics.gauge_fn(
+                "log_events_dropped",
+                "log events dropped because an appender buffer was full",
+                move || logger.dropped_events().total() as f64,
+            );
+        }
+        let mut base_modules = self.base_modules_mut();
+        let _ = base_modules.metrics.insert(Ref::new(self.metrics.clone()));
+        let _ = base_modules
+            .disposer
+            .insert(Ref::new(self.disposer.clone()));
+        let _ = base_modules.clock.insert(self.clock.clone());
+        let _ = base_modules.events.insert(self.events.clone());
+        let _ = base_modules.health.insert(self.health.clone());
+        let _ = base_modules
+            .app_info
+            .insert(Ref::new(self.app_info.clone()));
+        let _ = base_modules
+            .shutdown_token
+            .insert(self.shutdown_token.clone());
+        base_modules.strict_module_config = self.strict_module_config;
+    }
+
+    /// register the base services and the services of the modules in dependency order,
+    /// verify the service graph and build the provider.
+    ///
+    /// Missing dependencies and cycles are reported with their whole chain, see
+    /// [`ServiceGraph`].
+    ///
+    /// The collection lives on the calling thread only: its factories need not be `Send`, only
+    /// the provider built from it is kept.
+    fn initialize_services(&self) -> Result<(), BootstrapError> {
+        let service_collection = RwLock::new(ServiceCollection::new());
+        self.base_modules().configure(&service_collection);
+        let start = Instant::now();
+        for (_, module) in self.module_order()? {
+            let name = module.name();
+            tracing::info_span!("module", name = %name, step = "configure")
+                .in_scope(|| module.configure(&service_collection));
+        }
+        self.record_init_duration("modules", start);
+        let service_collection = service_collection
+            .into_inner()
+            .unwrap_or_else(|e| e.into_inner());
+        ServiceGraph::new(&service_collection).validate()?;
+        let provider = service_collection
+            .build_provider()
+            .map_err(|e| BootstrapError::ServiceGraphError(e.to_string()))?;
+        let _ = self
+            .service_provider
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(provider);
+        Ok(())
+    }
+
+    fn initialize_runtime(&self) -> Result<(), BootstrapError> {
+        let config = self.base_modules().config.clone();
+        if let Some(config) = config {
+            let runtime_config = RuntimeConfig::new(&config)?;
+            let runtime_env = RuntimeEnv::detect_with_fs(&*self.fs);
+            tracing::info!(
+                "runtime environment: cgroup {:?}, cpu quota {:?}, memory limit {:?}, parallelism {}",
+                runtime_env.cgroup_version(),
+                runtime_env.cpu_quota(),
+                runtime_env.memory_limit(),
+                runtime_env.parallelism()
+            );
+            let runtime = ManagedRuntime::with_env(&runtime_config, &runtime_env)
+                .map_err(|e| BootstrapError::RuntimeInitError(Box::new(e)))?;
+            let mut base_modules = self.base_modules_mut();
+            let _ = base_modules.runtime.insert(Ref::new(runtime));
+            let _ = base_modules.runtime_env.insert(Ref::new(runtime_env));
+            let _ = base_modules.runtime_config.insert(Ref::new(runtime_config));
+        }
+        Ok(())
+    }
+
+    /// the modules with their index of registration, each after its dependencies, otherwise in
+    /// order of registration.
+    fn module_order(&self) -> Result<Vec<(usize, &dyn Module)>, BootstrapError> {
+        let names: Vec<String> = self.modules.iter().map(|x| x.name()).collect();
+        let dependencies: Vec<Vec<String>> =
+            self.modules.iter().map(|x| x.dependencies()).collect();
+        for (name, dependencies) in names.iter().zip(&dependencies) {
+            if let Some(unknown) = dependencies.iter().find(|x| !names.contains(x)) {
+                return Err(BootstrapError::ModuleInitError(format!(
+                    "module {} depends on unknown module {}",
+                    name, unknown
+                )));
+            }
+        }
+        let mut ordered: Vec<usize> = Vec::with_capacity(names.len());
+        while ordered.len() < names.len() {
+            let ready = (0..names.len()).find(|index| {
+                !ordered.contains(index)
+                    && dependencies[*index]
+                        .iter()
+                        .all(|x| ordered.iter().any(|y| names[*y] == *x))
+            });
+            match ready {
+                Some(index) => ordered.push(index),
+                None => {
+                    // every module left is in a cycle or depends on one, follow it from the first
+                    let mut chain = vec![(0..names.len()).find(|x| !ordered.contains(x))];
+                    while let Some(Some(index)) = chain.last() {
+                        let next = dependencies[*index].iter().find_map(|x| {
+                            (0..names.len()).find(|y| names[*y] == *x && !ordered.contains(y))
+                        });
+                        let repeated = chain.contains(&next);
+                        chain.push(next);
+                        if repeated {
+                            break;
+                        }
+                    }
+                    let chain: Vec<&str> = chain
+                        .into_iter()
+                        .flatten()
+                        .map(|x| names[x].as_str())
+                        .collect();
+                    return Err(BootstrapError::ModuleInitError(format!(
+                        "module dependency cycle: {}",
+                        chain.join(" -> ")
+                    )));
+                }
+            }
+        }
+        Ok(ordered
+            .into_iter()
+            .map(|x| (x, self.modules[x].as_ref()))
+            .collect())
+    }
+
+    /// run the asynchronous initialization of every module on the managed runtime, started in
+    /// the order of [`Bootstrap::module_order`].
+    fn initialize_modules(&self) -> Result<(), BootstrapError> {
+        let (runtime, runtime_config) = {
+            let base_modules = self.base_modules();
+            (
+                base_modules.runtime.clone(),
+                base_modules.runtime_config.clone(),
+            )
+        };
+        let (Some(runtime), Some(runtime_config), Some(provider)) =
+            (runtime, runtime_config, self.service_provider())
+        else {
+            return Ok(());
+        };
+        let initializers: Vec<(String, InitFuture)> = self
+            .module_order()?
             .into_iter()
-            .map(String::from)
-            .collect::<Vec<String>>();
-        let mut logger_target: Vec<&Logger> = Vec::new();
-        for target in targets {
-            // unwrap is safe, validate during logging config init
-            let value = logger_map.get(target.as_str()).unwrap();
-            logger_target.push(value);
+            .filter_map(|(index, module)| {
+                let init = injected_module(index).or_else(|| module.initialize(&provider))?;
+                Some((module.name(), init))
+            })
+            .collect();
+        if initializers.is_empty() {
+            return Ok(());
         }
-        let (non_blocking_file_writer, file_writer_guard) =
-            tracing_appender::non_blocking(file_appender);
-        let target_builder: Targets = Targets::new();
-        let targets = logger_target.into_iter().fold(target_builder, |acc, item| {
-            if item.target().is_empty() {
-                acc.with_default(item.level().as_tracing_level_filter())
-            } else {
-                acc.with_target(item.target(), item.level().as_tracing_level_filter())
+        let failures = runtime.run(run_initializers(
+            initializers,
+            runtime_config.init_concurrency(),
+            runtime_config.init_timeout(),
+        ));
+        if failures.is_empty() {
+            return Ok(());
+        }
+        let failures: Vec<String> = failures
+            .into_iter()
+            .map(|(name, e)| format!("{}: {}", name, e))
+            .collect();
+        Err(BootstrapError::ModuleInitError(failures.join("; ")))
+    }
+
+    /// initialize, then run `app` on the managed runtime and shut down, returning the exit
+    /// code of the first error, see [`BootstrapError::exit_code`].
+    ///
+    /// SIGINT and SIGTERM cancel the shutdown token of the [`BeaverContext`], the application
+    /// is then given `runtime.shutdown_timeout` to return. Errors are logged, or written to
+    /// stderr when logging is not initialized yet, and errors of the application exit with
+    /// `EX_SOFTWARE`.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use std::process::ExitCode;
+    /// use beaver_bootstrap::bootstrap::Bootstrap;
+    /// fn main() -> ExitCode {
+    ///     Bootstrap::builder().build().run(|ctx| async move {
+    ///         tracing::info!("bootstrap initialized");
+    ///         ctx.shutdown_requested().await;
+    ///         Ok(())
+    ///     })
+    /// }
+    /// ```
+    pub fn run<F, Fut>(self, app: F) -> ExitCode
+    where
+        F: FnOnce(BeaverContext) -> Fut,
+        Fut: Future<Output = AppResult> + Send + 'static,
+    {
+        if let Err(e) = self.initialize() {
+            self.shutdown();
+            if !tracing::dispatcher::has_been_set() {
+                // the events before the failure were not written by any appender
+                self.startup_log().iter().for_each(|x| eprintln!("{}", x));
+            }
+            report_error(&e);
+            return ExitCode::from(e.exit_code());
+        }
+        let result = self.run_app(app);
+        self.shutdown();
+        match result {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => {
+                report_error(e.as_ref());
+                ExitCode::from(EX_SOFTWARE)
             }
+        }
+    }
+
+    /// run `app` until it returns, or until the shutdown timeout after a shutdown signal.
+    fn run_app<F, Fut>(&self, app: F) -> AppResult
+    where
+        F: FnOnce(BeaverContext) -> Fut,
+        Fut: Future<Output = AppResult> + Send + 'static,
+    {
+        let (runtime, runtime_config) = {
+            let base_modules = self.base_modules();
+            (
+                base_modules.runtime.clone(),
+                base_modules.runtime_config.clone(),
+            )
+        };
+        let (Some(runtime), Some(runtime_config), Some(provider)) =
+            (runtime, runtime_config, self.service_provider())
+        else {
+            return Err("the bootstrap has no runtime, config is missing".into());
+        };
+        let context = provider.get_required::<BeaverContext>();
+        self.events.publish(&LifecycleEvent::Started);
+        let app = app(BeaverContext::clone(&context));
+        let timeout = runtime_config.shutdown_timeout();
+        let (shutdown, events) = (self.shutdown_token.clone(), self.events.clone());
+        // reloads are applied on this thread, which owns the bootstrap
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let _volumes = self.watch_config_sources(&sender);
+        let task = runtime.handle().spawn(async move {
+            let signals = tokio::spawn({
+                let shutdown = shutdown.clone();
+                async move {
+                    match signal::shutdown_signal().await {
+                        Ok(name) => tracing::info!("{} received, shutting down", name),
+                        Err(e) => {
+                            tracing::warn!("unable to listen to shutdown signals: {}", e);
+                            return;
+                        }
+                    }
+                    shutdown.cancel();
+                    events.publish(&LifecycleEvent::ShutdownRequested);
+                }
+            });
+            let mut app = Box::pin(app);
+            let result = tokio::select! {
+                result = &mut app => result,
+                _ = shutdown.cancelled() => match tokio::time::timeout(timeout, app).await {
+                    Ok(result) => result,
+                    Err(_) => Err(format!("application did not stop within {:?}", timeout).into()),
+                },
+            };
+            signals.abort();
+            result
         });
-        Ok((non_blocking_file_writer, targets, level, file_writer_guard))
+        runtime.handle().spawn(async move {
+            let result = match task.await {
+                Ok(result) => result,
+                Err(e) => Err(format!("application task failed: {}", e).into()),
+            };
+            let _ = sender.send(RunStep::Finished(result));
+        });
+        let result = loop {
+            match receiver.recv() {
+                Ok(RunStep::Finished(result)) => break result,
+                Ok(RunStep::SourceChanged(name)) => match self.reload_config() {
+                    Ok(diff) => tracing::info!(
+                        "config reloaded after source {} changed, {} changes",
+                        name,
+                        diff.changes().len()
+                    ),
+                    Err(e) => {
+                        tracing::warn!("config reload after source {} changed failed: {}", name, e)
+                    }
+                },
+                Err(_) => break Err("the runtime stopped before the application returned".into()),
+            }
+        };
+        self.events.publish(&LifecycleEvent::Stopping);
+        result
+    }
+
+    /// start the watches of the config sources and of the kubernetes volumes, reporting their
+    /// changes to `sender`. The volumes are watched until the returned watcher is dropped.
+    fn watch_config_sources(
+        &self,
+        sender: &std::sync::mpsc::Sender<RunStep>,
+    ) -> Option<VolumeWatcher> {
+        for config_source in &self.config_sources {
+            let name = config_source.name();
+            let changed = {
+                let (sender, name) = (sender.clone(), name.clone());
+                SourceChanged::new(move || {
+                    let _ = sender.send(RunStep::SourceChanged(name.clone()));
+                })
+            };
+            match config_source.watch(changed) {
+                Ok(true) => tracing::info!("watching config source {}", name),
+                Ok(false) => {}
+                Err(e) => tracing::warn!("unable to watch config source {}: {}", name, e),
+            }
+        }
+        let kubernetes = self.kubernetes.as_ref()?;
+        let sender = sender.clone();
+        let changed = SourceChanged::new(move || {
+            let _ = sender.send(RunStep::SourceChanged("kubernetes".to_string()));
+        });
+        match kubernetes.watch(changed) {
+            Ok(watcher) => watcher,
+            Err(e) => {
+                tracing::warn!("unable to watch the kubernetes volumes: {}", e);
+                None
+            }
+        }
+    }
+
+    /// dispose the [`Disposable`](crate::dispose::Disposable) services in reverse order of
+    /// construction, each within `runtime.dispose_timeout`, on the managed runtime, then flush
+    /// the log appenders within `logging.flush_timeout`.
+    ///
+    /// Failures are logged. Called on drop when not called before, so services are disposed
+    /// before the runtime is torn down.
+    pub fn shutdown(&self) {
+        if self.shut_down.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        let (runtime, runtime_config, logging_manager, logging_config) = {
+            let base_modules = self.base_modules();
+            (
+                base_modules.runtime.clone(),
+                base_modules.runtime_config.clone(),
+                base_modules.logging_manager.clone(),
+                base_modules.logging_config.clone(),
+            )
+        };
+        if let (Some(runtime), Some(runtime_config)) = (runtime, runtime_config) {
+            let disposer = self.disposer.clone();
+            #[cfg(feature = "fault-injection")]
+            for index in crate::fault::shutdown_hooks() {
+                disposer.inject_failure(index);
+            }
+            let timeout = runtime_config.dispose_timeout();
+            runtime.run(async move { disposer.dispose_all(timeout).await });
+        }
+        if let (Some(manager), Some(logging_config)) = (logging_manager, logging_config) {
+            let timeout = logging_config.flush_timeout();
+            if !manager.flush(timeout) {
+                eprintln!("log appenders not drained within {:?}", timeout);
+            }
+        }
+    }
+
+    /// the cipher of the file appenders with `encrypt` set, keyed by the config key provider,
+    /// to decrypt their files with [`logcat`](crate::log::encrypt::logcat).
+    pub fn log_cipher(&self) -> Result<ConfigCipher, BootstrapError> {
+        let env = EnvKeyProvider::default();
+        let secrets_key_provider = self
+            .secrets_key_provider
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_ref()
+            .map(|(_, provider)| provider.clone());
+        let provider: &dyn SecretKeyProvider =
+            match (&self.config_key_provider, &secrets_key_provider) {
+                (Some(provider), _) => provider.as_ref(),
+                (None, Some(provider)) => provider.as_ref(),
+                // the config was not loaded, so no backend selected
+                (None, None) => &env,
+            };
+        provider
+            .key()
+            .map(ConfigCipher::new)
+            .map_err(BootstrapError::LogEncryptionKeyError)
+    }
+
+    /// Manager flushing the log appenders, available once logging is initialized.
+    pub fn logging_manager(&self) -> Option<Ref<LoggingManager>> {
+        self.base_modules().logging_manager.clone()
+    }
+
+    fn base_modules(&self) -> RwLockReadGuard<'_, BootstrapBaseModule> {
+        self.base_modules.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn base_modules_mut(&self) -> RwLockWriteGuard<'_, BootstrapBaseModule> {
+        self.base_modules.write().unwrap_or_else(|e| e.into_inner())
     }
+
+    fn config_warnings(&self) -> MutexGuard<'_, Vec<String>> {
+        self.config_warnings
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Provider of the registered services, available once initialized.
+    pub fn service_provider(&self) -> Option<ServiceProvider> {
+        self.service_provider
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Time elapsed since the bootstrap was created.
+    pub fn uptime(&self) -> Duration {
+        self.clock
+            .instant()
+            .saturating_duration_since(self.started_at)
+    }
+
+    /// Health checks of the process, evaluated every `health.interval` once initialized.
+    pub fn health(&self) -> &HealthRegistry {
+        &self.health
+    }
+
+    /// register the health checks of the builder and start evaluating them with the ones
+    /// registered by modules meanwhile.
+    fn initialize_health(&self) -> Result<(), BootstrapError> {
+        let Some(config) = self.base_modules().config.clone() else {
+            return Ok(());
+        };
+        let health_config = HealthConfig::new(&config)?;
+        for check in &self.health_checks {
+            self.health.register(check.clone());
+        }
+        // the usage of the filesystems is only known on unix
+        if cfg!(unix) {
+            let log_dirs: Vec<PathBuf> = self
+                .base_modules()
+                .logging_config
+                .iter()
+                .flat_map(|x| x.file_appender_config())
+                .filter(|x| x.enable())
+                .map(|x| PathBuf::from(x.file_dir()))
+                .collect();
+            if let Some(check) = health_config.disk().check(log_dirs) {
+                self.health.register(Arc::new(check));
+            }
+        }
+        if let Some(check) = health_config
+            .certs()
+            .check(self.fs.clone(), self.clock.clone())
+        {
+            // an expiring certificate is worth a warning even without the monitor
+            let result = check.check();
+            if result.status() != HealthStatus::Healthy {
+                tracing::warn!(
+                    target: "health",
+                    status = %result.status(),
+                    "certificates are {}: {}",
+                    result.status(),
+                    result.detail().unwrap_or("")
+                );
+            }
+            self.health.register(Arc::new(check));
+        }
+        for (name, dependency) in health_config.dependencies() {
+            let check = DependencyCheck::new(name, dependency.clone(), self.clock.clone())?;
+            self.health.register(Arc::new(check));
+        }
+        if health_config.enable() {
+            let monitor =
+                HealthMonitor::start(&health_config, self.health.clone(), self.clock.clone())?;
+            let _ = self
+                .base_modules_mut()
+                .health_monitor
+                .insert(Ref::new(monitor));
+        }
+        Ok(())
+    }
+
+    /// block until the dependencies of `startup.wait_for` are reachable.
+    fn wait_for_dependencies(&self) -> Result<(), BootstrapError> {
+        let Some(config) = self.base_modules().config.clone() else {
+            return Ok(());
+        };
+        let startup_config = StartupConfig::new(&config)?;
+        let rng = self
+            .base_modules()
+            .rng
+            .clone()
+            .unwrap_or_else(|| Ref::new(ThreadRngProvider));
+        startup_config.wait_for().wait(self.clock.clone(), rng)
+    }
+
+    fn initialize_heartbeat(&self) -> Result<(), BootstrapError> {
+        let Some(config) = self.base_modules().config.clone() else {
+            return Ok(());
+        };
+        let heartbeat_config = HeartbeatConfig::new(&config)?;
+        if !heartbeat_config.enable() {
+            return Ok(());
+        }
+        let emitter = HeartbeatEmitter::start(
+            &heartbeat_config,
+            self.clock.clone(),
+            self.started_at,
+            Arc::new(|| "up".into()),
+        )?;
+        let _ = self.base_modules_mut().heartbeat.insert(Ref::new(emitter));
+        Ok(())
+    }
+
+    /// create the [`SignalBus`], reserving `signal.reserved` and the signal reopening the
+    /// log files.
+    fn initialize_signals(&self) -> Result<(), BootstrapError> {
+        let Some(config) = self.base_modules().config.clone() else {
+            return Ok(());
+        };
+        let mut reserved = SignalConfig::new(&config)?.reserved();
+        if let Some(logging_config) = self.base_modules().logging_config.clone()
+            && let Some(reopen_signal) = logging_config.reopen_signal_config()
+            && reopen_signal.enable()
+            && let Some(signal) = Signal::from_name(reopen_signal.signal())
+        {
+            reserved.insert(signal);
+        }
+        let _ = self
+            .base_modules_mut()
+            .signal_bus
+            .insert(Ref::new(SignalBus::new(reserved)));
+        Ok(())
+    }
+
     pub fn initialize_logging(&self) -> Result<(), BootstrapError> {
         if self.initialize_logging {
             self.initialize_logging_config()?;
             self.initialize_logging_loggers()?;
+            self.initialize_logging_audit()?;
         }
         Ok(())
     }
     pub fn show_config(&self) -> Result<(), BootstrapError> {
-        if let Some(config) = &self.base_modules.borrow().config {
+        if let Some(config) = &self.base_modules().config {
             let properties = config
-                .to_properties()
-                .map_err(|e| BootstrapError::ConfigShowError(e))?;
-            for (key, value) in properties.get_properties() {
-                tracing::info!("load config {}={}", key, value);
+                .to_properties_with(&self.show_config_properties)
+                .map_err(BootstrapError::ConfigShowError)?;
+            for (key, value) in properties.entries() {
+                match properties.origin(key) {
+                    Some(origin) => tracing::info!("load config {}={} from {}", key, value, origin),
+                    None => tracing::info!("load config {}={}", key, value),
+                }
             }
         }
         Ok(())
     }
 }
 
+/// run the bootstrap phase `name` in its span.
+fn phase(
+    name: &'static str,
+    f: impl FnOnce() -> Result<(), BootstrapError>,
+) -> Result<(), BootstrapError> {
+    tracing::info_span!("bootstrap", phase = name).in_scope(|| {
+        #[cfg(feature = "fault-injection")]
+        crate::fault::trip_phase(name)?;
+        f()
+    })
+}
+
+/// the failing initialization of the module at `index` when a fault is injected at it.
+#[cfg(not(feature = "fault-injection"))]
+fn injected_module(_index: usize) -> Option<InitFuture> {
+    None
+}
+
+/// `[logging]` of the example config, its defaults do not make a usable config.
+const LOGGING_EXAMPLE: &str = r#"[logging.all_logger]
+default_level = "info"
+default_name = "root"
+
+[[logging.file_appenders]]
+name = "app"
+logger_names = ["root"]
+enable = true
+write_level = "info"
+file_max_size = 100_000_000
+file_max_count = 3
+file_name = "app.log"
+
+[logging.console_appender]
+logger_names = ["root"]
+enable = true
+write_level = "info"
+"#;
+
+/// log `error`, or write it to stderr when logging is not initialized.
+fn report_error(error: &dyn std::error::Error) {
+    if tracing::dispatcher::has_been_set() {
+        tracing::error!("{}", error);
+    } else {
+        eprintln!("{}", error);
+    }
+}
+
+impl Drop for Bootstrap {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
 /// a module used for di configuration.
 ///
 /// # Description
 ///
 /// A module is a collection of services that can be registered with the service collection.
 ///
+/// [`Bootstrap::initialize`] configures the modules once the base services are registered, a
+/// module after the modules of its [`Module::dependencies`], then builds the service provider
+/// and runs the [`Module::initialize`] futures. This is the only module trait of beaver,
+/// [`config::module`](crate::config::module) holds the config view of modules,
+/// [`ModuleConfig`](crate::config::module::ModuleConfig).
+///
 /// # Example
 /// ```
 /// use di::ServiceCollection;
@@ -307,7 +1777,7 @@ impl Bootstrap {
 ///     }
 /// }
 /// ```
-pub trait Module {
+pub trait Module: Send + Sync {
     /// Configures the module by adding services to the service collection.
     ///
     /// # Arguments
@@ -317,29 +1787,146 @@ pub trait Module {
     /// # Note
     /// binder is RwLock<ServiceCollection>, so it is thread safe.
     fn configure(&self, binder: &RwLock<ServiceCollection>);
+
+    /// Loggers of the module, merged into `logging.all_logger`.
+    ///
+    /// They are routed to the appenders of the default logger; a logger with the same name or
+    /// target in config.toml overrides them.
+    fn loggers(&self) -> Vec<Logger> {
+        vec![]
+    }
+
+    /// Name of the module in diagnostics, its type name by default.
+    fn name(&self) -> String {
+        std::any::type_name::<Self>().to_string()
+    }
+
+    /// Names of the modules configured before this one, e.g. because its services replace
+    /// theirs. Unknown names and cycles fail the startup.
+    fn dependencies(&self) -> Vec<String> {
+        vec![]
+    }
+
+    /// Asynchronous initialization, e.g. a connection pool awaiting its handshake.
+    ///
+    /// The future runs on the managed runtime once the service provider is built, next to
+    /// the initialization of other modules, started after the ones of its dependencies;
+    /// `[runtime]` bounds how many run at a time and how long each may take. A failure aborts
+    /// the startup.
+    fn initialize(&self, _provider: &ServiceProvider) -> Option<InitFuture> {
+        None
+    }
+
+    /// Config sections read by the module, listed in the example config, see
+    /// [`ConfigSection::of`].
+    fn config_sections(&self) -> Vec<ConfigSection> {
+        vec![]
+    }
+
+    /// Name of the config namespace `modules.<name>` of the module, see
+    /// [`BeaverContext::config_for_module`]. The snake case name of its type by default, e.g.
+    /// `cache_module` for `my_app::CacheModule`.
+    fn config_namespace(&self) -> String {
+        let name = self.name();
+        let name = name.rsplit("::").next().unwrap_or_default();
+        let mut namespace = String::with_capacity(name.len() + 4);
+        let chars: Vec<char> = name.chars().collect();
+        for (i, c) in chars.iter().enumerate() {
+            // acronyms stay one word, e.g. `http_module` for `HTTPModule`
+            let word_start = i > 0
+                && c.is_uppercase()
+                && (!chars[i - 1].is_uppercase()
+                    || chars.get(i + 1).is_some_and(|x| x.is_lowercase()));
+            if word_start {
+                namespace.push('_');
+            }
+            namespace.extend(c.to_lowercase());
+        }
+        namespace
+    }
 }
+#[derive(Default)]
 struct BootstrapBaseModule {
     config: Option<Ref<Config>>,
+    live_config: Option<Ref<LiveConfig>>,
     logger: Option<Ref<AppenderGuard>>,
+    logging_manager: Option<Ref<LoggingManager>>,
     logging_config: Option<Ref<LoggingConfig>>,
-}
-
-impl Default for BootstrapBaseModule {
-    fn default() -> Self {
-        Self {
-            config: None,
-            logger: None,
-            logging_config: None,
-        }
-    }
+    audit_logger: Option<Ref<AuditLogger>>,
+    id_generator: Option<Ref<IdGenerator>>,
+    error_monitor: Option<Ref<ErrorMonitor>>,
+    schema: Option<Ref<SchemaValidator>>,
+    admin_routes: Option<Ref<AdminRoutes>>,
+    admin_server: Option<Ref<AdminServer>>,
+    heartbeat: Option<Ref<HeartbeatEmitter>>,
+    metrics: Option<Ref<MetricsRegistry>>,
+    clock: Option<Ref<dyn Clock>>,
+    rng: Option<Ref<dyn RngProvider>>,
+    pid_file: Option<Ref<PidFile>>,
+    runtime: Option<Ref<ManagedRuntime>>,
+    runtime_config: Option<Ref<RuntimeConfig>>,
+    disposer: Option<Ref<Disposer>>,
+    signal_bus: Option<Ref<SignalBus>>,
+    runtime_env: Option<Ref<RuntimeEnv>>,
+    events: Option<Ref<EventBus>>,
+    health: Option<Ref<HealthRegistry>>,
+    health_monitor: Option<Ref<HealthMonitor>>,
+    app_info: Option<Ref<AppInfo>>,
+    shutdown_token: Option<CancellationToken>,
+    strict_module_config: bool,
+    #[cfg(feature = "sentry")]
+    crash_reporter: Option<Ref<CrashReporter>>,
 }
 
 impl Module for BootstrapBaseModule {
     fn configure(&self, binder: &RwLock<ServiceCollection>) {
         // register base services
         self.register_service::<Config>(&self.config, binder);
+        self.register_service::<LiveConfig>(&self.live_config, binder);
         self.register_service::<LoggingConfig>(&self.logging_config, binder);
         self.register_service::<AppenderGuard>(&self.logger, binder);
+        self.register_service::<LoggingManager>(&self.logging_manager, binder);
+        self.register_service::<AuditLogger>(&self.audit_logger, binder);
+        self.register_service::<IdGenerator>(&self.id_generator, binder);
+        self.register_service::<ErrorMonitor>(&self.error_monitor, binder);
+        self.register_service::<SchemaValidator>(&self.schema, binder);
+        self.register_service::<AdminRoutes>(&self.admin_routes, binder);
+        self.register_service::<AdminServer>(&self.admin_server, binder);
+        self.register_service::<HeartbeatEmitter>(&self.heartbeat, binder);
+        self.register_service::<MetricsRegistry>(&self.metrics, binder);
+        self.register_service::<dyn Clock>(&self.clock, binder);
+        self.register_service::<dyn RngProvider>(&self.rng, binder);
+        self.register_service::<PidFile>(&self.pid_file, binder);
+        self.register_service::<ManagedRuntime>(&self.runtime, binder);
+        self.register_service::<RuntimeConfig>(&self.runtime_config, binder);
+        self.register_service::<Disposer>(&self.disposer, binder);
+        self.register_service::<SignalBus>(&self.signal_bus, binder);
+        self.register_service::<RuntimeEnv>(&self.runtime_env, binder);
+        self.register_service::<EventBus>(&self.events, binder);
+        self.register_service::<HealthRegistry>(&self.health, binder);
+        self.register_service::<AppInfo>(&self.app_info, binder);
+        if let Some(shutdown) = self.shutdown_token.clone()
+            && let Ok(mut service_collection) = binder.write()
+        {
+            let strict_module_config = self.strict_module_config;
+            // transient, a singleton holding the provider would keep it alive forever
+            service_collection.add(transient_factory::<BeaverContext, _>(move |provider| {
+                Ref::new(
+                    BeaverContext::new(
+                        provider.clone(),
+                        provider.get_required::<LiveConfig>(),
+                        shutdown.clone(),
+                        provider.get_required::<EventBus>(),
+                        provider.get_required::<MetricsRegistry>(),
+                        provider.get_required::<AppInfo>(),
+                    )
+                    .with_strict_module_config(strict_module_config),
+                )
+            }));
+        }
+        RequestScope::register(binder);
+        #[cfg(feature = "sentry")]
+        self.register_service::<CrashReporter>(&self.crash_reporter, binder);
     }
 }
 
@@ -350,15 +1937,15 @@ impl BootstrapBaseModule {
     ///
     /// * `service` - The service to register.
     /// * `binder` - The service collection to configure.
-    fn register_service<T: Send + Sync + 'static>(
+    fn register_service<T: ?Sized + Send + Sync + 'static>(
         &self,
         service: &Option<Ref<T>>,
         binder: &RwLock<ServiceCollection>,
     ) {
-        if let Some(svc) = service.clone() {
-            if let Ok(mut service_collection) = binder.write() {
-                service_collection.add(singleton_as_self::<T>().from(move |_| svc.clone()));
-            }
+        if let Some(svc) = service.clone()
+            && let Ok(mut service_collection) = binder.write()
+        {
+            service_collection.add(singleton_factory::<T, _>(move |_| svc.clone()));
         }
     }
 }
@@ -0,0 +1,256 @@
+use std::{
+    ffi::OsString, fs::File, future::Future, io::BufReader, path::PathBuf, process::ExitCode,
+};
+
+use clap::{Arg, ArgMatches, Command, value_parser};
+use clap_complete::Shell;
+
+use crate::{
+    bootstrap::Bootstrap,
+    config::export::ConfigExportFormat,
+    context::{AppResult, BeaverContext},
+    error::BootstrapError,
+    log::encrypt::logcat,
+};
+
+/// App is the command line of a beaver service, so every service offers the same
+/// operational subcommands:
+///
+/// - `run` runs the application, the default without subcommand,
+/// - `check-config` loads and validates the config,
+/// - `print-config [--format toml|json|properties]` prints the effective config, redacted,
+/// - `print-default-config` prints a commented example config,
+/// - `version` prints the application and beaver versions,
+/// - `modules` lists the registered modules,
+/// - `env-vars` lists the environment variables overriding config keys,
+/// - `logcat <file>...` prints log files written with `encrypt = true`, decrypted with the
+///   config key.
+///
+/// The hidden `generate completions <bash|zsh|fish|...>` and `generate man` subcommands write
+/// shell completions and a man page to stdout, for packaging.
+///
+/// # Example
+/// ```no_run
+/// use std::process::ExitCode;
+/// use beaver_bootstrap::{bootstrap::Bootstrap, cli::App, context::AppInfo};
+/// fn main() -> ExitCode {
+///     let bootstrap = Bootstrap::builder()
+///         .app_info(AppInfo::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")))
+///         .build();
+///     App::new(bootstrap).run(|ctx| async move {
+///         ctx.shutdown_requested().await;
+///         Ok(())
+///     })
+/// }
+/// ```
+pub struct App {
+    bootstrap: Bootstrap,
+}
+
+impl std::fmt::Debug for App {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("App")
+            .field("app_info", self.bootstrap.app_info())
+            .finish_non_exhaustive()
+    }
+}
+
+impl App {
+    pub fn new(bootstrap: Bootstrap) -> Self {
+        Self { bootstrap }
+    }
+
+    /// the command line definition, named after the [`AppInfo`](crate::context::AppInfo).
+    ///
+    /// The config sections are listed after the long help, and so in the man page.
+    pub fn command(&self) -> Command {
+        let app_info = self.bootstrap.app_info();
+        let sections: String = self
+            .bootstrap
+            .config_sections()
+            .unwrap_or_default()
+            .iter()
+            .map(|x| format!("  [{}]\n      {}\n", x.prefix(), x.description()))
+            .collect();
+        Command::new(app_info.name().to_string())
+            .version(app_info.version().to_string())
+            .after_long_help(format!("Config sections of config.toml:\n{}", sections))
+            .subcommand(Command::new("run").about("Run the application (default)"))
+            .subcommand(Command::new("check-config").about("Load and validate the config"))
+            .subcommand(
+                Command::new("print-config")
+                    .about("Print the effective config, sensitive values redacted")
+                    .arg(
+                        Arg::new("format")
+                            .long("format")
+                            .value_parser(["toml", "json", "properties"])
+                            .default_value("toml"),
+                    ),
+            )
+            .subcommand(
+                Command::new("print-default-config")
+                    .about("Print a commented example config with the default values"),
+            )
+            .subcommand(Command::new("version").about("Print the application and beaver versions"))
+            .subcommand(Command::new("modules").about("List the registered modules"))
+            .subcommand(
+                Command::new("env-vars")
+                    .about("List the environment variables overriding config keys"),
+            )
+            .subcommand(
+                Command::new("logcat")
+                    .about("Print encrypted log files, decrypted with the config key")
+                    .arg(
+                        Arg::new("file")
+                            .required(true)
+                            .num_args(1..)
+                            .value_parser(value_parser!(PathBuf)),
+                    ),
+            )
+            .subcommand(
+                Command::new("generate")
+                    .about("Generate shell completions or a man page")
+                    .hide(true)
+                    .subcommand_required(true)
+                    .subcommand(
+                        Command::new("completions").arg(
+                            Arg::new("shell")
+                                .required(true)
+                                .value_parser(value_parser!(Shell)),
+                        ),
+                    )
+                    .subcommand(Command::new("man")),
+            )
+    }
+
+    /// run the subcommand of the process arguments, see [`App::run_from`].
+    pub fn run<F, Fut>(self, app: F) -> ExitCode
+    where
+        F: FnOnce(BeaverContext) -> Fut,
+        Fut: Future<Output = AppResult> + Send + 'static,
+    {
+        self.run_from(std::env::args_os(), app)
+    }
+
+    /// run the subcommand of `args`, the first being the program name. `run` hands `app` to
+    /// [`Bootstrap::run`], other subcommands exit without initializing the bootstrap.
+    pub fn run_from<I, T, F, Fut>(self, args: I, app: F) -> ExitCode
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString> + Clone,
+        F: FnOnce(BeaverContext) -> Fut,
+        Fut: Future<Output = AppResult> + Send + 'static,
+    {
+        let matches = match self.command().try_get_matches_from(args) {
+            Ok(matches) => matches,
+            Err(e) => {
+                let _ = e.print();
+                return ExitCode::from(e.exit_code().clamp(0, u8::MAX as i32) as u8);
+            }
+        };
+        let result = match matches.subcommand() {
+            None | Some(("run", _)) => return self.bootstrap.run(app),
+            Some(("check-config", _)) => self.check_config(),
+            Some(("print-config", args)) => self.print_config(args),
+            Some(("print-default-config", _)) => {
+                self.bootstrap.default_config().map(|x| print!("{}", x))
+            }
+            Some(("version", _)) => {
+                self.print_version();
+                Ok(())
+            }
+            Some(("generate", args)) => {
+                self.generate(args);
+                Ok(())
+            }
+            Some(("env-vars", _)) => self.print_env_vars(),
+            Some(("logcat", args)) => self.logcat(args),
+            Some(("modules", _)) => {
+                self.bootstrap
+                    .module_names()
+                    .iter()
+                    .for_each(|x| println!("{}", x));
+                Ok(())
+            }
+            Some((name, _)) => unreachable!("unknown subcommand {}", name),
+        };
+        match result {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => {
+                eprintln!("{}", e);
+                ExitCode::from(e.exit_code())
+            }
+        }
+    }
+
+    fn check_config(&self) -> Result<(), BootstrapError> {
+        for warning in self.bootstrap.check_config()? {
+            eprintln!("warning: {}", warning);
+        }
+        println!("config is valid");
+        Ok(())
+    }
+
+    fn print_config(&self, args: &ArgMatches) -> Result<(), BootstrapError> {
+        let format = args
+            .get_one::<String>("format")
+            .map_or(Ok(ConfigExportFormat::Toml), |x| x.parse())?;
+        print!("{}", self.bootstrap.effective_config(format)?);
+        Ok(())
+    }
+
+    /// write completions or the man page to stdout, like clap a closed stdout is ignored.
+    fn generate(&self, args: &ArgMatches) {
+        let mut command = self.command();
+        let mut out = std::io::stdout();
+        match args.subcommand() {
+            Some(("completions", args)) => {
+                if let Some(shell) = args.get_one::<Shell>("shell") {
+                    let name = command.get_name().to_string();
+                    clap_complete::generate(*shell, &mut command, name, &mut out);
+                }
+            }
+            _ => {
+                let _ = clap_mangen::Man::new(command).render(&mut out);
+            }
+        }
+    }
+
+    /// one variable per line with its key, `-` for keys which cannot be set from the
+    /// environment.
+    fn print_env_vars(&self) -> Result<(), BootstrapError> {
+        for var in self.bootstrap.env_vars()? {
+            let required = if var.is_required() { " (required)" } else { "" };
+            println!(
+                "{:<48} {}{}",
+                var.name().unwrap_or("-"),
+                var.key(),
+                required
+            );
+        }
+        Ok(())
+    }
+
+    /// decrypt the files to stdout in order, stopping at the first line which cannot be.
+    fn logcat(&self, args: &ArgMatches) -> Result<(), BootstrapError> {
+        let cipher = self.bootstrap.log_cipher()?;
+        let mut out = std::io::stdout().lock();
+        for path in args.get_many::<PathBuf>("file").into_iter().flatten() {
+            let error = |reason: String| {
+                BootstrapError::LogDecryptionError(format!("{}: {}", path.display(), reason))
+            };
+            let file = File::open(path).map_err(|e| error(e.to_string()))?;
+            logcat(BufReader::new(file), &cipher, &mut out).map_err(|e| match e {
+                BootstrapError::LogDecryptionError(reason) => error(reason),
+                e => e,
+            })?;
+        }
+        Ok(())
+    }
+
+    fn print_version(&self) {
+        let app_info = self.bootstrap.app_info();
+        println!("{} {}", app_info.name(), app_info.version());
+        println!("beaver-bootstrap {}", env!("CARGO_PKG_VERSION"));
+    }
+}
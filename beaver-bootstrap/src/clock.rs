@@ -0,0 +1,137 @@
+use std::{
+    sync::{
+        Arc, Condvar, Mutex,
+        mpsc::{Receiver, RecvTimeoutError, TryRecvError},
+    },
+    time::{Duration, Instant, SystemTime},
+};
+
+/// real time between two checks of the stop signal while waiting on a [`ManualClock`].
+const MANUAL_CLOCK_POLL: Duration = Duration::from_millis(10);
+
+/// Clock is the source of time of beaver's background tasks.
+///
+/// [`SystemClock`] is used in production, [`ManualClock`] makes time dependent behavior
+/// deterministic in tests.
+pub trait Clock: Send + Sync {
+    /// wall clock time.
+    fn now(&self) -> SystemTime;
+    /// monotonic time.
+    fn instant(&self) -> Instant;
+    /// block the caller for `duration`.
+    fn sleep(&self, duration: Duration);
+    /// block the caller until `stop` receives or disconnects, or `timeout` elapses.
+    ///
+    /// This is the timer of periodic tasks, which stop through a channel.
+    fn wait(&self, stop: &Receiver<()>, timeout: Duration) -> Result<(), RecvTimeoutError>;
+}
+
+/// SystemClock reads the operating system clocks.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+
+    fn instant(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        std::thread::sleep(duration)
+    }
+
+    fn wait(&self, stop: &Receiver<()>, timeout: Duration) -> Result<(), RecvTimeoutError> {
+        stop.recv_timeout(timeout)
+    }
+}
+
+/// ManualClock only moves when [`ManualClock::advance`] is called.
+///
+/// Sleeping and waiting threads wake up once the clock is advanced past their deadline.
+///
+/// # Example
+/// ```
+/// use std::time::Duration;
+/// use beaver_bootstrap::clock::{Clock, ManualClock};
+/// let clock = ManualClock::default();
+/// let start = clock.instant();
+/// clock.advance(Duration::from_secs(90));
+/// assert_eq!(clock.instant() - start, Duration::from_secs(90));
+/// ```
+#[derive(Debug, Clone)]
+pub struct ManualClock {
+    base_instant: Instant,
+    base_time: SystemTime,
+    elapsed: Arc<(Mutex<Duration>, Condvar)>,
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new(SystemTime::now())
+    }
+}
+
+impl ManualClock {
+    /// a clock reading `now` as wall clock time.
+    pub fn new(now: SystemTime) -> Self {
+        Self {
+            base_instant: Instant::now(),
+            base_time: now,
+            elapsed: Arc::new((Mutex::new(Duration::ZERO), Condvar::new())),
+        }
+    }
+
+    /// move the clock forward, waking up the threads whose deadline passed.
+    pub fn advance(&self, duration: Duration) {
+        let (elapsed, cond) = &*self.elapsed;
+        let mut elapsed = elapsed.lock().unwrap_or_else(|e| e.into_inner());
+        *elapsed += duration;
+        cond.notify_all();
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        *self.elapsed.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> SystemTime {
+        self.base_time + self.elapsed()
+    }
+
+    fn instant(&self) -> Instant {
+        self.base_instant + self.elapsed()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        let (elapsed, cond) = &*self.elapsed;
+        let mut current = elapsed.lock().unwrap_or_else(|e| e.into_inner());
+        let deadline = *current + duration;
+        while *current < deadline {
+            current = cond.wait(current).unwrap_or_else(|e| e.into_inner());
+        }
+    }
+
+    fn wait(&self, stop: &Receiver<()>, timeout: Duration) -> Result<(), RecvTimeoutError> {
+        let (elapsed, cond) = &*self.elapsed;
+        let mut current = elapsed.lock().unwrap_or_else(|e| e.into_inner());
+        let deadline = *current + timeout;
+        loop {
+            match stop.try_recv() {
+                Ok(()) => return Ok(()),
+                Err(TryRecvError::Disconnected) => return Err(RecvTimeoutError::Disconnected),
+                Err(TryRecvError::Empty) => {}
+            }
+            if *current >= deadline {
+                return Err(RecvTimeoutError::Timeout);
+            }
+            current = cond
+                .wait_timeout(current, MANUAL_CLOCK_POLL)
+                .map(|(guard, _)| guard)
+                .unwrap_or_else(|e| e.into_inner().0);
+        }
+    }
+}
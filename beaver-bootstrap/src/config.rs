@@ -1,63 +1,179 @@
+use std::collections::BTreeMap;
+#[cfg(feature = "config")]
 use std::{
-    collections::HashMap,
+    any::{Any, TypeId},
+    collections::{BTreeSet, HashMap},
     env,
+    fmt::Write,
     path::{Path, PathBuf},
-    sync::LazyLock,
+    sync::{Arc, LazyLock, RwLock},
 };
 
-use config::{ConfigError, File, ValueKind};
-use di::injectable;
-use serde::Deserialize;
+#[cfg(feature = "config")]
+use config::{ConfigError, File, FileFormat, Map, Source, Value, ValueKind};
+#[cfg(feature = "config")]
+use serde::{Deserialize, de::DeserializeOwned};
 
+#[cfg(feature = "config")]
+use crate::{
+    config::{
+        alias::KeyAliases,
+        environment::{EnvNaming, EnvironmentSource},
+        migration::ConfigMigration,
+        secret::SecretKeyProvider,
+    },
+    fs::{Fs, OsFs},
+};
+
+#[cfg(feature = "config")]
+pub mod alias;
+#[cfg(feature = "config")]
+pub mod coerce;
+#[cfg(feature = "config")]
+pub mod environment;
+#[cfg(feature = "config")]
+pub mod export;
+#[cfg(feature = "proptest")]
+pub mod fuzz;
+#[cfg(feature = "config")]
+pub mod history;
+#[cfg(feature = "config")]
+pub mod k8s;
+#[cfg(feature = "config")]
+pub mod migration;
+#[cfg(feature = "config")]
+pub mod module;
+#[cfg(feature = "config")]
+pub mod reload;
+#[cfg(feature = "config")]
+pub mod secret;
+#[cfg(feature = "config")]
+pub mod source;
+#[cfg(feature = "config")]
+pub mod template;
+
+/// environment variable naming the folder of `config.toml`, see [`default_config_folder`].
+#[cfg(feature = "config")]
+pub const CONFIG_FOLDER_ENV: &str = "BEAVER_CONFIG";
+
+#[cfg(feature = "config")]
 static DEFAULT_CONFIG_FOLDER: LazyLock<PathBuf> = LazyLock::new(|| {
-    match env::var("CARGO_MANIFEST_DIR") {
-        Ok(dir) => PathBuf::from(dir).join("etc"),
-        Err(_) => {
-            match env::var("BEAVER_CONFIG") {
-                Ok(dir) => PathBuf::from(dir), // get config path from env
-                Err(_) => {
-                    // get config path from current executable file path
-                    let mut current_exe =
-                        env::current_exe().expect("failed to get current executable file path");
-                    current_exe.pop();
-                    current_exe.push("etc");
-                    current_exe
-                }
-            }
+    // an explicit folder wins, also over the crate folder of `cargo run`
+    if let Some(dir) = env::var_os(CONFIG_FOLDER_ENV).filter(|x| !x.is_empty()) {
+        return PathBuf::from(dir);
+    }
+    if let Some(dir) = env::var_os("CARGO_MANIFEST_DIR") {
+        return PathBuf::from(dir).join("etc");
+    }
+    // get config path from current executable file path
+    match env::current_exe() {
+        Ok(mut current_exe) => {
+            current_exe.pop();
+            current_exe.push("etc");
+            current_exe
         }
+        Err(_) => PathBuf::from("etc"),
     }
 });
 
+/// ConfigFilePolicy is whether `config.toml` must exist, see
+/// [`Config::from_folder_with_policy`].
+///
+/// Servers keep it [`Required`](ConfigFilePolicy::Required) to catch a deploy missing its
+/// config, services configured by environment variables only make it optional.
+#[cfg(feature = "config")]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum ConfigFilePolicy {
+    /// a missing config file fails the startup.
+    #[default]
+    Required,
+    /// a missing config file is an empty one.
+    Optional,
+    /// a missing config file is an empty one and a config warning names it.
+    OptionalWithWarning,
+}
+
+/// the folder of `config.toml` unless the [`Bootstrap`](crate::bootstrap::Bootstrap) is given
+/// one, the first of:
+///
+/// 1. the folder named by the `BEAVER_CONFIG` environment variable,
+/// 2. the `etc` folder of the crate when run by cargo, from `CARGO_MANIFEST_DIR`,
+/// 3. the `etc` folder next to the executable.
+///
+/// It is resolved once per process.
+#[cfg(feature = "config")]
+pub fn default_config_folder() -> &'static Path {
+    DEFAULT_CONFIG_FOLDER.as_path()
+}
+
 /// Config is the configuration of the application.
 ///
-/// It is loaded from the `config.toml` file in the `etc` folder of the application.
+/// It is loaded from the `config.toml` file in the `etc` folder of the application, see
+/// [`default_config_folder`].
 ///
 /// # Example
-/// ```
-/// use beaver_bootstrap::config::Config;
+/// ```no_run
+/// use beaver_bootstrap::config::{Config, ConfigPrefix};
+/// use serde::Deserialize;
+/// #[derive(Deserialize)]
+/// struct PortConfig {
+///     port: u16,
+/// }
+/// impl ConfigPrefix for PortConfig {
+///     const PREFIX: &'static str = "server";
+/// }
 /// let config = Config::load(None, "_").unwrap();
 /// let port = config.get::<PortConfig>().unwrap().port;
 /// ```
 ///
+#[cfg(feature = "config")]
 #[derive(Clone)]
-#[injectable]
+#[cfg_attr(feature = "di", di::injectable)]
 pub struct Config {
     inner: config::Config,
+    /// sections deserialized by [`Config::get_cached`], by type, shared by clones.
+    sections: Arc<RwLock<HashMap<TypeId, Arc<dyn Any + Send + Sync>>>>,
 }
 
+#[cfg(feature = "config")]
 impl Config {
     pub fn new(inner: config::Config) -> Self {
-        Self { inner }
+        Self {
+            inner,
+            sections: Arc::default(),
+        }
     }
 
     pub fn load(
         env_config_prefix: Option<&str>,
         env_config_split: &str,
     ) -> Result<Self, ConfigError> {
-        Self::from_folder(
-            DEFAULT_CONFIG_FOLDER.as_path(),
+        Self::load_with_sources(env_config_prefix, env_config_split, vec![])
+    }
+
+    /// load config from the default folder, layering `sources` between the config file and
+    /// environment variables.
+    pub fn load_with_sources(
+        env_config_prefix: Option<&str>,
+        env_config_split: &str,
+        sources: Vec<Box<dyn Source + Send + Sync>>,
+    ) -> Result<Self, ConfigError> {
+        Self::load_with_fs(&OsFs, env_config_prefix, env_config_split, sources)
+    }
+
+    /// like [`Config::load_with_sources`], reading the config file from `fs`.
+    pub fn load_with_fs(
+        fs: &dyn Fs,
+        env_config_prefix: Option<&str>,
+        env_config_split: &str,
+        sources: Vec<Box<dyn Source + Send + Sync>>,
+    ) -> Result<Self, ConfigError> {
+        Self::from_folder_with_fs(
+            fs,
+            default_config_folder(),
             env_config_prefix,
             env_config_split,
+            sources,
         )
     }
     pub fn from_folder(
@@ -65,28 +181,170 @@ impl Config {
         env_config_prefix: Option<&str>,
         env_config_split: &str,
     ) -> Result<Self, ConfigError> {
+        Self::from_folder_with_sources(path, env_config_prefix, env_config_split, vec![])
+    }
+    pub fn from_folder_with_sources(
+        path: &Path,
+        env_config_prefix: Option<&str>,
+        env_config_split: &str,
+        sources: Vec<Box<dyn Source + Send + Sync>>,
+    ) -> Result<Self, ConfigError> {
+        Self::from_folder_with_fs(&OsFs, path, env_config_prefix, env_config_split, sources)
+    }
+    pub fn from_folder_with_fs(
+        fs: &dyn Fs,
+        path: &Path,
+        env_config_prefix: Option<&str>,
+        env_config_split: &str,
+        sources: Vec<Box<dyn Source + Send + Sync>>,
+    ) -> Result<Self, ConfigError> {
+        let (config, _) = Self::from_folder_with_policy(
+            fs,
+            path,
+            ConfigFilePolicy::Required,
+            env_config_prefix,
+            env_config_split,
+            sources,
+        )?;
+        Ok(config)
+    }
+
+    /// like [`Config::from_folder_with_fs`], a missing `config.toml` handled by `policy`.
+    /// Returns the warnings of the config, naming the missing file for
+    /// [`ConfigFilePolicy::OptionalWithWarning`].
+    ///
+    /// # Example
+    /// ```
+    /// use std::path::Path;
+    /// use beaver_bootstrap::{config::{Config, ConfigFilePolicy}, fs::MemoryFs};
+    /// let fs = MemoryFs::default();
+    /// let folder = Path::new("/srv/app/etc");
+    /// assert!(Config::from_folder_with_policy(
+    ///     &fs, folder, ConfigFilePolicy::Required, None, "_", vec![]
+    /// ).is_err());
+    /// let (_, warnings) = Config::from_folder_with_policy(
+    ///     &fs, folder, ConfigFilePolicy::OptionalWithWarning, None, "_", vec![]
+    /// ).unwrap();
+    /// assert!(warnings[0].contains("/srv/app/etc/config.toml"));
+    /// ```
+    pub fn from_folder_with_policy(
+        fs: &dyn Fs,
+        path: &Path,
+        policy: ConfigFilePolicy,
+        env_config_prefix: Option<&str>,
+        env_config_split: &str,
+        sources: Vec<Box<dyn Source + Send + Sync>>,
+    ) -> Result<(Self, Vec<String>), ConfigError> {
         let cfg = path.join("config.toml");
+        let mut warnings = Vec::new();
+        let content = match fs.read_to_string(&cfg) {
+            Ok(content) => Some(content),
+            Err(e)
+                if e.kind() == std::io::ErrorKind::NotFound
+                    && policy != ConfigFilePolicy::Required =>
+            {
+                if policy == ConfigFilePolicy::OptionalWithWarning {
+                    warnings.push(format!(
+                        "configuration file {} not found, using environment variables and defaults",
+                        cfg.display()
+                    ));
+                }
+                None
+            }
+            Err(e) => {
+                return Err(ConfigError::Foreign(Box::new(std::io::Error::new(
+                    e.kind(),
+                    format!("configuration file {}: {}", cfg.display(), e),
+                ))));
+            }
+        };
         let mut builder = config::Config::builder();
-        // add default config file
-        builder = builder.add_source(File::from(cfg).required(true));
+        // add default config file, its values originating from the file
+        if let Some(content) = content {
+            let mut file = File::from_str(&content, FileFormat::Toml).collect()?;
+            let origin = format!("{}{}", FILE_ORIGIN_PREFIX, cfg.display());
+            for value in file.values_mut() {
+                set_origin(value, &origin);
+            }
+            builder = builder.add_source(MapSource::new(file));
+        }
 
-        // add environment variables to config
-        if let Some(prefix) = env_config_prefix {
-            builder = builder
-                .add_source(config::Environment::with_prefix(prefix).separator(env_config_split));
-        } else {
-            builder =
-                builder.add_source(config::Environment::default().separator(env_config_split));
+        // add extra sources, e.g. mounted kubernetes volumes, each merged into the layers below
+        if !sources.is_empty() {
+            builder = builder.add_source(LayeredSource::new(sources));
         }
+
+        // add environment variables to config
+        builder = builder.add_source(EnvironmentSource::new(EnvNaming::new(
+            env_config_prefix,
+            env_config_split,
+        )));
         let config = builder.build()?;
 
-        Ok(Self { inner: config })
+        Ok((Self::new(config), warnings))
     }
     pub fn get<'de, T>(&self) -> Result<T, ConfigError>
     where
         T: ConfigPrefix + Deserialize<'de>,
     {
-        match self.inner.get::<T>(T::PREFIX) {
+        self.get_at(T::PREFIX)
+    }
+
+    /// the section of `T` like [`Config::get`], deserialized on the first call only, later
+    /// calls share it.
+    ///
+    /// A config is never modified, a reload swaps in a new config with an empty cache, so
+    /// read the section from [`LiveConfig::current`](reload::LiveConfig::current) to follow
+    /// reloads. Errors are not cached.
+    ///
+    /// # Example
+    /// ```
+    /// use std::sync::Arc;
+    /// use beaver_bootstrap::config::{Config, ConfigPrefix};
+    /// #[derive(serde::Deserialize)]
+    /// struct PoolConfig {
+    ///     size: u32,
+    /// }
+    /// impl ConfigPrefix for PoolConfig {
+    ///     const PREFIX: &'static str = "pool";
+    /// }
+    /// let inner = config::Config::builder().set_override("pool.size", 8).unwrap();
+    /// let config = Config::new(inner.build().unwrap());
+    /// let pool = config.get_cached::<PoolConfig>().unwrap();
+    /// assert_eq!(pool.size, 8);
+    /// assert!(Arc::ptr_eq(&pool, &config.get_cached::<PoolConfig>().unwrap()));
+    /// ```
+    pub fn get_cached<T>(&self) -> Result<Arc<T>, ConfigError>
+    where
+        T: ConfigPrefix + DeserializeOwned + Send + Sync + 'static,
+    {
+        let id = TypeId::of::<T>();
+        let cached = self
+            .sections
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&id)
+            .cloned();
+        // sections are keyed by their type, the downcast cannot fail
+        if let Some(section) = cached.and_then(|x| x.downcast::<T>().ok()) {
+            return Ok(section);
+        }
+        let section = Arc::new(self.get::<T>()?);
+        let mut sections = self.sections.write().unwrap_or_else(|e| e.into_inner());
+        // a concurrent call may have cached the section first, share that one
+        let cached = sections
+            .entry(id)
+            .or_insert_with(|| section.clone())
+            .clone();
+        Ok(cached.downcast::<T>().unwrap_or(section))
+    }
+
+    /// the table at `path` deserialized as `T`, an absent table deserializes like an empty one.
+    pub fn get_at<'de, T>(&self, path: &str) -> Result<T, ConfigError>
+    where
+        T: Deserialize<'de>,
+    {
+        match self.inner.get::<T>(path) {
             Ok(o) => Ok(o),
             Err(e) => {
                 let ConfigError::NotFound(_) = &e else {
@@ -102,9 +360,309 @@ impl Config {
             }
         }
     }
-    pub(crate) fn to_properties(&self) -> Result<Properties, ConfigError> {
+
+    /// raw value at `path`, e.g. `logging.console_appender` or `logging.file_appenders[0]`.
+    pub fn get_value(&self, path: &str) -> Option<Value> {
+        if path.is_empty() {
+            return Some(self.inner.cache.clone());
+        }
+        self.inner.get::<Value>(path).ok()
+    }
+
+    pub fn contains(&self, path: &str) -> bool {
+        self.get_value(path).is_some()
+    }
+
+    /// origin of the value at `path`, the source which supplied it last in load order:
+    ///
+    /// 1. `file:<path>`, config.toml,
+    /// 2. `file:<path>`, files of mounted kubernetes volumes, and `env:<variable>` of the
+    ///    downward API,
+    /// 3. `source:<name>`, [`ConfigSource`](source::ConfigSource) plugins by priority, unless
+    ///    they set an origin themselves,
+    /// 4. `env:<variable>`, environment variables,
+    /// 5. `override`, values of [`Config::with_overrides`].
+    ///
+    /// `None` when the value is absent or was built without origin.
+    ///
+    /// # Example
+    /// ```
+    /// use beaver_bootstrap::config::Config;
+    /// let inner = config::Config::builder().build().unwrap();
+    /// let config = Config::new(inner)
+    ///     .with_overrides(&[("admin.port".to_string(), 9000.into())])
+    ///     .unwrap();
+    /// assert_eq!(config.provenance("admin.port").as_deref(), Some("override"));
+    /// assert_eq!(config.provenance("admin.bind"), None);
+    /// ```
+    pub fn provenance(&self, path: &str) -> Option<String> {
+        // values deserialized by `get_value` lose their origin, so the path is walked
+        let mut value = &self.inner.cache;
+        for segment in path.split('.') {
+            let (key, indexes) = segment.split_once('[').unwrap_or((segment, ""));
+            if !key.is_empty() {
+                let ValueKind::Table(table) = &value.kind else {
+                    return None;
+                };
+                value = table.get(key)?;
+            }
+            for index in indexes.split('[').filter(|x| !x.is_empty()) {
+                let index: usize = index.strip_suffix(']')?.parse().ok()?;
+                let ValueKind::Array(arr) = &value.kind else {
+                    return None;
+                };
+                value = arr.get(index)?;
+            }
+        }
+        value.origin().map(str::to_string)
+    }
+
+    /// sorted keys of the table at `prefix`, the top level keys when `prefix` is empty.
+    ///
+    /// Used to iterate sections of named entries such as `[datasources.*]`.
+    ///
+    /// # Example
+    /// ```
+    /// use beaver_bootstrap::config::Config;
+    /// let inner = config::Config::builder()
+    ///     .set_override("datasources.primary.url", "db1")
+    ///     .unwrap()
+    ///     .set_override("datasources.analytics.url", "db2")
+    ///     .unwrap()
+    ///     .build()
+    ///     .unwrap();
+    /// let config = Config::new(inner);
+    /// assert_eq!(config.keys_with_prefix("datasources"), ["analytics", "primary"]);
+    /// assert!(config.contains("datasources.primary.url"));
+    /// assert!(!config.contains("datasources.primary.user"));
+    /// ```
+    pub fn keys_with_prefix(&self, prefix: &str) -> Vec<String> {
+        let mut keys: Vec<String> = self
+            .get_value(prefix)
+            .and_then(|value| value.into_table().ok())
+            .map(|table| table.into_keys().collect())
+            .unwrap_or_default();
+        keys.sort();
+        keys
+    }
+
+    /// named instances of a multi-instance section, e.g. `[databases.primary]` and
+    /// `[databases.analytics]` under the `databases` prefix of `T`.
+    ///
+    /// An absent section has no instance.
+    ///
+    /// # Example
+    /// ```
+    /// use beaver_bootstrap::config::{Config, ConfigPrefix};
+    /// use serde::Deserialize;
+    /// #[derive(Deserialize)]
+    /// struct DbConfig {
+    ///     url: String,
+    /// }
+    /// impl ConfigPrefix for DbConfig {
+    ///     const PREFIX: &'static str = "databases";
+    /// }
+    /// let inner = config::Config::builder()
+    ///     .set_override("databases.analytics.url", "db2")
+    ///     .unwrap()
+    ///     .build()
+    ///     .unwrap();
+    /// let databases = Config::new(inner).get_instances::<DbConfig>().unwrap();
+    /// assert_eq!(databases["analytics"].url, "db2");
+    /// ```
+    pub fn get_instances<'de, T>(&self) -> Result<HashMap<String, T>, ConfigError>
+    where
+        T: ConfigPrefix + Deserialize<'de>,
+    {
+        match self.inner.get::<HashMap<String, T>>(T::PREFIX) {
+            Err(ConfigError::NotFound(_)) => Ok(HashMap::new()),
+            result => result,
+        }
+    }
+
+    /// flatten this config to `key=value` properties.
+    pub fn to_properties(&self) -> Result<Properties, ConfigError> {
         Properties::from_config(self)
     }
+
+    /// like [`Config::to_properties`], with the separator and array handling of
+    /// `properties_config`.
+    pub fn to_properties_with(
+        &self,
+        properties_config: &PropertiesConfig,
+    ) -> Result<Properties, ConfigError> {
+        Properties::from_config_opt(self, properties_config)
+    }
+
+    /// rename the keys of this config with `aliases`, see [`alias::KeyAliases`].
+    ///
+    /// Returns the renamed config and a warning for every key ignored in favor of another.
+    pub fn with_aliases(self, aliases: &KeyAliases) -> Result<(Self, Vec<String>), ConfigError> {
+        alias::apply(self, aliases)
+    }
+
+    /// upgrade this config to `current_version` with the given migrations.
+    ///
+    /// Returns the migrated config and a warning for every migration that was applied.
+    pub fn migrate(
+        self,
+        current_version: u32,
+        migrations: &[ConfigMigration],
+    ) -> Result<(Self, Vec<String>), ConfigError> {
+        migration::migrate(self, current_version, migrations)
+    }
+
+    /// decrypt every `enc:` prefixed value, see [`secret::ConfigCipher`].
+    ///
+    /// The key is only requested from `key_provider` when an encrypted value is present.
+    pub fn decrypt(self, key_provider: &dyn SecretKeyProvider) -> Result<Self, ConfigError> {
+        secret::decrypt(self, key_provider)
+    }
+
+    /// convert the string values of environment variables to the type they spell:
+    ///
+    /// - `true`, `yes`, `on` and `false`, `no`, `off`, in any case, to booleans,
+    /// - integers and floats written canonically to numbers, `1` reads as an integer, which
+    ///   deserializes to `true` as well,
+    /// - `null` to an absent value, so an optional key can be unset.
+    ///
+    /// Values of other sources are kept, as is a string field set to such a value, which
+    /// reads back as the normalized spelling, e.g. `yes` as `true`.
+    ///
+    /// # Example
+    /// ```
+    /// use beaver_bootstrap::config::Config;
+    /// use config::{Value, ValueKind};
+    /// let env = |s: &str| Value::new(Some(&"env:VAR".to_string()), ValueKind::String(s.into()));
+    /// let inner = config::Config::builder()
+    ///     .set_override("admin.enable", env("yes"))
+    ///     .unwrap()
+    ///     .set_override("admin.port", env("8080"))
+    ///     .unwrap()
+    ///     .set_override("node.id", env("007"))
+    ///     .unwrap()
+    ///     .build()
+    ///     .unwrap();
+    /// let config = Config::new(inner).coerce_env_values().unwrap();
+    /// let value = |key: &str| config.get_value(key).unwrap().kind;
+    /// assert_eq!(value("admin.enable"), ValueKind::Boolean(true));
+    /// assert_eq!(value("admin.port"), ValueKind::I64(8080));
+    /// assert_eq!(value("node.id"), ValueKind::String("007".to_string()));
+    /// ```
+    pub fn coerce_env_values(self) -> Result<Self, ConfigError> {
+        coerce::coerce_env(self)
+    }
+
+    /// layer `overrides` above every other source.
+    ///
+    /// Keys are config paths such as `logging.console_appender.enable` or
+    /// `logging.file_appenders[0].level`.
+    pub fn with_overrides(self, overrides: &[(String, Value)]) -> Result<Self, ConfigError> {
+        if overrides.is_empty() {
+            return Ok(self);
+        }
+        let mut builder = config::Config::builder().add_source(self.inner);
+        for (key, value) in overrides {
+            let mut value = value.clone();
+            if value.origin().is_none() {
+                set_origin(&mut value, OVERRIDE_ORIGIN);
+            }
+            builder = builder.set_override(key.as_str(), value)?;
+        }
+        Ok(Self::new(builder.build()?))
+    }
+
+    /// compute the redacted difference from this config to `other`.
+    pub fn diff(&self, other: &Config) -> Result<ConfigDiff, ConfigError> {
+        ConfigDiff::between(self, other, &Redactor::default())
+    }
+}
+
+/// prefix of the origin of values read from files, followed by the path.
+#[cfg(feature = "config")]
+pub const FILE_ORIGIN_PREFIX: &str = "file:";
+/// origin of the values of [`Config::with_overrides`].
+#[cfg(feature = "config")]
+pub const OVERRIDE_ORIGIN: &str = "override";
+
+/// set the origin of `value` and its items to `origin`.
+#[cfg(feature = "config")]
+pub(crate) fn set_origin(value: &mut Value, origin: &str) {
+    let kind = std::mem::replace(&mut value.kind, ValueKind::Nil);
+    let kind = match kind {
+        ValueKind::Array(mut arr) => {
+            arr.iter_mut().for_each(|x| set_origin(x, origin));
+            ValueKind::Array(arr)
+        }
+        ValueKind::Table(mut table) => {
+            table.values_mut().for_each(|x| set_origin(x, origin));
+            ValueKind::Table(table)
+        }
+        kind => kind,
+    };
+    *value = Value::new(Some(&origin.to_string()), kind);
+}
+
+/// MapSource is a config source backed by an already collected value map.
+#[cfg(feature = "config")]
+#[derive(Debug, Clone)]
+pub(crate) struct MapSource {
+    map: Map<String, Value>,
+}
+
+#[cfg(feature = "config")]
+impl MapSource {
+    pub(crate) fn new(map: Map<String, Value>) -> Self {
+        Self { map }
+    }
+}
+
+#[cfg(feature = "config")]
+impl Source for MapSource {
+    fn clone_into_box(&self) -> Box<dyn Source + Send + Sync> {
+        Box::new(self.clone())
+    }
+
+    fn collect(&self) -> Result<Map<String, Value>, ConfigError> {
+        Ok(self.map.clone())
+    }
+}
+
+/// LayeredSource collects its sources in order into the values of the sources before it, so
+/// each merges with `config.toml` by its own rules, see
+/// [`ConfigSource::merge_policy`](source::ConfigSource::merge_policy).
+///
+/// A `Vec` of sources collects them apart from the layers below instead, its arrays replacing
+/// theirs.
+#[cfg(feature = "config")]
+#[derive(Debug, Clone)]
+pub(crate) struct LayeredSource {
+    sources: Vec<Box<dyn Source + Send + Sync>>,
+}
+
+#[cfg(feature = "config")]
+impl LayeredSource {
+    pub(crate) fn new(sources: Vec<Box<dyn Source + Send + Sync>>) -> Self {
+        Self { sources }
+    }
+}
+
+#[cfg(feature = "config")]
+impl Source for LayeredSource {
+    fn clone_into_box(&self) -> Box<dyn Source + Send + Sync> {
+        Box::new(self.clone())
+    }
+
+    fn collect(&self) -> Result<Map<String, Value>, ConfigError> {
+        self.sources.collect()
+    }
+
+    fn collect_to(&self, cache: &mut Value) -> Result<(), ConfigError> {
+        for source in &self.sources {
+            source.collect_to(cache)?;
+        }
+        Ok(())
+    }
 }
 
 /// ConfigPrefix is a trait that is used to identify the prefix of a configuration.
@@ -112,6 +670,7 @@ impl Config {
 /// # Example
 /// ```
 /// use beaver_bootstrap::config::ConfigPrefix;
+/// use serde::Deserialize;
 /// #[derive(Deserialize)]
 /// struct PortConfig {
 ///     port: u16,
@@ -124,23 +683,101 @@ pub trait ConfigPrefix {
     const PREFIX: &'static str;
 }
 
-pub(crate) struct Properties {
-    properties: HashMap<String, String>,
+/// Properties is a config flattened to `key=value` pairs, which other tooling can consume as
+/// JSON, dotenv or Java properties.
+///
+/// Properties are kept sorted by key, so every export of the same config is the same text,
+/// from one run or one host to another.
+///
+/// # Example
+/// ```
+/// use beaver_bootstrap::config::{Config, PropertiesConfig};
+/// let inner = config::Config::builder()
+///     .set_override("db.url", "jdbc:h2:mem")
+///     .unwrap()
+///     .set_override("db.hosts", vec!["a", "b"])
+///     .unwrap();
+/// let config = Config::new(inner.build().unwrap());
+/// let properties = config.to_properties_with(&PropertiesConfig::default()).unwrap();
+/// assert_eq!(
+///     properties.to_java_properties(),
+///     "db.hosts[0]=a\ndb.hosts[1]=b\ndb.url=jdbc\\:h2\\:mem\n"
+/// );
+/// assert_eq!(
+///     properties.to_dotenv(),
+///     "DB_HOSTS_0=\"a\"\nDB_HOSTS_1=\"b\"\nDB_URL=\"jdbc:h2:mem\"\n"
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct Properties {
+    properties: BTreeMap<String, String>,
+    /// origin of the value of every property, when known, see [`Config::provenance`].
+    origins: BTreeMap<String, String>,
+}
+
+/// FloatFormat is how floats are written to [`Properties`].
+///
+/// # Example
+/// ```
+/// use beaver_bootstrap::config::FloatFormat;
+/// assert_eq!(FloatFormat::Full.format(0.001), "0.001");
+/// assert_eq!(FloatFormat::Fixed(2).format(0.001), "0.00");
+/// ```
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum FloatFormat {
+    /// the shortest text reading back to the same float.
+    #[default]
+    Full,
+    /// a fixed number of decimals.
+    Fixed(usize),
 }
 
-pub(crate) struct PropertiesConfig {
+impl FloatFormat {
+    pub fn format(&self, value: f64) -> String {
+        match self {
+            FloatFormat::Full => value.to_string(),
+            FloatFormat::Fixed(decimals) => format!("{:.*}", decimals, value),
+        }
+    }
+}
+
+/// PropertiesConfig is how a config is flattened to [`Properties`].
+#[derive(Debug, Clone)]
+pub struct PropertiesConfig {
     array_split: bool,
     separator: char,
+    float_format: FloatFormat,
 }
 impl Default for PropertiesConfig {
     fn default() -> Self {
         PropertiesConfig {
             array_split: true,
             separator: '.',
+            float_format: FloatFormat::Full,
         }
     }
 }
 
+impl PropertiesConfig {
+    /// whether arrays are flattened to one `key[index]` per item, or joined with commas.
+    pub fn with_array_split(mut self, array_split: bool) -> Self {
+        self.array_split = array_split;
+        self
+    }
+
+    /// separator between the segments of nested keys, `.` by default.
+    pub fn with_separator(mut self, separator: char) -> Self {
+        self.separator = separator;
+        self
+    }
+
+    pub fn with_float_format(mut self, float_format: FloatFormat) -> Self {
+        self.float_format = float_format;
+        self
+    }
+}
+
+#[cfg(feature = "config")]
 impl Properties {
     pub fn from_config(config: &Config) -> Result<Self, ConfigError> {
         Self::from_config_opt(config, &PropertiesConfig::default())
@@ -150,79 +787,427 @@ impl Properties {
         config: &Config,
         properties_config: &PropertiesConfig,
     ) -> Result<Self, ConfigError> {
-        let mut properties = HashMap::new();
-        let config_map: HashMap<String, config::Value> = config.inner.clone().try_deserialize()?;
-        Self::flatten("", &config_map, &mut properties, properties_config);
-        Ok(Self { properties })
+        let mut properties = Self {
+            properties: BTreeMap::new(),
+            origins: BTreeMap::new(),
+        };
+        // the cache is flattened as is, deserialized values lose their origin
+        if let ValueKind::Table(config_map) = &config.inner.cache.kind {
+            properties.flatten(config_map, properties_config);
+        }
+        Ok(properties)
     }
 
-    fn flatten(
-        prefix: &str,
-        map: &HashMap<String, config::Value>,
-        properties: &mut HashMap<String, String>,
-        properties_config: &PropertiesConfig,
-    ) {
-        for (key, value) in map {
-            let full_key = if prefix.is_empty() {
-                key.clone()
-            } else {
-                format!("{}{}{}", prefix, properties_config.separator, key)
+    /// flatten `root` depth first without recursion, the key of the current value is built in
+    /// a single buffer, truncated back to the key of its parent when a table or an array is
+    /// exhausted.
+    fn flatten(&mut self, root: &Map<String, Value>, properties_config: &PropertiesConfig) {
+        let mut key = String::new();
+        // open tables and arrays, with the length of their key
+        let mut stack = vec![(Nested::Table(root.iter()), 0)];
+        while let Some((nested, len)) = stack.last_mut() {
+            let len = *len;
+            key.truncate(len);
+            let value = match nested {
+                Nested::Table(entries) => match entries.next() {
+                    Some((name, value)) => {
+                        if len > 0 {
+                            key.push(properties_config.separator);
+                        }
+                        key.push_str(name);
+                        value
+                    }
+                    None => {
+                        stack.pop();
+                        continue;
+                    }
+                },
+                Nested::Array(items) => match items.next() {
+                    Some((index, value)) => {
+                        // writing to a String cannot fail
+                        let _ = write!(key, "[{}]", index);
+                        value
+                    }
+                    None => {
+                        stack.pop();
+                        continue;
+                    }
+                },
             };
-            Self::handle_value(&full_key, value, properties, properties_config);
+            match &value.kind {
+                ValueKind::Table(table) => stack.push((Nested::Table(table.iter()), key.len())),
+                ValueKind::Array(arr) if properties_config.array_split => {
+                    stack.push((Nested::Array(arr.iter().enumerate()), key.len()))
+                }
+                _ => self.insert_leaf(&key, value, properties_config),
+            }
         }
     }
-    fn handle_value(
-        prefix: &str,
-        value: &config::Value,
-        properties: &mut HashMap<String, String>,
-        properties_config: &PropertiesConfig,
-    ) {
-        match &value.kind {
-            ValueKind::Boolean(b) => {
-                properties.insert(prefix.to_string(), b.to_string());
-            }
-            ValueKind::I64(i_64) => {
-                properties.insert(prefix.to_string(), i_64.to_string());
-            }
-            ValueKind::I128(i_128) => {
-                properties.insert(prefix.to_string(), i_128.to_string());
+
+    fn insert_leaf(&mut self, key: &str, value: &Value, properties_config: &PropertiesConfig) {
+        if let Some(origin) = value.origin() {
+            self.origins.insert(key.to_string(), origin.to_string());
+        }
+        let text = match &value.kind {
+            ValueKind::Boolean(b) => b.to_string(),
+            ValueKind::I64(i_64) => i_64.to_string(),
+            ValueKind::I128(i_128) => i_128.to_string(),
+            ValueKind::U64(u_64) => u_64.to_string(),
+            ValueKind::U128(u_128) => u_128.to_string(),
+            ValueKind::Float(f) => properties_config.float_format.format(*f),
+            ValueKind::String(s) => s.clone(),
+            ValueKind::Array(arr) => arr
+                .iter()
+                .map(|v| v.to_string())
+                .collect::<Vec<String>>()
+                .join(","),
+            ValueKind::Nil => "Null".to_string(),
+            // tables are flattened by the caller
+            ValueKind::Table(_) => return,
+        };
+        self.properties.insert(key.to_string(), text);
+    }
+}
+
+/// a table or an array being flattened to [`Properties`].
+#[cfg(feature = "config")]
+enum Nested<'a> {
+    Table(std::collections::hash_map::Iter<'a, String, Value>),
+    Array(std::iter::Enumerate<std::slice::Iter<'a, Value>>),
+}
+
+impl Properties {
+    pub fn get_properties(&self) -> &BTreeMap<String, String> {
+        &self.properties
+    }
+
+    /// the properties as owned pairs, sorted by key.
+    ///
+    /// # Example
+    /// ```
+    /// use beaver_bootstrap::config::Properties;
+    /// let properties: Properties = [("b", "2"), ("a", "1")]
+    ///     .into_iter()
+    ///     .map(|(k, v)| (k.to_string(), v.to_string()))
+    ///     .collect();
+    /// assert_eq!(
+    ///     properties.get_properties_sorted(),
+    ///     [("a".to_string(), "1".to_string()), ("b".to_string(), "2".to_string())]
+    /// );
+    /// ```
+    pub fn get_properties_sorted(&self) -> Vec<(String, String)> {
+        self.properties
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect()
+    }
+
+    /// origin of the value of property `key`, see [`Config::provenance`].
+    pub fn origin(&self, key: &str) -> Option<&str> {
+        self.origins.get(key).map(String::as_str)
+    }
+
+    /// the properties, sorted by key.
+    pub fn entries(&self) -> Vec<(&String, &String)> {
+        self.properties.iter().collect()
+    }
+
+    /// a flat JSON object of the properties, sorted by key.
+    pub fn to_json(&self) -> String {
+        let map: serde_json::Map<String, serde_json::Value> = self
+            .properties
+            .iter()
+            .map(|(k, v)| (k.clone(), serde_json::Value::from(v.as_str())))
+            .collect();
+        serde_json::Value::Object(map).to_string()
+    }
+
+    /// `KEY="value"` lines, keys upper cased with every run of other characters than letters,
+    /// digits and `_` replaced by one `_`, e.g. `DB_HOSTS_0` for `db.hosts[0]`.
+    pub fn to_dotenv(&self) -> String {
+        let mut dotenv = String::new();
+        for (key, value) in self.entries() {
+            let mut name = String::with_capacity(key.len());
+            for c in key.chars() {
+                if c.is_ascii_alphanumeric() || c == '_' {
+                    name.push(c.to_ascii_uppercase());
+                } else if !name.ends_with('_') {
+                    name.push('_');
+                }
             }
-            ValueKind::U64(u_64) => {
-                properties.insert(prefix.to_string(), u_64.to_string());
+            dotenv.push_str(name.trim_end_matches('_'));
+            dotenv.push_str("=\"");
+            for c in value.chars() {
+                match c {
+                    '\\' | '"' | '$' | '`' => {
+                        dotenv.push('\\');
+                        dotenv.push(c);
+                    }
+                    '\n' => dotenv.push_str("\\n"),
+                    '\r' => dotenv.push_str("\\r"),
+                    _ => dotenv.push(c),
+                }
             }
-            ValueKind::U128(u_128) => {
-                properties.insert(prefix.to_string(), u_128.to_string());
+            dotenv.push_str("\"\n");
+        }
+        dotenv
+    }
+
+    /// `key=value` lines of a Java `.properties` file, escaped so any key and value read back
+    /// unchanged, non ASCII characters as `\uXXXX`.
+    pub fn to_java_properties(&self) -> String {
+        self.java_properties(false)
+    }
+
+    /// like [`Properties::to_java_properties`], every property preceded by a comment naming
+    /// its origin when known, e.g. `# env:BEAVER__ADMIN__BIND`.
+    pub fn to_java_properties_with_origins(&self) -> String {
+        self.java_properties(true)
+    }
+
+    /// the properties with the values of sensitive keys masked.
+    pub fn redacted(mut self, redactor: &Redactor) -> Self {
+        for (key, value) in self.properties.iter_mut() {
+            if redactor.is_sensitive(key) {
+                *value = REDACTED_VALUE.to_string();
             }
-            ValueKind::Float(f) => {
-                properties.insert(prefix.to_string(), format!("{:.2}", f));
+        }
+        self
+    }
+
+    fn java_properties(&self, origins: bool) -> String {
+        let mut properties = String::new();
+        for (key, value) in self.entries() {
+            if origins && let Some(origin) = self.origin(key) {
+                properties.push_str("# ");
+                properties.push_str(origin);
+                properties.push('\n');
             }
-            ValueKind::String(s) => {
-                properties.insert(prefix.to_string(), s.clone());
+            escape_java_properties(&mut properties, key, true);
+            properties.push('=');
+            escape_java_properties(&mut properties, value, false);
+            properties.push('\n');
+        }
+        properties
+    }
+}
+
+/// properties of `key=value` pairs sorted by key, without origins, e.g. collected by a crate which does not
+/// load a Config.
+///
+/// # Example
+/// ```
+/// use beaver_bootstrap::config::Properties;
+/// let properties: Properties = [("db.url".to_string(), "jdbc:h2:mem".to_string())]
+///     .into_iter()
+///     .collect();
+/// assert_eq!(properties.to_java_properties(), "db.url=jdbc\\:h2\\:mem\n");
+/// ```
+impl FromIterator<(String, String)> for Properties {
+    fn from_iter<I: IntoIterator<Item = (String, String)>>(iter: I) -> Self {
+        Self {
+            properties: iter.into_iter().collect(),
+            origins: BTreeMap::new(),
+        }
+    }
+}
+
+/// escape `text` as a key or value of a Java `.properties` file.
+fn escape_java_properties(out: &mut String, text: &str, key: bool) {
+    for (i, c) in text.chars().enumerate() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\x0c' => out.push_str("\\f"),
+            '=' | ':' | '#' | '!' => {
+                out.push('\\');
+                out.push(c);
             }
-            ValueKind::Array(arr) => {
-                if properties_config.array_split {
-                    for (index, item) in arr.iter().enumerate() {
-                        let array_key = format!("{}[{}]", prefix, index);
-                        Self::handle_value(&array_key, item, properties, properties_config);
-                    }
-                } else {
-                    let array_str = arr
-                        .iter()
-                        .map(|v| v.to_string())
-                        .collect::<Vec<String>>()
-                        .join(",");
-                    properties.insert(prefix.to_string(), array_str);
+            // spaces separate the key, and lead of a value is trimmed
+            ' ' if key || i == 0 => out.push_str("\\ "),
+            ' '..='~' => out.push(c),
+            _ => {
+                let mut units = [0; 2];
+                for unit in c.encode_utf16(&mut units) {
+                    out.push_str(&format!("\\u{:04X}", unit));
                 }
             }
-            ValueKind::Table(nested_map) => {
-                Self::flatten(prefix, nested_map, properties, properties_config);
-            }
-            ValueKind::Nil => {
-                properties.insert(prefix.to_string(), "Null".to_string());
-            }
         }
     }
-    pub fn get_properties(&self) -> &HashMap<String, String> {
-        &self.properties
+}
+
+/// Redactor masks values of sensitive keys before they are printed or compared in logs.
+///
+/// A key is sensitive when any of its segments contains one of the configured patterns
+/// (case-insensitive).
+///
+/// # Example
+/// ```
+/// use beaver_bootstrap::config::Redactor;
+/// let redactor = Redactor::default();
+/// assert_eq!(redactor.redact("db.password", "p@ss"), "******");
+/// assert_eq!(redactor.redact("db.host", "localhost"), "localhost");
+/// ```
+#[derive(Debug, Clone)]
+pub struct Redactor {
+    patterns: Vec<String>,
+}
+
+const REDACTED_VALUE: &str = "******";
+
+impl Default for Redactor {
+    fn default() -> Self {
+        Self::new(&[
+            "password",
+            "passwd",
+            "secret",
+            "token",
+            "credential",
+            "private_key",
+        ])
+    }
+}
+
+impl Redactor {
+    pub fn new(patterns: &[&str]) -> Self {
+        Self {
+            patterns: patterns.iter().map(|p| p.to_ascii_lowercase()).collect(),
+        }
+    }
+
+    pub fn is_sensitive(&self, key: &str) -> bool {
+        let key = key.to_ascii_lowercase();
+        self.patterns.iter().any(|p| key.contains(p.as_str()))
+    }
+
+    pub fn redact<'a>(&self, key: &str, value: &'a str) -> &'a str {
+        if self.is_sensitive(key) {
+            REDACTED_VALUE
+        } else {
+            value
+        }
+    }
+}
+
+/// ConfigChange is a single changed key between two config snapshots.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigChange {
+    Added {
+        key: String,
+        value: String,
+    },
+    Removed {
+        key: String,
+        value: String,
+    },
+    Modified {
+        key: String,
+        old: String,
+        new: String,
+    },
+}
+
+impl ConfigChange {
+    pub fn key(&self) -> &str {
+        match self {
+            ConfigChange::Added { key, .. } => key,
+            ConfigChange::Removed { key, .. } => key,
+            ConfigChange::Modified { key, .. } => key,
+        }
+    }
+}
+
+impl std::fmt::Display for ConfigChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigChange::Added { key, value } => write!(f, "+ {}={}", key, value),
+            ConfigChange::Removed { key, value } => write!(f, "- {}={}", key, value),
+            ConfigChange::Modified { key, old, new } => write!(f, "~ {}={}->{}", key, old, new),
+        }
+    }
+}
+
+/// ConfigDiff is the structured difference between two config snapshots.
+///
+/// Values of sensitive keys are redacted, so a diff is always safe to log or hand to
+/// subscribers of a config reload.
+///
+/// # Example
+/// ```no_run
+/// use beaver_bootstrap::config::Config;
+/// let old = Config::load(None, "_").unwrap();
+/// let new = Config::load(None, "_").unwrap();
+/// let diff = old.diff(&new).unwrap();
+/// diff.log();
+/// ```
+#[cfg(feature = "config")]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConfigDiff {
+    changes: Vec<ConfigChange>,
+}
+
+#[cfg(feature = "config")]
+impl ConfigDiff {
+    pub fn between(old: &Config, new: &Config, redactor: &Redactor) -> Result<Self, ConfigError> {
+        let old_properties = old.to_properties()?;
+        let new_properties = new.to_properties()?;
+        let old_map = old_properties.get_properties();
+        let new_map = new_properties.get_properties();
+
+        let keys: BTreeSet<&String> = old_map.keys().chain(new_map.keys()).collect();
+
+        let mut changes = Vec::new();
+        for key in keys {
+            let change = match (old_map.get(key), new_map.get(key)) {
+                (Some(old), Some(new)) if old != new => ConfigChange::Modified {
+                    key: key.clone(),
+                    old: redactor.redact(key, old).to_string(),
+                    new: redactor.redact(key, new).to_string(),
+                },
+                (None, Some(new)) => ConfigChange::Added {
+                    key: key.clone(),
+                    value: redactor.redact(key, new).to_string(),
+                },
+                (Some(old), None) => ConfigChange::Removed {
+                    key: key.clone(),
+                    value: redactor.redact(key, old).to_string(),
+                },
+                _ => continue,
+            };
+            changes.push(change);
+        }
+        Ok(Self { changes })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    pub fn changes(&self) -> &[ConfigChange] {
+        &self.changes
+    }
+
+    /// whether any changed key is equal to or nested under `prefix`.
+    pub fn touches(&self, prefix: &str) -> bool {
+        self.changes.iter().any(|c| {
+            let key = c.key();
+            key == prefix
+                || key
+                    .strip_prefix(prefix)
+                    .is_some_and(|rest| rest.starts_with('.') || rest.starts_with('['))
+        })
+    }
+
+    /// log every change at info level.
+    pub fn log(&self) {
+        if self.changes.is_empty() {
+            tracing::info!("config unchanged");
+            return;
+        }
+        for change in &self.changes {
+            tracing::info!(key = change.key(), "config changed {}", change);
+        }
     }
 }
@@ -0,0 +1,158 @@
+use config::{ConfigError, Map, Source, Value, ValueKind};
+
+use super::Config;
+
+/// KeyAliases accepts config layouts of other frameworks: keys are renamed to the keys beaver
+/// reads before the config is migrated and deserialized.
+///
+/// Aliases are full dotted paths applied in declaration order, so an alias of a table also
+/// renames the keys below it for the following aliases. When both an alias and its key are
+/// set, the key wins.
+///
+/// # Example
+/// ```
+/// use beaver_bootstrap::config::{Config, alias::KeyAliases};
+/// let inner = config::Config::builder()
+///     .add_source(config::File::from_str(
+///         "[Log.Appenders]\nfile_name = \"app.log\"\n",
+///         config::FileFormat::Toml,
+///     ))
+///     .build()
+///     .unwrap();
+/// let aliases = KeyAliases::default()
+///     .case_insensitive(true)
+///     .alias("log", "logging")
+///     .alias("logging.appenders", "logging.file_appenders");
+/// let (config, warnings) = Config::new(inner).with_aliases(&aliases).unwrap();
+/// assert!(config.contains("logging.file_appenders.file_name"));
+/// assert!(warnings.is_empty());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct KeyAliases {
+    aliases: Vec<(String, String)>,
+    case_insensitive: bool,
+}
+
+impl KeyAliases {
+    /// read the value at `alias` as `key`.
+    pub fn alias(mut self, alias: &str, key: &str) -> Self {
+        self.aliases.push((alias.to_string(), key.to_string()));
+        self
+    }
+
+    /// lower case every key, so `Logging.Level` matches `logging.level`.
+    pub fn case_insensitive(mut self, case_insensitive: bool) -> Self {
+        self.case_insensitive = case_insensitive;
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.aliases.is_empty() && !self.case_insensitive
+    }
+}
+
+/// rename the keys of `config` with `aliases`, returning a warning for every ignored key.
+pub(crate) fn apply(
+    config: Config,
+    aliases: &KeyAliases,
+) -> Result<(Config, Vec<String>), ConfigError> {
+    if aliases.is_empty() {
+        return Ok((config, vec![]));
+    }
+    let mut root = config.inner.collect()?;
+    let mut warnings = Vec::new();
+    if aliases.case_insensitive {
+        root = lower_case(root, "", &mut warnings);
+    }
+    for (alias, key) in &aliases.aliases {
+        let (alias, key) = if aliases.case_insensitive {
+            (alias.to_lowercase(), key.to_lowercase())
+        } else {
+            (alias.clone(), key.clone())
+        };
+        let Some(value) = remove(&mut root, &alias) else {
+            continue;
+        };
+        if !insert(&mut root, &key, value) {
+            warnings.push(format!(
+                "config key {} is ignored, {} which it is an alias of is set",
+                alias, key
+            ));
+        }
+    }
+    let inner = config::Config::builder()
+        .add_source(super::MapSource::new(root))
+        .build()?;
+    Ok((Config::new(inner), warnings))
+}
+
+fn lower_case(
+    table: Map<String, Value>,
+    prefix: &str,
+    warnings: &mut Vec<String>,
+) -> Map<String, Value> {
+    let mut lowered = Map::new();
+    for (key, mut value) in table {
+        let path = if prefix.is_empty() {
+            key.to_lowercase()
+        } else {
+            format!("{}.{}", prefix, key.to_lowercase())
+        };
+        if let ValueKind::Table(nested) = value.kind {
+            value.kind = ValueKind::Table(lower_case(nested, &path, warnings));
+        }
+        if lowered.insert(key.to_lowercase(), value).is_some() {
+            warnings.push(format!(
+                "config key {} is set more than once ignoring case",
+                path
+            ));
+        }
+    }
+    lowered
+}
+
+/// remove the value at the dotted `path`.
+fn remove(root: &mut Map<String, Value>, path: &str) -> Option<Value> {
+    let (parent, name) = match path.rsplit_once('.') {
+        Some((parent, name)) => (table_mut(root, parent)?, name),
+        None => (root, path),
+    };
+    parent.remove(name)
+}
+
+/// insert `value` at the dotted `path`, creating the missing tables, unless a value is there.
+fn insert(root: &mut Map<String, Value>, path: &str, value: Value) -> bool {
+    let mut table = root;
+    let mut segments = path.split('.').peekable();
+    while let Some(segment) = segments.next() {
+        if segments.peek().is_none() {
+            if table.contains_key(segment) {
+                return false;
+            }
+            table.insert(segment.to_string(), value);
+            return true;
+        }
+        let entry = table
+            .entry(segment.to_string())
+            .or_insert_with(|| Value::new(None, ValueKind::Table(Map::new())));
+        let ValueKind::Table(nested) = &mut entry.kind else {
+            return false;
+        };
+        table = nested;
+    }
+    false
+}
+
+fn table_mut<'a>(
+    root: &'a mut Map<String, Value>,
+    path: &str,
+) -> Option<&'a mut Map<String, Value>> {
+    let mut table = root;
+    for segment in path.split('.') {
+        match &mut table.get_mut(segment)?.kind {
+            ValueKind::Table(nested) => table = nested,
+            _ => return None,
+        }
+    }
+    Some(table)
+}
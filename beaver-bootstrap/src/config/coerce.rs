@@ -0,0 +1,62 @@
+use config::{ConfigError, Source, Value, ValueKind};
+
+use super::{Config, MapSource};
+
+/// prefix of the origin of values read from environment variables, followed by the variable.
+pub const ENV_ORIGIN_PREFIX: &str = "env:";
+
+/// convert the string values set by environment variables to the type they spell.
+pub(crate) fn coerce_env(config: Config) -> Result<Config, ConfigError> {
+    let mut root = config.inner.collect()?;
+    let mut coerced = false;
+    for value in root.values_mut() {
+        coerced |= coerce_value(value);
+    }
+    if !coerced {
+        return Ok(config);
+    }
+    let inner = config::Config::builder()
+        .add_source(MapSource::new(root))
+        .build()?;
+    Ok(Config::new(inner))
+}
+
+fn coerce_value(value: &mut Value) -> bool {
+    let from_env = value
+        .origin()
+        .is_some_and(|x| x.starts_with(ENV_ORIGIN_PREFIX));
+    match &mut value.kind {
+        ValueKind::String(s) if from_env => match coerce(s) {
+            Some(kind) => {
+                value.kind = kind;
+                true
+            }
+            None => false,
+        },
+        ValueKind::Array(arr) => arr.iter_mut().fold(false, |x, item| coerce_value(item) | x),
+        ValueKind::Table(table) => table
+            .values_mut()
+            .fold(false, |x, item| coerce_value(item) | x),
+        _ => false,
+    }
+}
+
+/// the typed value of `s`, only numbers written canonically are converted, so strings such
+/// as `007` keep their leading zeros.
+fn coerce(s: &str) -> Option<ValueKind> {
+    match s.to_lowercase().as_str() {
+        "true" | "yes" | "on" => return Some(ValueKind::Boolean(true)),
+        "false" | "no" | "off" => return Some(ValueKind::Boolean(false)),
+        "null" => return Some(ValueKind::Nil),
+        _ => {}
+    }
+    if let Ok(int) = s.parse::<i64>()
+        && int.to_string() == s
+    {
+        return Some(ValueKind::I64(int));
+    }
+    match s.parse::<f64>() {
+        Ok(float) if float.is_finite() && float.to_string() == s => Some(ValueKind::Float(float)),
+        _ => None,
+    }
+}
@@ -0,0 +1,340 @@
+use config::{ConfigError, Map, Source, Value, ValueKind};
+
+use super::{ConfigPrefix, MapSource, coerce::ENV_ORIGIN_PREFIX};
+
+/// RequiredEnv marks keys of a config struct which must be supplied by environment
+/// variables, e.g. credentials kept out of config.toml.
+///
+/// The keys are relative to the prefix of the struct. [`Bootstrap`](crate::bootstrap::Bootstrap)
+/// checks them all at startup and reports every missing variable at once.
+///
+/// # Example
+/// ```
+/// use beaver_bootstrap::config::{ConfigPrefix, environment::RequiredEnv};
+/// #[derive(serde::Deserialize)]
+/// struct DatabaseConfig {
+///     url: String,
+///     password: String,
+/// }
+/// impl ConfigPrefix for DatabaseConfig {
+///     const PREFIX: &'static str = "database";
+/// }
+/// impl RequiredEnv for DatabaseConfig {
+///     const REQUIRED_ENV: &'static [&'static str] = &["password"];
+/// }
+/// let bootstrap = beaver_bootstrap::bootstrap::Bootstrap::builder()
+///     .require_env::<DatabaseConfig>()
+///     .build();
+/// ```
+pub trait RequiredEnv: ConfigPrefix {
+    const REQUIRED_ENV: &'static [&'static str];
+}
+
+/// EnvNaming derives the environment variables overriding config keys, following the prefix
+/// and separator rules of the environment source of Config.
+///
+/// A variable is the prefix, the separator and the key with dots replaced by the separator,
+/// upper cased. Keys with a segment containing the separator cannot be set from the
+/// environment, as the separator of their variable would nest.
+///
+/// Array elements are addressed by their index as a segment, so
+/// `logging.file_appenders[0].file_name` is overridden by
+/// `BEAVER__LOGGING__FILE_APPENDERS__0__FILE_NAME`, see [`EnvironmentSource`].
+///
+/// # Example
+/// ```
+/// use beaver_bootstrap::config::environment::EnvNaming;
+/// let naming = EnvNaming::new(Some("BEAVER"), "__");
+/// assert_eq!(
+///     naming.var_name("runtime.worker_threads").as_deref(),
+///     Some("BEAVER__RUNTIME__WORKER_THREADS")
+/// );
+/// let naming = EnvNaming::new(Some("BEAVER"), "_");
+/// assert_eq!(naming.var_name("admin.port").as_deref(), Some("BEAVER_ADMIN_PORT"));
+/// assert_eq!(naming.var_name("runtime.worker_threads"), None);
+/// let naming = EnvNaming::new(Some("BEAVER"), "__");
+/// assert_eq!(
+///     naming.var_name("logging.file_appenders[1].logger_names[0]").as_deref(),
+///     Some("BEAVER__LOGGING__FILE_APPENDERS__1__LOGGER_NAMES__0")
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvNaming {
+    prefix: Option<String>,
+    separator: String,
+}
+
+impl EnvNaming {
+    pub fn new(prefix: Option<&str>, separator: &str) -> Self {
+        Self {
+            prefix: prefix.map(str::to_string),
+            separator: separator.to_string(),
+        }
+    }
+
+    /// the variable overriding `key`, `None` when the key cannot be set from the environment.
+    pub fn var_name(&self, key: &str) -> Option<String> {
+        // indices are segments of their own, `a[0]` is `a.0`
+        let key = key.replace('[', ".").replace(']', "");
+        let addressable = key.chars().all(|c| !c.is_uppercase())
+            && if self.separator.is_empty() {
+                !key.contains('.')
+            } else {
+                key.split('.').all(|x| !x.contains(self.separator.as_str()))
+            };
+        if !addressable {
+            return None;
+        }
+        let name = key.replace('.', &self.separator).to_uppercase();
+        match &self.prefix {
+            Some(prefix) => Some(format!("{}{}{}", prefix, self.prefix_separator(), name)),
+            None => Some(name),
+        }
+    }
+
+    /// the key overridden by variable `var`, `None` when the variable lacks the prefix.
+    pub fn key(&self, var: &str) -> Option<String> {
+        let var = var.to_lowercase();
+        let key = match &self.prefix {
+            Some(prefix) => {
+                let pattern = format!("{}{}", prefix, self.prefix_separator()).to_lowercase();
+                var.strip_prefix(&pattern)?.to_string()
+            }
+            None => var,
+        };
+        match self.separator.as_str() {
+            _ if key.is_empty() => None,
+            "" => Some(key),
+            separator => Some(key.replace(separator, ".")),
+        }
+    }
+
+    fn prefix_separator(&self) -> &str {
+        match self.separator.as_str() {
+            "" => "_",
+            separator => separator,
+        }
+    }
+}
+
+/// EnvironmentSource reads the environment variables named by [`EnvNaming`], recording the
+/// variable as the origin of its value, e.g. `env:BEAVER__ADMIN__PORT`.
+///
+/// A numeric segment below an array of the sources before, or below a key they do not set,
+/// is an index, so `BEAVER__LOGGING__FILE_APPENDERS__0__FILE_NAME` overrides the file name of
+/// the first `[[logging.file_appenders]]` and keeps its other keys, and
+/// `BEAVER__LOGGING__FILE_APPENDERS__1__FILE_NAME` appends a second appender when the file
+/// declares one only. Below a table, a numeric segment is a key. Indices past the element
+/// after the last one would leave holes in the array and fail the config, naming the
+/// variable.
+///
+/// # Example
+/// ```
+/// use beaver_bootstrap::config::environment::{EnvNaming, EnvironmentSource};
+/// use config::Source;
+/// let vars = [("APP__ADMIN__PORT".to_string(), "9000".to_string())];
+/// let source = EnvironmentSource::new(EnvNaming::new(Some("APP"), "__"))
+///     .source(Some(vars.into_iter().collect()));
+/// let map = source.collect().unwrap();
+/// assert!(map.contains_key("admin"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct EnvironmentSource {
+    naming: EnvNaming,
+    source: Option<Map<String, String>>,
+}
+
+impl EnvironmentSource {
+    pub fn new(naming: EnvNaming) -> Self {
+        Self {
+            naming,
+            source: None,
+        }
+    }
+
+    /// read the variables of `source` instead of the process environment, like
+    /// [`config::Environment::source`].
+    pub fn source(mut self, source: Option<Map<String, String>>) -> Self {
+        self.source = source;
+        self
+    }
+
+    /// the variables with a key and their values, sorted by variable.
+    fn vars(&self) -> Result<Vec<(String, String, Value)>, ConfigError> {
+        let entries: Box<dyn Iterator<Item = (String, Result<String, String>)>> = match &self.source
+        {
+            Some(source) => Box::new(source.iter().map(|(k, v)| (k.clone(), Ok(v.clone())))),
+            None => Box::new(std::env::vars_os().filter_map(|(var, value)| {
+                // variables which are not unicode cannot be config keys
+                let var = var.into_string().ok()?;
+                Some((var, value.into_string().map_err(|x| format!("{:?}", x))))
+            })),
+        };
+        let mut vars = Vec::new();
+        for (var, value) in entries {
+            let Some(key) = self.naming.key(&var) else {
+                continue;
+            };
+            let value = value.map_err(|x| {
+                ConfigError::Message(format!(
+                    "env variable {} contains non-unicode data: {}",
+                    var, x
+                ))
+            })?;
+            let origin = format!("{}{}", ENV_ORIGIN_PREFIX, var);
+            let value = Value::new(Some(&origin), ValueKind::String(value));
+            vars.push((var, key, value));
+        }
+        vars.sort_by(|x, y| x.0.cmp(&y.0));
+        Ok(vars)
+    }
+}
+
+impl Source for EnvironmentSource {
+    fn clone_into_box(&self) -> Box<dyn Source + Send + Sync> {
+        Box::new(self.clone())
+    }
+
+    fn collect(&self) -> Result<Map<String, Value>, ConfigError> {
+        let mut root = Value::from(Map::<String, Value>::new());
+        self.collect_to(&mut root)?;
+        match root.kind {
+            ValueKind::Table(map) => Ok(map),
+            _ => unreachable!("the root of the config is a table"),
+        }
+    }
+
+    fn collect_to(&self, cache: &mut Value) -> Result<(), ConfigError> {
+        let mut keys = Vec::new();
+        for (var, key, value) in self.vars()? {
+            // numeric segments are resolved against the values set so far
+            let key = resolve_indices(cache, &key);
+            let mut map = Map::new();
+            map.insert(key.clone(), value);
+            MapSource::new(map).collect_to(cache)?;
+            keys.push((var, key));
+        }
+        for (var, key) in keys {
+            check_indices(cache, &var, &key)?;
+        }
+        Ok(())
+    }
+}
+
+/// `key` in the path syntax of config-rs, its numeric segments below arrays or absent values
+/// written as indices, e.g. `logging.file_appenders.0.file_name` as
+/// `logging.file_appenders[0].file_name`.
+fn resolve_indices(root: &Value, key: &str) -> String {
+    let mut resolved = String::new();
+    let mut current = Some(root);
+    for segment in key.split('.') {
+        let index = match current.map(|x| &x.kind) {
+            _ if resolved.is_empty() => None,
+            Some(ValueKind::Table(_)) => None,
+            _ if !segment.bytes().all(|x| x.is_ascii_digit()) => None,
+            _ => segment.parse::<usize>().ok(),
+        };
+        match index {
+            Some(index) => {
+                resolved.push_str(&format!("[{}]", index));
+                current = match current.map(|x| &x.kind) {
+                    Some(ValueKind::Array(array)) => array.get(index),
+                    _ => None,
+                };
+            }
+            None => {
+                if !resolved.is_empty() {
+                    resolved.push('.');
+                }
+                resolved.push_str(segment);
+                current = match current.map(|x| &x.kind) {
+                    Some(ValueKind::Table(map)) => map.get(segment),
+                    _ => None,
+                };
+            }
+        }
+    }
+    resolved
+}
+
+/// fail when the indices of `key`, set by `var`, left holes in their arrays.
+fn check_indices(root: &Value, var: &str, key: &str) -> Result<(), ConfigError> {
+    let mut path = String::new();
+    let mut current = root;
+    for segment in key.split('.') {
+        let (name, indices) = match segment.find('[') {
+            Some(start) => segment.split_at(start),
+            None => (segment, ""),
+        };
+        if !path.is_empty() {
+            path.push('.');
+        }
+        path.push_str(name);
+        current = match &current.kind {
+            ValueKind::Table(map) => match map.get(name) {
+                Some(value) => value,
+                None => return Ok(()),
+            },
+            _ => return Ok(()),
+        };
+        for index in indices.split(['[', ']']).filter(|x| !x.is_empty()) {
+            let ValueKind::Array(array) = &current.kind else {
+                return Ok(());
+            };
+            let index: usize = index.parse().unwrap_or_default();
+            let hole = array.iter().position(|x| matches!(x.kind, ValueKind::Nil));
+            if let Some(hole) = hole.filter(|x| *x < index) {
+                return Err(ConfigError::Message(format!(
+                    "env variable {} sets {}[{}], leaving {}[{}] unset: array elements are \
+                     overridden by their index or appended at the index after the last one",
+                    var, path, index, path, hole
+                )));
+            }
+            path.push_str(&format!("[{}]", index));
+            current = match array.get(index) {
+                Some(value) => value,
+                None => return Ok(()),
+            };
+        }
+    }
+    Ok(())
+}
+
+/// EnvVar is a config key with the environment variable overriding it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvVar {
+    key: String,
+    name: Option<String>,
+    required: bool,
+}
+
+impl EnvVar {
+    pub fn new(naming: &EnvNaming, key: &str, required: bool) -> Self {
+        Self {
+            key: key.to_string(),
+            name: naming.var_name(key),
+            required,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// the variable, `None` when the key cannot be set from the environment.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn is_required(&self) -> bool {
+        self.required
+    }
+
+    /// whether the variable is set to a non empty value.
+    pub fn is_set(&self) -> bool {
+        self.name
+            .as_ref()
+            .and_then(std::env::var_os)
+            .is_some_and(|x| !x.is_empty())
+    }
+}
@@ -0,0 +1,295 @@
+#[cfg(feature = "full")]
+use std::sync::Arc;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use config::ValueKind;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "full")]
+use super::reload::LiveConfig;
+use super::{Config, ConfigPrefix, REDACTED_VALUE, Redactor};
+#[cfg(feature = "full")]
+use crate::admin::{AdminResponse, AdminRoutes};
+use crate::{error::BootstrapError, fs::Fs};
+
+/// ConfigExportFormat is the file format of the exported config.
+#[derive(Debug, Default, Copy, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfigExportFormat {
+    #[default]
+    Toml,
+    Json,
+    /// Java `.properties`, keys flattened as by [`Config::to_properties`].
+    Properties,
+}
+
+impl std::str::FromStr for ConfigExportFormat {
+    type Err = BootstrapError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "toml" => Ok(Self::Toml),
+            "json" => Ok(Self::Json),
+            "properties" => Ok(Self::Properties),
+            _ => Err(BootstrapError::InvalidConfigValueError(format!(
+                "format={}: expected toml, json or properties",
+                s
+            ))),
+        }
+    }
+}
+
+/// ConfigExportConfig configures the export of the effective config at startup, see
+/// `[config_export]`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConfigExportConfig {
+    enable: bool,
+    /// file written, relative to the working directory.
+    path: PathBuf,
+    format: ConfigExportFormat,
+}
+
+impl Default for ConfigExportConfig {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            path: PathBuf::from("logs/effective-config.toml"),
+            format: ConfigExportFormat::Toml,
+        }
+    }
+}
+
+impl ConfigPrefix for ConfigExportConfig {
+    const PREFIX: &'static str = "config_export";
+}
+
+impl ConfigExportConfig {
+    pub fn new(config: &Config) -> Result<Self, BootstrapError> {
+        config
+            .get::<ConfigExportConfig>()
+            .map_err(BootstrapError::ConfigLoadError)
+    }
+
+    pub fn enable(&self) -> bool {
+        self.enable
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn format(&self) -> ConfigExportFormat {
+        self.format
+    }
+}
+
+/// render `config` in `format` with keys sorted and values of sensitive keys redacted.
+///
+/// # Example
+/// ```
+/// use beaver_bootstrap::config::{
+///     Config, Redactor,
+///     export::{ConfigExportFormat, render},
+/// };
+/// let inner = config::Config::builder()
+///     .set_override("db.host", "localhost")
+///     .unwrap()
+///     .set_override("db.password", "p@ss")
+///     .unwrap();
+/// let config = Config::new(inner.build().unwrap());
+/// let toml = render(&config, ConfigExportFormat::Toml, &Redactor::default()).unwrap();
+/// assert_eq!(toml, "[db]\nhost = \"localhost\"\npassword = \"******\"\n");
+/// ```
+pub fn render(
+    config: &Config,
+    format: ConfigExportFormat,
+    redactor: &Redactor,
+) -> Result<String, BootstrapError> {
+    let value = redacted("", &config.inner.cache, redactor).unwrap_or_default();
+    match format {
+        ConfigExportFormat::Properties => Ok(properties(config, redactor)?.to_java_properties()),
+        format => serialize(&value, format),
+    }
+}
+
+/// like [`render`], with the origin of every value, see [`Config::provenance`].
+///
+/// TOML and JSON hold the config under `values` and the origin of each flattened key under
+/// `provenance`, properties are preceded by a comment naming their origin.
+///
+/// # Example
+/// ```
+/// use beaver_bootstrap::config::{
+///     Config, Redactor,
+///     export::{ConfigExportFormat, render_with_provenance},
+/// };
+/// let inner = config::Config::builder().build().unwrap();
+/// let config = Config::new(inner)
+///     .with_overrides(&[("db.password".to_string(), "p@ss".into())])
+///     .unwrap();
+/// let properties =
+///     render_with_provenance(&config, ConfigExportFormat::Properties, &Redactor::default());
+/// assert_eq!(properties.unwrap(), "# override\ndb.password=******\n");
+/// let json = render_with_provenance(&config, ConfigExportFormat::Json, &Redactor::default());
+/// assert!(json.unwrap().contains("\"db.password\": \"override\""));
+/// ```
+pub fn render_with_provenance(
+    config: &Config,
+    format: ConfigExportFormat,
+    redactor: &Redactor,
+) -> Result<String, BootstrapError> {
+    if format == ConfigExportFormat::Properties {
+        return Ok(properties(config, redactor)?.to_java_properties_with_origins());
+    }
+    let mut origins = BTreeMap::new();
+    provenance("", &config.inner.cache, &mut origins);
+    let mut document = serde_json::Map::new();
+    document.insert(
+        "values".to_string(),
+        redacted("", &config.inner.cache, redactor).unwrap_or_default(),
+    );
+    document.insert(
+        "provenance".to_string(),
+        serde_json::to_value(origins).unwrap_or_default(),
+    );
+    serialize(&serde_json::Value::Object(document), format)
+}
+
+fn serialize(
+    value: &serde_json::Value,
+    format: ConfigExportFormat,
+) -> Result<String, BootstrapError> {
+    match format {
+        ConfigExportFormat::Toml => {
+            toml::to_string(value).map_err(|e| BootstrapError::ConfigExportError(Box::new(e)))
+        }
+        _ => serde_json::to_string_pretty(value)
+            .map(|x| x + "\n")
+            .map_err(|e| BootstrapError::ConfigExportError(Box::new(e))),
+    }
+}
+
+fn properties(config: &Config, redactor: &Redactor) -> Result<super::Properties, BootstrapError> {
+    config
+        .to_properties()
+        .map(|x| x.redacted(redactor))
+        .map_err(|e| BootstrapError::ConfigExportError(Box::new(e)))
+}
+
+/// collect the origin of every value under `key`, arrays by item.
+fn provenance(key: &str, value: &config::Value, origins: &mut BTreeMap<String, String>) {
+    match &value.kind {
+        ValueKind::Table(table) => {
+            for (name, value) in table {
+                let key = if key.is_empty() {
+                    name.clone()
+                } else {
+                    format!("{}.{}", key, name)
+                };
+                provenance(&key, value, origins);
+            }
+        }
+        ValueKind::Array(array) => {
+            for (i, value) in array.iter().enumerate() {
+                provenance(&format!("{}[{}]", key, i), value, origins);
+            }
+        }
+        _ => {
+            if let Some(origin) = value.origin() {
+                origins.insert(key.to_string(), origin.to_string());
+            }
+        }
+    }
+}
+
+/// register `/config`, the active config redacted with the origin of its values, see
+/// [`render_with_provenance`]. `?format=toml|json|properties` selects the format, JSON by
+/// default.
+///
+/// The config describes the deployment, so the route is only registered when `authenticated`,
+/// i.e. the admin endpoint requires a token.
+#[cfg(feature = "full")]
+pub fn register_routes(routes: &AdminRoutes, live_config: Arc<LiveConfig>, authenticated: bool) {
+    if !authenticated {
+        return;
+    }
+    routes.route("/config", move |request| {
+        let format = match request
+            .query("format")
+            .unwrap_or("json")
+            .parse::<ConfigExportFormat>()
+        {
+            Ok(format) => format,
+            Err(e) => return AdminResponse::text(400, &e.to_string()),
+        };
+        let content_type = match format {
+            ConfigExportFormat::Toml => "application/toml",
+            ConfigExportFormat::Json => "application/json",
+            ConfigExportFormat::Properties => "text/plain; charset=utf-8",
+        };
+        match render_with_provenance(&live_config.current(), format, &Redactor::default()) {
+            Ok(body) => AdminResponse::new(200, content_type, body.into_bytes()),
+            Err(e) => AdminResponse::text(500, &e.to_string()),
+        }
+    });
+}
+
+/// write the effective config to the path of `export_config`, creating its directory.
+pub fn export(
+    fs: &dyn Fs,
+    config: &Config,
+    export_config: &ConfigExportConfig,
+) -> Result<(), BootstrapError> {
+    let content = render(config, export_config.format, &Redactor::default())?;
+    let path = export_config.path();
+    if let Some(parent) = path.parent().filter(|x| !x.as_os_str().is_empty()) {
+        fs.create_dir_all(parent)
+            .map_err(|e| BootstrapError::ConfigExportError(Box::new(e)))?;
+    }
+    fs.write(path, content.as_bytes())
+        .map_err(|e| BootstrapError::ConfigExportError(Box::new(e)))?;
+    tracing::info!("effective config written to {}", path.display());
+    Ok(())
+}
+
+/// `value` at `key` as JSON, sorted and redacted. Nil values are dropped since TOML has no null.
+fn redacted(key: &str, value: &config::Value, redactor: &Redactor) -> Option<serde_json::Value> {
+    let value = match &value.kind {
+        ValueKind::Nil => return None,
+        ValueKind::Table(table) => {
+            let mut map = serde_json::Map::new();
+            for (name, value) in table {
+                let key = if key.is_empty() {
+                    name.clone()
+                } else {
+                    format!("{}.{}", key, name)
+                };
+                if let Some(value) = redacted(&key, value, redactor) {
+                    map.insert(name.clone(), value);
+                }
+            }
+            return Some(serde_json::Value::Object(map));
+        }
+        ValueKind::Array(array) => {
+            let items = array
+                .iter()
+                .enumerate()
+                .filter_map(|(i, x)| redacted(&format!("{}[{}]", key, i), x, redactor))
+                .collect();
+            return Some(serde_json::Value::Array(items));
+        }
+        _ if redactor.is_sensitive(key) => return Some(serde_json::Value::from(REDACTED_VALUE)),
+        ValueKind::Boolean(x) => serde_json::Value::from(*x),
+        ValueKind::I64(x) => serde_json::Value::from(*x),
+        ValueKind::U64(x) => serde_json::Value::from(*x),
+        ValueKind::I128(x) => serde_json::Value::from(x.to_string()),
+        ValueKind::U128(x) => serde_json::Value::from(x.to_string()),
+        ValueKind::Float(x) => serde_json::Value::from(*x),
+        ValueKind::String(x) => serde_json::Value::from(x.as_str()),
+    };
+    Some(value)
+}
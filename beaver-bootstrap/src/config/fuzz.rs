@@ -0,0 +1,360 @@
+use std::{fmt, marker::PhantomData, sync::Arc};
+
+use config::{ConfigError, File, FileFormat};
+use proptest::{collection, prelude::*, sample::select};
+use serde::{Serialize, de::DeserializeOwned};
+use toml::{Table, Value};
+
+use super::{Config, ConfigPrefix};
+
+/// strings tried at every leaf, covering the durations, sizes, levels and formats of beaver.
+const STRINGS: [&str; 24] = [
+    "",
+    "x",
+    "true",
+    "0",
+    "1",
+    "250ms",
+    "5s",
+    "1m",
+    "1h",
+    "1KiB",
+    "64MB",
+    "trace",
+    "debug",
+    "info",
+    "warn",
+    "error",
+    "off",
+    "json",
+    "text",
+    "full",
+    "compact",
+    "127.0.0.1:0",
+    "http://127.0.0.1:8080",
+    "/tmp",
+];
+/// integers tried at every leaf, at the bounds of the common integer types.
+const INTEGERS: [i64; 10] = [
+    0,
+    1,
+    -1,
+    8,
+    255,
+    1024,
+    65_535,
+    65_536,
+    i32::MAX as i64,
+    i64::MAX,
+];
+/// floats tried at every leaf, ratios among them.
+const FLOATS: [f64; 5] = [0.0, 0.25, 1.0, -1.0, 1e9];
+/// key inserted into a table to tell whether it denies unknown fields.
+const UNKNOWN_KEY: &str = "beaver_fuzz_unknown";
+
+/// a change of the default tree of a section.
+#[derive(Debug, Clone)]
+enum Mutation {
+    /// set the leaf at the path.
+    Set(Vec<String>, Value),
+    /// insert [`UNKNOWN_KEY`] into the table at the path.
+    Unknown(Vec<String>),
+}
+
+impl Mutation {
+    fn apply(&self, tree: &mut Table) {
+        match self {
+            Mutation::Set(path, value) => set(tree, path, value.clone()),
+            Mutation::Unknown(path) => {
+                if let Some(table) = table_mut(tree, path) {
+                    table.insert(UNKNOWN_KEY.to_string(), Value::from("x"));
+                }
+            }
+        }
+    }
+}
+
+/// a leaf of the default tree and the values it accepts when the others keep their default.
+#[derive(Debug, Clone)]
+struct Leaf {
+    path: Vec<String>,
+    /// the default first.
+    accepted: Vec<Value>,
+}
+
+/// ConfigShape is the shape of the config section `T`, inferred from its default and its
+/// deserialization: every leaf of the default tree is set to candidate booleans, numbers,
+/// strings, arrays and tables, and every table gets an unknown key, each change alone, to
+/// learn what a config.toml may hold.
+///
+/// It generates [`proptest`] strategies of whole config.toml trees, the section nested under
+/// its prefix:
+/// - [`ConfigShape::valid`] trees deserialize to `T`, with some leaves changed to values
+///   they accept;
+/// - [`ConfigShape::invalid`] trees do not, with a leaf of the wrong type or an unknown key
+///   on top of a valid tree.
+///
+/// Validity is that of the deserialization by [`Config::get`], the checks of a section
+/// applied after it, such as a zero timeout, are for the property tests to assert. Only keys
+/// present in the default tree are explored: a field defaulting to `None` or to an empty map
+/// is not.
+///
+/// # Example
+/// ```
+/// use beaver_bootstrap::config::{ConfigPrefix, fuzz::{ConfigShape, deserialize}};
+/// use proptest::{prelude::*, test_runner::TestRunner};
+/// use serde::{Deserialize, Serialize};
+/// #[derive(Debug, Default, Serialize, Deserialize)]
+/// #[serde(default, deny_unknown_fields)]
+/// struct PoolConfig {
+///     size: u16,
+///     lazy: bool,
+/// }
+/// impl ConfigPrefix for PoolConfig {
+///     const PREFIX: &'static str = "db.pool";
+/// }
+/// let shape = ConfigShape::<PoolConfig>::infer().unwrap();
+/// assert_eq!(shape.keys(), ["db.pool.lazy", "db.pool.size"]);
+/// let mut runner = TestRunner::default();
+/// runner
+///     .run(&shape.valid(), |tree| {
+///         prop_assert!(deserialize::<PoolConfig>(&tree).is_ok());
+///         Ok(())
+///     })
+///     .unwrap();
+/// runner
+///     .run(&shape.invalid().unwrap(), |tree| {
+///         prop_assert!(deserialize::<PoolConfig>(&tree).is_err());
+///         Ok(())
+///     })
+///     .unwrap();
+/// ```
+pub struct ConfigShape<T> {
+    /// the default tree.
+    base: Arc<Table>,
+    leaves: Vec<Leaf>,
+    /// the changes the section rejects.
+    rejected: Vec<Mutation>,
+    section: PhantomData<fn() -> T>,
+}
+
+impl<T> fmt::Debug for ConfigShape<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConfigShape")
+            .field("base", &self.base)
+            .field("leaves", &self.leaves)
+            .field("rejected", &self.rejected)
+            .finish()
+    }
+}
+
+impl<T> ConfigShape<T>
+where
+    T: ConfigPrefix + Default + Serialize + DeserializeOwned + 'static,
+{
+    /// infer the shape of `T`, failing when its default does not deserialize back.
+    pub fn infer() -> Result<Self, ConfigError> {
+        let section =
+            Table::try_from(T::default()).map_err(|e| ConfigError::Foreign(Box::new(e)))?;
+        let prefix: Vec<String> = T::PREFIX.split('.').map(str::to_string).collect();
+        let mut base = Table::new();
+        set(&mut base, &prefix, Value::Table(section.clone()));
+        if let Err(e) = deserialize::<T>(&base) {
+            return Err(ConfigError::Message(format!(
+                "the default of {} does not deserialize: {}",
+                T::PREFIX,
+                e
+            )));
+        }
+        let accepts = |mutation: &Mutation| {
+            let mut tree = base.clone();
+            mutation.apply(&mut tree);
+            deserialize::<T>(&tree).is_ok()
+        };
+        let mut leaves = Vec::new();
+        let mut rejected = Vec::new();
+        let mut tables = vec![(prefix, &section)];
+        while let Some((path, table)) = tables.pop() {
+            let unknown = Mutation::Unknown(path.clone());
+            if !accepts(&unknown) {
+                rejected.push(unknown);
+            }
+            for (key, value) in table {
+                let mut path = path.clone();
+                path.push(key.clone());
+                if let Value::Table(table) = value {
+                    tables.push((path, table));
+                    continue;
+                }
+                let mut accepted = vec![value.clone()];
+                for candidate in candidates(value) {
+                    let mutation = Mutation::Set(path.clone(), candidate.clone());
+                    if accepts(&mutation) {
+                        accepted.push(candidate);
+                    } else {
+                        rejected.push(mutation);
+                    }
+                }
+                leaves.push(Leaf { path, accepted });
+            }
+        }
+        leaves.sort_by(|x, y| x.path.cmp(&y.path));
+        Ok(Self {
+            base: Arc::new(base),
+            leaves,
+            rejected,
+            section: PhantomData,
+        })
+    }
+
+    /// the default tree of the section.
+    pub fn base(&self) -> &Table {
+        &self.base
+    }
+
+    /// full keys of the leaves of the default tree, arrays being one leaf.
+    pub fn keys(&self) -> Vec<String> {
+        self.leaves.iter().map(|x| x.path.join(".")).collect()
+    }
+
+    /// trees deserializing to `T`, mostly at the default, some leaves set to values they
+    /// accept.
+    pub fn valid(&self) -> BoxedStrategy<Table> {
+        let base = self.base.clone();
+        let paths: Vec<Vec<String>> = self.leaves.iter().map(|x| x.path.clone()).collect();
+        let values: Vec<BoxedStrategy<Value>> = self
+            .leaves
+            .iter()
+            .map(|x| {
+                prop_oneof![
+                    3 => Just(x.accepted[0].clone()),
+                    1 => select(x.accepted.clone()),
+                ]
+                .boxed()
+            })
+            .collect();
+        values
+            .prop_map(move |values| {
+                let mut tree = (*base).clone();
+                for (path, value) in paths.iter().zip(values) {
+                    set(&mut tree, path, value);
+                }
+                tree
+            })
+            // values accepted alone may still conflict
+            .prop_filter("rejected by the section", |tree| {
+                deserialize::<T>(tree).is_ok()
+            })
+            .boxed()
+    }
+
+    /// trees not deserializing to `T`, a valid tree with a leaf of the wrong type or an
+    /// unknown key, `None` when the section accepted every change tried.
+    pub fn invalid(&self) -> Option<BoxedStrategy<Table>> {
+        if self.rejected.is_empty() {
+            return None;
+        }
+        let strategy = (self.valid(), select(self.rejected.clone()))
+            .prop_map(|(mut tree, mutation)| {
+                mutation.apply(&mut tree);
+                tree
+            })
+            .prop_filter("accepted by the section", |tree| {
+                deserialize::<T>(tree).is_err()
+            });
+        Some(strategy.boxed())
+    }
+}
+
+/// the candidates of a leaf: values of every type, the empty array and table, and for an
+/// array its first item alone.
+fn candidates(leaf: &Value) -> Vec<Value> {
+    let mut candidates: Vec<Value> = [true, false].into_iter().map(Value::from).collect();
+    candidates.extend(INTEGERS.into_iter().map(Value::from));
+    candidates.extend(FLOATS.into_iter().map(Value::from));
+    candidates.extend(STRINGS.into_iter().map(Value::from));
+    candidates.push(Value::Array(vec![]));
+    candidates.push(Value::Table(Table::new()));
+    if let Value::Array(items) = leaf
+        && let Some(first) = items.first()
+    {
+        candidates.push(Value::Array(vec![first.clone()]));
+    }
+    candidates.retain(|x| x != leaf);
+    candidates
+}
+
+/// the table at `path`, `None` when a key of the path is not a table.
+fn table_mut<'a>(tree: &'a mut Table, path: &[String]) -> Option<&'a mut Table> {
+    path.iter()
+        .try_fold(tree, |table, key| match table.get_mut(key) {
+            Some(Value::Table(table)) => Some(table),
+            _ => None,
+        })
+}
+
+/// set the value at `path`, creating the missing tables on the way.
+fn set(tree: &mut Table, path: &[String], value: Value) {
+    let Some((key, parents)) = path.split_last() else {
+        return;
+    };
+    let mut table = tree;
+    for parent in parents {
+        let entry = table
+            .entry(parent.clone())
+            .or_insert_with(|| Value::Table(Table::new()));
+        if !entry.is_table() {
+            *entry = Value::Table(Table::new());
+        }
+        let Value::Table(child) = entry else {
+            unreachable!("replaced by a table above");
+        };
+        table = child;
+    }
+    table.insert(key.clone(), value);
+}
+
+/// load `tree` as a config.toml.
+pub fn load(tree: &Table) -> Result<Config, ConfigError> {
+    let toml = toml::to_string(tree).map_err(|e| ConfigError::Foreign(Box::new(e)))?;
+    let inner = config::Config::builder()
+        .add_source(File::from_str(&toml, FileFormat::Toml))
+        .build()?;
+    Ok(Config::new(inner))
+}
+
+/// load `tree` as a config.toml and deserialize its section `T`.
+pub fn deserialize<T>(tree: &Table) -> Result<T, ConfigError>
+where
+    T: ConfigPrefix + DeserializeOwned,
+{
+    load(tree)?.get::<T>()
+}
+
+/// arbitrary config.toml trees, of booleans, integers, floats and strings nested in tables
+/// and arrays up to `depth`, to fuzz the loading, layering and flattening of configs.
+///
+/// Keys are lower case words and arrays hold values of one type, as TOML and environment
+/// variables allow.
+pub fn arbitrary_tree(depth: u32) -> BoxedStrategy<Table> {
+    let scalar = prop_oneof![
+        any::<bool>().prop_map(Value::from),
+        any::<i64>().prop_map(Value::from),
+        (-1e9..1e9f64).prop_map(Value::from),
+        "[a-zA-Z0-9 ,._:/-]{0,12}".prop_map(Value::from),
+    ];
+    let array = prop_oneof![
+        collection::vec(any::<bool>().prop_map(Value::from), 0..4),
+        collection::vec(any::<i64>().prop_map(Value::from), 0..4),
+        collection::vec("[a-z0-9]{0,8}".prop_map(Value::from), 0..4),
+    ]
+    .prop_map(Value::Array);
+    let leaf = prop_oneof![4 => scalar, 1 => array];
+    let value = leaf.prop_recursive(depth, 64, 6, |inner| {
+        collection::btree_map("[a-z][a-z0-9_]{0,5}", inner, 0..6)
+            .prop_map(|x| Value::Table(x.into_iter().collect()))
+    });
+    collection::btree_map("[a-z][a-z0-9_]{0,5}", value, 0..6)
+        .prop_map(|x| x.into_iter().collect())
+        .boxed()
+}
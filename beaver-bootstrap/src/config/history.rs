@@ -0,0 +1,281 @@
+#[cfg(feature = "full")]
+use std::time::UNIX_EPOCH;
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "full")]
+use super::reload::LiveConfig;
+use super::{Config, ConfigDiff, ConfigPrefix};
+#[cfg(feature = "full")]
+use crate::admin::{AdminResponse, AdminRoutes};
+use crate::{clock::Clock, error::BootstrapError};
+
+/// ConfigHistoryConfig configures the snapshots kept by the [`ConfigHistory`], see
+/// `[config_history]`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConfigHistoryConfig {
+    /// snapshots kept, including the active one.
+    size: usize,
+}
+
+impl Default for ConfigHistoryConfig {
+    fn default() -> Self {
+        Self { size: 10 }
+    }
+}
+
+impl ConfigPrefix for ConfigHistoryConfig {
+    const PREFIX: &'static str = "config_history";
+}
+
+impl ConfigHistoryConfig {
+    pub fn new(config: &Config) -> Result<Self, BootstrapError> {
+        let history_config = config
+            .get::<ConfigHistoryConfig>()
+            .map_err(BootstrapError::ConfigLoadError)?;
+        if history_config.size == 0 {
+            return Err(BootstrapError::InvalidConfigValueError(
+                "config_history.size=0".to_string(),
+            ));
+        }
+        Ok(history_config)
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+}
+
+/// ConfigSnapshot is a config which was active, with when and how it was loaded.
+#[derive(Clone)]
+pub struct ConfigSnapshot {
+    version: u64,
+    loaded_at: SystemTime,
+    source: String,
+    config: Arc<Config>,
+}
+
+impl std::fmt::Debug for ConfigSnapshot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ConfigSnapshot")
+            .field("version", &self.version)
+            .field("loaded_at", &self.loaded_at)
+            .field("source", &self.source)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ConfigSnapshot {
+    /// number of the snapshot, increasing from 1.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    pub fn loaded_at(&self) -> SystemTime {
+        self.loaded_at
+    }
+
+    /// how the snapshot was loaded: `startup`, `reload` or `rollback to <version>`.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn config(&self) -> Arc<Config> {
+        self.config.clone()
+    }
+}
+
+/// ConfigHistory keeps the last config snapshots in memory, so a bad reload can be rolled back
+/// with [`LiveConfig::rollback`].
+///
+/// # Example
+/// ```
+/// use std::sync::Arc;
+/// use beaver_bootstrap::{
+///     clock::SystemClock,
+///     config::{Config, history::ConfigHistory},
+/// };
+/// let config = |size: i64| {
+///     let inner = config::Config::builder().set_override("pool.size", size).unwrap();
+///     Arc::new(Config::new(inner.build().unwrap()))
+/// };
+/// let history = ConfigHistory::new(2, Arc::new(SystemClock));
+/// history.record(config(1), "startup");
+/// history.record(config(2), "reload");
+/// history.record(config(3), "reload");
+/// let versions: Vec<u64> = history.snapshots().iter().map(|x| x.version()).collect();
+/// assert_eq!(versions, [2, 3]);
+/// assert_eq!(history.diff(2, 3).unwrap().changes().len(), 1);
+/// assert!(history.get(1).is_none());
+/// ```
+pub struct ConfigHistory {
+    size: usize,
+    clock: Arc<dyn Clock>,
+    inner: Mutex<ConfigHistoryInner>,
+}
+
+#[derive(Default)]
+struct ConfigHistoryInner {
+    last_version: u64,
+    snapshots: VecDeque<ConfigSnapshot>,
+}
+
+impl std::fmt::Debug for ConfigHistory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ConfigHistory")
+            .field("size", &self.size)
+            .field("snapshots", &self.snapshots())
+            .finish()
+    }
+}
+
+impl ConfigHistory {
+    pub fn new(size: usize, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            size: size.max(1),
+            clock,
+            inner: Mutex::default(),
+        }
+    }
+
+    /// keep `config` as the newest snapshot, dropping the oldest beyond the size, and return
+    /// its version.
+    pub fn record(&self, config: Arc<Config>, source: &str) -> u64 {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        inner.last_version += 1;
+        let snapshot = ConfigSnapshot {
+            version: inner.last_version,
+            loaded_at: self.clock.now(),
+            source: source.to_string(),
+            config,
+        };
+        inner.snapshots.push_back(snapshot);
+        while inner.snapshots.len() > self.size {
+            inner.snapshots.pop_front();
+        }
+        inner.last_version
+    }
+
+    /// kept snapshots, oldest first.
+    pub fn snapshots(&self) -> Vec<ConfigSnapshot> {
+        let inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        inner.snapshots.iter().cloned().collect()
+    }
+
+    pub fn get(&self, version: u64) -> Option<ConfigSnapshot> {
+        let inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        inner
+            .snapshots
+            .iter()
+            .find(|x| x.version == version)
+            .cloned()
+    }
+
+    /// the newest snapshot, which is the active config.
+    pub fn latest(&self) -> Option<ConfigSnapshot> {
+        let inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        inner.snapshots.back().cloned()
+    }
+
+    /// redacted changes from snapshot `from` to snapshot `to`.
+    pub fn diff(&self, from: u64, to: u64) -> Result<ConfigDiff, BootstrapError> {
+        let snapshot = |version| {
+            self.get(version)
+                .ok_or(BootstrapError::ConfigSnapshotNotFoundError(version))
+        };
+        snapshot(from)?
+            .config
+            .diff(&snapshot(to)?.config)
+            .map_err(BootstrapError::ConfigLoadError)
+    }
+}
+
+/// a snapshot as listed by the admin endpoint.
+#[cfg(feature = "full")]
+#[derive(Debug, Serialize)]
+struct SnapshotSummary {
+    version: u64,
+    loaded_at_ms: u64,
+    source: String,
+    active: bool,
+}
+
+/// serve the config history on the admin endpoint:
+///
+/// - `/config/history` lists the snapshots,
+/// - `/config/history/diff?from=<version>&to=<version>` shows the changes between two
+///   snapshots, `to` is the active one by default,
+/// - `POST /config/rollback?version=<version>` rolls back, when `rollback` is true.
+#[cfg(feature = "full")]
+pub fn register_routes(routes: &AdminRoutes, live_config: Arc<LiveConfig>, rollback: bool) {
+    let Some(history) = live_config.history() else {
+        return;
+    };
+    let list = history.clone();
+    routes.route("/config/history", move |_| {
+        let active = list.latest().map(|x| x.version);
+        let snapshots: Vec<SnapshotSummary> = list
+            .snapshots()
+            .into_iter()
+            .map(|x| SnapshotSummary {
+                version: x.version,
+                loaded_at_ms: x
+                    .loaded_at
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_millis() as u64)
+                    .unwrap_or_default(),
+                active: Some(x.version) == active,
+                source: x.source,
+            })
+            .collect();
+        AdminResponse::json(200, &snapshots)
+    });
+    routes.route("/config/history/diff", move |request| {
+        let version = |key| request.query(key).map(str::parse::<u64>);
+        let from = match version("from") {
+            Some(Ok(from)) => from,
+            _ => return AdminResponse::text(400, "from must be a snapshot version"),
+        };
+        let to = match version("to") {
+            None => history.latest().map(|x| x.version).unwrap_or_default(),
+            Some(Ok(to)) => to,
+            Some(Err(_)) => return AdminResponse::text(400, "to must be a snapshot version"),
+        };
+        changes_response(history.diff(from, to))
+    });
+    if !rollback {
+        return;
+    }
+    routes.route("/config/rollback", move |request| {
+        if request.method() != "POST" {
+            return AdminResponse::text(405, "method not allowed");
+        }
+        match request.query("version").map(str::parse::<u64>) {
+            Some(Ok(version)) => changes_response(live_config.rollback(version)),
+            _ => AdminResponse::text(400, "version must be a snapshot version"),
+        }
+    });
+}
+
+#[cfg(feature = "full")]
+fn changes_response(diff: Result<ConfigDiff, BootstrapError>) -> AdminResponse {
+    match diff {
+        Ok(diff) => {
+            let changes: Vec<String> = diff.changes().iter().map(|x| x.to_string()).collect();
+            AdminResponse::json(200, &changes)
+        }
+        Err(e @ BootstrapError::ConfigSnapshotNotFoundError(_)) => {
+            AdminResponse::text(404, &e.to_string())
+        }
+        Err(e @ BootstrapError::ConfigReloadRejectedError(_)) => {
+            AdminResponse::text(409, &e.to_string())
+        }
+        Err(e) => AdminResponse::text(500, &e.to_string()),
+    }
+}
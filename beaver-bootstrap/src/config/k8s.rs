@@ -0,0 +1,319 @@
+use std::{
+    env, fmt, fs, io,
+    path::{Path, PathBuf},
+    sync::mpsc::{self, RecvTimeoutError, Sender},
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use config::{ConfigError, Map, Source, Value, ValueKind};
+
+use super::{FILE_ORIGIN_PREFIX, coerce::ENV_ORIGIN_PREFIX, source::SourceChanged};
+
+/// prefix of the downward-API metadata keys.
+pub const K8S_PREFIX: &str = "k8s";
+
+/// time between two checks of the mounted volumes unless
+/// [`KubernetesConfig::watch_interval`] is set. The kubelet syncs them about once a minute.
+pub const DEFAULT_WATCH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// the symlink kubernetes swaps to a new timestamped folder on every update of a volume.
+const VOLUME_DATA_LINK: &str = "..data";
+
+/// KeyPerFileSource reads a mounted ConfigMap or Secret volume.
+///
+/// Every regular file in the directory becomes one config value: the file name is the key
+/// (dots nest, e.g. `logging.console_appender.enable`) and the trimmed content is the value.
+/// Hidden entries such as the `..data` symlink kubernetes maintains are skipped.
+/// A missing directory yields no values.
+#[derive(Debug, Clone)]
+pub struct KeyPerFileSource {
+    dir: PathBuf,
+    prefix: Option<String>,
+}
+
+impl KeyPerFileSource {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            prefix: None,
+        }
+    }
+
+    /// nest every key read from the directory under `prefix`.
+    pub fn with_prefix(mut self, prefix: &str) -> Self {
+        self.prefix = Some(prefix.to_string());
+        self
+    }
+
+    pub fn dir(&self) -> &Path {
+        self.dir.as_path()
+    }
+}
+
+impl Source for KeyPerFileSource {
+    fn clone_into_box(&self) -> Box<dyn Source + Send + Sync> {
+        Box::new(self.clone())
+    }
+
+    fn collect(&self) -> Result<Map<String, Value>, ConfigError> {
+        let mut map = Map::new();
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(map),
+            Err(e) => return Err(ConfigError::Foreign(Box::new(e))),
+        };
+        for entry in entries {
+            let entry = entry.map_err(|e| ConfigError::Foreign(Box::new(e)))?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.starts_with('.') || !entry.path().is_file() {
+                continue;
+            }
+            let content =
+                fs::read_to_string(entry.path()).map_err(|e| ConfigError::Foreign(Box::new(e)))?;
+            let origin = format!("{}{}", FILE_ORIGIN_PREFIX, entry.path().display());
+            let key = match &self.prefix {
+                Some(prefix) => format!("{}.{}", prefix, name),
+                None => name,
+            };
+            map.insert(
+                key,
+                Value::new(Some(&origin), ValueKind::String(content.trim().to_string())),
+            );
+        }
+        Ok(map)
+    }
+}
+
+/// DownwardApiSource exposes pod metadata under the `k8s.*` prefix.
+///
+/// `k8s.pod_name`, `k8s.namespace`, `k8s.node_name` and `k8s.pod_ip` come from the
+/// `POD_NAME`, `POD_NAMESPACE`, `NODE_NAME` and `POD_IP` environment variables that the
+/// deployment maps with `fieldRef`. `k8s.labels.*` and `k8s.annotations.*` come from the
+/// `labels` and `annotations` files of a downward-API volume, when one is mounted.
+#[derive(Debug, Clone)]
+pub struct DownwardApiSource {
+    podinfo_dir: Option<PathBuf>,
+}
+
+const DOWNWARD_API_ENV: [(&str, &str); 4] = [
+    ("pod_name", "POD_NAME"),
+    ("namespace", "POD_NAMESPACE"),
+    ("node_name", "NODE_NAME"),
+    ("pod_ip", "POD_IP"),
+];
+
+impl DownwardApiSource {
+    pub fn new(podinfo_dir: Option<PathBuf>) -> Self {
+        Self { podinfo_dir }
+    }
+
+    fn collect_podinfo(&self, file: &str, map: &mut Map<String, Value>) {
+        let Some(dir) = &self.podinfo_dir else {
+            return;
+        };
+        let path = dir.join(file);
+        let Ok(content) = fs::read_to_string(&path) else {
+            return;
+        };
+        let origin = format!("{}{}", FILE_ORIGIN_PREFIX, path.display());
+        // each line is `key="value"`
+        for line in content.lines() {
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let value = value.trim().trim_matches('"').to_string();
+            // label keys may contain dots, which would otherwise nest
+            let key = key.trim().replace('.', "_");
+            map.insert(
+                format!("{}.{}.{}", K8S_PREFIX, file, key),
+                Value::new(Some(&origin), ValueKind::String(value)),
+            );
+        }
+    }
+}
+
+impl Source for DownwardApiSource {
+    fn clone_into_box(&self) -> Box<dyn Source + Send + Sync> {
+        Box::new(self.clone())
+    }
+
+    fn collect(&self) -> Result<Map<String, Value>, ConfigError> {
+        let mut map = Map::new();
+        for (key, var) in DOWNWARD_API_ENV {
+            if let Ok(value) = env::var(var) {
+                let origin = format!("{}{}", ENV_ORIGIN_PREFIX, var);
+                map.insert(
+                    format!("{}.{}", K8S_PREFIX, key),
+                    Value::new(Some(&origin), ValueKind::String(value)),
+                );
+            }
+        }
+        self.collect_podinfo("labels", &mut map);
+        self.collect_podinfo("annotations", &mut map);
+        Ok(map)
+    }
+}
+
+/// KubernetesConfig describes the volumes layered into Config in kubernetes mode.
+///
+/// Mounted ConfigMaps are layered above `config.toml`, mounted Secrets above the ConfigMaps,
+/// and environment variables stay on top.
+///
+/// # Example
+/// ```
+/// use beaver_bootstrap::config::k8s::KubernetesConfig;
+/// let k8s = KubernetesConfig::default()
+///     .config_map_dir("/etc/beaver/config")
+///     .secret_dir("/etc/beaver/secrets")
+///     .podinfo_dir("/etc/podinfo");
+/// assert_eq!(k8s.sources().len(), 3);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct KubernetesConfig {
+    config_map_dirs: Vec<PathBuf>,
+    secret_dirs: Vec<PathBuf>,
+    podinfo_dir: Option<PathBuf>,
+    watch_interval: Option<Duration>,
+}
+
+impl KubernetesConfig {
+    pub fn config_map_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.config_map_dirs.push(dir.into());
+        self
+    }
+
+    pub fn secret_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.secret_dirs.push(dir.into());
+        self
+    }
+
+    pub fn podinfo_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.podinfo_dir = Some(dir.into());
+        self
+    }
+
+    /// check the mounted volumes every `interval`, [`DEFAULT_WATCH_INTERVAL`] unless set.
+    pub fn watch_interval(mut self, interval: Duration) -> Self {
+        self.watch_interval = Some(interval);
+        self
+    }
+
+    /// the config sources of this mode, in load order.
+    pub fn sources(&self) -> Vec<Box<dyn Source + Send + Sync>> {
+        let mut sources: Vec<Box<dyn Source + Send + Sync>> = Vec::new();
+        for dir in self.config_map_dirs.iter().chain(self.secret_dirs.iter()) {
+            sources.push(Box::new(KeyPerFileSource::new(dir.clone())));
+        }
+        sources.push(Box::new(DownwardApiSource::new(self.podinfo_dir.clone())));
+        sources
+    }
+
+    /// start watching the mounted volumes, calling [`SourceChanged::notify`] when the files
+    /// of one change, `None` when no volume is mounted.
+    ///
+    /// [`Bootstrap::run`](crate::bootstrap::Bootstrap::run) watches the volumes of its
+    /// kubernetes mode while the application runs, a notification reloads the config.
+    ///
+    /// # Example
+    /// ```
+    /// use beaver_bootstrap::config::{k8s::KubernetesConfig, source::SourceChanged};
+    /// let watcher = KubernetesConfig::default()
+    ///     .config_map_dir("/etc/beaver/config")
+    ///     .watch(SourceChanged::new(|| println!("config map changed")))
+    ///     .unwrap();
+    /// assert!(watcher.is_some());
+    /// ```
+    pub fn watch(&self, changed: SourceChanged) -> io::Result<Option<VolumeWatcher>> {
+        let dirs: Vec<PathBuf> = self
+            .config_map_dirs
+            .iter()
+            .chain(self.secret_dirs.iter())
+            .chain(self.podinfo_dir.iter())
+            .cloned()
+            .collect();
+        if dirs.is_empty() {
+            return Ok(None);
+        }
+        let interval = self.watch_interval.unwrap_or(DEFAULT_WATCH_INTERVAL);
+        VolumeWatcher::start(dirs, interval, changed).map(Some)
+    }
+}
+
+/// VolumeWatcher polls mounted volumes for updates, until dropped.
+///
+/// Kubernetes updates a volume by writing a new timestamped folder and swapping the `..data`
+/// symlink to it, so a volume with that link changes with its target. Other folders change
+/// with the names, sizes and modification times of their files.
+pub struct VolumeWatcher {
+    stop: Sender<()>,
+}
+
+impl fmt::Debug for VolumeWatcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VolumeWatcher").finish_non_exhaustive()
+    }
+}
+
+impl VolumeWatcher {
+    fn start(dirs: Vec<PathBuf>, interval: Duration, changed: SourceChanged) -> io::Result<Self> {
+        let (stop, rx) = mpsc::channel::<()>();
+        let mut states: Vec<VolumeState> = dirs.iter().map(|x| VolumeState::read(x)).collect();
+        thread::Builder::new()
+            .name("beaver-k8s-watch".to_string())
+            .spawn(move || {
+                while let Err(RecvTimeoutError::Timeout) = rx.recv_timeout(interval) {
+                    let mut updated = false;
+                    for (dir, state) in dirs.iter().zip(states.iter_mut()) {
+                        let current = VolumeState::read(dir);
+                        if current != *state {
+                            tracing::debug!("mounted volume {} changed", dir.display());
+                            *state = current;
+                            updated = true;
+                        }
+                    }
+                    // volumes updated together reload once
+                    if updated {
+                        changed.notify();
+                    }
+                }
+            })?;
+        Ok(Self { stop })
+    }
+}
+
+impl Drop for VolumeWatcher {
+    fn drop(&mut self) {
+        let _ = self.stop.send(());
+    }
+}
+
+/// what a check of a mounted volume compares with the previous one.
+#[derive(Debug, PartialEq, Eq)]
+enum VolumeState {
+    Missing,
+    Linked(PathBuf),
+    Files(Vec<(String, u64, Option<SystemTime>)>),
+}
+
+impl VolumeState {
+    fn read(dir: &Path) -> Self {
+        if let Ok(target) = fs::read_link(dir.join(VOLUME_DATA_LINK)) {
+            return Self::Linked(target);
+        }
+        let Ok(entries) = fs::read_dir(dir) else {
+            return Self::Missing;
+        };
+        let mut files: Vec<_> = entries
+            .flatten()
+            .filter_map(|entry| {
+                // keys of a volume are symlinks into its data folder, followed here
+                let metadata = fs::metadata(entry.path()).ok()?;
+                let name = entry.file_name().to_string_lossy().to_string();
+                Some((name, metadata.len(), metadata.modified().ok()))
+            })
+            .collect();
+        files.sort();
+        Self::Files(files)
+    }
+}
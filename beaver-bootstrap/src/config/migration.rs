@@ -0,0 +1,119 @@
+use config::{ConfigError, Map, Source, Value, ValueKind};
+
+use super::Config;
+
+/// key holding the layout version of a config file.
+pub const CONFIG_VERSION_KEY: &str = "config_version";
+
+/// the layout version assumed when a config file has no `config_version` key.
+pub const INITIAL_CONFIG_VERSION: u32 = 1;
+
+type MigrateFn = dyn Fn(&mut Map<String, Value>) -> Result<(), String> + Send + Sync;
+
+/// ConfigMigration upgrades a config layout from `from_version` to `from_version + 1`.
+///
+/// # Example
+/// ```
+/// use beaver_bootstrap::config::migration::ConfigMigration;
+/// // version 2 renamed `logging.appenders` to `logging.file_appenders`
+/// let migration = ConfigMigration::new(1, "rename logging.appenders", |root| {
+///     if let Some(logging) = root.get_mut("logging")
+///         && let config::ValueKind::Table(table) = &mut logging.kind
+///         && let Some(appenders) = table.remove("appenders")
+///     {
+///         table.insert("file_appenders".to_string(), appenders);
+///     }
+///     Ok(())
+/// });
+/// assert_eq!(migration.from_version(), 1);
+/// ```
+pub struct ConfigMigration {
+    from_version: u32,
+    description: String,
+    migrate: Box<MigrateFn>,
+}
+
+impl ConfigMigration {
+    pub fn new<F>(from_version: u32, description: &str, migrate: F) -> Self
+    where
+        F: Fn(&mut Map<String, Value>) -> Result<(), String> + Send + Sync + 'static,
+    {
+        Self {
+            from_version,
+            description: description.to_string(),
+            migrate: Box::new(migrate),
+        }
+    }
+
+    pub fn from_version(&self) -> u32 {
+        self.from_version
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+}
+
+impl std::fmt::Debug for ConfigMigration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ConfigMigration")
+            .field("from_version", &self.from_version)
+            .field("description", &self.description)
+            .finish()
+    }
+}
+
+/// run the migrations needed to bring `config` up to `current_version`.
+///
+/// Returns the migrated config and a warning for every migration that was applied.
+pub(crate) fn migrate(
+    config: Config,
+    current_version: u32,
+    migrations: &[ConfigMigration],
+) -> Result<(Config, Vec<String>), ConfigError> {
+    let version = match config.inner.get::<u32>(CONFIG_VERSION_KEY) {
+        Ok(version) => version,
+        Err(ConfigError::NotFound(_)) => INITIAL_CONFIG_VERSION,
+        Err(e) => return Err(e),
+    };
+    if version > current_version {
+        return Err(ConfigError::Message(format!(
+            "{}={} is newer than the supported version {}",
+            CONFIG_VERSION_KEY, version, current_version
+        )));
+    }
+    if version == current_version {
+        return Ok((config, vec![]));
+    }
+
+    let mut root = config.inner.collect()?;
+    let mut warnings = Vec::new();
+    for step in version..current_version {
+        let Some(migration) = migrations.iter().find(|m| m.from_version == step) else {
+            return Err(ConfigError::Message(format!(
+                "no config migration registered from {}={}",
+                CONFIG_VERSION_KEY, step
+            )));
+        };
+        (migration.migrate)(&mut root).map_err(|e| {
+            ConfigError::Message(format!(
+                "config migration from version {} ({}) failed: {}",
+                step, migration.description, e
+            ))
+        })?;
+        warnings.push(format!(
+            "config migrated from version {} to {}: {}, please update the config file",
+            step,
+            step + 1,
+            migration.description
+        ));
+    }
+    root.insert(
+        CONFIG_VERSION_KEY.to_string(),
+        Value::new(None, ValueKind::U64(current_version as u64)),
+    );
+    let inner = config::Config::builder()
+        .add_source(super::MapSource::new(root))
+        .build()?;
+    Ok((Config::new(inner), warnings))
+}
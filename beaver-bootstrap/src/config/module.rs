@@ -0,0 +1,115 @@
+use std::sync::Arc;
+
+use config::{ConfigError, Value};
+use serde::de::DeserializeOwned;
+
+use super::reload::LiveConfig;
+
+/// prefix of the config namespaces of modules, `[modules.<name>]`.
+pub const MODULES_PREFIX: &str = "modules";
+
+/// ModuleConfig is the view of a module on the config, scoped to its namespace
+/// `modules.<name>`, which follows reloads.
+///
+/// Paths are relative to the namespace. Keys outside of it are read with
+/// [`ModuleConfig::get_global`], which a strict view refuses, so the config a module depends
+/// on stays in its own section.
+///
+/// # Example
+/// ```
+/// use beaver_bootstrap::config::{Config, module::ModuleConfig, reload::LiveConfig};
+/// use std::sync::Arc;
+/// use serde::Deserialize;
+/// #[derive(Deserialize)]
+/// struct CacheConfig {
+///     capacity: u32,
+/// }
+/// let inner = config::Config::builder()
+///     .set_override("modules.cache.capacity", 64)
+///     .unwrap()
+///     .set_override("server.port", 8080)
+///     .unwrap()
+///     .build()
+///     .unwrap();
+/// let live_config = Arc::new(LiveConfig::new(Arc::new(Config::new(inner))));
+/// let cache = ModuleConfig::new(live_config.clone(), "cache", true);
+/// assert_eq!(cache.namespace(), "modules.cache");
+/// assert_eq!(cache.get::<CacheConfig>().unwrap().capacity, 64);
+/// assert!(cache.contains("capacity"));
+/// assert!(cache.get_global("server.port").is_err());
+/// let lenient = ModuleConfig::new(live_config, "cache", false);
+/// assert!(lenient.get_global("server.port").unwrap().is_some());
+/// ```
+#[derive(Clone)]
+pub struct ModuleConfig {
+    live_config: Arc<LiveConfig>,
+    namespace: String,
+    strict: bool,
+}
+
+impl std::fmt::Debug for ModuleConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ModuleConfig")
+            .field("namespace", &self.namespace)
+            .field("strict", &self.strict)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ModuleConfig {
+    /// the view of module `module` on `live_config`, refusing reads outside of its namespace
+    /// when `strict`.
+    pub fn new(live_config: Arc<LiveConfig>, module: &str, strict: bool) -> Self {
+        Self {
+            live_config,
+            namespace: format!("{}.{}", MODULES_PREFIX, module),
+            strict,
+        }
+    }
+
+    /// path of the namespace, e.g. `modules.cache`.
+    pub fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    /// the namespace deserialized as `T`, an absent namespace deserializes like an empty one.
+    pub fn get<T: DeserializeOwned>(&self) -> Result<T, ConfigError> {
+        self.live_config.current().get_at(&self.namespace)
+    }
+
+    /// raw value at `path` of the namespace, e.g. `pool.size` or `hosts[0]`.
+    pub fn get_value(&self, path: &str) -> Option<Value> {
+        self.live_config.current().get_value(&self.path(path))
+    }
+
+    pub fn contains(&self, path: &str) -> bool {
+        self.get_value(path).is_some()
+    }
+
+    /// raw value at the absolute `path`, an error for a strict view unless the path is in
+    /// the namespace.
+    pub fn get_global(&self, path: &str) -> Result<Option<Value>, ConfigError> {
+        let inside = path
+            .strip_prefix(&self.namespace)
+            .is_some_and(|x| x.is_empty() || x.starts_with(['.', '[']));
+        if self.strict && !inside {
+            return Err(ConfigError::Message(format!(
+                "{} is outside of the config namespace {}",
+                path, self.namespace
+            )));
+        }
+        Ok(self.live_config.current().get_value(path))
+    }
+
+    fn path(&self, path: &str) -> String {
+        if path.is_empty() || path.starts_with('[') {
+            format!("{}{}", self.namespace, path)
+        } else {
+            format!("{}.{}", self.namespace, path)
+        }
+    }
+}
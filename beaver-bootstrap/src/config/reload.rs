@@ -0,0 +1,283 @@
+use std::sync::{Arc, Mutex, RwLock};
+
+use config::ConfigError;
+#[cfg(feature = "full")]
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+#[cfg(feature = "full")]
+use tokio::sync::watch;
+
+use super::{Config, ConfigDiff, ConfigPrefix, history::ConfigHistory};
+use crate::error::BootstrapError;
+
+/// ConfigSubscriber is a component following the reloads of the config.
+///
+/// A reload is applied in two phases: every subscriber validates the proposed config first,
+/// and only once all of them accepted it the active snapshot is swapped and `apply` is called.
+pub trait ConfigSubscriber: Send + Sync {
+    /// name of the component in the logs of rejected reloads.
+    fn name(&self) -> String {
+        std::any::type_name::<Self>().to_string()
+    }
+
+    /// check the `proposed` config, an error vetoes the reload with its reason.
+    fn validate(&self, _proposed: &Config, _diff: &ConfigDiff) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// switch to `config`, which every subscriber accepted.
+    fn apply(&self, config: &Config, diff: &ConfigDiff);
+}
+
+/// LiveConfig holds the active config snapshot and applies reloads to its subscribers.
+///
+/// The `Config` service stays the snapshot of startup, components following reloads resolve
+/// `LiveConfig` and read [`LiveConfig::current`].
+///
+/// # Example
+/// ```
+/// use beaver_bootstrap::config::{
+///     Config, ConfigDiff,
+///     reload::{ConfigSubscriber, LiveConfig},
+/// };
+/// use std::sync::Arc;
+/// struct Pool;
+/// impl ConfigSubscriber for Pool {
+///     fn validate(&self, proposed: &Config, _diff: &ConfigDiff) -> Result<(), String> {
+///         match proposed.get_value("pool.size").and_then(|x| x.into_int().ok()) {
+///             Some(size) if size > 0 => Ok(()),
+///             _ => Err("pool.size must be positive".to_string()),
+///         }
+///     }
+///     fn apply(&self, _config: &Config, _diff: &ConfigDiff) {}
+/// }
+/// let config = |size: i64| {
+///     let inner = config::Config::builder().set_override("pool.size", size).unwrap();
+///     Config::new(inner.build().unwrap())
+/// };
+/// let live = LiveConfig::new(Arc::new(config(4)));
+/// live.subscribe(Arc::new(Pool));
+/// assert!(live.propose(config(0)).is_err());
+/// assert!(live.current().contains("pool.size"));
+/// assert_eq!(live.propose(config(8)).unwrap().changes().len(), 1);
+/// ```
+pub struct LiveConfig {
+    active: RwLock<Arc<Config>>,
+    subscribers: RwLock<Vec<Arc<dyn ConfigSubscriber>>>,
+    history: Option<Arc<ConfigHistory>>,
+    /// one reload at a time, so validation and application see the same active snapshot.
+    reloading: Mutex<()>,
+}
+
+impl std::fmt::Debug for LiveConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let subscribers: Vec<String> = self
+            .subscribers
+            .read()
+            .map(|x| x.iter().map(|s| s.name()).collect())
+            .unwrap_or_default();
+        f.debug_struct("LiveConfig")
+            .field("subscribers", &subscribers)
+            .finish_non_exhaustive()
+    }
+}
+
+impl LiveConfig {
+    pub fn new(config: Arc<Config>) -> Self {
+        Self {
+            active: RwLock::new(config),
+            subscribers: RwLock::default(),
+            history: None,
+            reloading: Mutex::default(),
+        }
+    }
+
+    /// record every applied config in `history`, which should hold the active one already.
+    pub fn with_history(mut self, history: Arc<ConfigHistory>) -> Self {
+        self.history = Some(history);
+        self
+    }
+
+    pub fn history(&self) -> Option<Arc<ConfigHistory>> {
+        self.history.clone()
+    }
+
+    /// the active snapshot.
+    pub fn current(&self) -> Arc<Config> {
+        self.active
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// the section of `T` of the active snapshot, see [`Config::get_cached`]. The first read
+    /// after a reload deserializes the section of the new snapshot.
+    pub fn get_cached<T>(&self) -> Result<Arc<T>, ConfigError>
+    where
+        T: ConfigPrefix + DeserializeOwned + Send + Sync + 'static,
+    {
+        self.current().get_cached()
+    }
+
+    pub fn subscribe(&self, subscriber: Arc<dyn ConfigSubscriber>) {
+        self.subscribers
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(subscriber);
+    }
+
+    /// a channel of the section of `T`, holding the section of the active snapshot and
+    /// updated only by reloads which change its deserialized value.
+    ///
+    /// Sections are compared by their serialized form, so a reload rewriting a value to an
+    /// equivalent one, e.g. `level = "INFO"` to `level = "info"`, is not sent. A reload whose
+    /// section of `T` does not deserialize is rejected.
+    ///
+    /// # Example
+    /// ```
+    /// use std::sync::Arc;
+    /// use beaver_bootstrap::config::{Config, ConfigPrefix, reload::LiveConfig};
+    /// #[derive(serde::Serialize, serde::Deserialize)]
+    /// struct PoolConfig {
+    ///     size: u32,
+    /// }
+    /// impl ConfigPrefix for PoolConfig {
+    ///     const PREFIX: &'static str = "pool";
+    /// }
+    /// let config = |size: i64, port: i64| {
+    ///     let inner = config::Config::builder()
+    ///         .set_override("pool.size", size)
+    ///         .unwrap()
+    ///         .set_override("server.port", port)
+    ///         .unwrap();
+    ///     Config::new(inner.build().unwrap())
+    /// };
+    /// let live = LiveConfig::new(Arc::new(config(4, 8080)));
+    /// let mut pool = live.subscribe_section::<PoolConfig>().unwrap();
+    /// live.propose(config(4, 9090)).unwrap();
+    /// assert!(!pool.has_changed().unwrap());
+    /// live.propose(config(8, 9090)).unwrap();
+    /// assert!(pool.has_changed().unwrap());
+    /// assert_eq!(pool.borrow_and_update().size, 8);
+    /// ```
+    #[cfg(feature = "full")]
+    pub fn subscribe_section<T>(&self) -> Result<watch::Receiver<Arc<T>>, ConfigError>
+    where
+        T: ConfigPrefix + Serialize + DeserializeOwned + Send + Sync + 'static,
+    {
+        let (sender, receiver) = watch::channel(self.current().get_cached::<T>()?);
+        self.subscribe(Arc::new(SectionWatch { sender }));
+        Ok(receiver)
+    }
+
+    /// validate `proposed` with every subscriber, then make it the active snapshot and apply
+    /// it, returning the changes.
+    ///
+    /// Vetoes are logged with the rejecting component and nothing is applied. An unchanged
+    /// config is not handed to the subscribers.
+    pub fn propose(&self, proposed: Config) -> Result<ConfigDiff, BootstrapError> {
+        self.propose_from(proposed, "reload")
+    }
+
+    /// make snapshot `version` of the history the active config again, like a reload.
+    pub fn rollback(&self, version: u64) -> Result<ConfigDiff, BootstrapError> {
+        let snapshot = self
+            .history
+            .as_ref()
+            .and_then(|x| x.get(version))
+            .ok_or(BootstrapError::ConfigSnapshotNotFoundError(version))?;
+        let config = Config::clone(&snapshot.config());
+        self.propose_from(config, &format!("rollback to {}", version))
+    }
+
+    /// like [`LiveConfig::propose`], recording `source` in the history.
+    pub fn propose_from(
+        &self,
+        proposed: Config,
+        source: &str,
+    ) -> Result<ConfigDiff, BootstrapError> {
+        let _reloading = self.reloading.lock().unwrap_or_else(|e| e.into_inner());
+        let diff = self
+            .current()
+            .diff(&proposed)
+            .map_err(BootstrapError::ConfigLoadError)?;
+        if diff.is_empty() {
+            return Ok(diff);
+        }
+        let subscribers = self
+            .subscribers
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        let vetoes: Vec<String> = subscribers
+            .iter()
+            .filter_map(|subscriber| {
+                let reason = subscriber.validate(&proposed, &diff).err()?;
+                tracing::warn!(
+                    "config reload rejected by {}: {}",
+                    subscriber.name(),
+                    reason
+                );
+                Some(format!("{}: {}", subscriber.name(), reason))
+            })
+            .collect();
+        if !vetoes.is_empty() {
+            return Err(BootstrapError::ConfigReloadRejectedError(vetoes.join("; ")));
+        }
+        let proposed = Arc::new(proposed);
+        *self.active.write().unwrap_or_else(|e| e.into_inner()) = proposed.clone();
+        if let Some(history) = &self.history {
+            history.record(proposed.clone(), source);
+        }
+        diff.log();
+        for subscriber in &subscribers {
+            subscriber.apply(&proposed, &diff);
+        }
+        Ok(diff)
+    }
+}
+
+/// SectionWatch sends the section of `T` of every applied config which changed it, see
+/// [`LiveConfig::subscribe_section`].
+#[cfg(feature = "full")]
+struct SectionWatch<T> {
+    sender: watch::Sender<Arc<T>>,
+}
+
+#[cfg(feature = "full")]
+impl<T> ConfigSubscriber for SectionWatch<T>
+where
+    T: ConfigPrefix + Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+    fn name(&self) -> String {
+        format!("section watch of [{}]", T::PREFIX)
+    }
+
+    fn validate(&self, proposed: &Config, diff: &ConfigDiff) -> Result<(), String> {
+        if self.sender.is_closed() || !diff.touches(T::PREFIX) {
+            return Ok(());
+        }
+        proposed
+            .get_cached::<T>()
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+
+    fn apply(&self, config: &Config, diff: &ConfigDiff) {
+        // every receiver is dropped, or no key of the section changed
+        if self.sender.is_closed() || !diff.touches(T::PREFIX) {
+            return;
+        }
+        let Ok(section) = config.get_cached::<T>() else {
+            return;
+        };
+        self.sender.send_if_modified(|current| {
+            let changed =
+                serde_json::to_value(&**current).ok() != serde_json::to_value(&*section).ok();
+            if changed {
+                *current = section;
+            }
+            changed
+        });
+    }
+}
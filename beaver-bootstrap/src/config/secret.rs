@@ -0,0 +1,532 @@
+use std::{
+    env,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+#[cfg(feature = "aws")]
+pub mod aws;
+#[cfg(feature = "gcp")]
+pub mod gcp;
+
+use aes_gcm::{
+    Aes256Gcm, Key, Nonce,
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use config::{ConfigError, Source, Value, ValueKind};
+use serde::{Deserialize, Serialize};
+
+use super::{Config, ConfigPrefix, MapSource};
+use crate::{error::BootstrapError, serde::duration_opt};
+
+/// prefix marking an encrypted config value, e.g. `password = "enc:..."`.
+pub const ENCRYPTED_VALUE_PREFIX: &str = "enc:";
+
+/// default environment variable holding the base64 encoded config key.
+pub const DEFAULT_CONFIG_KEY_ENV: &str = "BEAVER_CONFIG_KEY";
+
+/// time a key fetched from a cloud backend is used before it is fetched again, unless
+/// `secrets.ttl` is set.
+pub const DEFAULT_SECRETS_TTL: Duration = Duration::from_secs(300);
+/// time the last key of a cloud backend is still used while fetching it fails, unless
+/// `secrets.max_stale` is set.
+pub const DEFAULT_SECRETS_MAX_STALE: Duration = Duration::from_secs(3600);
+
+const KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+
+/// SecretsBackend is where the config key is read from.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SecretsBackend {
+    /// an environment variable, see [`EnvKeyProvider`].
+    #[default]
+    Env,
+    /// a secret of AWS Secrets Manager, with the `aws` feature.
+    Aws,
+    /// a secret version of GCP Secret Manager, with the `gcp` feature.
+    Gcp,
+}
+
+impl SecretsBackend {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SecretsBackend::Env => "env",
+            SecretsBackend::Aws => "aws",
+            SecretsBackend::Gcp => "gcp",
+        }
+    }
+}
+
+/// SecretsConfig selects the backend holding the key of the `enc:` config values, see
+/// `[secrets]`:
+///
+/// ```toml
+/// [secrets]
+/// backend = "aws"
+/// ttl = "10m"
+/// aws = { secret_id = "billing/config-key", region = "eu-west-1" }
+/// ```
+///
+/// The cloud backends resolve their credentials with the standard chain of their SDK, the
+/// environment, the profile files, web identity, then the instance or pod metadata, and
+/// their key is cached for `ttl`, see [`CachedKeyProvider`]. The secret holds the key base64
+/// encoded, or its 32 raw bytes. The section is read before the config is decrypted, so its
+/// values must not be encrypted. Other keys of `[secrets]` are left to the application.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SecretsConfig {
+    backend: SecretsBackend,
+    /// variable of the `env` backend, [`DEFAULT_CONFIG_KEY_ENV`] by default.
+    key_env: Option<String>,
+    /// time a fetched key is used, [`DEFAULT_SECRETS_TTL`] by default.
+    #[serde(deserialize_with = "duration_opt")]
+    ttl: Option<Duration>,
+    /// time the last key is used while fetching it fails, [`DEFAULT_SECRETS_MAX_STALE`] by
+    /// default.
+    #[serde(deserialize_with = "duration_opt")]
+    max_stale: Option<Duration>,
+    aws: AwsSecretConfig,
+    gcp: GcpSecretConfig,
+}
+
+impl ConfigPrefix for SecretsConfig {
+    const PREFIX: &'static str = "secrets";
+}
+
+/// AwsSecretConfig is the secret of the `aws` backend, see `[secrets.aws]`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AwsSecretConfig {
+    /// name or ARN of the secret.
+    secret_id: String,
+    /// region of the secret, the one of the SDK chain by default.
+    region: Option<String>,
+    /// staging label of the version, `AWSCURRENT` by default.
+    version_stage: Option<String>,
+    /// url of the service, `https://secretsmanager.<region>.amazonaws.com` by default.
+    endpoint: Option<String>,
+}
+
+impl AwsSecretConfig {
+    pub fn secret_id(&self) -> &str {
+        &self.secret_id
+    }
+
+    pub fn region(&self) -> Option<&str> {
+        self.region.as_deref()
+    }
+
+    pub fn version_stage(&self) -> Option<&str> {
+        self.version_stage.as_deref()
+    }
+
+    pub fn endpoint(&self) -> Option<&str> {
+        self.endpoint.as_deref()
+    }
+}
+
+/// GcpSecretConfig is the secret of the `gcp` backend, see `[secrets.gcp]`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GcpSecretConfig {
+    /// resource name of the version, `projects/<project>/secrets/<secret>/versions/latest`.
+    name: String,
+    /// url of the service, `https://secretmanager.googleapis.com` by default.
+    endpoint: Option<String>,
+}
+
+impl GcpSecretConfig {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn endpoint(&self) -> Option<&str> {
+        self.endpoint.as_deref()
+    }
+}
+
+impl SecretsConfig {
+    pub fn new(config: &Config) -> Result<Self, BootstrapError> {
+        let secrets_config = config
+            .get::<SecretsConfig>()
+            .map_err(BootstrapError::ConfigLoadError)?;
+        match secrets_config.backend {
+            SecretsBackend::Aws if secrets_config.aws.secret_id.is_empty() => {
+                return Err(BootstrapError::InvalidConfigValueError(
+                    "secrets.aws.secret_id is required by the aws backend".to_string(),
+                ));
+            }
+            SecretsBackend::Gcp if secrets_config.gcp.name.is_empty() => {
+                return Err(BootstrapError::InvalidConfigValueError(
+                    "secrets.gcp.name is required by the gcp backend".to_string(),
+                ));
+            }
+            _ => {}
+        }
+        if secrets_config.ttl.is_some_and(|x| x.is_zero()) {
+            return Err(BootstrapError::InvalidConfigValueError(
+                "secrets.ttl=0".to_string(),
+            ));
+        }
+        Ok(secrets_config)
+    }
+
+    pub fn backend(&self) -> SecretsBackend {
+        self.backend
+    }
+
+    pub fn ttl(&self) -> Duration {
+        self.ttl.unwrap_or(DEFAULT_SECRETS_TTL)
+    }
+
+    pub fn max_stale(&self) -> Duration {
+        self.max_stale.unwrap_or(DEFAULT_SECRETS_MAX_STALE)
+    }
+
+    pub fn aws(&self) -> &AwsSecretConfig {
+        &self.aws
+    }
+
+    pub fn gcp(&self) -> &GcpSecretConfig {
+        &self.gcp
+    }
+
+    /// the provider of the selected backend, the cloud ones behind a [`CachedKeyProvider`].
+    ///
+    /// Fails when the backend is not compiled in, its feature being off.
+    ///
+    /// # Example
+    /// ```
+    /// use beaver_bootstrap::config::{Config, secret::SecretsConfig};
+    /// let inner = config::Config::builder()
+    ///     .set_override("secrets.backend", "aws")
+    ///     .unwrap()
+    ///     .set_override("secrets.aws.secret_id", "billing/config-key")
+    ///     .unwrap();
+    /// let secrets = SecretsConfig::new(&Config::new(inner.build().unwrap())).unwrap();
+    /// assert_eq!(secrets.aws().secret_id(), "billing/config-key");
+    /// // resolves the AWS credentials only once the key is needed
+    /// assert_eq!(secrets.key_provider().is_ok(), cfg!(feature = "aws"));
+    /// ```
+    pub fn key_provider(&self) -> Result<Box<dyn SecretKeyProvider>, BootstrapError> {
+        match self.backend {
+            SecretsBackend::Env => {
+                let var = self.key_env.as_deref().unwrap_or(DEFAULT_CONFIG_KEY_ENV);
+                Ok(Box::new(EnvKeyProvider::new(var)))
+            }
+            #[cfg(feature = "aws")]
+            SecretsBackend::Aws => Ok(Box::new(CachedKeyProvider::new(
+                Box::new(aws::AwsSecretsManagerKeyProvider::new(self.aws.clone())),
+                self.ttl(),
+                self.max_stale(),
+            ))),
+            #[cfg(feature = "gcp")]
+            SecretsBackend::Gcp => Ok(Box::new(CachedKeyProvider::new(
+                Box::new(gcp::GcpSecretManagerKeyProvider::new(self.gcp.clone())),
+                self.ttl(),
+                self.max_stale(),
+            ))),
+            #[allow(unreachable_patterns)]
+            backend => Err(BootstrapError::InvalidConfigValueError(format!(
+                "secrets.backend={} requires the {} feature of beaver-bootstrap",
+                backend.name(),
+                backend.name()
+            ))),
+        }
+    }
+}
+
+/// SecretKeyProvider supplies the key used to decrypt `enc:` config values.
+///
+/// The key is only requested when the config actually contains an encrypted value.
+/// Implement it to fetch the key from a KMS or a secrets store.
+pub trait SecretKeyProvider: Send + Sync {
+    /// return the 256-bit key.
+    fn key(&self) -> Result<[u8; KEY_LEN], String>;
+}
+
+/// EnvKeyProvider reads a base64 encoded 256-bit key from an environment variable.
+#[derive(Debug, Clone)]
+pub struct EnvKeyProvider {
+    var: String,
+}
+
+impl EnvKeyProvider {
+    pub fn new(var: &str) -> Self {
+        Self {
+            var: var.to_string(),
+        }
+    }
+}
+
+impl Default for EnvKeyProvider {
+    fn default() -> Self {
+        Self::new(DEFAULT_CONFIG_KEY_ENV)
+    }
+}
+
+impl SecretKeyProvider for EnvKeyProvider {
+    fn key(&self) -> Result<[u8; KEY_LEN], String> {
+        let encoded = env::var(&self.var).map_err(|e| format!("{}: {}", self.var, e))?;
+        decode_key(&encoded).map_err(|e| format!("{}: {}", self.var, e))
+    }
+}
+
+/// StaticKeyProvider hands out a fixed key, mostly useful in tests and tools.
+#[derive(Clone)]
+pub struct StaticKeyProvider {
+    key: [u8; KEY_LEN],
+}
+
+impl StaticKeyProvider {
+    pub fn new(key: [u8; KEY_LEN]) -> Self {
+        Self { key }
+    }
+}
+
+impl SecretKeyProvider for StaticKeyProvider {
+    fn key(&self) -> Result<[u8; KEY_LEN], String> {
+        Ok(self.key)
+    }
+}
+
+/// CachedKeyProvider caches the key of a remote provider and refreshes it after a TTL.
+///
+/// Wrap cloud secrets backends with it so repeated loads don't hit the remote store, and a
+/// rotated key is picked up once the TTL expires. When a refresh fails the last good key is
+/// kept until `max_stale` has passed.
+///
+/// # Example
+/// ```
+/// use std::time::Duration;
+/// use beaver_bootstrap::config::secret::{CachedKeyProvider, SecretKeyProvider, StaticKeyProvider};
+/// let provider = CachedKeyProvider::new(
+///     Box::new(StaticKeyProvider::new([7; 32])),
+///     Duration::from_secs(300),
+///     Duration::from_secs(3600),
+/// );
+/// assert_eq!(provider.key().unwrap(), [7; 32]);
+/// ```
+pub struct CachedKeyProvider {
+    inner: Box<dyn SecretKeyProvider>,
+    ttl: Duration,
+    max_stale: Duration,
+    cached: Mutex<Option<([u8; KEY_LEN], Instant)>>,
+}
+
+impl CachedKeyProvider {
+    pub fn new(inner: Box<dyn SecretKeyProvider>, ttl: Duration, max_stale: Duration) -> Self {
+        Self {
+            inner,
+            ttl,
+            max_stale,
+            cached: Mutex::new(None),
+        }
+    }
+
+    /// drop the cached key so the next call fetches it again.
+    pub fn invalidate(&self) {
+        if let Ok(mut cached) = self.cached.lock() {
+            cached.take();
+        }
+    }
+}
+
+impl SecretKeyProvider for CachedKeyProvider {
+    fn key(&self) -> Result<[u8; KEY_LEN], String> {
+        let mut cached = self
+            .cached
+            .lock()
+            .map_err(|_| "secret key cache is poisoned".to_string())?;
+        if let Some((key, fetched_at)) = *cached
+            && fetched_at.elapsed() < self.ttl
+        {
+            return Ok(key);
+        }
+        match self.inner.key() {
+            Ok(key) => {
+                let _ = cached.insert((key, Instant::now()));
+                Ok(key)
+            }
+            Err(e) => match *cached {
+                Some((key, fetched_at)) if fetched_at.elapsed() < self.ttl + self.max_stale => {
+                    Ok(key)
+                }
+                _ => Err(e),
+            },
+        }
+    }
+}
+
+/// decode a base64 encoded 256-bit key.
+pub fn decode_key(encoded: &str) -> Result<[u8; KEY_LEN], String> {
+    let bytes = STANDARD
+        .decode(encoded.trim())
+        .map_err(|e| format!("invalid base64 key: {}", e))?;
+    bytes
+        .try_into()
+        .map_err(|v: Vec<u8>| format!("key must be {} bytes, got {}", KEY_LEN, v.len()))
+}
+
+/// the key held by a secret of a cloud backend: base64 encoded, or its raw bytes.
+#[cfg(any(feature = "aws", feature = "gcp"))]
+fn secret_key(secret: &[u8]) -> Result<[u8; KEY_LEN], String> {
+    if let Ok(key) = <[u8; KEY_LEN]>::try_from(secret) {
+        return Ok(key);
+    }
+    let encoded = std::str::from_utf8(secret)
+        .map_err(|_| format!("secret is neither {} bytes nor base64", KEY_LEN))?;
+    decode_key(encoded)
+}
+
+/// runtime of the fetches of the cloud backends, started on the first one.
+#[cfg(any(feature = "aws", feature = "gcp"))]
+static SECRETS_RUNTIME: std::sync::LazyLock<Result<tokio::runtime::Runtime, String>> =
+    std::sync::LazyLock::new(|| {
+        tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .thread_name("beaver-secrets")
+            .enable_all()
+            .build()
+            .map_err(|e| format!("unable to start the secrets runtime: {}", e))
+    });
+
+/// client of the cloud backends, sharing its connections between fetches.
+#[cfg(any(feature = "aws", feature = "gcp"))]
+static HTTP_CLIENT: std::sync::LazyLock<reqwest::Client> =
+    std::sync::LazyLock::new(reqwest::Client::new);
+
+/// run `future` to completion on the secrets runtime, waiting on a channel so it works from
+/// both sync and async callers of [`SecretKeyProvider::key`].
+#[cfg(any(feature = "aws", feature = "gcp"))]
+fn block_on<F>(future: F) -> Result<F::Output, String>
+where
+    F: std::future::Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    let runtime = SECRETS_RUNTIME.as_ref().map_err(Clone::clone)?;
+    let (sender, receiver) = std::sync::mpsc::channel();
+    runtime.spawn(async move {
+        let _ = sender.send(future.await);
+    });
+    receiver
+        .recv()
+        .map_err(|_| "secrets runtime dropped the fetch".to_string())
+}
+
+/// generate a random 256-bit key, base64 encoded.
+pub fn generate_key() -> String {
+    STANDARD.encode(Aes256Gcm::generate_key(&mut OsRng))
+}
+
+/// ConfigCipher encrypts and decrypts config values with AES-256-GCM.
+///
+/// Encrypted values are written as `enc:` followed by the base64 encoded nonce and ciphertext.
+///
+/// # Example
+/// ```
+/// use beaver_bootstrap::config::secret::{ConfigCipher, decode_key, generate_key};
+/// let cipher = ConfigCipher::new(decode_key(&generate_key()).unwrap());
+/// let value = cipher.encrypt("p@ssw0rd").unwrap();
+/// assert!(value.starts_with("enc:"));
+/// assert_eq!(cipher.decrypt(&value).unwrap(), "p@ssw0rd");
+/// ```
+pub struct ConfigCipher {
+    cipher: Aes256Gcm,
+}
+
+impl ConfigCipher {
+    pub fn new(key: [u8; KEY_LEN]) -> Self {
+        Self {
+            cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key)),
+        }
+    }
+
+    pub fn encrypt(&self, plaintext: &str) -> Result<String, String> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, plaintext.as_bytes())
+            .map_err(|e| format!("unable to encrypt value: {}", e))?;
+        let mut payload = nonce.to_vec();
+        payload.extend(ciphertext);
+        Ok(format!(
+            "{}{}",
+            ENCRYPTED_VALUE_PREFIX,
+            STANDARD.encode(payload)
+        ))
+    }
+
+    pub fn decrypt(&self, value: &str) -> Result<String, String> {
+        let encoded = value
+            .strip_prefix(ENCRYPTED_VALUE_PREFIX)
+            .ok_or_else(|| format!("value is not prefixed with {}", ENCRYPTED_VALUE_PREFIX))?;
+        let payload = STANDARD
+            .decode(encoded)
+            .map_err(|e| format!("invalid base64 payload: {}", e))?;
+        if payload.len() <= NONCE_LEN {
+            return Err("encrypted payload is too short".to_string());
+        }
+        let (nonce, ciphertext) = payload.split_at(NONCE_LEN);
+        let plaintext = self
+            .cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| "unable to decrypt value, wrong key or corrupted value".to_string())?;
+        String::from_utf8(plaintext).map_err(|e| format!("decrypted value is not utf-8: {}", e))
+    }
+}
+
+/// replace every `enc:` value of `config` with its plaintext.
+pub(crate) fn decrypt(
+    config: Config,
+    key_provider: &dyn SecretKeyProvider,
+) -> Result<Config, ConfigError> {
+    let mut root = config.inner.collect()?;
+    if !root.values().any(contains_encrypted) {
+        return Ok(config);
+    }
+    let key = key_provider
+        .key()
+        .map_err(|e| ConfigError::Message(format!("unable to get config key: {}", e)))?;
+    let cipher = ConfigCipher::new(key);
+    for (key, value) in root.iter_mut() {
+        decrypt_value(key, value, &cipher)?;
+    }
+    let inner = config::Config::builder()
+        .add_source(MapSource::new(root))
+        .build()?;
+    Ok(Config::new(inner))
+}
+
+fn contains_encrypted(value: &Value) -> bool {
+    match &value.kind {
+        ValueKind::String(s) => s.starts_with(ENCRYPTED_VALUE_PREFIX),
+        ValueKind::Array(arr) => arr.iter().any(contains_encrypted),
+        ValueKind::Table(table) => table.values().any(contains_encrypted),
+        _ => false,
+    }
+}
+
+fn decrypt_value(path: &str, value: &mut Value, cipher: &ConfigCipher) -> Result<(), ConfigError> {
+    match &mut value.kind {
+        ValueKind::String(s) if s.starts_with(ENCRYPTED_VALUE_PREFIX) => {
+            *s = cipher
+                .decrypt(s)
+                .map_err(|e| ConfigError::Message(format!("{}: {}", path, e)))?;
+        }
+        ValueKind::Array(arr) => {
+            for (index, item) in arr.iter_mut().enumerate() {
+                decrypt_value(&format!("{}[{}]", path, index), item, cipher)?;
+            }
+        }
+        ValueKind::Table(table) => {
+            for (key, item) in table.iter_mut() {
+                decrypt_value(&format!("{}.{}", path, key), item, cipher)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
@@ -0,0 +1,181 @@
+use std::{sync::Arc, time::SystemTime};
+
+use aws_config::{BehaviorVersion, Region, SdkConfig};
+pub use aws_credential_types::Credentials;
+use aws_credential_types::provider::{ProvideCredentials, SharedCredentialsProvider};
+use aws_sigv4::{
+    http_request::{SignableBody, SignableRequest, SigningSettings, sign},
+    sign::v4,
+};
+use serde::Deserialize;
+use tokio::sync::OnceCell;
+
+use super::{AwsSecretConfig, HTTP_CLIENT, KEY_LEN, SecretKeyProvider, block_on, secret_key};
+
+/// target of the `GetSecretValue` action of the JSON protocol of Secrets Manager.
+const GET_SECRET_VALUE: &str = "secretsmanager.GetSecretValue";
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct SecretValue {
+    secret_string: Option<String>,
+    /// base64 encoded.
+    secret_binary: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ServiceError {
+    #[serde(rename = "__type", default)]
+    kind: String,
+    #[serde(default, alias = "Message")]
+    message: String,
+}
+
+/// AwsSecretsManagerKeyProvider reads the config key from a secret of AWS Secrets Manager,
+/// `[secrets] backend = "aws"`.
+///
+/// The credentials and the default region come from the chain of the AWS SDK: the
+/// `AWS_*` environment variables, the shared config and credentials files, web identity
+/// tokens of EKS, then the ECS or EC2 metadata endpoints. The chain is resolved once, on
+/// the first fetch. Every call fetches the secret, wrap the provider in a
+/// [`CachedKeyProvider`](super::CachedKeyProvider).
+#[derive(Debug, Clone)]
+pub struct AwsSecretsManagerKeyProvider {
+    secret: AwsSecretConfig,
+    credentials: Option<SharedCredentialsProvider>,
+    sdk_config: Arc<OnceCell<SdkConfig>>,
+}
+
+impl AwsSecretsManagerKeyProvider {
+    pub fn new(secret: AwsSecretConfig) -> Self {
+        Self {
+            secret,
+            credentials: None,
+            sdk_config: Arc::new(OnceCell::new()),
+        }
+    }
+
+    /// sign with `credentials` instead of the ones of the SDK chain.
+    pub fn with_credentials(mut self, credentials: impl ProvideCredentials + 'static) -> Self {
+        self.credentials = Some(SharedCredentialsProvider::new(credentials));
+        self
+    }
+
+    /// the config of the SDK chain, loaded once.
+    async fn sdk_config(&self) -> &SdkConfig {
+        self.sdk_config
+            .get_or_init(|| async {
+                let mut loader = aws_config::defaults(BehaviorVersion::latest());
+                if let Some(region) = self.secret.region() {
+                    loader = loader.region(Region::new(region.to_string()));
+                }
+                loader.load().await
+            })
+            .await
+    }
+
+    async fn fetch(self) -> Result<Vec<u8>, String> {
+        let (region, credentials) = match (self.secret.region(), &self.credentials) {
+            // nothing left to the chain
+            (Some(region), Some(credentials)) => (region.to_string(), credentials.clone()),
+            (region, credentials) => {
+                let sdk_config = self.sdk_config().await;
+                let region = region
+                    .map(str::to_string)
+                    .or_else(|| sdk_config.region().map(Region::to_string))
+                    .ok_or("no AWS region, set secrets.aws.region or AWS_REGION")?;
+                let credentials = credentials
+                    .clone()
+                    .or_else(|| sdk_config.credentials_provider())
+                    .ok_or("no AWS credentials provider")?;
+                (region, credentials)
+            }
+        };
+        let credentials = credentials
+            .provide_credentials()
+            .await
+            .map_err(|e| format!("unable to resolve AWS credentials: {}", e))?;
+        let url = match self.secret.endpoint() {
+            Some(endpoint) => endpoint.to_string(),
+            None => format!("https://secretsmanager.{}.amazonaws.com/", region),
+        };
+        let mut body = serde_json::json!({ "SecretId": self.secret.secret_id() });
+        if let Some(stage) = self.secret.version_stage() {
+            body["VersionStage"] = stage.into();
+        }
+        let body = body.to_string();
+        let mut headers = vec![
+            ("content-type", "application/x-amz-json-1.1".to_string()),
+            ("x-amz-target", GET_SECRET_VALUE.to_string()),
+        ];
+        let identity = credentials.into();
+        let params = v4::SigningParams::builder()
+            .identity(&identity)
+            .region(&region)
+            .name("secretsmanager")
+            .time(SystemTime::now())
+            .settings(SigningSettings::default())
+            .build()
+            .map_err(|e| format!("unable to sign the request: {}", e))?
+            .into();
+        let request = SignableRequest::new(
+            "POST",
+            &url,
+            headers.iter().map(|(k, v)| (*k, v.as_str())),
+            SignableBody::Bytes(body.as_bytes()),
+        )
+        .map_err(|e| format!("unable to sign the request: {}", e))?;
+        let (instructions, _) = sign(request, &params)
+            .map_err(|e| format!("unable to sign the request: {}", e))?
+            .into_parts();
+        headers.extend(
+            instructions
+                .headers()
+                .map(|(k, v)| (k, v.to_string()))
+                .collect::<Vec<_>>(),
+        );
+        let mut request = HTTP_CLIENT.post(&url).body(body);
+        for (key, value) in headers {
+            request = request.header(key, value);
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| format!("{}: {}", url, e))?;
+        let status = response.status();
+        let text = response
+            .text()
+            .await
+            .map_err(|e| format!("{}: {}", url, e))?;
+        if !status.is_success() {
+            let error = serde_json::from_str::<ServiceError>(&text)
+                .map(|e| format!("{} {}", e.kind, e.message))
+                .unwrap_or(text);
+            return Err(format!(
+                "secret {}: {} {}",
+                self.secret.secret_id(),
+                status,
+                error.trim()
+            ));
+        }
+        let value = serde_json::from_str::<SecretValue>(&text)
+            .map_err(|e| format!("secret {}: {}", self.secret.secret_id(), e))?;
+        match (value.secret_string, value.secret_binary) {
+            (Some(string), _) => Ok(string.into_bytes()),
+            (None, Some(binary)) => {
+                use base64::{Engine, engine::general_purpose::STANDARD};
+                STANDARD
+                    .decode(binary)
+                    .map_err(|e| format!("secret {}: {}", self.secret.secret_id(), e))
+            }
+            (None, None) => Err(format!("secret {} has no value", self.secret.secret_id())),
+        }
+    }
+}
+
+impl SecretKeyProvider for AwsSecretsManagerKeyProvider {
+    fn key(&self) -> Result<[u8; KEY_LEN], String> {
+        let secret = block_on(self.clone().fetch())??;
+        secret_key(&secret).map_err(|e| format!("secret {}: {}", self.secret.secret_id(), e))
+    }
+}
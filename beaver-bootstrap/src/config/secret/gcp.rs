@@ -0,0 +1,137 @@
+use std::{error::Error, fmt, future::Future, pin::Pin, sync::Arc};
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use google_cloud_auth::{project::Config, token::DefaultTokenSourceProvider};
+pub use google_cloud_token::TokenSource;
+use google_cloud_token::TokenSourceProvider;
+use serde::Deserialize;
+use tokio::sync::OnceCell;
+
+use super::{GcpSecretConfig, HTTP_CLIENT, KEY_LEN, SecretKeyProvider, block_on, secret_key};
+
+const DEFAULT_ENDPOINT: &str = "https://secretmanager.googleapis.com";
+const SCOPES: [&str; 1] = ["https://www.googleapis.com/auth/cloud-platform"];
+
+#[derive(Deserialize)]
+struct AccessResponse {
+    payload: Payload,
+}
+
+#[derive(Deserialize)]
+struct Payload {
+    /// base64 encoded.
+    data: String,
+}
+
+/// GcpSecretManagerKeyProvider reads the config key from a secret version of GCP Secret
+/// Manager, `[secrets] backend = "gcp"`.
+///
+/// The credentials come from the application default credentials: the file of
+/// `GOOGLE_APPLICATION_CREDENTIALS`, the one of `gcloud auth application-default login`,
+/// then the metadata server of GCE, GKE or Cloud Run. They are resolved once, on the first
+/// fetch. Every call fetches the secret, wrap the provider in a
+/// [`CachedKeyProvider`](super::CachedKeyProvider).
+#[derive(Debug, Clone)]
+pub struct GcpSecretManagerKeyProvider {
+    secret: GcpSecretConfig,
+    token_source: Arc<OnceCell<Arc<dyn TokenSource>>>,
+}
+
+impl GcpSecretManagerKeyProvider {
+    pub fn new(secret: GcpSecretConfig) -> Self {
+        Self {
+            secret,
+            token_source: Arc::new(OnceCell::new()),
+        }
+    }
+
+    /// authorize with the tokens of `token_source` instead of the default credentials.
+    pub fn with_token_source(mut self, token_source: Arc<dyn TokenSource>) -> Self {
+        self.token_source = Arc::new(OnceCell::new_with(Some(token_source)));
+        self
+    }
+
+    /// authorize with the fixed OAuth2 access token `token`.
+    pub fn with_access_token(self, token: impl Into<String>) -> Self {
+        self.with_token_source(Arc::new(AccessToken(token.into())))
+    }
+
+    /// the token source of the default credentials, resolved once.
+    async fn token_source(&self) -> Result<&Arc<dyn TokenSource>, String> {
+        self.token_source
+            .get_or_try_init(|| async {
+                DefaultTokenSourceProvider::new(Config::default().with_scopes(&SCOPES))
+                    .await
+                    .map(|provider| provider.token_source())
+                    .map_err(|e| format!("unable to resolve GCP credentials: {}", e))
+            })
+            .await
+    }
+
+    async fn fetch(self) -> Result<Vec<u8>, String> {
+        let token = self
+            .token_source()
+            .await?
+            .token()
+            .await
+            .map_err(|e| format!("unable to get a GCP access token: {}", e))?;
+        let url = format!(
+            "{}/v1/{}:access",
+            self.secret.endpoint().unwrap_or(DEFAULT_ENDPOINT),
+            self.secret.name()
+        );
+        let response = HTTP_CLIENT
+            .get(&url)
+            .header("authorization", token)
+            .send()
+            .await
+            .map_err(|e| format!("{}: {}", url, e))?;
+        let status = response.status();
+        let text = response
+            .text()
+            .await
+            .map_err(|e| format!("{}: {}", url, e))?;
+        if !status.is_success() {
+            return Err(format!(
+                "secret {}: {} {}",
+                self.secret.name(),
+                status,
+                text.trim()
+            ));
+        }
+        let response = serde_json::from_str::<AccessResponse>(&text)
+            .map_err(|e| format!("secret {}: {}", self.secret.name(), e))?;
+        STANDARD
+            .decode(response.payload.data)
+            .map_err(|e| format!("secret {}: {}", self.secret.name(), e))
+    }
+}
+
+impl SecretKeyProvider for GcpSecretManagerKeyProvider {
+    fn key(&self) -> Result<[u8; KEY_LEN], String> {
+        let secret = block_on(self.clone().fetch())??;
+        secret_key(&secret).map_err(|e| format!("secret {}: {}", self.secret.name(), e))
+    }
+}
+
+/// a fixed access token, sent as a bearer token.
+struct AccessToken(String);
+
+impl fmt::Debug for AccessToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("AccessToken(..)")
+    }
+}
+
+type TokenFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<String, Box<dyn Error + Send + Sync>>> + Send + 'a>>;
+
+impl TokenSource for AccessToken {
+    fn token<'a, 'b>(&'a self) -> TokenFuture<'b>
+    where
+        'a: 'b,
+        Self: 'b,
+    {
+        Box::pin(async move { Ok(format!("Bearer {}", self.0)) })
+    }
+}
@@ -0,0 +1,261 @@
+use std::sync::Arc;
+
+use config::{ConfigError, Map, Source, Value, ValueKind};
+
+use super::{MapSource, set_origin};
+
+/// prefix of the origin of values loaded by a [`ConfigSource`], followed by its name.
+pub const SOURCE_ORIGIN_PREFIX: &str = "source:";
+
+/// ConfigSource plugs a config system beaver does not ship, e.g. a proprietary key-value
+/// store, into the loading of Config.
+///
+/// The sources are layered above `config.toml` and the kubernetes volumes, in ascending
+/// [`ConfigSource::priority`], and below environment variables. Keys of the loaded map are
+/// paths, dots nest, e.g. `logging.console_appender.level`. Their values merge with the layers
+/// below by the [`ConfigSource::merge_policy`] of the source.
+///
+/// # Example
+/// ```
+/// use beaver_bootstrap::config::source::ConfigSource;
+/// use config::{ConfigError, Map, Value, ValueKind};
+/// struct Vault;
+/// impl ConfigSource for Vault {
+///     fn name(&self) -> String {
+///         "vault".to_string()
+///     }
+///     fn priority(&self) -> i32 {
+///         10
+///     }
+///     fn load(&self) -> Result<Map<String, Value>, ConfigError> {
+///         let origin = self.name();
+///         let value = Value::new(Some(&origin), ValueKind::String("s3cr3t".to_string()));
+///         Ok(Map::from([("database.password".to_string(), value)]))
+///     }
+/// }
+/// let bootstrap = beaver_bootstrap::bootstrap::Bootstrap::builder()
+///     .config_source(Box::new(Vault))
+///     .build();
+/// ```
+pub trait ConfigSource: Send + Sync {
+    /// name of the source in logs and errors.
+    fn name(&self) -> String;
+
+    /// sources of higher priority override the values of lower ones, equal priorities keep
+    /// the order of registration.
+    fn priority(&self) -> i32 {
+        0
+    }
+
+    /// read the values of the source, called on startup and on every reload.
+    fn load(&self) -> Result<Map<String, Value>, ConfigError>;
+
+    /// how the values of the source merge with the layers below, tables deep merged and
+    /// arrays replaced by default.
+    fn merge_policy(&self) -> MergePolicy {
+        MergePolicy::default()
+    }
+
+    /// start watching the source and call [`SourceChanged::notify`] on updates, returning
+    /// whether the source is watched. Not watched by default.
+    ///
+    /// Watches start when [`Bootstrap::run`](crate::bootstrap::Bootstrap::run) runs the
+    /// application, a notification reloads the config.
+    fn watch(&self, _changed: SourceChanged) -> Result<bool, ConfigError> {
+        Ok(false)
+    }
+}
+
+/// TableMerge is how a table of a source merges with the table of the layers below.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum TableMerge {
+    /// keys of both are kept, the keys of the source winning, recursively.
+    #[default]
+    Deep,
+    /// the table of the source replaces the table below.
+    Replace,
+}
+
+/// ArrayMerge is how an array of a source merges with the array of the layers below.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum ArrayMerge {
+    /// the array of the source replaces the array below.
+    #[default]
+    Replace,
+    /// the elements of the source are appended to the array below.
+    Append,
+}
+
+/// MergePolicy is how the values of a [`ConfigSource`] merge with the layers below it.
+///
+/// Tables are deep merged and arrays replaced by default, as by the config crate. A source
+/// adding one file appender to those of `config.toml` appends arrays instead, its
+/// `logging.file_appenders` holding the new appender only:
+///
+/// # Example
+/// ```
+/// use beaver_bootstrap::config::source::{ArrayMerge, MergePolicy};
+/// use config::Value;
+/// let names = |x: Value| x.try_deserialize::<Vec<String>>().unwrap();
+/// let mut merged = Value::from(vec!["root"]);
+/// MergePolicy::default().merge(&mut merged, Value::from(vec!["http"]));
+/// assert_eq!(names(merged), ["http"]);
+/// let mut merged = Value::from(vec!["root"]);
+/// MergePolicy::default()
+///     .with_arrays(ArrayMerge::Append)
+///     .merge(&mut merged, Value::from(vec!["http"]));
+/// assert_eq!(names(merged), ["root", "http"]);
+/// ```
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct MergePolicy {
+    tables: TableMerge,
+    arrays: ArrayMerge,
+}
+
+impl MergePolicy {
+    pub fn with_tables(mut self, tables: TableMerge) -> Self {
+        self.tables = tables;
+        self
+    }
+
+    pub fn with_arrays(mut self, arrays: ArrayMerge) -> Self {
+        self.arrays = arrays;
+        self
+    }
+
+    pub fn tables(&self) -> TableMerge {
+        self.tables
+    }
+
+    pub fn arrays(&self) -> ArrayMerge {
+        self.arrays
+    }
+
+    /// merge `value` into `target`.
+    pub fn merge(&self, target: &mut Value, value: Value) {
+        let origin = value.origin().map(str::to_string);
+        match (&mut target.kind, value.kind) {
+            (ValueKind::Table(below), ValueKind::Table(table))
+                if self.tables == TableMerge::Deep =>
+            {
+                for (key, value) in table {
+                    let entry = below
+                        .entry(key)
+                        .or_insert_with(|| Value::new(None, ValueKind::Nil));
+                    self.merge(entry, value);
+                }
+            }
+            (ValueKind::Array(below), ValueKind::Array(array))
+                if self.arrays == ArrayMerge::Append =>
+            {
+                below.extend(array);
+            }
+            (_, kind) => *target = Value::new(origin.as_ref(), kind),
+        }
+    }
+}
+
+/// the value at the dotted `key` of the table `root`, its missing tables created.
+fn value_mut<'a>(root: &'a mut Value, key: &str) -> &'a mut Value {
+    let mut current = root;
+    for segment in key.split('.') {
+        if !matches!(current.kind, ValueKind::Table(_)) {
+            *current = Value::from(Map::<String, Value>::new());
+        }
+        let ValueKind::Table(table) = &mut current.kind else {
+            unreachable!("the value was made a table")
+        };
+        current = table
+            .entry(segment.to_string())
+            .or_insert_with(|| Value::new(None, ValueKind::Nil));
+    }
+    current
+}
+
+/// SourceChanged is handed to [`ConfigSource::watch`] to report updates of the source.
+#[derive(Clone)]
+pub struct SourceChanged {
+    notify: Arc<dyn Fn() + Send + Sync>,
+}
+
+impl std::fmt::Debug for SourceChanged {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SourceChanged").finish_non_exhaustive()
+    }
+}
+
+impl SourceChanged {
+    pub fn new(notify: impl Fn() + Send + Sync + 'static) -> Self {
+        Self {
+            notify: Arc::new(notify),
+        }
+    }
+
+    /// report that the values of the source changed.
+    pub fn notify(&self) {
+        (self.notify)()
+    }
+}
+
+/// PluginSource adapts a [`ConfigSource`] to the sources of the config crate.
+#[derive(Clone)]
+pub(crate) struct PluginSource {
+    source: Arc<dyn ConfigSource>,
+}
+
+impl std::fmt::Debug for PluginSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PluginSource")
+            .field("name", &self.source.name())
+            .finish()
+    }
+}
+
+impl PluginSource {
+    pub(crate) fn new(source: Arc<dyn ConfigSource>) -> Self {
+        Self { source }
+    }
+}
+
+impl Source for PluginSource {
+    fn clone_into_box(&self) -> Box<dyn Source + Send + Sync> {
+        Box::new(self.clone())
+    }
+
+    fn collect(&self) -> Result<Map<String, Value>, ConfigError> {
+        let name = self.source.name();
+        let mut map = self
+            .source
+            .load()
+            .map_err(|e| ConfigError::Message(format!("config source {}: {}", name, e)))?;
+        let origin = format!("{}{}", SOURCE_ORIGIN_PREFIX, name);
+        for value in map.values_mut().filter(|x| x.origin().is_none()) {
+            set_origin(value, &origin);
+        }
+        Ok(map)
+    }
+
+    fn collect_to(&self, cache: &mut Value) -> Result<(), ConfigError> {
+        let policy = self.source.merge_policy();
+        for (key, value) in self.collect()? {
+            match key.contains('[') {
+                // indexed keys set their element, in the path syntax of the config crate
+                true => MapSource::new(Map::from([(key, value)])).collect_to(cache)?,
+                false => policy.merge(value_mut(cache, &key), value),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// the config crate sources of `sources`, in ascending priority, to add to a
+/// `config::ConfigBuilder` when loading a Config without [`Bootstrap`](crate::bootstrap::Bootstrap).
+pub fn plugin_sources(sources: &[Arc<dyn ConfigSource>]) -> Vec<Box<dyn Source + Send + Sync>> {
+    let mut sources = sources.to_vec();
+    // stable, equal priorities keep the order of registration
+    sources.sort_by_key(|x| x.priority());
+    sources
+        .into_iter()
+        .map(|x| Box::new(PluginSource::new(x)) as Box<dyn Source + Send + Sync>)
+        .collect()
+}
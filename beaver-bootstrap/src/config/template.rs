@@ -0,0 +1,106 @@
+use serde::Serialize;
+
+use super::ConfigPrefix;
+use crate::error::BootstrapError;
+
+/// ConfigSection describes a section of config.toml for the generated example config, see
+/// [`render`].
+#[derive(Debug, Clone)]
+pub struct ConfigSection {
+    prefix: String,
+    description: String,
+    /// TOML of the section, table headers included.
+    example: String,
+}
+
+impl ConfigSection {
+    /// the section of `T`, with its default values.
+    pub fn of<T>(description: &str) -> Result<Self, BootstrapError>
+    where
+        T: ConfigPrefix + Default + Serialize,
+    {
+        let mut table = toml::Table::try_from(T::default())
+            .map_err(|e| BootstrapError::ConfigExportError(Box::new(e)))?;
+        for segment in T::PREFIX.rsplit('.') {
+            let mut parent = toml::Table::new();
+            parent.insert(segment.to_string(), toml::Value::Table(table));
+            table = parent;
+        }
+        let example =
+            toml::to_string(&table).map_err(|e| BootstrapError::ConfigExportError(Box::new(e)))?;
+        Ok(Self::with_example(T::PREFIX, description, &example))
+    }
+
+    /// a section written by hand, table headers included, for sections without meaningful
+    /// defaults.
+    pub fn with_example(prefix: &str, description: &str, example: &str) -> Self {
+        Self {
+            prefix: prefix.to_string(),
+            description: description.to_string(),
+            example: example.to_string(),
+        }
+    }
+
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// full keys of the values of the example, arrays being one value.
+    pub fn keys(&self) -> Vec<String> {
+        fn collect(prefix: &str, table: &toml::Table, keys: &mut Vec<String>) {
+            for (key, value) in table {
+                let key = match prefix {
+                    "" => key.clone(),
+                    prefix => format!("{}.{}", prefix, key),
+                };
+                match value {
+                    toml::Value::Table(table) => collect(&key, table, keys),
+                    _ => keys.push(key),
+                }
+            }
+        }
+        let mut keys = vec![];
+        if let Ok(table) = self.example.parse::<toml::Table>() {
+            collect("", &table, &mut keys);
+        }
+        keys
+    }
+}
+
+/// render `sections` as an example config.toml, each described and commented out, so it
+/// starts a deployment with the keys at hand without pinning the defaults.
+///
+/// # Example
+/// ```
+/// use beaver_bootstrap::{
+///     config::template::{ConfigSection, render},
+///     runtime::RuntimeConfig,
+/// };
+/// let section = ConfigSection::of::<RuntimeConfig>("managed tokio runtime.").unwrap();
+/// let example = render(&[section]);
+/// assert!(example.contains("# managed tokio runtime.\n# [runtime]\n# init_concurrency = 4\n"));
+/// ```
+pub fn render(sections: &[ConfigSection]) -> String {
+    let mut out = String::from(
+        "# example config, generated with the default values.\n\
+         # uncomment and change the keys to set.\n",
+    );
+    for section in sections {
+        out.push('\n');
+        for line in section.description.lines() {
+            out.push_str(&format!("# {}\n", line));
+        }
+        for line in section.example.trim().lines() {
+            if line.is_empty() {
+                out.push_str("#\n");
+            } else {
+                out.push_str(&format!("# {}\n", line));
+            }
+        }
+    }
+    out
+}
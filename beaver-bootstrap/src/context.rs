@@ -0,0 +1,149 @@
+use std::error::Error;
+
+use di::{Ref, ServiceProvider};
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    config::{Config, module::ModuleConfig, reload::LiveConfig},
+    event::EventBus,
+    metrics::MetricsRegistry,
+};
+
+/// result of the application run by [`Bootstrap::run`](crate::bootstrap::Bootstrap::run).
+pub type AppResult = Result<(), Box<dyn Error + Send + Sync>>;
+
+/// AppInfo names the application, in logs and the `version` output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppInfo {
+    name: String,
+    version: String,
+}
+
+impl Default for AppInfo {
+    /// the file name of the executable, without version.
+    fn default() -> Self {
+        let name = std::env::current_exe()
+            .ok()
+            .and_then(|x| x.file_stem().map(|x| x.to_string_lossy().into_owned()))
+            .unwrap_or_else(|| "beaver".to_string());
+        Self::new(&name, "unknown")
+    }
+}
+
+impl AppInfo {
+    /// usually `AppInfo::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))`.
+    pub fn new(name: &str, version: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            version: version.to_string(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+}
+
+/// BeaverContext is the handle of application code on what the bootstrap provides. It is
+/// passed to the application of [`Bootstrap::run`](crate::bootstrap::Bootstrap::run) and
+/// can be resolved from the service provider.
+///
+/// The shutdown token is cancelled on SIGINT or SIGTERM, the application should then stop
+/// accepting work and return within `runtime.shutdown_timeout`.
+#[derive(Clone)]
+pub struct BeaverContext {
+    provider: ServiceProvider,
+    live_config: Ref<LiveConfig>,
+    shutdown: CancellationToken,
+    events: Ref<EventBus>,
+    metrics: Ref<MetricsRegistry>,
+    app_info: Ref<AppInfo>,
+    strict_module_config: bool,
+}
+
+impl std::fmt::Debug for BeaverContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BeaverContext")
+            .field("app_info", &self.app_info)
+            .field("shutdown", &self.shutdown)
+            .finish_non_exhaustive()
+    }
+}
+
+impl BeaverContext {
+    pub fn new(
+        provider: ServiceProvider,
+        live_config: Ref<LiveConfig>,
+        shutdown: CancellationToken,
+        events: Ref<EventBus>,
+        metrics: Ref<MetricsRegistry>,
+        app_info: Ref<AppInfo>,
+    ) -> Self {
+        Self {
+            provider,
+            live_config,
+            shutdown,
+            events,
+            metrics,
+            app_info,
+            strict_module_config: false,
+        }
+    }
+
+    /// make the views of [`BeaverContext::config_for_module`] refuse reads outside of their
+    /// namespace.
+    pub fn with_strict_module_config(mut self, strict: bool) -> Self {
+        self.strict_module_config = strict;
+        self
+    }
+
+    /// the active config snapshot, which follows reloads.
+    pub fn config(&self) -> Ref<Config> {
+        self.live_config.current()
+    }
+
+    pub fn live_config(&self) -> &Ref<LiveConfig> {
+        &self.live_config
+    }
+
+    /// the config of `module` under `modules.<module>`, usually named after
+    /// [`Module::config_namespace`](crate::bootstrap::Module::config_namespace).
+    pub fn config_for_module(&self, module: &str) -> ModuleConfig {
+        ModuleConfig::new(self.live_config.clone(), module, self.strict_module_config)
+    }
+
+    /// provider of the registered services.
+    pub fn provider(&self) -> &ServiceProvider {
+        &self.provider
+    }
+
+    /// token cancelled when the process is asked to shut down.
+    pub fn shutdown_token(&self) -> CancellationToken {
+        self.shutdown.clone()
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutdown.is_cancelled()
+    }
+
+    /// wait until the process is asked to shut down.
+    pub async fn shutdown_requested(&self) {
+        self.shutdown.cancelled().await
+    }
+
+    pub fn events(&self) -> &Ref<EventBus> {
+        &self.events
+    }
+
+    pub fn metrics(&self) -> &Ref<MetricsRegistry> {
+        &self.metrics
+    }
+
+    pub fn app_info(&self) -> &AppInfo {
+        &self.app_info
+    }
+}
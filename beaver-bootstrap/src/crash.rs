@@ -0,0 +1,134 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::{Config, ConfigPrefix},
+    error::BootstrapError,
+    serde::duration_opt,
+};
+
+/// SentryConfig configures crash reporting to Sentry, see `[sentry]`.
+///
+/// The dsn can be stored encrypted (`enc:` prefix), it is decrypted with the config key
+/// provider like any other value. Requires the `sentry` feature.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SentryConfig {
+    enable: bool,
+    dsn: Option<String>,
+    environment: Option<String>,
+    release: Option<String>,
+    /// share of error events sent, 0.0 to 1.0.
+    sample_rate: f32,
+    /// time given to pending events on shutdown.
+    #[serde(deserialize_with = "duration_opt")]
+    shutdown_timeout: Option<Duration>,
+}
+
+impl Default for SentryConfig {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            dsn: None,
+            environment: None,
+            release: None,
+            sample_rate: 1.0,
+            shutdown_timeout: None,
+        }
+    }
+}
+
+impl ConfigPrefix for SentryConfig {
+    const PREFIX: &'static str = "sentry";
+}
+
+impl SentryConfig {
+    pub fn new(config: &Config) -> Result<Self, BootstrapError> {
+        let sentry_config = config
+            .get::<SentryConfig>()
+            .map_err(BootstrapError::ConfigLoadError)?;
+        sentry_config.validate()?;
+        Ok(sentry_config)
+    }
+
+    fn validate(&self) -> Result<(), BootstrapError> {
+        if !self.enable {
+            return Ok(());
+        }
+        if self.dsn.as_deref().is_none_or(str::is_empty) {
+            return Err(BootstrapError::MissingConfigValueError(
+                "sentry.dsn".to_string(),
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.sample_rate) {
+            return Err(BootstrapError::InvalidConfigValueError(format!(
+                "sentry.sample_rate={}",
+                self.sample_rate
+            )));
+        }
+        Ok(())
+    }
+
+    pub fn enable(&self) -> bool {
+        self.enable
+    }
+
+    pub fn environment(&self) -> Option<&str> {
+        self.environment.as_deref()
+    }
+
+    pub fn release(&self) -> Option<&str> {
+        self.release.as_deref()
+    }
+}
+
+/// CrashReporter keeps the Sentry client alive, flushing pending events when dropped.
+///
+/// Its creation installs the panic hook, and [`CrashReporter::layer`] forwards ERROR events
+/// as Sentry events and lower levels as breadcrumbs.
+#[cfg(feature = "sentry")]
+pub struct CrashReporter {
+    _guard: sentry::ClientInitGuard,
+}
+
+#[cfg(feature = "sentry")]
+impl std::fmt::Debug for CrashReporter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CrashReporter").finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "sentry")]
+impl CrashReporter {
+    pub fn init(config: &SentryConfig) -> Result<Self, BootstrapError> {
+        let dsn = config
+            .dsn
+            .as_deref()
+            .unwrap_or_default()
+            .parse::<sentry::types::Dsn>()
+            .map_err(|e| BootstrapError::InvalidConfigValueError(format!("sentry.dsn: {}", e)))?;
+        let mut options = sentry::ClientOptions {
+            dsn: Some(dsn),
+            environment: config.environment.clone().map(Into::into),
+            release: config.release.clone().map(Into::into),
+            sample_rate: config.sample_rate,
+            ..Default::default()
+        };
+        if let Some(timeout) = config.shutdown_timeout {
+            options.shutdown_timeout = timeout;
+        }
+        Ok(Self {
+            _guard: sentry::init(options),
+        })
+    }
+
+    pub fn layer<S>() -> Box<dyn tracing_subscriber::Layer<S> + Send + Sync>
+    where
+        S: tracing::Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+    {
+        use tracing_subscriber::Layer;
+
+        sentry_tracing::layer().boxed()
+    }
+}
@@ -0,0 +1,98 @@
+use std::net::SocketAddr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::{Config, ConfigPrefix},
+    debug::profile::ProfilingConfig,
+    error::BootstrapError,
+};
+
+pub mod profile;
+
+/// DebugConfig holds the diagnostics switched on from `[debug]`.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct DebugConfig {
+    tokio_console: TokioConsoleConfig,
+    profiling: ProfilingConfig,
+}
+
+impl ConfigPrefix for DebugConfig {
+    const PREFIX: &'static str = "debug";
+}
+
+impl DebugConfig {
+    pub fn new(config: &Config) -> Result<Self, BootstrapError> {
+        let debug_config = config
+            .get::<DebugConfig>()
+            .map_err(BootstrapError::ConfigLoadError)?;
+        if debug_config.profiling.frequency() <= 0 {
+            return Err(BootstrapError::InvalidConfigValueError(format!(
+                "debug.profiling.frequency={}",
+                debug_config.profiling.frequency()
+            )));
+        }
+        Ok(debug_config)
+    }
+
+    pub fn tokio_console(&self) -> &TokioConsoleConfig {
+        &self.tokio_console
+    }
+
+    pub fn profiling(&self) -> &ProfilingConfig {
+        &self.profiling
+    }
+}
+
+/// TokioConsoleConfig configures the tokio-console server, see `[debug.tokio_console]`.
+///
+/// Requires the `tokio-console` feature, and the application built with
+/// `RUSTFLAGS="--cfg tokio_unstable"` for tokio to emit task instrumentation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TokioConsoleConfig {
+    enable: bool,
+    /// address the console server listens on.
+    bind: String,
+}
+
+impl Default for TokioConsoleConfig {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            bind: "127.0.0.1:6669".to_string(),
+        }
+    }
+}
+
+impl TokioConsoleConfig {
+    pub fn enable(&self) -> bool {
+        self.enable
+    }
+
+    pub fn bind(&self) -> Result<SocketAddr, BootstrapError> {
+        self.bind.parse().map_err(|e| {
+            BootstrapError::InvalidConfigValueError(format!(
+                "debug.tokio_console.bind={}: {}",
+                self.bind, e
+            ))
+        })
+    }
+
+    /// spawn the console server and return the layer feeding it.
+    #[cfg(feature = "tokio-console")]
+    pub(crate) fn layer<S>(
+        &self,
+    ) -> Result<Box<dyn tracing_subscriber::Layer<S> + Send + Sync>, BootstrapError>
+    where
+        S: tracing::Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+    {
+        use tracing_subscriber::Layer;
+
+        Ok(console_subscriber::ConsoleLayer::builder()
+            .server_addr(self.bind()?)
+            .spawn()
+            .boxed())
+    }
+}
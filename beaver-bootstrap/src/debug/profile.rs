@@ -0,0 +1,271 @@
+use std::{io, thread, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use crate::admin::{AdminResponse, AdminRoutes};
+
+/// seconds a profile lasts when the request does not ask, at most `max_seconds`.
+pub const DEFAULT_PROFILE_SECONDS: u64 = 30;
+
+/// ProfilingConfig configures the profiling routes of the admin endpoint, see
+/// `[debug.profiling]`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProfilingConfig {
+    enable: bool,
+    /// longest profile a request may ask for.
+    max_seconds: u64,
+    /// samples per second of the `pprof` profiles.
+    frequency: i32,
+}
+
+impl Default for ProfilingConfig {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            max_seconds: 300,
+            frequency: 99,
+        }
+    }
+}
+
+impl ProfilingConfig {
+    pub fn enable(&self) -> bool {
+        self.enable
+    }
+
+    pub fn max_seconds(&self) -> u64 {
+        self.max_seconds
+    }
+
+    pub fn frequency(&self) -> i32 {
+        self.frequency
+    }
+}
+
+/// CpuProfile is the cpu time spent by the process over a sampling window.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct CpuProfile {
+    pub seconds: u64,
+    pub user_cpu_seconds: f64,
+    pub system_cpu_seconds: f64,
+    /// cpu time over wall time, 1.0 being one core fully busy.
+    pub cpu_utilization: f64,
+    pub voluntary_context_switches: i64,
+    pub involuntary_context_switches: i64,
+}
+
+/// MemoryStats is the memory footprint of the process.
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Eq)]
+pub struct MemoryStats {
+    pub resident_bytes: Option<u64>,
+    pub peak_resident_bytes: Option<u64>,
+    pub virtual_bytes: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct CpuUsage {
+    user: f64,
+    system: f64,
+    voluntary: i64,
+    involuntary: i64,
+    max_rss_bytes: u64,
+}
+
+#[cfg(unix)]
+fn cpu_usage() -> io::Result<CpuUsage> {
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    // SAFETY: usage is a valid out pointer.
+    if unsafe { libc::getrusage(libc::RUSAGE_SELF, &mut usage) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let seconds = |t: libc::timeval| t.tv_sec as f64 + t.tv_usec as f64 / 1_000_000.0;
+    // ru_maxrss is in kilobytes on linux and in bytes on macOS
+    let max_rss_bytes = if cfg!(target_os = "macos") {
+        usage.ru_maxrss as u64
+    } else {
+        usage.ru_maxrss as u64 * 1024
+    };
+    Ok(CpuUsage {
+        user: seconds(usage.ru_utime),
+        system: seconds(usage.ru_stime),
+        voluntary: usage.ru_nvcsw as i64,
+        involuntary: usage.ru_nivcsw as i64,
+        max_rss_bytes,
+    })
+}
+
+#[cfg(not(unix))]
+fn cpu_usage() -> io::Result<CpuUsage> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "cpu usage is only supported on unix",
+    ))
+}
+
+/// sample the cpu time of the process for `seconds`, blocking the caller meanwhile.
+pub fn cpu_profile(seconds: u64) -> io::Result<CpuProfile> {
+    let start = cpu_usage()?;
+    thread::sleep(Duration::from_secs(seconds));
+    let end = cpu_usage()?;
+    let user = end.user - start.user;
+    let system = end.system - start.system;
+    Ok(CpuProfile {
+        seconds,
+        user_cpu_seconds: user,
+        system_cpu_seconds: system,
+        cpu_utilization: if seconds == 0 {
+            0.0
+        } else {
+            (user + system) / seconds as f64
+        },
+        voluntary_context_switches: end.voluntary - start.voluntary,
+        involuntary_context_switches: end.involuntary - start.involuntary,
+    })
+}
+
+/// current memory footprint, from `/proc/self/status` on linux.
+pub fn memory_stats() -> io::Result<MemoryStats> {
+    let mut stats = MemoryStats::default();
+    if let Ok(status) = std::fs::read_to_string("/proc/self/status") {
+        for line in status.lines() {
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let bytes = value
+                .trim()
+                .trim_end_matches("kB")
+                .trim()
+                .parse::<u64>()
+                .ok()
+                .map(|kb| kb * 1024);
+            match key {
+                "VmRSS" => stats.resident_bytes = bytes,
+                "VmHWM" => stats.peak_resident_bytes = bytes,
+                "VmSize" => stats.virtual_bytes = bytes,
+                _ => {}
+            }
+        }
+        return Ok(stats);
+    }
+    stats.peak_resident_bytes = Some(cpu_usage()?.max_rss_bytes);
+    Ok(stats)
+}
+
+/// ProfileFormat is the encoding of a sampled CPU profile.
+#[cfg(all(feature = "pprof", unix))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProfileFormat {
+    /// the protobuf of `go tool pprof`.
+    Pprof,
+    /// an SVG flamegraph.
+    Flamegraph,
+}
+
+/// sample the stacks of every thread `frequency` times a second for `seconds`, blocking the
+/// caller meanwhile, and encode the profile as `format`.
+///
+/// Fails when another profile is being sampled, the profiler being process wide.
+///
+/// # Example
+/// ```
+/// use beaver_bootstrap::debug::profile::{ProfileFormat, sampled_profile};
+/// let profile = sampled_profile(1, 99, ProfileFormat::Pprof).unwrap();
+/// assert!(!profile.is_empty());
+/// ```
+#[cfg(all(feature = "pprof", unix))]
+pub fn sampled_profile(seconds: u64, frequency: i32, format: ProfileFormat) -> io::Result<Vec<u8>> {
+    use pprof::protos::Message;
+    let profile_error = |e: pprof::Error| io::Error::other(e.to_string());
+    let guard = pprof::ProfilerGuardBuilder::default()
+        .frequency(frequency)
+        .build()
+        .map_err(profile_error)?;
+    thread::sleep(Duration::from_secs(seconds));
+    let report = guard.report().build().map_err(profile_error)?;
+    let mut body = Vec::new();
+    match format {
+        ProfileFormat::Pprof => report
+            .pprof()
+            .map_err(profile_error)?
+            .encode(&mut body)
+            .map_err(io::Error::other)?,
+        ProfileFormat::Flamegraph => report.flamegraph(&mut body).map_err(profile_error)?,
+    }
+    Ok(body)
+}
+
+/// the `seconds` of a profile request, up to `max_seconds`.
+fn profile_seconds(
+    request: &crate::admin::AdminRequest,
+    max_seconds: u64,
+) -> Result<u64, AdminResponse> {
+    match request.query("seconds").map(str::parse::<u64>) {
+        None => Ok(DEFAULT_PROFILE_SECONDS.min(max_seconds)),
+        Some(Ok(seconds)) if seconds <= max_seconds => Ok(seconds),
+        Some(_) => Err(AdminResponse::text(
+            400,
+            &format!("seconds must be an integer up to {}", max_seconds),
+        )),
+    }
+}
+
+/// register `/debug/cpu?seconds=N`, the cpu time spent over the window, and
+/// `/debug/memory` on the admin routes.
+///
+/// With the `pprof` feature, on unix, `/debug/pprof/profile?seconds=N` serves a sampled
+/// profile for `go tool pprof`, or an SVG flamegraph with `&format=flamegraph`.
+///
+/// # Example
+/// ```
+/// use beaver_bootstrap::{
+///     admin::{AdminRequest, AdminRoutes},
+///     config::Config,
+///     debug::{DebugConfig, profile::register_routes},
+/// };
+/// let inner = config::Config::builder().set_override("debug.profiling.max_seconds", 0);
+/// let debug = DebugConfig::new(&Config::new(inner.unwrap().build().unwrap())).unwrap();
+/// let routes = AdminRoutes::default();
+/// register_routes(&routes, debug.profiling());
+/// // 30 seconds by default, at most max_seconds
+/// let response = routes.handle(&AdminRequest::new("GET", "/debug/cpu"), None);
+/// assert_eq!(response.status(), 200);
+/// ```
+pub fn register_routes(routes: &AdminRoutes, config: &ProfilingConfig) {
+    let max_seconds = config.max_seconds();
+    routes.route("/debug/cpu", move |request| {
+        let seconds = match profile_seconds(request, max_seconds) {
+            Ok(seconds) => seconds,
+            Err(response) => return response,
+        };
+        match cpu_profile(seconds) {
+            Ok(profile) => AdminResponse::json(200, &profile),
+            Err(e) => AdminResponse::text(501, &e.to_string()),
+        }
+    });
+    routes.route("/debug/memory", |_| match memory_stats() {
+        Ok(stats) => AdminResponse::json(200, &stats),
+        Err(e) => AdminResponse::text(501, &e.to_string()),
+    });
+    #[cfg(all(feature = "pprof", unix))]
+    {
+        let frequency = config.frequency();
+        routes.route("/debug/pprof/profile", move |request| {
+            let seconds = match profile_seconds(request, max_seconds) {
+                Ok(seconds) => seconds,
+                Err(response) => return response,
+            };
+            let (format, content_type) = match request.query("format") {
+                None | Some("pprof") => (ProfileFormat::Pprof, "application/octet-stream"),
+                Some("flamegraph") => (ProfileFormat::Flamegraph, "image/svg+xml"),
+                Some(_) => {
+                    return AdminResponse::text(400, "format must be pprof or flamegraph");
+                }
+            };
+            match sampled_profile(seconds, frequency, format) {
+                Ok(body) => AdminResponse::new(200, content_type, body),
+                Err(e) => AdminResponse::text(503, &e.to_string()),
+            }
+        });
+    }
+}
@@ -0,0 +1,49 @@
+use std::{io, path::Path};
+
+/// DiskUsage is the space and inode usage of the filesystem holding a path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiskUsage {
+    /// total bytes of the filesystem.
+    pub total_bytes: u64,
+    /// bytes available to unprivileged users.
+    pub available_bytes: u64,
+    /// total inodes, 0 when the filesystem does not report them.
+    pub total_inodes: u64,
+    /// inodes available to unprivileged users.
+    pub available_inodes: u64,
+}
+
+/// query the usage of the filesystem holding `path`.
+///
+/// # Example
+/// ```
+/// let usage = beaver_bootstrap::disk::disk_usage(std::path::Path::new(".")).unwrap();
+/// assert!(usage.available_bytes <= usage.total_bytes);
+/// ```
+#[cfg(unix)]
+pub fn disk_usage(path: &Path) -> io::Result<DiskUsage> {
+    use std::{ffi::CString, os::unix::ffi::OsStrExt};
+
+    let c_path = CString::new(path.as_os_str().as_bytes())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    // SAFETY: c_path is a valid nul terminated string and stat is a valid out pointer.
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let fragment_size = stat.f_frsize as u64;
+    Ok(DiskUsage {
+        total_bytes: stat.f_blocks as u64 * fragment_size,
+        available_bytes: stat.f_bavail as u64 * fragment_size,
+        total_inodes: stat.f_files as u64,
+        available_inodes: stat.f_favail as u64,
+    })
+}
+
+#[cfg(not(unix))]
+pub fn disk_usage(_path: &Path) -> io::Result<DiskUsage> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "disk usage is only supported on unix",
+    ))
+}
@@ -0,0 +1,113 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use di::Ref;
+
+/// future of a disposal, see [`Disposable::dispose`].
+pub type DisposeFuture =
+    Pin<Box<dyn Future<Output = Result<(), Box<dyn std::error::Error + Send + Sync>>> + Send>>;
+
+/// Disposable is a service releasing resources at shutdown, e.g. a pool closing its
+/// connections.
+///
+/// Register it with
+/// [`ServiceCollectionExt::singleton_disposable`](crate::services::ServiceCollectionExt::singleton_disposable)
+/// so the bootstrap disposes it, in reverse order of construction, before the runtime is torn
+/// down.
+pub trait Disposable: Send + Sync {
+    fn dispose(&self) -> DisposeFuture;
+}
+
+/// names and services in order of construction.
+type Tracked = Vec<(String, Ref<dyn Disposable>)>;
+
+/// Disposer tracks constructed [`Disposable`] services and disposes them in reverse order.
+///
+/// # Example
+/// ```
+/// use std::time::Duration;
+/// use beaver_bootstrap::{
+///     dispose::{Disposable, DisposeFuture, Disposer},
+///     runtime::{ManagedRuntime, RuntimeConfig},
+/// };
+/// use di::Ref;
+/// struct Pool;
+/// impl Disposable for Pool {
+///     fn dispose(&self) -> DisposeFuture {
+///         Box::pin(async { Err("connection reset".into()) })
+///     }
+/// }
+/// let disposer = Disposer::default();
+/// disposer.track("pool", Ref::new(Pool));
+/// let runtime = ManagedRuntime::new(&RuntimeConfig::default()).unwrap();
+/// let failures = runtime.run(async move { disposer.dispose_all(Duration::from_secs(1)).await });
+/// assert_eq!(failures, [("pool".to_string(), "connection reset".to_string())]);
+/// ```
+#[derive(Clone, Default)]
+pub struct Disposer {
+    services: Arc<Mutex<Tracked>>,
+}
+
+impl std::fmt::Debug for Disposer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let names: Vec<String> = self
+            .services
+            .lock()
+            .map(|x| x.iter().map(|(name, _)| name.clone()).collect())
+            .unwrap_or_default();
+        f.debug_struct("Disposer")
+            .field("services", &names)
+            .finish()
+    }
+}
+
+impl Disposer {
+    /// record a constructed service, disposed before every service tracked earlier.
+    pub fn track(&self, name: &str, service: Ref<dyn Disposable>) {
+        self.services
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push((name.to_string(), service));
+    }
+
+    /// make the disposal of the service tracked at `index` fail instead of running.
+    #[cfg(feature = "fault-injection")]
+    pub(crate) fn inject_failure(&self, index: usize) {
+        struct Failing(usize);
+        impl Disposable for Failing {
+            fn dispose(&self) -> DisposeFuture {
+                let index = self.0;
+                Box::pin(
+                    async move { Err(format!("injected fault at shutdown hook {}", index).into()) },
+                )
+            }
+        }
+        let mut services = self.services.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((_, service)) = services.get_mut(index) {
+            *service = Ref::new(Failing(index));
+        }
+    }
+
+    /// dispose every tracked service, newest first and each within `timeout`.
+    ///
+    /// Failures are logged and returned, they do not stop the disposal of other services.
+    pub async fn dispose_all(&self, timeout: Duration) -> Vec<(String, String)> {
+        let services =
+            std::mem::take(&mut *self.services.lock().unwrap_or_else(|e| e.into_inner()));
+        let mut failures = vec![];
+        for (name, service) in services.into_iter().rev() {
+            let error = match tokio::time::timeout(timeout, service.dispose()).await {
+                Ok(Ok(())) => continue,
+                Ok(Err(e)) => e.to_string(),
+                Err(_) => format!("timed out after {:?}", timeout),
+            };
+            tracing::error!("failed to dispose {}: {}", name, error);
+            failures.push((name, error));
+        }
+        failures
+    }
+}
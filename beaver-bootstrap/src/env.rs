@@ -0,0 +1,193 @@
+use std::path::{Path, PathBuf};
+
+use crate::fs::{Fs, OsFs};
+
+const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+const PROC_SELF_CGROUP: &str = "/proc/self/cgroup";
+/// limits from this value on mean unlimited, cgroup v1 reports it as a page aligned `i64::MAX`.
+const UNLIMITED_MEMORY: u64 = 1 << 62;
+
+/// CgroupVersion is the cgroup hierarchy the limits were read from.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CgroupVersion {
+    V1,
+    V2,
+}
+
+/// RuntimeEnv is the environment the process runs in: the CPU quota and memory limit of its
+/// cgroup, so defaults follow the limits of a container instead of the host.
+///
+/// # Example
+/// ```
+/// use beaver_bootstrap::{
+///     env::{CgroupVersion, RuntimeEnv},
+///     fs::MemoryFs,
+/// };
+/// let fs = MemoryFs::default()
+///     .with_file("/proc/self/cgroup", "0::/\n")
+///     .with_file("/sys/fs/cgroup/cpu.max", "150000 100000\n")
+///     .with_file("/sys/fs/cgroup/memory.max", "536870912\n");
+/// let env = RuntimeEnv::from_fs(&fs, 16);
+/// assert_eq!(env.cgroup_version(), Some(CgroupVersion::V2));
+/// assert_eq!(env.cpu_quota(), Some(1.5));
+/// assert_eq!(env.parallelism(), 2);
+/// assert_eq!(env.memory_limit(), Some(512 * 1024 * 1024));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuntimeEnv {
+    host_parallelism: usize,
+    cgroup_version: Option<CgroupVersion>,
+    cpu_quota: Option<f64>,
+    memory_limit: Option<u64>,
+}
+
+impl RuntimeEnv {
+    /// detect the limits of the current process.
+    pub fn detect() -> Self {
+        Self::detect_with_fs(&OsFs)
+    }
+
+    /// like [`RuntimeEnv::detect`], reading the cgroup files from `fs`.
+    pub fn detect_with_fs(fs: &dyn Fs) -> Self {
+        let host_parallelism = std::thread::available_parallelism()
+            .map(|x| x.get())
+            .unwrap_or(1);
+        Self::from_fs(fs, host_parallelism)
+    }
+
+    /// read the limits from `fs`, on a host with `host_parallelism` CPUs.
+    pub fn from_fs(fs: &dyn Fs, host_parallelism: usize) -> Self {
+        let mut env = Self {
+            host_parallelism: host_parallelism.max(1),
+            cgroup_version: None,
+            cpu_quota: None,
+            memory_limit: None,
+        };
+        let Ok(cgroups) = fs.read_to_string(Path::new(PROC_SELF_CGROUP)) else {
+            return env;
+        };
+        // `hierarchy:controllers:path`, v2 is the line of hierarchy 0 without controllers
+        let mut v1 = vec![];
+        let mut v2 = None;
+        for line in cgroups.lines() {
+            let mut parts = line.splitn(3, ':');
+            let (Some(hierarchy), Some(controllers), Some(path)) =
+                (parts.next(), parts.next(), parts.next())
+            else {
+                continue;
+            };
+            if hierarchy == "0" && controllers.is_empty() {
+                v2 = Some(path);
+            } else {
+                v1.push((controllers.split(',').collect::<Vec<_>>(), path));
+            }
+        }
+        let cgroup_path = |controller: &str| {
+            v1.iter()
+                .find(|(controllers, _)| controllers.contains(&controller))
+                .map(|(_, path)| *path)
+        };
+        // hybrid hierarchies also list hierarchy 0, but the controllers stay on v1
+        if let Some(path) = v2
+            && cgroup_path("cpu").is_none()
+            && cgroup_path("memory").is_none()
+        {
+            env.cgroup_version = Some(CgroupVersion::V2);
+            env.cpu_quota = read_cgroup(fs, "", path, "cpu.max").and_then(|x| cpu_max(&x));
+            env.memory_limit = read_cgroup(fs, "", path, "memory.max").and_then(|x| memory(&x));
+            return env;
+        }
+        if let Some(path) = cgroup_path("cpu") {
+            env.cgroup_version = Some(CgroupVersion::V1);
+            let quota = read_cgroup(fs, "cpu", path, "cpu.cfs_quota_us");
+            let period = read_cgroup(fs, "cpu", path, "cpu.cfs_period_us");
+            env.cpu_quota = quota.zip(period).and_then(|(quota, period)| {
+                cpu_quota(quota.trim().parse().ok()?, period.trim().parse().ok()?)
+            });
+        }
+        if let Some(path) = cgroup_path("memory") {
+            env.cgroup_version = Some(CgroupVersion::V1);
+            env.memory_limit =
+                read_cgroup(fs, "memory", path, "memory.limit_in_bytes").and_then(|x| memory(&x));
+        }
+        env
+    }
+
+    pub fn cgroup_version(&self) -> Option<CgroupVersion> {
+        self.cgroup_version
+    }
+
+    /// CPUs the cgroup may use per period, `None` when unlimited.
+    pub fn cpu_quota(&self) -> Option<f64> {
+        self.cpu_quota
+    }
+
+    /// bytes the cgroup may use, `None` when unlimited.
+    pub fn memory_limit(&self) -> Option<u64> {
+        self.memory_limit
+    }
+
+    pub fn host_parallelism(&self) -> usize {
+        self.host_parallelism
+    }
+
+    /// threads worth running: the CPU quota rounded up, at most the host CPUs and at least 1.
+    pub fn parallelism(&self) -> usize {
+        match self.cpu_quota {
+            Some(quota) => (quota.ceil() as usize).clamp(1, self.host_parallelism),
+            None => self.host_parallelism,
+        }
+    }
+}
+
+/// read `file` of the cgroup at `path`, falling back to the root of the hierarchy when the
+/// cgroup namespace hides the path.
+fn read_cgroup(fs: &dyn Fs, controller: &str, path: &str, file: &str) -> Option<String> {
+    let root = PathBuf::from(CGROUP_ROOT).join(controller);
+    let relative = path.trim_start_matches('/');
+    [root.join(relative).join(file), root.join(file)]
+        .iter()
+        .find_map(|x| fs.read_to_string(x).ok())
+}
+
+/// quota of `cpu.max`: `max 100000` or `150000 100000`.
+fn cpu_max(value: &str) -> Option<f64> {
+    let mut parts = value.split_whitespace();
+    let quota = parts.next()?;
+    let period = parts.next().unwrap_or("100000");
+    if quota == "max" {
+        return None;
+    }
+    cpu_quota(quota.parse().ok()?, period.parse().ok()?)
+}
+
+fn cpu_quota(quota: i64, period: i64) -> Option<f64> {
+    (quota > 0 && period > 0).then(|| quota as f64 / period as f64)
+}
+
+/// memory limit of `memory.max` or `memory.limit_in_bytes`.
+fn memory(value: &str) -> Option<u64> {
+    value
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .filter(|x| *x < UNLIMITED_MEMORY)
+}
+
+/// name of the host, `None` when it cannot be read.
+#[cfg(unix)]
+pub fn hostname() -> Option<String> {
+    let mut buf = [0u8; 256];
+    // SAFETY: buf is a valid out buffer of the given length.
+    if unsafe { libc::gethostname(buf.as_mut_ptr().cast(), buf.len()) } != 0 {
+        return None;
+    }
+    let len = buf.iter().position(|x| *x == 0).unwrap_or(buf.len());
+    Some(String::from_utf8_lossy(&buf[..len]).into_owned()).filter(|x| !x.is_empty())
+}
+
+/// name of the host, `None` when it cannot be read.
+#[cfg(not(unix))]
+pub fn hostname() -> Option<String> {
+    std::env::var("COMPUTERNAME").ok().filter(|x| !x.is_empty())
+}
@@ -1,26 +1,160 @@
+use std::io;
+
+#[cfg(feature = "config")]
 use config::ConfigError;
 use thiserror::Error;
 
+/// exit code of an internal error, `EX_SOFTWARE` of sysexits.h.
+pub const EX_SOFTWARE: u8 = 70;
+/// exit code of a required service which is unavailable, `EX_UNAVAILABLE`.
+pub const EX_UNAVAILABLE: u8 = 69;
+/// exit code of an operating system error, `EX_OSERR`.
+pub const EX_OSERR: u8 = 71;
+/// exit code of an output file which cannot be created, `EX_CANTCREAT`.
+pub const EX_CANTCREAT: u8 = 73;
+/// exit code of a temporary failure, retrying may succeed, `EX_TEMPFAIL`.
+pub const EX_TEMPFAIL: u8 = 75;
+/// exit code of insufficient permissions, `EX_NOPERM`.
+pub const EX_NOPERM: u8 = 77;
+/// exit code of a configuration error, `EX_CONFIG`.
+pub const EX_CONFIG: u8 = 78;
+
 #[derive(Debug, Error)]
 pub enum BootstrapError {
     #[error("unable to initialize tracing subscriber: {0}")]
-    TracingSubscriberInitError(Box<dyn std::error::Error>),
+    TracingSubscriberInitError(Box<dyn std::error::Error + Send + Sync>),
+    #[cfg(feature = "config")]
     #[error("unable to load config: {0}")]
     ConfigLoadError(ConfigError),
+    #[error("config reload rejected: {0}")]
+    ConfigReloadRejectedError(String),
+    #[error("config snapshot not found: {0}")]
+    ConfigSnapshotNotFoundError(u64),
+    #[error("unable to export config: {0}")]
+    ConfigExportError(Box<dyn std::error::Error + Send + Sync>),
+    #[cfg(feature = "config")]
     #[error("unable to show config: {0}")]
     ConfigShowError(ConfigError),
     #[error("invalid config value: {0}")]
     InvalidConfigValueError(String),
     #[error("missing config value: {0}")]
     MissingConfigValueError(String),
+    #[error("missing environment variables: {0}")]
+    MissingEnvVarsError(String),
+    #[cfg(feature = "config")]
     #[error("unable to load logging config: {0}")]
     LoggingConfigLoadError(ConfigError),
     #[error("unable to create log directory: {0}")]
-    LogDirectoryCreationError(Box<dyn std::error::Error>),
+    LogDirectoryCreationError(Box<dyn std::error::Error + Send + Sync>),
     #[error("unable to create log file: {0}")]
     LogFileCreationError(Box<&'static str>),
     #[error("duplicate logger: {0}")]
     DuplicateLoggerError(String),
+    #[error("duplicate log appender: {0}")]
+    DuplicateAppenderError(String),
     #[error("duplicate log file path: {0}")]
     DuplicateLogFilePathError(String),
+    #[error("invalid log file size: {0}")]
+    InvalidLogFileSizeError(String),
+    #[error("invalid log file count: {0}")]
+    InvalidLogFileCountError(String),
+    #[error("invalid log buffer size: {0}")]
+    InvalidLogBufferSizeError(String),
+    #[error("invalid log file name: {0}")]
+    InvalidLogFileNameError(String),
+    #[error("log file is not writable: {0}")]
+    LogFileNotWritableError(String),
+    #[error("insufficient disk space for logging: {0}")]
+    InsufficientDiskSpaceError(String),
+    #[error("unable to get the log encryption key: {0}")]
+    LogEncryptionKeyError(String),
+    #[error("unable to decrypt log: {0}")]
+    LogDecryptionError(String),
+    #[error("unable to open audit log: {0}")]
+    AuditLogOpenError(Box<dyn std::error::Error + Send + Sync>),
+    #[error("unable to write pid file: {0}")]
+    PidFileError(Box<dyn std::error::Error + Send + Sync>),
+    #[error("invalid service graph: {0}")]
+    ServiceGraphError(String),
+    #[error("unable to start the runtime: {0}")]
+    RuntimeInitError(Box<dyn std::error::Error + Send + Sync>),
+    #[error("unable to initialize modules: {0}")]
+    ModuleInitError(String),
+    #[error("unable to handle signal: {0}")]
+    SignalHandlerError(String),
+    #[error("unable to run as a windows service: {0}")]
+    WindowsServiceError(Box<dyn std::error::Error + Send + Sync>),
+    #[error("preflight checks failed: {0}")]
+    PreflightCheckError(String),
+    #[error("dependencies of the startup are unreachable: {0}")]
+    DependencyWaitTimeoutError(String),
+    #[cfg(feature = "fault-injection")]
+    #[error("injected fault: {0}")]
+    InjectedFaultError(String),
+}
+
+impl BootstrapError {
+    /// process exit code of this error, following sysexits.h so supervisors can tell
+    /// misconfiguration from transient failures:
+    ///
+    /// | code | errors |
+    /// |------|--------|
+    /// | 78 `EX_CONFIG` | invalid, missing or inconsistent config |
+    /// | 77 `EX_NOPERM` | files the process is not allowed to write |
+    /// | 75 `EX_TEMPFAIL` | insufficient disk space |
+    /// | 73 `EX_CANTCREAT` | log, audit, pid or export files which cannot be created |
+    /// | 71 `EX_OSERR` | runtime which cannot be started |
+    /// | 69 `EX_UNAVAILABLE` | failed preflight checks, dependencies unreachable at startup |
+    /// | 70 `EX_SOFTWARE` | everything else |
+    ///
+    /// # Example
+    /// ```
+    /// use beaver_bootstrap::error::{BootstrapError, EX_CONFIG};
+    /// let error = BootstrapError::MissingConfigValueError("node.id".to_string());
+    /// assert_eq!(error.exit_code(), EX_CONFIG);
+    /// ```
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            #[cfg(feature = "config")]
+            BootstrapError::ConfigLoadError(_) | BootstrapError::LoggingConfigLoadError(_) => {
+                EX_CONFIG
+            }
+            BootstrapError::ConfigReloadRejectedError(_)
+            | BootstrapError::ConfigSnapshotNotFoundError(_)
+            | BootstrapError::InvalidConfigValueError(_)
+            | BootstrapError::MissingConfigValueError(_)
+            | BootstrapError::MissingEnvVarsError(_)
+            | BootstrapError::DuplicateLoggerError(_)
+            | BootstrapError::DuplicateAppenderError(_)
+            | BootstrapError::DuplicateLogFilePathError(_)
+            | BootstrapError::InvalidLogFileSizeError(_)
+            | BootstrapError::InvalidLogFileCountError(_)
+            | BootstrapError::InvalidLogBufferSizeError(_)
+            | BootstrapError::InvalidLogFileNameError(_)
+            | BootstrapError::LogEncryptionKeyError(_)
+            | BootstrapError::ServiceGraphError(_) => EX_CONFIG,
+            BootstrapError::LogFileNotWritableError(_) => EX_NOPERM,
+            BootstrapError::InsufficientDiskSpaceError(_) => EX_TEMPFAIL,
+            BootstrapError::ConfigExportError(e)
+            | BootstrapError::LogDirectoryCreationError(e)
+            | BootstrapError::AuditLogOpenError(e)
+            | BootstrapError::PidFileError(e) => match e.downcast_ref::<io::Error>() {
+                Some(e) if e.kind() == io::ErrorKind::PermissionDenied => EX_NOPERM,
+                _ => EX_CANTCREAT,
+            },
+            BootstrapError::LogFileCreationError(_) => EX_CANTCREAT,
+            BootstrapError::RuntimeInitError(_) => EX_OSERR,
+            BootstrapError::PreflightCheckError(_)
+            | BootstrapError::DependencyWaitTimeoutError(_) => EX_UNAVAILABLE,
+            #[cfg(feature = "config")]
+            BootstrapError::ConfigShowError(_) => EX_SOFTWARE,
+            #[cfg(feature = "fault-injection")]
+            BootstrapError::InjectedFaultError(_) => EX_SOFTWARE,
+            BootstrapError::TracingSubscriberInitError(_)
+            | BootstrapError::LogDecryptionError(_)
+            | BootstrapError::ModuleInitError(_)
+            | BootstrapError::SignalHandlerError(_)
+            | BootstrapError::WindowsServiceError(_) => EX_SOFTWARE,
+        }
+    }
 }
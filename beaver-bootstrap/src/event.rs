@@ -0,0 +1,92 @@
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    fmt,
+    sync::{Arc, RwLock},
+};
+
+type EventHandler = Arc<dyn Fn(&dyn Any) + Send + Sync>;
+
+/// LifecycleEvent is published on the [`EventBus`] as the application run by
+/// [`Bootstrap::run`](crate::bootstrap::Bootstrap::run) goes through its lifecycle.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LifecycleEvent {
+    /// the bootstrap is initialized and the application starts.
+    Started,
+    /// a shutdown signal was received, the shutdown token is cancelled.
+    ShutdownRequested,
+    /// the application returned, services are disposed next.
+    Stopping,
+}
+
+/// EventBus delivers the events published by beaver and applications to the subscribers of
+/// their type.
+///
+/// Handlers are called synchronously on the publishing thread, in order of subscription.
+///
+/// # Example
+/// ```
+/// use std::sync::{Arc, Mutex};
+/// use beaver_bootstrap::event::{EventBus, LifecycleEvent};
+/// let bus = EventBus::default();
+/// let received = Arc::new(Mutex::new(vec![]));
+/// let events = received.clone();
+/// bus.subscribe(move |event: &LifecycleEvent| events.lock().unwrap().push(*event));
+/// assert_eq!(bus.publish(&LifecycleEvent::Started), 1);
+/// assert_eq!(bus.publish(&"unrelated"), 0);
+/// assert_eq!(*received.lock().unwrap(), [LifecycleEvent::Started]);
+/// ```
+#[derive(Default)]
+pub struct EventBus {
+    handlers: RwLock<HashMap<TypeId, Vec<EventHandler>>>,
+}
+
+impl fmt::Debug for EventBus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let handlers: usize = self
+            .handlers
+            .read()
+            .map(|x| x.values().map(Vec::len).sum())
+            .unwrap_or_default();
+        f.debug_struct("EventBus")
+            .field("handlers", &handlers)
+            .finish()
+    }
+}
+
+impl EventBus {
+    /// call `handler` on every published event of type `E`.
+    pub fn subscribe<E, F>(&self, handler: F)
+    where
+        E: Any,
+        F: Fn(&E) + Send + Sync + 'static,
+    {
+        let handler: EventHandler = Arc::new(move |event: &dyn Any| {
+            if let Some(event) = event.downcast_ref::<E>() {
+                handler(event);
+            }
+        });
+        self.handlers
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(TypeId::of::<E>())
+            .or_default()
+            .push(handler);
+    }
+
+    /// call the handlers of `E` with `event`, returning how many were called.
+    pub fn publish<E: Any>(&self, event: &E) -> usize {
+        // handlers are called without the lock, so they may publish or subscribe themselves
+        let handlers = self
+            .handlers
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&TypeId::of::<E>())
+            .cloned()
+            .unwrap_or_default();
+        for handler in &handlers {
+            handler(event);
+        }
+        handlers.len()
+    }
+}
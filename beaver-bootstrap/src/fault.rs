@@ -0,0 +1,127 @@
+use std::cell::RefCell;
+
+use crate::{error::BootstrapError, runtime::InitFuture};
+
+/// FaultPoint is where a [`FaultPlan`] makes the bootstrap fail.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FaultPoint {
+    /// a phase of [`Bootstrap::initialize`](crate::bootstrap::Bootstrap::initialize), by the
+    /// name of its `bootstrap` span, e.g. `config`, `logging` or `modules`.
+    Phase(String),
+    /// the initialization of the module at this index in the order of registration.
+    Module(usize),
+    /// the disposal of the service at this index in the order of construction.
+    ShutdownHook(usize),
+}
+
+/// FaultPlan forces failures at chosen points of the bootstraps initialized and shut down on
+/// the thread it is installed on, to test the code wrapping them. Only built with the
+/// `fault-injection` feature, it is meant for the dev-dependencies of such tests.
+///
+/// An injected phase fails with [`BootstrapError::InjectedFaultError`] before it runs; an
+/// injected module or shutdown hook fails like one returning an error, instead of running.
+///
+/// # Example
+/// ```
+/// use beaver_bootstrap::{bootstrap::Bootstrap, error::BootstrapError, fault::FaultPlan};
+/// let _faults = FaultPlan::default().fail_phase("config").install();
+/// let error = Bootstrap::builder()
+///     .initialize_logging(false)
+///     .build()
+///     .initialize()
+///     .unwrap_err();
+/// assert!(matches!(error, BootstrapError::InjectedFaultError(_)));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FaultPlan {
+    points: Vec<FaultPoint>,
+}
+
+thread_local! {
+    static PLAN: RefCell<Option<FaultPlan>> = const { RefCell::new(None) };
+}
+
+impl FaultPlan {
+    pub fn fail_phase(mut self, name: &str) -> Self {
+        self.points.push(FaultPoint::Phase(name.to_string()));
+        self
+    }
+
+    pub fn fail_module(mut self, index: usize) -> Self {
+        self.points.push(FaultPoint::Module(index));
+        self
+    }
+
+    pub fn fail_shutdown_hook(mut self, index: usize) -> Self {
+        self.points.push(FaultPoint::ShutdownHook(index));
+        self
+    }
+
+    pub fn points(&self) -> &[FaultPoint] {
+        &self.points
+    }
+
+    /// inject the faults on this thread until the guard is dropped, replacing the plan
+    /// installed before, if any, meanwhile.
+    pub fn install(self) -> FaultGuard {
+        let previous = PLAN.with(|x| x.replace(Some(self)));
+        FaultGuard { previous }
+    }
+}
+
+/// FaultGuard removes its [`FaultPlan`] when dropped.
+#[derive(Debug)]
+#[must_use = "the faults are removed once the guard is dropped"]
+pub struct FaultGuard {
+    previous: Option<FaultPlan>,
+}
+
+impl Drop for FaultGuard {
+    fn drop(&mut self) {
+        PLAN.with(|x| x.replace(self.previous.take()));
+    }
+}
+
+/// whether the installed plan holds `point`.
+fn injected(point: &FaultPoint) -> bool {
+    PLAN.with(|x| {
+        x.borrow()
+            .as_ref()
+            .is_some_and(|x| x.points.contains(point))
+    })
+}
+
+/// fail when a fault is injected at the phase `name`.
+pub(crate) fn trip_phase(name: &str) -> Result<(), BootstrapError> {
+    if injected(&FaultPoint::Phase(name.to_string())) {
+        return Err(BootstrapError::InjectedFaultError(format!(
+            "phase {}",
+            name
+        )));
+    }
+    Ok(())
+}
+
+/// the initialization of the module at `index`, failing, when a fault is injected at it.
+pub(crate) fn module(index: usize) -> Option<InitFuture> {
+    if !injected(&FaultPoint::Module(index)) {
+        return None;
+    }
+    Some(Box::pin(async move {
+        Err(format!("injected fault at module {}", index).into())
+    }))
+}
+
+/// the indexes of the shutdown hooks with a fault injected.
+pub(crate) fn shutdown_hooks() -> Vec<usize> {
+    PLAN.with(|x| {
+        x.borrow()
+            .iter()
+            .flat_map(|x| &x.points)
+            .filter_map(|x| match x {
+                FaultPoint::ShutdownHook(index) => Some(*index),
+                _ => None,
+            })
+            .collect()
+    })
+}
@@ -0,0 +1,219 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+/// Fs is the filesystem seen by the bootstrap: config discovery, log directories and PID files.
+///
+/// [`OsFs`] is used in production, [`MemoryFs`] lets the bootstrap path run in tests without
+/// touching the real filesystem.
+pub trait Fs: Send + Sync {
+    fn exists(&self, path: &Path) -> bool;
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()>;
+
+    fn read_to_string(&self, path: &Path) -> io::Result<String>;
+
+    /// create or truncate `path` and write `contents`.
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()>;
+
+    /// create `path` if needed and append `contents`.
+    fn append(&self, path: &Path, contents: &[u8]) -> io::Result<()>;
+
+    fn remove_file(&self, path: &Path) -> io::Result<()>;
+}
+
+/// OsFs is the filesystem of the operating system.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OsFs;
+
+impl Fs for OsFs {
+    fn exists(&self, path: &Path) -> bool {
+        path.exists()
+    }
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        std::fs::create_dir_all(path)
+    }
+
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        std::fs::read_to_string(path)
+    }
+
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        std::fs::write(path, contents)
+    }
+
+    fn append(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?
+            .write_all(contents)
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        std::fs::remove_file(path)
+    }
+}
+
+#[derive(Debug, Default)]
+struct MemoryFsState {
+    dirs: BTreeSet<PathBuf>,
+    files: BTreeMap<PathBuf, Vec<u8>>,
+}
+
+/// MemoryFs keeps directories and files in memory, clones share the same tree.
+///
+/// Files can only be created in existing directories, like on a real filesystem.
+///
+/// # Example
+/// ```
+/// use std::path::Path;
+/// use beaver_bootstrap::fs::{Fs, MemoryFs};
+/// let fs = MemoryFs::default().with_file("/app/etc/config.toml", "[logging]");
+/// assert_eq!(fs.read_to_string(Path::new("/app/etc/config.toml")).unwrap(), "[logging]");
+/// assert!(fs.write(Path::new("/app/logs/app.log"), b"").is_err());
+/// fs.create_dir_all(Path::new("/app/logs")).unwrap();
+/// fs.append(Path::new("/app/logs/app.log"), b"started").unwrap();
+/// assert!(fs.exists(Path::new("/app/logs/app.log")));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MemoryFs {
+    state: Arc<Mutex<MemoryFsState>>,
+}
+
+impl MemoryFs {
+    /// add a file, creating its parent directories.
+    pub fn with_file(self, path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> Self {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            let _ = self.create_dir_all(parent);
+        }
+        let _ = self.write(path, contents.as_ref());
+        self
+    }
+
+    /// contents of `path`, if it is a file.
+    pub fn file(&self, path: impl AsRef<Path>) -> Option<Vec<u8>> {
+        self.lock().files.get(path.as_ref()).cloned()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, MemoryFsState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl MemoryFsState {
+    fn require_parent(&self, path: &Path) -> io::Result<()> {
+        if self.dirs.contains(path) {
+            return Err(io::Error::other(format!(
+                "{} is a directory",
+                path.display()
+            )));
+        }
+        match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() && !self.dirs.contains(parent) => {
+                Err(not_found(parent))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Fs for MemoryFs {
+    fn exists(&self, path: &Path) -> bool {
+        let state = self.lock();
+        state.dirs.contains(path) || state.files.contains_key(path)
+    }
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        let mut state = self.lock();
+        if state.files.contains_key(path) {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} is a file", path.display()),
+            ));
+        }
+        for dir in path.ancestors().filter(|x| !x.as_os_str().is_empty()) {
+            state.dirs.insert(dir.to_path_buf());
+        }
+        Ok(())
+    }
+
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        let contents = self.file(path).ok_or_else(|| not_found(path))?;
+        String::from_utf8(contents).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        let mut state = self.lock();
+        state.require_parent(path)?;
+        state.files.insert(path.to_path_buf(), contents.to_vec());
+        Ok(())
+    }
+
+    fn append(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        let mut state = self.lock();
+        state.require_parent(path)?;
+        state
+            .files
+            .entry(path.to_path_buf())
+            .or_default()
+            .extend_from_slice(contents);
+        Ok(())
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        self.lock()
+            .files
+            .remove(path)
+            .map(|_| ())
+            .ok_or_else(|| not_found(path))
+    }
+}
+
+fn not_found(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("{} not found", path.display()),
+    )
+}
+
+/// PidFile holds the id of the running process, the file is removed on drop.
+pub struct PidFile {
+    fs: Arc<dyn Fs>,
+    path: PathBuf,
+}
+
+impl std::fmt::Debug for PidFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PidFile").field("path", &self.path).finish()
+    }
+}
+
+impl PidFile {
+    /// write the process id to `path`, creating its directory if needed.
+    pub fn create(fs: Arc<dyn Fs>, path: &Path) -> io::Result<Self> {
+        if let Some(parent) = path.parent().filter(|x| !x.as_os_str().is_empty()) {
+            fs.create_dir_all(parent)?;
+        }
+        fs.write(path, format!("{}\n", std::process::id()).as_bytes())?;
+        Ok(Self {
+            fs,
+            path: path.to_path_buf(),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for PidFile {
+    fn drop(&mut self) {
+        let _ = self.fs.remove_file(&self.path);
+    }
+}
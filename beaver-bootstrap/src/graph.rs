@@ -0,0 +1,183 @@
+use std::collections::{HashMap, HashSet};
+
+use di::{ServiceCardinality, ServiceCollection, Type};
+
+use crate::error::BootstrapError;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mark {
+    Visiting,
+    Done,
+}
+
+/// ServiceGraph is the dependency graph of a service collection, checked before the service
+/// provider is built so that a bad registration fails the startup with the whole chain rather
+/// than a panic on first resolution.
+///
+/// # Example
+/// ```
+/// use beaver_bootstrap::graph::ServiceGraph;
+/// use di::*;
+/// struct Config;
+/// #[injectable]
+/// struct Pool {
+///     _config: Ref<Config>,
+/// }
+/// #[injectable]
+/// struct Repo {
+///     _pool: Ref<Pool>,
+/// }
+/// let mut services = ServiceCollection::new();
+/// services.add(Repo::singleton()).add(Pool::singleton());
+/// let graph = ServiceGraph::new(&services);
+/// assert_eq!(graph.missing()[0].len(), 3);
+/// let error = graph.validate().unwrap_err().to_string();
+/// assert!(error.contains("Repo -> ") && error.contains("Pool -> ") && error.ends_with("Config"));
+/// ```
+pub struct ServiceGraph {
+    /// registered service types and what they depend on.
+    edges: HashMap<Type, Vec<(Type, ServiceCardinality)>>,
+    /// service types in registration order, to report in a stable order.
+    order: Vec<Type>,
+}
+
+impl ServiceGraph {
+    pub fn new(services: &ServiceCollection) -> Self {
+        let mut edges: HashMap<Type, Vec<(Type, ServiceCardinality)>> = HashMap::new();
+        let mut order = Vec::new();
+        for descriptor in services {
+            let service_type = descriptor.service_type().clone();
+            let dependencies = edges.entry(service_type.clone()).or_insert_with(|| {
+                order.push(service_type);
+                vec![]
+            });
+            dependencies.extend(
+                descriptor
+                    .dependencies()
+                    .iter()
+                    .map(|x| (x.injected_type().clone(), x.cardinality())),
+            );
+        }
+        Self { edges, order }
+    }
+
+    /// chains ending in a required service which is not registered, from a root service.
+    pub fn missing(&self) -> Vec<Vec<Type>> {
+        self.walk().0
+    }
+
+    /// dependency cycles, each starting and ending with the same service.
+    pub fn cycles(&self) -> Vec<Vec<Type>> {
+        self.walk().1
+    }
+
+    /// fail with every missing chain and cycle, type names joined by `->`.
+    pub fn validate(&self) -> Result<(), BootstrapError> {
+        let (missing, cycles) = self.walk();
+        let mut problems: Vec<String> = missing
+            .iter()
+            .map(|chain| format!("missing {}", render_chain(chain)))
+            .collect();
+        problems.extend(
+            cycles
+                .iter()
+                .map(|chain| format!("cycle {}", render_chain(chain))),
+        );
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(BootstrapError::ServiceGraphError(problems.join("; ")))
+        }
+    }
+
+    /// depth first walk from the services nobody depends on, then from the rest, which are
+    /// only reachable through cycles.
+    fn walk(&self) -> (Vec<Vec<Type>>, Vec<Vec<Type>>) {
+        let dependents: HashSet<&Type> = self
+            .edges
+            .values()
+            .flat_map(|deps| deps.iter().map(|(x, _)| x))
+            .collect();
+        let roots = self.order.iter().filter(|x| !dependents.contains(x));
+        let rest = self.order.iter().filter(|x| dependents.contains(x));
+
+        let mut marks: HashMap<&Type, Mark> = HashMap::new();
+        let mut missing = vec![];
+        let mut cycles = vec![];
+        let mut seen_cycles: HashSet<Vec<u64>> = HashSet::new();
+        for start in roots.chain(rest) {
+            let mut path = vec![];
+            self.visit(
+                start,
+                &mut path,
+                &mut marks,
+                &mut missing,
+                &mut cycles,
+                &mut seen_cycles,
+            );
+        }
+        (missing, cycles)
+    }
+
+    fn visit<'a>(
+        &'a self,
+        node: &'a Type,
+        path: &mut Vec<&'a Type>,
+        marks: &mut HashMap<&'a Type, Mark>,
+        missing: &mut Vec<Vec<Type>>,
+        cycles: &mut Vec<Vec<Type>>,
+        seen_cycles: &mut HashSet<Vec<u64>>,
+    ) {
+        match marks.get(node) {
+            Some(Mark::Done) => return,
+            Some(Mark::Visiting) => {
+                let start = path.iter().position(|x| *x == node).unwrap_or_default();
+                let mut cycle: Vec<Type> = path[start..].iter().map(|x| (*x).clone()).collect();
+                // the same cycle is found from any of its members, compare it rotated to start
+                // with its smallest member
+                let mut key: Vec<u64> = cycle.iter().map(Type::id).collect();
+                let min = key
+                    .iter()
+                    .enumerate()
+                    .min_by_key(|(_, id)| **id)
+                    .map(|(i, _)| i)
+                    .unwrap_or_default();
+                key.rotate_left(min);
+                if seen_cycles.insert(key) {
+                    cycle.push(node.clone());
+                    cycles.push(cycle);
+                }
+                return;
+            }
+            None => {}
+        }
+        marks.insert(node, Mark::Visiting);
+        path.push(node);
+        for (dependency, cardinality) in self.edges.get(node).into_iter().flatten() {
+            if self.edges.contains_key(dependency) {
+                self.visit(dependency, path, marks, missing, cycles, seen_cycles);
+            } else if *cardinality == ServiceCardinality::ExactlyOne {
+                let mut chain: Vec<Type> = path.iter().map(|x| (*x).clone()).collect();
+                chain.push(dependency.clone());
+                missing.push(chain);
+            }
+        }
+        path.pop();
+        marks.insert(node, Mark::Done);
+    }
+}
+
+fn render_type(t: &Type) -> String {
+    match Type::deconstruct(t) {
+        (name, Some(key)) => format!("{}[{}]", name, key),
+        (name, None) => name.to_string(),
+    }
+}
+
+fn render_chain(chain: &[Type]) -> String {
+    chain
+        .iter()
+        .map(render_type)
+        .collect::<Vec<_>>()
+        .join(" -> ")
+}
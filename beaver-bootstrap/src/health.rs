@@ -0,0 +1,463 @@
+use std::{
+    collections::BTreeMap,
+    fmt,
+    sync::{
+        Arc, Mutex, RwLock,
+        mpsc::{self, RecvTimeoutError, Sender},
+    },
+    thread,
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use self::{cert::CertHealthConfig, dependency::DependencyConfig, disk::DiskHealthConfig};
+use crate::{
+    admin::{AdminResponse, AdminRoutes},
+    clock::Clock,
+    config::{Config, ConfigPrefix},
+    error::BootstrapError,
+    event::EventBus,
+    serde::duration_opt,
+};
+
+pub mod cert;
+pub mod dependency;
+pub mod disk;
+
+/// time between two evaluations of the health checks unless `health.interval` is set.
+pub const DEFAULT_HEALTH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// HealthStatus is the health of a check or of the process, ordered from best to worst.
+#[derive(
+    Debug, Default, Copy, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthStatus {
+    #[default]
+    Healthy,
+    /// working with reduced capacity or about to fail, e.g. a certificate expiring soon.
+    Degraded,
+    /// not able to serve.
+    Unhealthy,
+}
+
+impl fmt::Display for HealthStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            HealthStatus::Healthy => "healthy",
+            HealthStatus::Degraded => "degraded",
+            HealthStatus::Unhealthy => "unhealthy",
+        };
+        f.write_str(name)
+    }
+}
+
+/// HealthCheckResult is the outcome of one run of a [`HealthCheck`].
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Eq)]
+pub struct HealthCheckResult {
+    status: HealthStatus,
+    /// what is wrong, for the checks which are not healthy.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<String>,
+}
+
+impl HealthCheckResult {
+    pub fn healthy() -> Self {
+        Self::default()
+    }
+
+    pub fn degraded(detail: impl Into<String>) -> Self {
+        Self {
+            status: HealthStatus::Degraded,
+            detail: Some(detail.into()),
+        }
+    }
+
+    pub fn unhealthy(detail: impl Into<String>) -> Self {
+        Self {
+            status: HealthStatus::Unhealthy,
+            detail: Some(detail.into()),
+        }
+    }
+
+    pub fn status(&self) -> HealthStatus {
+        self.status
+    }
+
+    pub fn detail(&self) -> Option<&str> {
+        self.detail.as_deref()
+    }
+
+    /// the worst of the statuses of the parts of a check, detailed by the parts which are not
+    /// healthy.
+    pub(crate) fn worst(parts: impl IntoIterator<Item = (HealthStatus, String)>) -> Self {
+        let parts: Vec<(HealthStatus, String)> = parts.into_iter().collect();
+        let status = parts.iter().map(|(x, _)| *x).max().unwrap_or_default();
+        let details: Vec<&str> = parts
+            .iter()
+            .filter(|(x, _)| *x != HealthStatus::Healthy)
+            .map(|(_, x)| x.as_str())
+            .collect();
+        match status {
+            HealthStatus::Healthy => Self::healthy(),
+            HealthStatus::Degraded => Self::degraded(details.join(", ")),
+            HealthStatus::Unhealthy => Self::unhealthy(details.join(", ")),
+        }
+    }
+}
+
+/// HealthCheck is a part of the process whose health is evaluated periodically, once it is
+/// registered in the [`HealthRegistry`].
+///
+/// # Example
+/// ```
+/// use beaver_bootstrap::health::{HealthCheck, HealthCheckResult};
+/// struct QueueDepth(fn() -> usize);
+/// impl HealthCheck for QueueDepth {
+///     fn name(&self) -> String {
+///         "queue_depth".to_string()
+///     }
+///     fn check(&self) -> HealthCheckResult {
+///         match (self.0)() {
+///             0..1000 => HealthCheckResult::healthy(),
+///             depth => HealthCheckResult::degraded(format!("{} messages queued", depth)),
+///         }
+///     }
+/// }
+/// ```
+pub trait HealthCheck: Send + Sync {
+    /// name of the check, unique in its registry.
+    fn name(&self) -> String;
+    /// evaluate the check, it should return within a few seconds.
+    fn check(&self) -> HealthCheckResult;
+}
+
+/// CheckReport is the result of a check in a [`HealthReport`].
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct CheckReport {
+    pub name: String,
+    #[serde(flatten)]
+    pub result: HealthCheckResult,
+}
+
+/// HealthReport is the health of the process, the worst status of its checks.
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Eq)]
+pub struct HealthReport {
+    pub status: HealthStatus,
+    pub checks: Vec<CheckReport>,
+}
+
+impl HealthReport {
+    /// the checks which are not healthy.
+    pub fn failing(&self) -> Vec<CheckReport> {
+        self.checks
+            .iter()
+            .filter(|x| x.result.status != HealthStatus::Healthy)
+            .cloned()
+            .collect()
+    }
+}
+
+/// HealthTransition is published on the [`EventBus`] when the health of the process
+/// changes, e.g. to shed load once it is degraded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HealthTransition {
+    pub from: HealthStatus,
+    pub to: HealthStatus,
+    /// the checks which are not healthy after the transition.
+    pub failing: Vec<CheckReport>,
+}
+
+/// HealthRegistry evaluates the registered [`HealthCheck`]s and reports their changes: a
+/// [`HealthTransition`] is published on its event bus when the status of the process
+/// changes, and a warning names every check which becomes degraded or unhealthy.
+///
+/// # Example
+/// ```
+/// use std::sync::{Arc, Mutex};
+/// use beaver_bootstrap::{
+///     event::EventBus,
+///     health::{HealthCheck, HealthCheckResult, HealthRegistry, HealthStatus, HealthTransition},
+/// };
+/// struct Replica;
+/// impl HealthCheck for Replica {
+///     fn name(&self) -> String {
+///         "replica".to_string()
+///     }
+///     fn check(&self) -> HealthCheckResult {
+///         HealthCheckResult::degraded("replication lag 42s")
+///     }
+/// }
+/// let events = Arc::new(EventBus::default());
+/// let transitions = Arc::new(Mutex::new(vec![]));
+/// let received = transitions.clone();
+/// events.subscribe(move |x: &HealthTransition| received.lock().unwrap().push(x.to));
+/// let registry = HealthRegistry::default().with_events(events);
+/// registry.register(Arc::new(Replica));
+/// assert_eq!(registry.evaluate().status, HealthStatus::Degraded);
+/// registry.evaluate();
+/// assert_eq!(*transitions.lock().unwrap(), [HealthStatus::Degraded]);
+/// ```
+#[derive(Default)]
+pub struct HealthRegistry {
+    checks: RwLock<Vec<Arc<dyn HealthCheck>>>,
+    /// report of the last evaluation.
+    last: Mutex<HealthReport>,
+    events: Option<Arc<EventBus>>,
+}
+
+impl fmt::Debug for HealthRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HealthRegistry")
+            .field("status", &self.status())
+            .finish_non_exhaustive()
+    }
+}
+
+impl HealthRegistry {
+    /// publish the [`HealthTransition`]s on `events`.
+    pub fn with_events(mut self, events: Arc<EventBus>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    pub fn register(&self, check: Arc<dyn HealthCheck>) {
+        self.checks
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(check);
+    }
+
+    /// run every check, reporting the changes since the last evaluation.
+    pub fn evaluate(&self) -> HealthReport {
+        // checks run without the lock, they may take a while
+        let checks = self
+            .checks
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        let checks: Vec<CheckReport> = checks
+            .iter()
+            .map(|x| CheckReport {
+                name: x.name(),
+                result: x.check(),
+            })
+            .collect();
+        let report = HealthReport {
+            status: checks
+                .iter()
+                .map(|x| x.result.status)
+                .max()
+                .unwrap_or_default(),
+            checks,
+        };
+        let last = std::mem::replace(
+            &mut *self.last.lock().unwrap_or_else(|e| e.into_inner()),
+            report.clone(),
+        );
+        self.report_changes(&last, &report);
+        report
+    }
+
+    /// the report of the last evaluation, healthy before the first.
+    pub fn report(&self) -> HealthReport {
+        self.last.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    pub fn status(&self) -> HealthStatus {
+        self.last.lock().unwrap_or_else(|e| e.into_inner()).status
+    }
+
+    fn report_changes(&self, last: &HealthReport, report: &HealthReport) {
+        let previous: BTreeMap<&str, HealthStatus> = last
+            .checks
+            .iter()
+            .map(|x| (x.name.as_str(), x.result.status))
+            .collect();
+        for check in &report.checks {
+            let (status, detail) = (check.result.status, check.result.detail().unwrap_or(""));
+            let before = previous
+                .get(check.name.as_str())
+                .copied()
+                .unwrap_or_default();
+            if status == before {
+                continue;
+            }
+            match status {
+                HealthStatus::Healthy => tracing::info!(
+                    target: "health",
+                    check = check.name.as_str(),
+                    status = %status,
+                    "health check {} recovered",
+                    check.name
+                ),
+                _ => tracing::warn!(
+                    target: "health",
+                    check = check.name.as_str(),
+                    status = %status,
+                    detail,
+                    "health check {} is {}: {}",
+                    check.name,
+                    status,
+                    detail
+                ),
+            }
+        }
+        if report.status == last.status {
+            return;
+        }
+        tracing::warn!(
+            target: "health",
+            from = %last.status,
+            to = %report.status,
+            "health changed from {} to {}",
+            last.status,
+            report.status
+        );
+        if let Some(events) = &self.events {
+            events.publish(&HealthTransition {
+                from: last.status,
+                to: report.status,
+                failing: report.failing(),
+            });
+        }
+    }
+}
+
+/// HealthConfig schedules the evaluation of the health checks, see `[health]`:
+///
+/// ```toml
+/// [health]
+/// interval = "30s"
+///
+/// [health.disk]
+/// unhealthy_free_ratio = 0.02
+///
+/// [health.certs]
+/// paths = ["/etc/billing/tls/server.pem"]
+///
+/// [health.dependencies]
+/// api = { url = "http://api.internal:8080/health", timeout = "2s" }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HealthConfig {
+    /// whether the checks are evaluated periodically, otherwise on demand only.
+    enable: bool,
+    /// time between two evaluations, [`DEFAULT_HEALTH_INTERVAL`] by default.
+    #[serde(deserialize_with = "duration_opt")]
+    interval: Option<Duration>,
+    /// the built-in check of the free space of the log and data directories.
+    disk: DiskHealthConfig,
+    /// the built-in check of the expiry of the TLS certificates.
+    certs: CertHealthConfig,
+    /// the services the process depends on, by name.
+    dependencies: BTreeMap<String, DependencyConfig>,
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        Self {
+            enable: true,
+            interval: None,
+            disk: DiskHealthConfig::default(),
+            certs: CertHealthConfig::default(),
+            dependencies: BTreeMap::new(),
+        }
+    }
+}
+
+impl ConfigPrefix for HealthConfig {
+    const PREFIX: &'static str = "health";
+}
+
+impl HealthConfig {
+    pub fn new(config: &Config) -> Result<Self, BootstrapError> {
+        let health_config = config
+            .get::<HealthConfig>()
+            .map_err(BootstrapError::ConfigLoadError)?;
+        if health_config.interval.is_some_and(|x| x.is_zero()) {
+            return Err(BootstrapError::InvalidConfigValueError(
+                "health.interval=0".to_string(),
+            ));
+        }
+        health_config.disk.validate()?;
+        health_config.certs.validate()?;
+        for (name, dependency) in &health_config.dependencies {
+            dependency.validate(name)?;
+        }
+        Ok(health_config)
+    }
+
+    pub fn enable(&self) -> bool {
+        self.enable
+    }
+
+    pub fn interval(&self) -> Duration {
+        self.interval.unwrap_or(DEFAULT_HEALTH_INTERVAL)
+    }
+
+    pub fn disk(&self) -> &DiskHealthConfig {
+        &self.disk
+    }
+
+    pub fn certs(&self) -> &CertHealthConfig {
+        &self.certs
+    }
+
+    pub fn dependencies(&self) -> &BTreeMap<String, DependencyConfig> {
+        &self.dependencies
+    }
+}
+
+/// HealthMonitor evaluates a [`HealthRegistry`] on start then every interval, until dropped.
+pub struct HealthMonitor {
+    stop: Sender<()>,
+}
+
+impl fmt::Debug for HealthMonitor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HealthMonitor").finish_non_exhaustive()
+    }
+}
+
+impl HealthMonitor {
+    pub fn start(
+        config: &HealthConfig,
+        registry: Arc<HealthRegistry>,
+        clock: Arc<dyn Clock>,
+    ) -> Result<Self, BootstrapError> {
+        let (stop, rx) = mpsc::channel::<()>();
+        let interval = config.interval();
+        thread::Builder::new()
+            .name("beaver-health".to_string())
+            .spawn(move || {
+                registry.evaluate();
+                while let Err(RecvTimeoutError::Timeout) = clock.wait(&rx, interval) {
+                    registry.evaluate();
+                }
+            })
+            .map_err(|e| BootstrapError::InvalidConfigValueError(format!("health: {}", e)))?;
+        Ok(Self { stop })
+    }
+}
+
+impl Drop for HealthMonitor {
+    fn drop(&mut self) {
+        let _ = self.stop.send(());
+    }
+}
+
+/// register `/health`, the report of the last evaluation of `registry` as JSON, served with
+/// 503 when unhealthy.
+pub fn register_routes(routes: &AdminRoutes, registry: Arc<HealthRegistry>) {
+    routes.route("/health", move |_| {
+        let report = registry.report();
+        let status = match report.status {
+            HealthStatus::Unhealthy => 503,
+            _ => 200,
+        };
+        AdminResponse::json(status, &report)
+    });
+}
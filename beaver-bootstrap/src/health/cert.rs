@@ -0,0 +1,284 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use serde::{Deserialize, Serialize};
+
+use super::{HealthCheck, HealthCheckResult, HealthStatus};
+use crate::{
+    clock::{Clock, SystemClock},
+    error::BootstrapError,
+    fs::{Fs, OsFs},
+    serde::duration_opt,
+};
+
+/// time before the expiry of a certificate from which it is reported as degraded, unless
+/// `health.certs.window` is set.
+pub const DEFAULT_CERT_EXPIRY_WINDOW: Duration = Duration::from_secs(30 * 86400);
+
+/// CertHealthConfig lists the TLS certificates whose expiry is checked, see `[health.certs]`:
+///
+/// ```toml
+/// [health.certs]
+/// paths = ["/etc/billing/tls/server.pem"]
+/// window = "14d"
+/// ```
+///
+/// Every certificate of a PEM file is checked, so a chain expires with its first
+/// certificate.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CertHealthConfig {
+    /// PEM files of the certificates.
+    paths: Vec<PathBuf>,
+    /// time before the expiry from which a certificate is degraded,
+    /// [`DEFAULT_CERT_EXPIRY_WINDOW`] by default.
+    #[serde(deserialize_with = "duration_opt")]
+    window: Option<Duration>,
+}
+
+impl CertHealthConfig {
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+
+    pub fn window(&self) -> Duration {
+        self.window.unwrap_or(DEFAULT_CERT_EXPIRY_WINDOW)
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), BootstrapError> {
+        if self.window.is_some_and(|x| x.is_zero()) {
+            return Err(BootstrapError::InvalidConfigValueError(
+                "health.certs.window=0".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// the check of `paths`, `None` when there is none.
+    pub fn check(&self, fs: Arc<dyn Fs>, clock: Arc<dyn Clock>) -> Option<CertExpiryCheck> {
+        if self.paths.is_empty() {
+            return None;
+        }
+        Some(
+            CertExpiryCheck::new(self.paths.clone())
+                .with_window(self.window())
+                .with_fs(fs)
+                .with_clock(clock),
+        )
+    }
+}
+
+/// CertExpiryCheck reads the certificates of PEM files and is degraded when one of them
+/// expires within its window, unhealthy once one has expired or can not be read.
+///
+/// # Example
+/// ```
+/// use std::{sync::Arc, time::{Duration, UNIX_EPOCH}};
+/// use beaver_bootstrap::{
+///     clock::ManualClock,
+///     fs::MemoryFs,
+///     health::{HealthCheck, HealthStatus, cert::CertExpiryCheck},
+/// };
+/// let pem = "-----BEGIN CERTIFICATE-----
+/// MIIBdjCCAR2gAwIBAgIUOXptnbVOBSvLJ26xlBUQ/uom6pgwCgYIKoZIzj0EAwIw
+/// ETEPMA0GA1UEAwwGYmVhdmVyMB4XDTI1MDEwMTAwMDAwMFoXDTMwMDEwMTAwMDAw
+/// MFowETEPMA0GA1UEAwwGYmVhdmVyMFkwEwYHKoZIzj0CAQYIKoZIzj0DAQcDQgAE
+/// 5t7htzDUkPmYzWlb1UsLozxmXVGxbHFtrCBFjg34dSeXZ+035/BLb6c27NAnN6Hr
+/// Q8C3tWwS8f/c8NJBHt+TgKNTMFEwHQYDVR0OBBYEFF37U324K1gb776tlT1R+nP1
+/// jhLJMB8GA1UdIwQYMBaAFF37U324K1gb776tlT1R+nP1jhLJMA8GA1UdEwEB/wQF
+/// MAMBAf8wCgYIKoZIzj0EAwIDRwAwRAIgPR/B0hHHEQPkYUrreCRw6Nncwi8s+Yqq
+/// lRZ2/hlfRBwCIFUPgnnhFsVOjM8bQI9mOJPtNmq5Sx290JjfWcySuDp/
+/// -----END CERTIFICATE-----";
+/// // the certificate expires on 2030-01-01
+/// let clock = Arc::new(ManualClock::new(UNIX_EPOCH + Duration::from_secs(1_891_000_000)));
+/// let check = CertExpiryCheck::new(vec!["/etc/tls/server.pem".into()])
+///     .with_fs(Arc::new(MemoryFs::default().with_file("/etc/tls/server.pem", pem)))
+///     .with_clock(clock);
+/// assert_eq!(check.check().status(), HealthStatus::Degraded);
+/// ```
+#[derive(Clone)]
+pub struct CertExpiryCheck {
+    paths: Vec<PathBuf>,
+    window: Duration,
+    fs: Arc<dyn Fs>,
+    clock: Arc<dyn Clock>,
+}
+
+impl std::fmt::Debug for CertExpiryCheck {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CertExpiryCheck")
+            .field("paths", &self.paths)
+            .field("window", &self.window)
+            .finish_non_exhaustive()
+    }
+}
+
+impl CertExpiryCheck {
+    /// a check of the PEM files `paths` with the [`DEFAULT_CERT_EXPIRY_WINDOW`].
+    pub fn new(paths: Vec<PathBuf>) -> Self {
+        Self {
+            paths,
+            window: DEFAULT_CERT_EXPIRY_WINDOW,
+            fs: Arc::new(OsFs),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    pub fn with_window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    pub fn with_fs(mut self, fs: Arc<dyn Fs>) -> Self {
+        self.fs = fs;
+        self
+    }
+
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// the status of the certificates of one file and why.
+    fn check_path(&self, path: &Path, now: SystemTime) -> (HealthStatus, String) {
+        let not_after = self
+            .fs
+            .read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|x| not_after(&x));
+        let not_after = match not_after {
+            Ok(not_after) => not_after,
+            Err(e) => {
+                return (
+                    HealthStatus::Unhealthy,
+                    format!("{}: unable to read its certificates: {}", path.display(), e),
+                );
+            }
+        };
+        match not_after.duration_since(now) {
+            Err(e) => (
+                HealthStatus::Unhealthy,
+                format!(
+                    "{}: expired {}s ago",
+                    path.display(),
+                    e.duration().as_secs()
+                ),
+            ),
+            Ok(left) if left < self.window => (
+                HealthStatus::Degraded,
+                format!(
+                    "{}: expires in {}d {}h",
+                    path.display(),
+                    left.as_secs() / 86400,
+                    left.as_secs() / 3600 % 24
+                ),
+            ),
+            Ok(_) => (HealthStatus::Healthy, String::new()),
+        }
+    }
+}
+
+impl HealthCheck for CertExpiryCheck {
+    fn name(&self) -> String {
+        "certs".to_string()
+    }
+
+    fn check(&self) -> HealthCheckResult {
+        let now = self.clock.now();
+        let checked = self.paths.iter().map(|x| self.check_path(x, now));
+        HealthCheckResult::worst(checked)
+    }
+}
+
+/// the earliest expiry of the certificates of `pem`.
+pub fn not_after(pem: &str) -> Result<SystemTime, String> {
+    let mut earliest: Option<SystemTime> = None;
+    for block in pem.split("-----BEGIN CERTIFICATE-----").skip(1) {
+        let Some((base64, _)) = block.split_once("-----END CERTIFICATE-----") else {
+            return Err("certificate without its END line".to_string());
+        };
+        let base64: String = base64.split_whitespace().collect();
+        let der = STANDARD.decode(base64).map_err(|e| e.to_string())?;
+        let time = der_not_after(&der).ok_or("malformed certificate")?;
+        earliest = Some(earliest.map_or(time, |x| x.min(time)));
+    }
+    earliest.ok_or_else(|| "no certificate".to_string())
+}
+
+/// the `notAfter` of the validity of a DER certificate.
+fn der_not_after(der: &[u8]) -> Option<SystemTime> {
+    let (_, certificate, _) = der_element(der, 0x30)?;
+    let (_, tbs, _) = der_element(certificate, 0x30)?;
+    // the version is only present in v2 and v3 certificates
+    let tbs = match tbs.first() {
+        Some(0xa0) => der_element(tbs, 0xa0)?.2,
+        _ => tbs,
+    };
+    let (_, _, tbs) = der_element(tbs, 0x02)?; // serial number
+    let (_, _, tbs) = der_element(tbs, 0x30)?; // signature algorithm
+    let (_, _, tbs) = der_element(tbs, 0x30)?; // issuer
+    let (_, validity, _) = der_element(tbs, 0x30)?;
+    let (_, _, validity) = der_element(validity, validity.first().copied()?)?;
+    let (tag, time, _) = der_element(validity, validity.first().copied()?)?;
+    der_time(tag, time)
+}
+
+/// the tag, the content and the rest of the element starting `input`, which must be tagged
+/// `tag`.
+fn der_element(input: &[u8], tag: u8) -> Option<(u8, &[u8], &[u8])> {
+    let (&first, input) = input.split_first()?;
+    if first != tag {
+        return None;
+    }
+    let (&length, mut input) = input.split_first()?;
+    let length = match length {
+        0..=0x7f => usize::from(length),
+        0x81..=0x84 => {
+            let (bytes, rest) = input.split_at_checked(usize::from(length & 0x7f))?;
+            input = rest;
+            bytes.iter().fold(0, |x, &b| x << 8 | usize::from(b))
+        }
+        _ => return None,
+    };
+    let (content, rest) = input.split_at_checked(length)?;
+    Some((tag, content, rest))
+}
+
+/// a UTCTime `YYMMDDHHMMSSZ` or a GeneralizedTime `YYYYMMDDHHMMSSZ`.
+fn der_time(tag: u8, time: &[u8]) -> Option<SystemTime> {
+    let time = std::str::from_utf8(time).ok()?.strip_suffix('Z')?;
+    let (year, rest) = match tag {
+        // years 50 to 99 are the 1900s
+        0x17 => {
+            let year: i64 = time.get(..2)?.parse().ok()?;
+            (
+                if year < 50 { 2000 + year } else { 1900 + year },
+                &time[2..],
+            )
+        }
+        0x18 => (time.get(..4)?.parse().ok()?, &time[4..]),
+        _ => return None,
+    };
+    if rest.len() != 10 || !rest.bytes().all(|x| x.is_ascii_digit()) {
+        return None;
+    }
+    let field = |i: usize| rest[i..i + 2].parse::<i64>().unwrap_or_default();
+    let days = days_from_civil(year, field(0), field(2));
+    let secs = days * 86400 + field(4) * 3600 + field(6) * 60 + field(8);
+    Some(UNIX_EPOCH + Duration::from_secs(u64::try_from(secs).ok()?))
+}
+
+/// days since 1970-01-01 of a date of the proleptic Gregorian calendar, the inverse of
+/// `civil_from_days` of the log formatter.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = year - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let doy = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
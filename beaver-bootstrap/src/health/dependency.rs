@@ -0,0 +1,324 @@
+use std::{
+    fmt,
+    net::{SocketAddr, TcpStream},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{HealthCheck, HealthCheckResult};
+use crate::{
+    clock::Clock,
+    error::BootstrapError,
+    net::{HttpUrl, http_request_to, resolve},
+    serde::duration_opt,
+};
+
+/// time a probe of a dependency may take unless its `timeout` is set.
+pub const DEFAULT_PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+/// time the result of a probe is reused unless the `cache` of the dependency is set.
+pub const DEFAULT_PROBE_CACHE: Duration = Duration::from_secs(10);
+/// time the address of a dependency is reused unless its `dns_ttl` is set.
+pub const DEFAULT_DNS_TTL: Duration = Duration::from_secs(60);
+
+/// DependencyConfig is a service the process depends on, probed by a [`DependencyCheck`],
+/// see `[health.dependencies]`:
+///
+/// ```toml
+/// [health.dependencies]
+/// api = { url = "http://api.internal:8080/health", timeout = "2s" }
+/// cache = { url = "tcp://redis.internal:6379", critical = false }
+/// ```
+///
+/// An `http://` dependency is reachable when it answers with a status below 500, a `tcp://`
+/// or a database one, such as `postgres://db:5432`, when it accepts a connection. An
+/// unreachable critical dependency makes the process unhealthy, so not ready, a non-critical
+/// one only degraded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DependencyConfig {
+    url: String,
+    /// time a probe may take, [`DEFAULT_PROBE_TIMEOUT`] by default.
+    #[serde(deserialize_with = "duration_opt")]
+    timeout: Option<Duration>,
+    /// whether the process can not serve without the dependency.
+    critical: bool,
+    /// time the result of a probe is reused, so that frequent health requests do not turn
+    /// into a storm of probes, [`DEFAULT_PROBE_CACHE`] by default.
+    #[serde(deserialize_with = "duration_opt")]
+    cache: Option<Duration>,
+    /// time the resolved address of the host is reused, [`DEFAULT_DNS_TTL`] by default.
+    #[serde(deserialize_with = "duration_opt")]
+    dns_ttl: Option<Duration>,
+}
+
+impl Default for DependencyConfig {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            timeout: None,
+            critical: true,
+            cache: None,
+            dns_ttl: None,
+        }
+    }
+}
+
+impl DependencyConfig {
+    /// a critical dependency at `url`.
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            ..Self::default()
+        }
+    }
+
+    pub fn with_critical(mut self, critical: bool) -> Self {
+        self.critical = critical;
+        self
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn critical(&self) -> bool {
+        self.critical
+    }
+
+    pub fn timeout(&self) -> Duration {
+        self.timeout.unwrap_or(DEFAULT_PROBE_TIMEOUT)
+    }
+
+    pub fn cache(&self) -> Duration {
+        self.cache.unwrap_or(DEFAULT_PROBE_CACHE)
+    }
+
+    pub fn dns_ttl(&self) -> Duration {
+        self.dns_ttl.unwrap_or(DEFAULT_DNS_TTL)
+    }
+
+    /// the target of `url`.
+    fn target(&self, name: &str) -> Result<ProbeTarget, BootstrapError> {
+        self.url.parse().map_err(|e| {
+            BootstrapError::InvalidConfigValueError(format!(
+                "health.dependencies.{}.url={}: {}",
+                name, self.url, e
+            ))
+        })
+    }
+
+    pub(crate) fn validate(&self, name: &str) -> Result<(), BootstrapError> {
+        self.target(name)?;
+        for (key, duration) in [("timeout", self.timeout), ("cache", self.cache)] {
+            if duration.is_some_and(|x| x.is_zero()) {
+                return Err(BootstrapError::InvalidConfigValueError(format!(
+                    "health.dependencies.{}.{}=0",
+                    name, key
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// schemes of the databases and brokers probed by a connection, with their default port.
+const TCP_SCHEMES: [(&str, Option<u16>); 7] = [
+    ("tcp", None),
+    ("postgres", Some(5432)),
+    ("postgresql", Some(5432)),
+    ("mysql", Some(3306)),
+    ("redis", Some(6379)),
+    ("mongodb", Some(27017)),
+    ("amqp", Some(5672)),
+];
+
+/// ProbeTarget is what a dependency probe reaches: an `http://` url answering with a status
+/// below 500, or a host accepting connections, of a `tcp://host:port` url or of a database
+/// url such as `postgres://user@db:5432/billing`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum ProbeTarget {
+    Http(HttpUrl),
+    Tcp(String, u16),
+}
+
+impl std::str::FromStr for ProbeTarget {
+    type Err = String;
+
+    fn from_str(url: &str) -> Result<Self, Self::Err> {
+        if url.starts_with("http://") {
+            return url.parse().map(ProbeTarget::Http);
+        }
+        let Some((scheme, rest)) = url.split_once("://") else {
+            return Err("expected scheme://host:port".to_string());
+        };
+        let Some((_, default_port)) = TCP_SCHEMES.iter().find(|(x, _)| *x == scheme) else {
+            return Err(format!("unsupported scheme {}", scheme));
+        };
+        let authority = rest.split(['/', '?']).next().unwrap_or_default();
+        // credentials are not needed to tell whether the host is up
+        let authority = authority.rsplit_once('@').map_or(authority, |(_, x)| x);
+        let (host, port) = match (authority.rsplit_once(':'), default_port) {
+            (Some((host, port)), _) => {
+                let port = port
+                    .parse::<u16>()
+                    .map_err(|e| format!("invalid port: {}", e))?;
+                (host, port)
+            }
+            (None, Some(port)) => (authority, *port),
+            (None, None) => return Err(format!("expected {}://host:port", scheme)),
+        };
+        if host.is_empty() {
+            return Err("missing host".to_string());
+        }
+        Ok(ProbeTarget::Tcp(host.to_string(), port))
+    }
+}
+
+impl fmt::Display for ProbeTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProbeTarget::Http(url) => write!(f, "{}", url),
+            ProbeTarget::Tcp(host, port) => write!(f, "{}:{}", host, port),
+        }
+    }
+}
+
+impl ProbeTarget {
+    pub(crate) fn host(&self) -> (&str, u16) {
+        match self {
+            ProbeTarget::Http(url) => (url.host(), url.port()),
+            ProbeTarget::Tcp(host, port) => (host, *port),
+        }
+    }
+
+    /// probe the target at `addr`, one of the addresses of its host.
+    pub(crate) fn probe_at(&self, addr: SocketAddr, timeout: Duration) -> Result<(), String> {
+        match self {
+            ProbeTarget::Tcp(..) => TcpStream::connect_timeout(&addr, timeout)
+                .map(|_| ())
+                .map_err(|e| format!("{}: {}", addr, e)),
+            ProbeTarget::Http(url) => {
+                match http_request_to(addr, "GET", url, "text/plain", &[], timeout) {
+                    Ok(status) if status < 500 => Ok(()),
+                    Ok(status) => Err(format!("{} answered {}", url, status)),
+                    Err(e) => Err(format!("{}: {}", url, e)),
+                }
+            }
+        }
+    }
+
+    /// resolve the host of the target then probe it.
+    pub(crate) fn probe(&self, timeout: Duration) -> Result<(), String> {
+        let (host, port) = self.host();
+        let addr = resolve(host, port).map_err(|e| format!("unable to resolve {}: {}", host, e))?;
+        self.probe_at(addr, timeout)
+    }
+}
+
+/// DependencyCheck probes a dependency of [`DependencyConfig`], named `dependencies.<name>`
+/// in the health report.
+///
+/// Concurrent evaluations wait for the probe in flight and share its result.
+///
+/// # Example
+/// ```
+/// use std::{net::TcpListener, sync::Arc};
+/// use beaver_bootstrap::{
+///     clock::SystemClock,
+///     health::{
+///         HealthCheck, HealthStatus,
+///         dependency::{DependencyCheck, DependencyConfig},
+///     },
+/// };
+/// let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+/// let config = DependencyConfig::new(format!("tcp://{}", listener.local_addr().unwrap()));
+/// let check = DependencyCheck::new("db", config, Arc::new(SystemClock)).unwrap();
+/// assert_eq!(check.name(), "dependencies.db");
+/// assert_eq!(check.check().status(), HealthStatus::Healthy);
+/// ```
+pub struct DependencyCheck {
+    name: String,
+    config: DependencyConfig,
+    target: ProbeTarget,
+    clock: Arc<dyn Clock>,
+    /// the last result and when it was probed, locked during a probe.
+    last: Mutex<Option<(Instant, HealthCheckResult)>>,
+    /// the resolved address and when it was resolved.
+    addr: Mutex<Option<(Instant, SocketAddr)>>,
+}
+
+impl fmt::Debug for DependencyCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DependencyCheck")
+            .field("name", &self.name)
+            .field("config", &self.config)
+            .finish_non_exhaustive()
+    }
+}
+
+impl DependencyCheck {
+    pub fn new(
+        name: &str,
+        config: DependencyConfig,
+        clock: Arc<dyn Clock>,
+    ) -> Result<Self, BootstrapError> {
+        config.validate(name)?;
+        Ok(Self {
+            name: name.to_string(),
+            target: config.target(name)?,
+            config,
+            clock,
+            last: Mutex::new(None),
+            addr: Mutex::new(None),
+        })
+    }
+
+    /// the address of the dependency, resolved again once `dns_ttl` elapsed.
+    fn addr(&self, now: Instant) -> Result<SocketAddr, String> {
+        let mut cached = self.addr.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((resolved, addr)) = *cached
+            && now.duration_since(resolved) < self.config.dns_ttl()
+        {
+            return Ok(addr);
+        }
+        let (host, port) = self.target.host();
+        let addr = resolve(host, port).map_err(|e| format!("unable to resolve {}: {}", host, e))?;
+        *cached = Some((now, addr));
+        Ok(addr)
+    }
+
+    fn probe(&self, now: Instant) -> Result<(), String> {
+        let addr = self.addr(now)?;
+        let probed = self.target.probe_at(addr, self.config.timeout());
+        if probed.is_err() {
+            // the host may have moved
+            *self.addr.lock().unwrap_or_else(|e| e.into_inner()) = None;
+        }
+        probed
+    }
+}
+
+impl HealthCheck for DependencyCheck {
+    fn name(&self) -> String {
+        format!("dependencies.{}", self.name)
+    }
+
+    fn check(&self) -> HealthCheckResult {
+        let mut last = self.last.lock().unwrap_or_else(|e| e.into_inner());
+        let now = self.clock.instant();
+        if let Some((probed, result)) = &*last
+            && now.duration_since(*probed) < self.config.cache()
+        {
+            return result.clone();
+        }
+        let result = match (self.probe(now), self.config.critical) {
+            (Ok(()), _) => HealthCheckResult::healthy(),
+            (Err(e), true) => HealthCheckResult::unhealthy(e),
+            (Err(e), false) => HealthCheckResult::degraded(format!("{} (not critical)", e)),
+        };
+        *last = Some((now, result.clone()));
+        result
+    }
+}
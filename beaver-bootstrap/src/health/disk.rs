@@ -0,0 +1,201 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::{HealthCheck, HealthCheckResult, HealthStatus};
+use crate::{disk::disk_usage, error::BootstrapError, serde::byte_size_opt};
+
+/// DiskHealthConfig is the disk space and inode check of `[health.disk]`:
+///
+/// ```toml
+/// [health.disk]
+/// paths = ["/var/lib/billing"]
+/// degraded_free_ratio = 0.1
+/// unhealthy_free_ratio = 0.05
+/// min_free_space = "1GiB"
+/// ```
+///
+/// The directories of the file appenders are checked too unless `log_dirs` is false, full
+/// log partitions being the most common way to lose a process.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DiskHealthConfig {
+    enable: bool,
+    /// directories checked next to the ones of the file appenders.
+    paths: Vec<PathBuf>,
+    /// whether the directories of the file appenders are checked.
+    log_dirs: bool,
+    /// ratio of free space or inodes under which a filesystem is degraded.
+    degraded_free_ratio: f64,
+    /// ratio of free space or inodes under which a filesystem is unhealthy.
+    unhealthy_free_ratio: f64,
+    /// free space under which a filesystem is unhealthy, whatever its size.
+    #[serde(deserialize_with = "byte_size_opt")]
+    min_free_space: Option<u64>,
+}
+
+impl Default for DiskHealthConfig {
+    fn default() -> Self {
+        Self {
+            enable: true,
+            paths: Vec::new(),
+            log_dirs: true,
+            degraded_free_ratio: 0.1,
+            unhealthy_free_ratio: 0.05,
+            min_free_space: None,
+        }
+    }
+}
+
+impl DiskHealthConfig {
+    pub fn enable(&self) -> bool {
+        self.enable
+    }
+
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+
+    pub fn log_dirs(&self) -> bool {
+        self.log_dirs
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), BootstrapError> {
+        for (key, ratio) in [
+            ("degraded_free_ratio", self.degraded_free_ratio),
+            ("unhealthy_free_ratio", self.unhealthy_free_ratio),
+        ] {
+            if !(0.0..=1.0).contains(&ratio) {
+                return Err(BootstrapError::InvalidConfigValueError(format!(
+                    "health.disk.{}={}, expected a ratio in [0, 1]",
+                    key, ratio
+                )));
+            }
+        }
+        if self.unhealthy_free_ratio > self.degraded_free_ratio {
+            return Err(BootstrapError::InvalidConfigValueError(format!(
+                "health.disk.unhealthy_free_ratio={} is above degraded_free_ratio={}",
+                self.unhealthy_free_ratio, self.degraded_free_ratio
+            )));
+        }
+        Ok(())
+    }
+
+    /// the check of `paths` and of `log_dirs` when enabled, `None` when there is nothing to
+    /// check.
+    pub fn check(&self, log_dirs: impl IntoIterator<Item = PathBuf>) -> Option<DiskSpaceCheck> {
+        if !self.enable {
+            return None;
+        }
+        let mut paths = self.paths.clone();
+        if self.log_dirs {
+            paths.extend(log_dirs);
+        }
+        paths.sort();
+        paths.dedup();
+        if paths.is_empty() {
+            return None;
+        }
+        Some(DiskSpaceCheck {
+            paths,
+            degraded_free_ratio: self.degraded_free_ratio,
+            unhealthy_free_ratio: self.unhealthy_free_ratio,
+            min_free_space: self.min_free_space,
+        })
+    }
+}
+
+/// DiskSpaceCheck is degraded or unhealthy when the free space or the free inodes of the
+/// filesystem of one of its paths is under its thresholds, see [`DiskHealthConfig`].
+///
+/// # Example
+/// ```
+/// use beaver_bootstrap::health::{HealthCheck, HealthStatus, disk::DiskSpaceCheck};
+/// let check = DiskSpaceCheck::new(vec![".".into()]).with_free_ratios(0.0, 0.0);
+/// assert_eq!(check.check().status(), HealthStatus::Healthy);
+/// ```
+#[derive(Debug, Clone)]
+pub struct DiskSpaceCheck {
+    paths: Vec<PathBuf>,
+    degraded_free_ratio: f64,
+    unhealthy_free_ratio: f64,
+    min_free_space: Option<u64>,
+}
+
+impl DiskSpaceCheck {
+    /// a check of `paths` with the default thresholds of [`DiskHealthConfig`].
+    pub fn new(paths: Vec<PathBuf>) -> Self {
+        let config = DiskHealthConfig::default();
+        Self {
+            paths,
+            degraded_free_ratio: config.degraded_free_ratio,
+            unhealthy_free_ratio: config.unhealthy_free_ratio,
+            min_free_space: config.min_free_space,
+        }
+    }
+
+    pub fn with_free_ratios(mut self, degraded: f64, unhealthy: f64) -> Self {
+        self.degraded_free_ratio = degraded;
+        self.unhealthy_free_ratio = unhealthy;
+        self
+    }
+
+    pub fn with_min_free_space(mut self, min_free_space: u64) -> Self {
+        self.min_free_space = Some(min_free_space);
+        self
+    }
+
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+
+    /// the status of the filesystem of one path and why.
+    fn check_path(&self, path: &Path) -> (HealthStatus, String) {
+        let usage = match disk_usage(path) {
+            Ok(usage) => usage,
+            Err(e) => {
+                return (
+                    HealthStatus::Degraded,
+                    format!("{}: unable to read its usage: {}", path.display(), e),
+                );
+            }
+        };
+        let ratio = |available: u64, total: u64| match total {
+            0 => 1.0,
+            _ => available as f64 / total as f64,
+        };
+        let space = ratio(usage.available_bytes, usage.total_bytes);
+        // filesystems without inodes report none in total
+        let inodes = ratio(usage.available_inodes, usage.total_inodes);
+        let status = |ratio: f64| match ratio {
+            x if x < self.unhealthy_free_ratio => HealthStatus::Unhealthy,
+            x if x < self.degraded_free_ratio => HealthStatus::Degraded,
+            _ => HealthStatus::Healthy,
+        };
+        let below_min = self
+            .min_free_space
+            .is_some_and(|x| usage.available_bytes < x);
+        let status = match below_min {
+            true => HealthStatus::Unhealthy,
+            false => status(space).max(status(inodes)),
+        };
+        let detail = format!(
+            "{}: {} bytes free ({:.1}%), {:.1}% of inodes free",
+            path.display(),
+            usage.available_bytes,
+            space * 100.0,
+            inodes * 100.0
+        );
+        (status, detail)
+    }
+}
+
+impl HealthCheck for DiskSpaceCheck {
+    fn name(&self) -> String {
+        "disk".to_string()
+    }
+
+    fn check(&self) -> HealthCheckResult {
+        HealthCheckResult::worst(self.paths.iter().map(|x| self.check_path(x)))
+    }
+}
@@ -0,0 +1,189 @@
+use std::{
+    sync::{
+        Arc,
+        mpsc::{self, RecvTimeoutError, Sender},
+    },
+    thread,
+    time::{Duration, Instant, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    clock::Clock,
+    config::{Config, ConfigPrefix},
+    error::BootstrapError,
+    net::{HttpUrl, http_request},
+    serde::duration_opt,
+};
+
+const PING_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// HeartbeatSink is where heartbeats are sent.
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HeartbeatSink {
+    /// an INFO event with target `heartbeat`.
+    Log,
+    /// a JSON POST to `heartbeat.url`.
+    Http,
+}
+
+/// HeartbeatConfig configures the heartbeat emitter, see `[heartbeat]`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HeartbeatConfig {
+    enable: bool,
+    /// time between two heartbeats, 30 seconds by default.
+    #[serde(deserialize_with = "duration_opt")]
+    interval: Option<Duration>,
+    sinks: Vec<HeartbeatSink>,
+    /// `http://` url of the http sink.
+    url: Option<String>,
+}
+
+impl Default for HeartbeatConfig {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            interval: None,
+            sinks: vec![HeartbeatSink::Log],
+            url: None,
+        }
+    }
+}
+
+impl ConfigPrefix for HeartbeatConfig {
+    const PREFIX: &'static str = "heartbeat";
+}
+
+impl HeartbeatConfig {
+    pub fn new(config: &Config) -> Result<Self, BootstrapError> {
+        let heartbeat_config = config
+            .get::<HeartbeatConfig>()
+            .map_err(BootstrapError::ConfigLoadError)?;
+        heartbeat_config.validate()?;
+        Ok(heartbeat_config)
+    }
+
+    fn validate(&self) -> Result<(), BootstrapError> {
+        if self.interval.is_some_and(|x| x.is_zero()) {
+            return Err(BootstrapError::InvalidConfigValueError(
+                "heartbeat.interval=0".to_string(),
+            ));
+        }
+        if self.sinks.contains(&HeartbeatSink::Http) {
+            let Some(url) = &self.url else {
+                return Err(BootstrapError::MissingConfigValueError(
+                    "heartbeat.url is required by the http sink".to_string(),
+                ));
+            };
+            url.parse::<HttpUrl>().map_err(|e| {
+                BootstrapError::InvalidConfigValueError(format!("heartbeat.url: {}", e))
+            })?;
+        }
+        Ok(())
+    }
+
+    pub fn enable(&self) -> bool {
+        self.enable
+    }
+
+    pub fn interval(&self) -> Duration {
+        self.interval.unwrap_or(Duration::from_secs(30))
+    }
+}
+
+/// Heartbeat is one liveness signal of the process.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct Heartbeat {
+    pub seq: u64,
+    pub status: String,
+    pub uptime_secs: u64,
+    pub ts_ms: u64,
+}
+
+pub type HeartbeatStatus = Arc<dyn Fn() -> String + Send + Sync>;
+
+/// HeartbeatEmitter sends a [`Heartbeat`] to its sinks every interval until dropped.
+pub struct HeartbeatEmitter {
+    stop: Sender<()>,
+}
+
+impl std::fmt::Debug for HeartbeatEmitter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HeartbeatEmitter").finish_non_exhaustive()
+    }
+}
+
+impl HeartbeatEmitter {
+    /// start emitting, `status` reports the health of the process, uptime counts from
+    /// `started_at` of `clock`.
+    pub fn start(
+        config: &HeartbeatConfig,
+        clock: Arc<dyn Clock>,
+        started_at: Instant,
+        status: HeartbeatStatus,
+    ) -> Result<Self, BootstrapError> {
+        let (stop, rx) = mpsc::channel::<()>();
+        let interval = config.interval();
+        let sinks = config.sinks.clone();
+        let url = config
+            .url
+            .as_deref()
+            .and_then(|x| x.parse::<HttpUrl>().ok());
+        thread::Builder::new()
+            .name("beaver-heartbeat".to_string())
+            .spawn(move || {
+                let mut seq = 0;
+                while let Err(RecvTimeoutError::Timeout) = clock.wait(&rx, interval) {
+                    seq += 1;
+                    let heartbeat = Heartbeat {
+                        seq,
+                        status: status(),
+                        uptime_secs: clock
+                            .instant()
+                            .saturating_duration_since(started_at)
+                            .as_secs(),
+                        ts_ms: clock
+                            .now()
+                            .duration_since(UNIX_EPOCH)
+                            .map(|d| d.as_millis() as u64)
+                            .unwrap_or_default(),
+                    };
+                    emit(&heartbeat, &sinks, url.as_ref());
+                }
+            })
+            .map_err(|e| BootstrapError::InvalidConfigValueError(format!("heartbeat: {}", e)))?;
+        Ok(Self { stop })
+    }
+}
+
+impl Drop for HeartbeatEmitter {
+    fn drop(&mut self) {
+        let _ = self.stop.send(());
+    }
+}
+
+fn emit(heartbeat: &Heartbeat, sinks: &[HeartbeatSink], url: Option<&HttpUrl>) {
+    for sink in sinks {
+        match (sink, url) {
+            (HeartbeatSink::Log, _) => tracing::info!(
+                target: "heartbeat",
+                seq = heartbeat.seq,
+                status = heartbeat.status.as_str(),
+                uptime_secs = heartbeat.uptime_secs,
+                "heartbeat"
+            ),
+            (HeartbeatSink::Http, Some(url)) => {
+                let body = serde_json::to_vec(heartbeat).unwrap_or_default();
+                match http_request("POST", url, "application/json", &body, PING_TIMEOUT) {
+                    Ok(status) if status < 400 => {}
+                    Ok(status) => tracing::warn!("heartbeat ping to {} got {}", url, status),
+                    Err(e) => tracing::warn!("heartbeat ping to {} failed: {}", url, e),
+                }
+            }
+            (HeartbeatSink::Http, None) => {}
+        }
+    }
+}
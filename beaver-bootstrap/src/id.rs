@@ -0,0 +1,285 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::UNIX_EPOCH,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    clock::{Clock, SystemClock},
+    config::{Config, ConfigPrefix},
+    error::BootstrapError,
+    random::{RngProvider, ThreadRngProvider},
+};
+
+/// log context key holding the request id.
+pub const REQUEST_ID_FIELD: &str = "request_id";
+
+/// custom epoch of snowflake ids, 2020-01-01T00:00:00Z.
+const SNOWFLAKE_EPOCH_MS: u64 = 1_577_836_800_000;
+const SNOWFLAKE_MAX_WORKER_ID: u16 = (1 << 10) - 1;
+const SNOWFLAKE_MAX_SEQUENCE: u16 = (1 << 12) - 1;
+/// longest incoming request id that is reused as is.
+const MAX_INCOMING_ID_LEN: usize = 128;
+
+const CROCKFORD_ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+#[derive(Debug, Default, Copy, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum IdKind {
+    /// RFC 9562 version 7 UUID, time ordered.
+    #[default]
+    UuidV7,
+    /// 26 character Crockford base32 ULID, time ordered.
+    Ulid,
+    /// 64-bit snowflake id: 41 bits of milliseconds, 10 bits of worker id, 12 bits of sequence.
+    Snowflake,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IdGeneratorConfig {
+    kind: IdKind,
+    /// worker id of snowflake ids, 0..=1023.
+    worker_id: u16,
+    /// header carrying the request id in and out of a service.
+    header: String,
+}
+
+impl Default for IdGeneratorConfig {
+    fn default() -> Self {
+        Self {
+            kind: IdKind::default(),
+            worker_id: 0,
+            header: "x-request-id".to_string(),
+        }
+    }
+}
+
+impl ConfigPrefix for IdGeneratorConfig {
+    const PREFIX: &'static str = "id_generator";
+}
+
+struct SnowflakeState {
+    last_ms: u64,
+    sequence: u16,
+}
+
+/// IdGenerator creates request/correlation ids of the kind selected by `[id_generator]`.
+///
+/// # Example
+/// ```
+/// use beaver_bootstrap::id::{IdGenerator, IdKind};
+/// let ids = IdGenerator::new(IdKind::Ulid, 0, "x-request-id");
+/// assert_eq!(ids.generate().len(), 26);
+/// let request_id = ids.request_id(Some("abc"));
+/// assert_eq!(request_id.value(), "abc");
+/// assert_eq!(request_id.header(), ("x-request-id", "abc"));
+/// assert_eq!(request_id.log_fields(), [("request_id", "abc")]);
+/// ```
+pub struct IdGenerator {
+    kind: IdKind,
+    worker_id: u16,
+    header: String,
+    rng: Arc<dyn RngProvider>,
+    clock: Arc<dyn Clock>,
+    snowflake: Mutex<SnowflakeState>,
+}
+
+impl IdGenerator {
+    pub fn new(kind: IdKind, worker_id: u16, header: &str) -> Self {
+        Self {
+            kind,
+            worker_id: worker_id.min(SNOWFLAKE_MAX_WORKER_ID),
+            header: header.to_string(),
+            rng: Arc::new(ThreadRngProvider),
+            clock: Arc::new(SystemClock),
+            snowflake: Mutex::new(SnowflakeState {
+                last_ms: 0,
+                sequence: 0,
+            }),
+        }
+    }
+
+    /// draw the random bits of ids from `rng`.
+    pub fn with_rng(mut self, rng: Arc<dyn RngProvider>) -> Self {
+        self.rng = rng;
+        self
+    }
+
+    /// read the time of ids from `clock`.
+    ///
+    /// # Example
+    /// ```
+    /// use std::{sync::Arc, time::{Duration, UNIX_EPOCH}};
+    /// use beaver_bootstrap::{clock::ManualClock, id::{IdGenerator, IdKind}};
+    /// // 2025-01-01T00:00:00Z, 0x1941f297c00 milliseconds
+    /// let now = UNIX_EPOCH + Duration::from_millis(1_735_689_600_000);
+    /// let ids = IdGenerator::new(IdKind::UuidV7, 0, "x-request-id")
+    ///     .with_clock(Arc::new(ManualClock::new(now)));
+    /// assert!(ids.generate().starts_with("01941f29-7c00-7"));
+    ///
+    /// // a clock before the snowflake epoch counts from it: worker 1, sequences 0 and 1
+    /// let ids = IdGenerator::new(IdKind::Snowflake, 1, "x-request-id")
+    ///     .with_clock(Arc::new(ManualClock::new(UNIX_EPOCH)));
+    /// assert_eq!(ids.generate(), "4096");
+    /// assert_eq!(ids.generate(), "4097");
+    /// ```
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    // called by the bootstrap only
+    #[cfg_attr(not(feature = "full"), allow(dead_code))]
+    pub(crate) fn from_config(
+        config: &Config,
+        rng: Arc<dyn RngProvider>,
+        clock: Arc<dyn Clock>,
+    ) -> Result<Self, BootstrapError> {
+        let id_config = config
+            .get::<IdGeneratorConfig>()
+            .map_err(BootstrapError::ConfigLoadError)?;
+        if id_config.worker_id > SNOWFLAKE_MAX_WORKER_ID {
+            return Err(BootstrapError::InvalidConfigValueError(format!(
+                "id_generator.worker_id={} exceeds {}",
+                id_config.worker_id, SNOWFLAKE_MAX_WORKER_ID
+            )));
+        }
+        Ok(
+            Self::new(id_config.kind, id_config.worker_id, &id_config.header)
+                .with_rng(rng)
+                .with_clock(clock),
+        )
+    }
+
+    pub fn kind(&self) -> IdKind {
+        self.kind
+    }
+
+    pub fn header_name(&self) -> &str {
+        &self.header
+    }
+
+    pub fn generate(&self) -> String {
+        match self.kind {
+            IdKind::UuidV7 => uuid_v7(self.now_ms(), self.random_u128()),
+            IdKind::Ulid => ulid(self.now_ms(), self.random_u128()),
+            IdKind::Snowflake => self.snowflake().to_string(),
+        }
+    }
+
+    /// start a request: reuse a valid incoming id or generate one.
+    ///
+    /// Middleware calls this with the value of [`IdGenerator::header_name`] from the request,
+    /// runs the handler with [`RequestId::log_fields`] in its log context and copies
+    /// [`RequestId::header`] to the response.
+    pub fn request_id(&self, incoming: Option<&str>) -> RequestId {
+        let value = match incoming.map(str::trim) {
+            Some(id) if is_valid_incoming(id) => id.to_string(),
+            _ => self.generate(),
+        };
+        RequestId {
+            header: self.header.clone(),
+            value,
+        }
+    }
+
+    /// milliseconds since the unix epoch, 0 for a clock before it.
+    fn now_ms(&self) -> u64 {
+        self.clock
+            .now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or_default()
+    }
+
+    fn random_u128(&self) -> u128 {
+        let mut bytes = [0u8; 16];
+        self.rng.fill_bytes(&mut bytes);
+        u128::from_be_bytes(bytes)
+    }
+
+    fn snowflake(&self) -> u64 {
+        let mut state = match self.snowflake.lock() {
+            Ok(state) => state,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        // a clock before the epoch, or unreadable, counts from the epoch rather than
+        // underflowing, the sequence keeping the ids unique
+        let mut now = self.now_ms().max(SNOWFLAKE_EPOCH_MS).max(state.last_ms);
+        if now == state.last_ms {
+            if state.sequence == SNOWFLAKE_MAX_SEQUENCE {
+                // sequence exhausted in this millisecond, borrow the next one
+                now += 1;
+                state.sequence = 0;
+            } else {
+                state.sequence += 1;
+            }
+        } else {
+            state.sequence = 0;
+        }
+        state.last_ms = now;
+        ((now - SNOWFLAKE_EPOCH_MS) << 22) | ((self.worker_id as u64) << 12) | state.sequence as u64
+    }
+}
+
+/// RequestId is the id of the request being handled.
+#[derive(Debug, Clone)]
+pub struct RequestId {
+    header: String,
+    value: String,
+}
+
+impl RequestId {
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// header name and value to set on the response.
+    pub fn header(&self) -> (&str, &str) {
+        (&self.header, &self.value)
+    }
+
+    /// the log context pairs of the request, to attach to its handler with
+    /// [`with_log_context`](crate::log::context::LogContextExt::with_log_context), or with
+    /// [`LogContext::scope`](crate::log::context::LogContext::scope) when it is synchronous.
+    pub fn log_fields(&self) -> [(&str, &str); 1] {
+        [(REQUEST_ID_FIELD, &self.value)]
+    }
+}
+
+fn is_valid_incoming(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_INCOMING_ID_LEN
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'))
+}
+
+fn uuid_v7(ms: u64, random: u128) -> String {
+    let mut value = ((ms as u128) & 0xFFFF_FFFF_FFFF) << 80;
+    // version 7
+    value |= 0x7 << 76;
+    value |= ((random >> 64) & 0xFFF) << 64;
+    // variant 0b10
+    value |= 0b10 << 62;
+    value |= random & 0x3FFF_FFFF_FFFF_FFFF;
+    let hex = format!("{:032x}", value);
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    )
+}
+
+fn ulid(ms: u64, random: u128) -> String {
+    let value = (((ms as u128) & 0xFFFF_FFFF_FFFF) << 80) | (random & ((1 << 80) - 1));
+    (0..26)
+        .rev()
+        .map(|i| CROCKFORD_ALPHABET[((value >> (i * 5)) & 0x1F) as usize] as char)
+        .collect()
+}
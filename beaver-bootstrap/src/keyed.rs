@@ -0,0 +1,202 @@
+use std::{ops::Deref, sync::RwLock};
+
+use di::{Ref, ServiceCollection, ServiceProvider, singleton_factory, transient_factory};
+use serde::de::DeserializeOwned;
+
+use crate::{
+    config::{Config, ConfigPrefix},
+    error::BootstrapError,
+};
+
+/// Keyed is a service registered under a name, so several instances of one type can coexist,
+/// e.g. the `primary` and `analytics` database pools.
+pub struct Keyed<T: ?Sized> {
+    key: String,
+    service: Ref<T>,
+}
+
+impl<T: ?Sized> Keyed<T> {
+    pub fn new(key: impl Into<String>, service: Ref<T>) -> Self {
+        Self {
+            key: key.into(),
+            service,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn service(&self) -> Ref<T> {
+        self.service.clone()
+    }
+}
+
+impl<T: ?Sized> Deref for Keyed<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.service
+    }
+}
+
+/// KeyedBinder adds named registrations to the binder handed to [`Module::configure`].
+///
+/// [`Module::configure`]: crate::bootstrap::Module::configure
+///
+/// # Example
+/// ```
+/// use std::sync::RwLock;
+/// use beaver_bootstrap::keyed::{KeyedBinder, KeyedProvider};
+/// use di::{Ref, ServiceCollection};
+/// trait Cache: Send + Sync {
+///     fn capacity(&self) -> usize;
+/// }
+/// struct Lru(usize);
+/// impl Cache for Lru {
+///     fn capacity(&self) -> usize {
+///         self.0
+///     }
+/// }
+/// let binder = RwLock::new(ServiceCollection::new());
+/// binder.singleton_keyed::<dyn Cache, _>("l1", |_| Ref::new(Lru(64)));
+/// binder.singleton_keyed::<dyn Cache, _>("l2", |_| Ref::new(Lru(4096)));
+/// let provider = binder.read().unwrap().build_provider().unwrap();
+/// assert_eq!(provider.get_required_keyed::<dyn Cache>("l2").capacity(), 4096);
+/// assert!(provider.get_keyed::<dyn Cache>("l3").is_none());
+/// assert_eq!(provider.keys::<dyn Cache>(), ["l1", "l2"]);
+/// ```
+pub trait KeyedBinder {
+    /// register the singleton `T` named `key`, created by `factory` on first resolution.
+    fn singleton_keyed<T, F>(&self, key: &str, factory: F)
+    where
+        T: ?Sized + Send + Sync + 'static,
+        F: Fn(&ServiceProvider) -> Ref<T> + Send + Sync + 'static;
+
+    /// register the `T` named `key`, created by `factory` on every resolution.
+    fn transient_keyed<T, F>(&self, key: &str, factory: F)
+    where
+        T: ?Sized + Send + Sync + 'static,
+        F: Fn(&ServiceProvider) -> Ref<T> + Send + Sync + 'static;
+}
+
+impl KeyedBinder for RwLock<ServiceCollection> {
+    fn singleton_keyed<T, F>(&self, key: &str, factory: F)
+    where
+        T: ?Sized + Send + Sync + 'static,
+        F: Fn(&ServiceProvider) -> Ref<T> + Send + Sync + 'static,
+    {
+        let key = key.to_string();
+        let mut service_collection = self.write().unwrap_or_else(|e| e.into_inner());
+        service_collection.add(singleton_factory::<Keyed<T>, _>(move |provider| {
+            Ref::new(Keyed::new(key.clone(), factory(provider)))
+        }));
+    }
+
+    fn transient_keyed<T, F>(&self, key: &str, factory: F)
+    where
+        T: ?Sized + Send + Sync + 'static,
+        F: Fn(&ServiceProvider) -> Ref<T> + Send + Sync + 'static,
+    {
+        let key = key.to_string();
+        let mut service_collection = self.write().unwrap_or_else(|e| e.into_inner());
+        service_collection.add(transient_factory::<Keyed<T>, _>(move |provider| {
+            Ref::new(Keyed::new(key.clone(), factory(provider)))
+        }));
+    }
+}
+
+/// KeyedProvider resolves the services registered through [`KeyedBinder`].
+pub trait KeyedProvider {
+    /// the `T` named `key`, the last registration wins when a key is registered twice.
+    fn get_keyed<T: ?Sized + Send + Sync + 'static>(&self, key: &str) -> Option<Ref<T>>;
+
+    /// the `T` named `key`.
+    ///
+    /// # Panics
+    ///
+    /// No `T` is registered under `key`.
+    fn get_required_keyed<T: ?Sized + Send + Sync + 'static>(&self, key: &str) -> Ref<T> {
+        match self.get_keyed::<T>(key) {
+            Some(service) => service,
+            None => panic!(
+                "No service for type '{}' has been registered with key '{}'.",
+                std::any::type_name::<T>(),
+                key
+            ),
+        }
+    }
+
+    /// sorted, deduplicated keys under which a `T` is registered.
+    fn keys<T: ?Sized + Send + Sync + 'static>(&self) -> Vec<String>;
+}
+
+impl KeyedProvider for ServiceProvider {
+    fn get_keyed<T: ?Sized + Send + Sync + 'static>(&self, key: &str) -> Option<Ref<T>> {
+        self.get_all::<Keyed<T>>()
+            .filter(|x| x.key() == key)
+            .last()
+            .map(|x| x.service())
+    }
+
+    fn keys<T: ?Sized + Send + Sync + 'static>(&self) -> Vec<String> {
+        let mut keys: Vec<String> = self
+            .get_all::<Keyed<T>>()
+            .map(|x| x.key().to_string())
+            .collect();
+        keys.sort();
+        keys.dedup();
+        keys
+    }
+}
+
+/// register every instance of the `T::PREFIX` section as a [`Keyed`] service named after its
+/// table, returning the names.
+///
+/// # Example
+/// ```
+/// use std::sync::RwLock;
+/// use beaver_bootstrap::{
+///     config::{Config, ConfigPrefix},
+///     keyed::{KeyedProvider, register_instances},
+/// };
+/// use di::ServiceCollection;
+/// use serde::Deserialize;
+/// #[derive(Deserialize)]
+/// struct DbConfig {
+///     url: String,
+/// }
+/// impl ConfigPrefix for DbConfig {
+///     const PREFIX: &'static str = "databases";
+/// }
+/// let inner = config::Config::builder()
+///     .set_override("databases.primary.url", "db1")
+///     .unwrap()
+///     .set_override("databases.analytics.url", "db2")
+///     .unwrap()
+///     .build()
+///     .unwrap();
+/// let binder = RwLock::new(ServiceCollection::new());
+/// let names = register_instances::<DbConfig>(&Config::new(inner), &binder).unwrap();
+/// assert_eq!(names, ["analytics", "primary"]);
+/// let provider = binder.read().unwrap().build_provider().unwrap();
+/// assert_eq!(provider.get_required_keyed::<DbConfig>("analytics").url, "db2");
+/// ```
+pub fn register_instances<T>(
+    config: &Config,
+    binder: &RwLock<ServiceCollection>,
+) -> Result<Vec<String>, BootstrapError>
+where
+    T: ConfigPrefix + DeserializeOwned + Send + Sync + 'static,
+{
+    let instances = config
+        .get_instances::<T>()
+        .map_err(BootstrapError::ConfigLoadError)?;
+    let mut names: Vec<String> = instances.keys().cloned().collect();
+    names.sort();
+    for (name, instance) in instances {
+        let instance = Ref::new(instance);
+        binder.singleton_keyed::<T, _>(&name, move |_| instance.clone());
+    }
+    Ok(names)
+}
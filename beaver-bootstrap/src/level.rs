@@ -0,0 +1,108 @@
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// Level is the verbosity of a logger or an appender in the config, parsed case-insensitively,
+/// e.g. `level = "warn"`.
+///
+/// It lives outside of the [`log`](crate::log) module, so crates defining config structs
+/// can use it in the `minimal` build.
+///
+/// # Example
+/// ```
+/// use beaver_bootstrap::level::Level;
+/// assert_eq!("WARN".parse::<Level>().unwrap(), Level::Warn);
+/// assert_eq!(Level::Warn.to_string(), "Warn");
+/// ```
+#[derive(Debug, Default, Copy, Clone, Serialize, PartialEq, Eq, Hash)]
+pub enum Level {
+    /// The "trace" level.
+    Trace,
+    /// The "debug" level.
+    Debug,
+    /// The "info" level.
+    #[default]
+    Info,
+    /// The "warn" level.
+    Warn,
+    /// The "error" level.
+    Error,
+    /// Off level.
+    Off,
+}
+
+impl<'de> Deserialize<'de> for Level {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        const VARIANTS: [&str; 6] = ["trace", "debug", "info", "warn", "error", "off"];
+
+        let s = String::deserialize(deserializer)?;
+        s.parse()
+            .map_err(|_| <D::Error as serde::de::Error>::unknown_variant(&s, &VARIANTS))
+    }
+}
+
+#[non_exhaustive]
+#[derive(Debug)]
+pub struct ParseLevelError;
+
+impl FromStr for Level {
+    type Err = ParseLevelError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            s if s.eq_ignore_ascii_case("trace") => Ok(Level::Trace),
+            s if s.eq_ignore_ascii_case("debug") => Ok(Level::Debug),
+            s if s.eq_ignore_ascii_case("info") => Ok(Level::Info),
+            s if s.eq_ignore_ascii_case("warn") => Ok(Level::Warn),
+            s if s.eq_ignore_ascii_case("error") => Ok(Level::Error),
+            s if s.eq_ignore_ascii_case("off") => Ok(Level::Off),
+            _ => Err(ParseLevelError),
+        }
+    }
+}
+
+impl Level {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Level::Trace => "Trace",
+            Level::Debug => "Debug",
+            Level::Info => "Info",
+            Level::Warn => "Warn",
+            Level::Error => "Error",
+            Level::Off => "Off",
+        }
+    }
+
+    #[cfg(feature = "config")]
+    pub fn as_tracing_level(&self) -> Option<tracing::Level> {
+        match self {
+            Level::Trace => Some(tracing::Level::TRACE),
+            Level::Debug => Some(tracing::Level::DEBUG),
+            Level::Info => Some(tracing::Level::INFO),
+            Level::Warn => Some(tracing::Level::WARN),
+            Level::Error => Some(tracing::Level::ERROR),
+            Level::Off => None,
+        }
+    }
+
+    #[cfg(feature = "config")]
+    pub fn as_tracing_level_filter(&self) -> tracing::level_filters::LevelFilter {
+        match self {
+            Level::Trace => tracing::level_filters::LevelFilter::TRACE,
+            Level::Debug => tracing::level_filters::LevelFilter::DEBUG,
+            Level::Info => tracing::level_filters::LevelFilter::INFO,
+            Level::Warn => tracing::level_filters::LevelFilter::WARN,
+            Level::Error => tracing::level_filters::LevelFilter::ERROR,
+            Level::Off => tracing::level_filters::LevelFilter::OFF,
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.as_str())
+    }
+}
@@ -1,5 +1,85 @@
+//! beaver-bootstrap is split into layered features, so a build only pulls what it uses:
+//!
+//! - `minimal` is the types a crate sharing config structs depends on, `ConfigPrefix`,
+//!   `Properties`, [`level::Level`] and the [`error`] types, with serde only,
+//! - `config` loads, layers, reloads and exports the config,
+//! - `logging` adds the log appenders, formats and writers, with `tracing-subscriber`,
+//!   `tracing-appender` and `tracing-rolling-file`,
+//! - `di` adds dependency injection with `more-di`, services of a request scope need both
+//!   `di` and `logging`,
+//! - `full`, the default, adds the [`bootstrap`] of an application on tokio, with its
+//!   lifecycle, admin server, metrics, heartbeats, preflight checks and allocator stats.
+//!
+//! `cli`, `sentry`, `tokio-console` and `windows-service` build on `full`. A config only
+//! build disables the default features:
+//!
+//! ```toml
+//! beaver-bootstrap = { version = "0.1", default-features = false, features = ["config"] }
+//! ```
+//!
+//! and a crate of config structs only the `minimal` feature.
+
+#[cfg(feature = "full")]
+pub mod admin;
+#[cfg(feature = "full")]
+pub mod alloc;
+#[cfg(feature = "full")]
 pub mod bootstrap;
+#[cfg(feature = "cli")]
+pub mod cli;
+#[cfg(feature = "config")]
+pub mod clock;
 pub mod config;
+#[cfg(feature = "full")]
+pub mod context;
+#[cfg(feature = "full")]
+pub mod crash;
+#[cfg(feature = "full")]
+pub mod debug;
+#[cfg(feature = "config")]
+pub mod disk;
+#[cfg(feature = "full")]
+pub mod dispose;
+#[cfg(feature = "config")]
+pub mod env;
 pub mod error;
+#[cfg(feature = "full")]
+pub mod event;
+#[cfg(feature = "fault-injection")]
+pub mod fault;
+#[cfg(feature = "config")]
+pub mod fs;
+#[cfg(feature = "di")]
+pub mod graph;
+#[cfg(feature = "full")]
+pub mod health;
+#[cfg(feature = "full")]
+pub mod heartbeat;
+#[cfg(feature = "logging")]
+pub mod id;
+#[cfg(feature = "full")]
+pub mod keyed;
+pub mod level;
+#[cfg(feature = "logging")]
 pub mod log;
+#[cfg(feature = "full")]
+pub mod metrics;
+#[cfg(feature = "logging")]
+pub mod net;
+#[cfg(feature = "full")]
+pub mod preflight;
+#[cfg(feature = "config")]
+pub mod random;
+#[cfg(all(feature = "di", feature = "logging"))]
+pub mod request;
+#[cfg(feature = "full")]
+pub mod runtime;
+#[cfg(feature = "windows-service")]
+pub mod scm;
 pub mod serde;
+#[cfg(feature = "full")]
+pub mod services;
+#[cfg(feature = "logging")]
+pub mod signal;
+#[cfg(feature = "full")]
+pub mod startup;
@@ -1,78 +1,274 @@
 use std::{
-    collections::HashSet,
-    env,
-    fmt::{self},
+    collections::{BTreeMap, HashSet},
     path::{Path, PathBuf},
-    str::FromStr,
-    sync::LazyLock,
+    sync::{LazyLock, Mutex, Once, mpsc::Sender},
+    time::{Duration, Instant},
 };
 
-use serde::{Deserialize, Deserializer, Serialize};
-use tracing_appender::non_blocking::WorkerGuard;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     config::{Config, ConfigPrefix},
     error::BootstrapError,
-    serde::non_empty,
+    fs::{Fs, OsFs},
+    log::{
+        audit::AuditAppenderConfig,
+        batch::BatchConfig,
+        buffer::{AppenderStats, DEFAULT_BUFFER_SIZE, DroppedEvents, OnFull},
+        filter::FilterExpr,
+        format::LogFormat,
+        location::{LogBaseDir, LogFolderStrategy, describe_dir},
+        maintenance::{LogMaintenance, LogMaintenanceConfig},
+        monitor::ErrorMonitorConfig,
+        pattern::PatternLayout,
+        reload::CONSOLE_APPENDER,
+        reopen::{ReopenSignalConfig, ReopenWatcher},
+        retention::RetentionPolicy,
+        schema::EventSchemaConfig,
+        tenant::{DEFAULT_MAX_OPEN_FILES, DEFAULT_TENANT_FIELD, TENANT_PLACEHOLDER},
+        writer::{AppenderFlusher, AppenderWriterGuard, FlushOn},
+    },
+    serde::{byte_size_opt, duration_opt, non_empty},
 };
 
-static DEFAULT_LOG_FOLDER: LazyLock<PathBuf> = LazyLock::new(|| {
-    let dir = match env::var("CARGO_MANIFEST_DIR") {
-        Ok(dir) => PathBuf::from(dir).join("logs"),
-        Err(_) => {
-            // get config path from current executable file path
-            if let Ok(mut current_exe) = env::current_exe() {
-                current_exe.pop();
-                current_exe.push("logs");
-                current_exe
-            } else {
-                // fallback to current directory
-                PathBuf::from("./logs")
-            }
-        }
-    };
-    dir
-});
+pub mod audit;
+pub mod batch;
+pub mod buffer;
+pub mod capture;
+pub mod context;
+pub mod dedup;
+pub mod early;
+pub mod encrypt;
+pub mod filter;
+pub mod format;
+pub mod json;
+pub mod location;
+pub mod maintenance;
+pub mod monitor;
+pub mod overlay;
+pub mod pattern;
+pub mod reload;
+pub mod reopen;
+pub mod retention;
+pub mod schema;
+pub mod tenant;
+pub mod writer;
+
+pub use crate::level::{Level, ParseLevelError};
+
+/// folder of the default [`LogFolderStrategy`], until the strategy of the config is resolved.
+static DEFAULT_LOG_FOLDER: LazyLock<PathBuf> =
+    LazyLock::new(|| LogFolderStrategy::default().folder());
+
+/// time given to the appenders to write their pending events on shutdown and on panic.
+pub const DEFAULT_FLUSH_TIMEOUT: Duration = Duration::from_secs(5);
 
 #[derive(Debug)]
 pub struct AppenderGuard {
-    _guards: Vec<WorkerGuard>,
+    _guards: Vec<AppenderWriterGuard>,
+    dropped_events: DroppedEvents,
+    _dropped_events_monitor: Option<Sender<()>>,
+    _reopen_watcher: Option<ReopenWatcher>,
+    _maintenance: Option<LogMaintenance>,
 }
 impl AppenderGuard {
-    pub fn new(guards: Vec<WorkerGuard>) -> Self {
+    pub fn new(guards: Vec<AppenderWriterGuard>) -> Self {
         let mut _guards = Vec::new();
         _guards.extend(guards);
-        Self { _guards }
+        Self {
+            _guards,
+            dropped_events: DroppedEvents::default(),
+            _dropped_events_monitor: None,
+            _reopen_watcher: None,
+            _maintenance: None,
+        }
+    }
+
+    /// track the dropped events of the appenders, warning periodically while the guard lives.
+    pub fn with_dropped_events(mut self, dropped_events: DroppedEvents) -> Self {
+        let _ = self
+            ._dropped_events_monitor
+            .insert(dropped_events.spawn_monitor());
+        self.dropped_events = dropped_events;
+        self
+    }
+
+    /// keep reopening the file appenders on signal while the guard lives.
+    pub fn with_reopen_watcher(mut self, watcher: ReopenWatcher) -> Self {
+        let _ = self._reopen_watcher.insert(watcher);
+        self
+    }
+
+    /// keep maintaining the file appenders while the guard lives.
+    pub fn with_maintenance(mut self, maintenance: LogMaintenance) -> Self {
+        let _ = self._maintenance.insert(maintenance);
+        self
+    }
+
+    pub fn dropped_events(&self) -> &DroppedEvents {
+        &self.dropped_events
+    }
+
+    /// the manager flushing the appenders of this guard while it lives.
+    pub fn logging_manager(&self) -> LoggingManager {
+        LoggingManager {
+            flushers: self._guards.iter().map(|x| x.flusher()).collect(),
+        }
+    }
+}
+
+/// LoggingManager drains the appenders of the running subscriber on demand, so the events
+/// logged before a process exits reach their files even when the appender guards are never
+/// dropped, e.g. on `std::process::exit` or a panic.
+///
+/// [`Bootstrap`](crate::bootstrap::Bootstrap) flushes it on shutdown and from its panic hook,
+/// within `logging.flush_timeout`.
+///
+/// # Example
+/// ```no_run
+/// use std::time::Duration;
+/// use beaver_bootstrap::bootstrap::Bootstrap;
+/// let bootstrap = Bootstrap::builder().build();
+/// bootstrap.initialize().unwrap();
+/// tracing::info!("exiting");
+/// let manager = bootstrap.logging_manager().unwrap();
+/// assert!(manager.flush(Duration::from_secs(1)));
+/// std::process::exit(0);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct LoggingManager {
+    flushers: Vec<AppenderFlusher>,
+}
+
+impl LoggingManager {
+    /// write out the events of every appender so far, waiting up to `timeout` in total for
+    /// the non blocking ones, returning whether all were drained.
+    pub fn flush(&self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        let mut drained = true;
+        for flusher in &self.flushers {
+            drained &= flusher.flush(deadline.saturating_duration_since(Instant::now()));
+        }
+        drained
     }
+
+    /// the counts of events of the non blocking appenders since startup, to check that
+    /// their `buffer_size` and `on_full` keep up with the events logged. Synchronous
+    /// appenders, of `flush_on = "error"` or `"every_event"`, are not listed.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use beaver_bootstrap::bootstrap::Bootstrap;
+    /// let bootstrap = Bootstrap::builder().build();
+    /// bootstrap.initialize().unwrap();
+    /// for stats in bootstrap.logging_manager().unwrap().stats() {
+    ///     assert_eq!(stats.dropped, 0, "{} drops events", stats.appender);
+    /// }
+    /// ```
+    pub fn stats(&self) -> Vec<AppenderStats> {
+        self.flushers.iter().filter_map(|x| x.stats()).collect()
+    }
+}
+
+/// the manager flushed by the panic hook, the one of the last initialized logging.
+static PANIC_FLUSH: Mutex<Option<(LoggingManager, Duration)>> = Mutex::new(None);
+
+/// log panics and flush `manager` within `timeout` from the panic hook, before the previous
+/// hook runs. The hook is installed once per process, later calls replace the manager.
+pub fn flush_on_panic(manager: LoggingManager, timeout: Duration) {
+    static INSTALL: Once = Once::new();
+    *PANIC_FLUSH.lock().unwrap_or_else(|e| e.into_inner()) = Some((manager, timeout));
+    INSTALL.call_once(|| {
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            tracing::error!("{}", info);
+            let flush = PANIC_FLUSH.lock().map(|x| x.clone()).ok().flatten();
+            if let Some((manager, timeout)) = flush {
+                manager.flush(timeout);
+            }
+            previous(info);
+        }));
+    });
 }
-#[derive(Debug, Clone, Serialize, Deserialize, Default, Eq, Hash, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, Hash, PartialEq)]
 #[serde(default, deny_unknown_fields)]
 pub struct Logger {
     #[serde(deserialize_with = "non_empty")]
     target: String,
-    level: Level,
+    /// inherited from the closest ancestor when absent, see [`AllLogger::level_source`].
+    level: Option<Level>,
     #[serde(deserialize_with = "non_empty")]
     name: String,
+    /// names of the appenders the logger writes to, in addition to the appenders listing it
+    /// in `logger_names`.
+    appenders: Vec<String>,
+    /// whether the logger also writes to the appenders of its ancestors, like the additivity
+    /// of log4j.
+    additive: bool,
+}
+
+impl Default for Logger {
+    fn default() -> Self {
+        Self {
+            target: String::new(),
+            level: None,
+            name: String::new(),
+            appenders: Vec::new(),
+            additive: true,
+        }
+    }
 }
 
 impl Logger {
     pub fn new(name: &str, level: &Level, target: &str) -> Self {
         Self {
             target: target.to_owned(),
-            level: level.to_owned(),
+            level: Some(level.to_owned()),
+            name: name.to_owned(),
+            ..Self::default()
+        }
+    }
+
+    /// a logger of `target` inheriting the level of its closest ancestor.
+    pub fn inheriting(name: &str, target: &str) -> Self {
+        Self {
+            target: target.to_owned(),
             name: name.to_owned(),
+            ..Self::default()
         }
     }
 
     pub fn name(&self) -> &str {
-        &self.name.as_str()
+        self.name.as_str()
     }
     pub fn target(&self) -> &str {
-        &self.target.as_str()
+        self.target.as_str()
+    }
+
+    /// the configured level, `None` when inherited, see [`AllLogger::effective_level`].
+    pub fn level(&self) -> Option<&Level> {
+        self.level.as_ref()
+    }
+
+    /// the appenders the logger declares, see [`LoggingConfig::route_loggers`].
+    pub fn appenders(&self) -> Vec<&str> {
+        self.appenders.iter().map(|x| x.as_str()).collect()
+    }
+
+    pub fn is_additive(&self) -> bool {
+        self.additive
     }
 
-    pub fn level(&self) -> &Level {
-        &self.level
+    /// whether `self` is an ancestor of target `target`, segments of targets are separated by
+    /// `::` or `.`, e.g. `my_app.db` is an ancestor of `my_app.db.pool`. The default logger is
+    /// the ancestor of every target.
+    pub fn is_ancestor_of(&self, target: &str) -> bool {
+        if self.target.is_empty() {
+            return !target.is_empty();
+        }
+        target
+            .strip_prefix(self.target.as_str())
+            .is_some_and(|x| x.starts_with("::") || x.starts_with('.'))
     }
 }
 
@@ -83,6 +279,8 @@ pub struct AllLoggerSerde {
     default_level: Level,
     #[serde(deserialize_with = "non_empty")]
     default_name: String,
+    /// appenders of the default logger, see [`Logger`].
+    default_appenders: Vec<String>,
 }
 impl From<AllLoggerSerde> for AllLogger {
     fn from(value: AllLoggerSerde) -> AllLogger {
@@ -93,8 +291,10 @@ impl From<AllLoggerSerde> for AllLogger {
             .for_each(|x| all_logger.push(x.to_owned()));
         all_logger.push(Logger {
             target: "".to_string(),
-            level: value.default_level,
+            level: Some(value.default_level),
             name: value.default_name,
+            appenders: value.default_appenders,
+            additive: true,
         });
         AllLogger {
             loggers: all_logger,
@@ -108,26 +308,106 @@ pub struct AllLogger {
     loggers: Vec<Logger>,
 }
 
+/// AllLogger is the hierarchy of the configured loggers, like log4j or logback a logger
+/// without level inherits the level of its closest ancestor with one.
+///
+/// # Example
+/// ```
+/// use beaver_bootstrap::log::{AllLogger, Level};
+/// let all_logger: AllLogger = serde_json::from_str(
+///     r#"{
+///         "default_level": "warn",
+///         "default_name": "root",
+///         "loggers": [
+///             { "name": "db", "target": "my_app.db", "level": "debug" },
+///             { "name": "pool", "target": "my_app.db.pool" },
+///             { "name": "http", "target": "my_app.http" }
+///         ]
+///     }"#,
+/// )
+/// .unwrap();
+/// let level = |name: &str| all_logger.effective_level(all_logger.logger(name).unwrap());
+/// assert_eq!(level("pool"), Level::Debug);
+/// assert_eq!(level("http"), Level::Warn);
+/// let pool = all_logger.logger("pool").unwrap();
+/// assert_eq!(all_logger.level_source(pool).name(), "db");
+/// ```
 impl AllLogger {
     pub fn loggers(&self) -> Vec<&Logger> {
         self.loggers.iter().collect()
     }
+
+    /// the logger named `name`.
+    pub fn logger(&self, name: &str) -> Option<&Logger> {
+        self.loggers.iter().find(|x| x.name == name)
+    }
+
+    /// the logger whose level `logger` uses, itself when it has one, otherwise its most
+    /// specific ancestor with a level.
+    pub fn level_source<'a>(&'a self, logger: &'a Logger) -> &'a Logger {
+        if logger.level.is_some() {
+            return logger;
+        }
+        self.loggers
+            .iter()
+            .filter(|x| x.level.is_some() && x.is_ancestor_of(&logger.target))
+            .max_by_key(|x| x.target.len())
+            .unwrap_or(logger)
+    }
+
+    /// the level of `logger`, its own or inherited.
+    pub fn effective_level(&self, logger: &Logger) -> Level {
+        self.level_source(logger).level.unwrap_or_default()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct FileAppenderConfigSerde {
+    #[serde(default)]
+    name: String,
     enable: bool,
     write_level: Option<Level>,
     file_dir: Option<String>,
     file_max_size: u64,
     file_max_count: usize,
+    #[serde(default)]
     file_name: String,
+    #[serde(default)]
+    file_name_template: Option<String>,
     logger_names: Vec<String>,
+    #[serde(default)]
+    fields: BTreeMap<String, String>,
+    #[serde(default)]
+    format: LogFormat,
+    #[serde(default)]
+    pattern: Option<PatternLayout>,
+    #[serde(default)]
+    filter: Option<FilterExpr>,
+    #[serde(default, deserialize_with = "duration_opt")]
+    dedup_window: Option<Duration>,
+    #[serde(default, deserialize_with = "duration_opt")]
+    max_age: Option<Duration>,
+    #[serde(default, deserialize_with = "byte_size_opt")]
+    max_total_size: Option<u64>,
+    #[serde(default)]
+    buffer_size: Option<usize>,
+    #[serde(default)]
+    on_full: OnFull,
+    #[serde(default)]
+    flush_on: FlushOn,
+    #[serde(default)]
+    tenant_field: Option<String>,
+    #[serde(default)]
+    max_open_files: Option<usize>,
+    #[serde(default)]
+    encrypt: bool,
+    #[serde(default)]
+    batch: Option<BatchConfig>,
 }
 impl From<FileAppenderConfigSerde> for FileAppenderConfig {
     fn from(value: FileAppenderConfigSerde) -> FileAppenderConfig {
         // get log file directory, if not set, use default log folder
-        let log_file_dir = match value.file_dir {
+        let log_file_dir = match value.file_dir.clone() {
             Some(path) => path,
             None => DEFAULT_LOG_FOLDER
                 .as_path()
@@ -141,15 +421,40 @@ impl From<FileAppenderConfigSerde> for FileAppenderConfig {
         };
         // get full log file path
         let full_file_path: PathBuf = PathBuf::from(&log_file_dir).join(&value.file_name);
+        // an appender is named after its file unless named
+        let name = match (value.name.is_empty(), &value.file_name_template) {
+            (false, _) => value.name,
+            (true, Some(template)) if value.file_name.is_empty() => template.clone(),
+            (true, _) => value.file_name.clone(),
+        };
         FileAppenderConfig {
+            name,
             enable: value.enable,
             write_level: log_level,
             file_dir: log_file_dir,
+            configured_dir: value.file_dir,
             file_max_size: value.file_max_size,
             file_max_count: value.file_max_count,
             file_name: value.file_name,
+            file_name_template: value.file_name_template,
             file_path: full_file_path,
             logger_names: value.logger_names,
+            fields: value.fields,
+            format: value.format,
+            pattern: value.pattern,
+            filter: value.filter,
+            dedup_window: value.dedup_window,
+            max_age: value.max_age,
+            max_total_size: value.max_total_size,
+            buffer_size: value.buffer_size,
+            on_full: value.on_full,
+            flush_on: value.flush_on,
+            tenant_field: value
+                .tenant_field
+                .unwrap_or_else(|| DEFAULT_TENANT_FIELD.to_string()),
+            max_open_files: value.max_open_files.unwrap_or(DEFAULT_MAX_OPEN_FILES),
+            encrypt: value.encrypt,
+            batch: value.batch,
         }
     }
 }
@@ -157,17 +462,53 @@ impl From<FileAppenderConfigSerde> for FileAppenderConfig {
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(default, deny_unknown_fields, from = "FileAppenderConfigSerde")]
 pub struct FileAppenderConfig {
+    /// unique among the appenders, referenced by the `appenders` of loggers.
+    name: String,
     enable: bool,
     write_level: Level,
     file_dir: String,
+    /// `file_dir` as configured, before it is resolved.
+    #[serde(skip)]
+    configured_dir: Option<String>,
     file_path: PathBuf,
     file_max_size: u64,
     file_max_count: usize,
     file_name: String,
+    /// file name with `{pid}`, `{hostname}` or `{instance_id}` placeholders, replacing
+    /// `file_name` so processes sharing a log directory write to their own files, and
+    /// `{tenant}` for a file per tenant, see [`TenantFiles`](tenant::TenantFiles).
+    file_name_template: Option<String>,
     logger_names: Vec<String>,
+    fields: BTreeMap<String, String>,
+    format: LogFormat,
+    /// layout of the lines of a text appender, see [`PatternLayout`].
+    pattern: Option<PatternLayout>,
+    /// events written by the appender among the ones its loggers enable, see [`FilterExpr`].
+    filter: Option<FilterExpr>,
+    /// window collapsing identical consecutive events, see [`DedupFormat`](dedup::DedupFormat).
+    dedup_window: Option<Duration>,
+    max_age: Option<Duration>,
+    max_total_size: Option<u64>,
+    buffer_size: Option<usize>,
+    on_full: OnFull,
+    flush_on: FlushOn,
+    /// [`LogContext`](context::LogContext) key of the tenant of `{tenant}` file names.
+    tenant_field: String,
+    /// number of tenant files kept open, the least recently written are closed past it.
+    max_open_files: usize,
+    /// whether lines are encrypted with the config key, see
+    /// [`EncryptingWriter`](encrypt::EncryptingWriter).
+    encrypt: bool,
+    /// coalescing of the writes to the file, see [`BatchConfig`].
+    batch: Option<BatchConfig>,
 }
 
 impl FileAppenderConfig {
+    /// the name of the appender, its `file_name` unless named.
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
     pub fn write_level(&self) -> Level {
         self.write_level
     }
@@ -192,20 +533,230 @@ impl FileAppenderConfig {
         self.file_max_count
     }
     pub fn file_name(&self) -> &str {
-        &self.file_name.as_str()
+        self.file_name.as_str()
+    }
+
+    pub fn file_name_template(&self) -> Option<&str> {
+        self.file_name_template.as_deref()
+    }
+
+    /// resolve a relative `file_dir` against `base_dir`, a missing one to the logs of
+    /// `base_dir` or to `default_folder`.
+    fn resolve_file_dir(&mut self, base_dir: Option<&LogBaseDir>, default_folder: &Path) {
+        let file_dir = match (&self.configured_dir, base_dir) {
+            (Some(dir), Some(base_dir)) => base_dir.resolve(dir),
+            (Some(_), None) => return,
+            (None, Some(base_dir)) => base_dir.logs_dir(),
+            (None, None) => default_folder.to_path_buf(),
+        };
+        self.file_path = file_dir.join(&self.file_name);
+        self.file_dir = file_dir.to_string_lossy().into_owned();
+    }
+
+    /// set the file name from `file_name_template`, replacing its placeholders with `vars`.
+    fn resolve_file_name(&mut self, vars: &[(&str, Option<String>)]) -> Result<(), BootstrapError> {
+        let Some(template) = &self.file_name_template else {
+            return Ok(());
+        };
+        let invalid = |reason: &str| {
+            BootstrapError::InvalidConfigValueError(format!(
+                "logging.file_appenders[{}].file_name_template={}: {}",
+                self.name, template, reason
+            ))
+        };
+        let mut file_name = String::new();
+        let mut rest = template.as_str();
+        while let Some(start) = rest.find('{') {
+            file_name.push_str(&rest[..start]);
+            let Some(end) = rest[start..].find('}') else {
+                return Err(invalid("unclosed placeholder"));
+            };
+            let name = &rest[start + 1..start + end];
+            match vars.iter().find(|(var, _)| *var == name) {
+                Some((_, Some(value))) => file_name.push_str(value),
+                Some((_, None)) => return Err(invalid(&format!("{{{}}} is not available", name))),
+                None => return Err(invalid(&format!("unknown placeholder {{{}}}", name))),
+            }
+            rest = &rest[start + end + 1..];
+        }
+        file_name.push_str(rest);
+        self.file_path = PathBuf::from(&self.file_dir).join(&file_name);
+        self.file_name = file_name;
+        Ok(())
     }
 
     pub fn logger_names(&self) -> Vec<&str> {
         self.logger_names.iter().map(|x| x.as_str()).collect()
     }
 
+    /// extra static fields added to every event written by this appender.
+    pub fn fields(&self) -> &BTreeMap<String, String> {
+        &self.fields
+    }
+
+    pub fn format(&self) -> LogFormat {
+        self.format
+    }
+
+    pub fn pattern(&self) -> Option<&PatternLayout> {
+        self.pattern.as_ref()
+    }
+
+    pub fn filter(&self) -> Option<&FilterExpr> {
+        self.filter.as_ref()
+    }
+
+    pub fn dedup_window(&self) -> Option<Duration> {
+        self.dedup_window
+    }
+
+    /// number of lines buffered before `on_full` applies.
+    pub fn buffer_size(&self) -> usize {
+        self.buffer_size.unwrap_or(DEFAULT_BUFFER_SIZE)
+    }
+
+    pub fn on_full(&self) -> OnFull {
+        self.on_full
+    }
+
+    pub fn flush_on(&self) -> FlushOn {
+        self.flush_on
+    }
+
+    /// whether the appender writes a file per tenant, its file name holding `{tenant}`.
+    pub fn is_per_tenant(&self) -> bool {
+        self.file_name.contains(TENANT_PLACEHOLDER)
+    }
+
+    pub fn tenant_field(&self) -> &str {
+        self.tenant_field.as_str()
+    }
+
+    pub fn max_open_files(&self) -> usize {
+        self.max_open_files
+    }
+
+    pub fn encrypt(&self) -> bool {
+        self.encrypt
+    }
+
+    pub fn batch(&self) -> Option<&BatchConfig> {
+        self.batch.as_ref()
+    }
+
+    /// retention of rotated files, from `max_age` and `max_total_size`.
+    pub fn retention(&self) -> RetentionPolicy {
+        RetentionPolicy {
+            max_age: self.max_age,
+            max_total_size: self.max_total_size,
+        }
+    }
+
+    /// check the size and count of the rotated files and that the file name names a file of
+    /// `file_dir`, before any of them reaches the rolling appender.
+    fn validate_rotation(&self) -> Result<(), BootstrapError> {
+        if self.file_max_size == 0 {
+            return Err(BootstrapError::InvalidLogFileSizeError(format!(
+                "logging.file_appenders[{}].file_max_size=0, expected a size in bytes above 0",
+                self.name
+            )));
+        }
+        if self.file_max_count == 0 {
+            return Err(BootstrapError::InvalidLogFileCountError(format!(
+                "logging.file_appenders[{}].file_max_count=0, expected at least 1 file",
+                self.name
+            )));
+        }
+        validate_buffer_size(
+            &format!("logging.file_appenders[{}]", self.name),
+            self.buffer_size,
+        )?;
+        if self.file_name.chars().any(std::path::is_separator) || self.file_name == ".." {
+            let key = match &self.file_name_template {
+                Some(template) => format!("file_name_template={} resolved to ", template),
+                None => "file_name=".to_string(),
+            };
+            return Err(BootstrapError::InvalidLogFileNameError(format!(
+                "logging.file_appenders[{}].{}{}: a file name has no path separators, set \
+                 file_dir for the directory",
+                self.name, key, self.file_name
+            )));
+        }
+        Ok(())
+    }
+
+    /// check the batch and that it may coalesce the writes of the appender.
+    fn validate_batch(&self) -> Result<(), BootstrapError> {
+        let Some(batch) = &self.batch else {
+            return Ok(());
+        };
+        batch.validate(&self.name)?;
+        // the batch thread replaces the worker, and batch.flush_on_error the flush_on
+        let replaced = [
+            ("buffer_size", self.buffer_size.is_some()),
+            ("on_full", self.on_full != OnFull::default()),
+            ("flush_on", self.flush_on != FlushOn::default()),
+        ];
+        if let Some((key, _)) = replaced.iter().find(|(_, set)| *set) {
+            return Err(BootstrapError::InvalidConfigValueError(format!(
+                "logging.file_appenders[{}]: {} does not apply to a batch, see batch.size and batch.flush_on_error",
+                self.name, key
+            )));
+        }
+        // the tenant of an event is known on the thread emitting it only
+        if self.is_per_tenant() {
+            return Err(BootstrapError::InvalidConfigValueError(format!(
+                "logging.file_appenders[{}]: batch does not apply to the files of tenants",
+                self.name
+            )));
+        }
+        Ok(())
+    }
+
     /// make sure log directory exists, if not, create it
-    pub fn ensure_log_directory(&self) -> std::io::Result<()> {
+    pub fn ensure_log_directory(&self, fs: &dyn Fs) -> std::io::Result<()> {
         let log_path = self.file_dir();
         let log_dir = PathBuf::from(log_path);
 
-        if !log_dir.exists() {
-            std::fs::create_dir_all(&log_dir)?;
+        if !fs.exists(&log_dir) {
+            fs.create_dir_all(&log_dir)?;
+        }
+        Ok(())
+    }
+
+    /// make sure the log file can be opened for appending, creating it if needed.
+    ///
+    /// The file of the [`DEFAULT_TENANT`](tenant::DEFAULT_TENANT) stands for the files of a
+    /// tenant appender.
+    pub fn ensure_log_file_writable(&self, fs: &dyn Fs) -> Result<(), BootstrapError> {
+        let file_path = match self.is_per_tenant() {
+            true => PathBuf::from(&self.file_dir).join(
+                self.file_name
+                    .replace(TENANT_PLACEHOLDER, tenant::DEFAULT_TENANT),
+            ),
+            false => self.file_path.clone(),
+        };
+        fs.append(&file_path, b"").map_err(|e| {
+            BootstrapError::LogFileNotWritableError(format!(
+                "{}: {}, check that the user running the process may write to {}",
+                file_path.display(),
+                e,
+                self.file_dir()
+            ))
+        })
+    }
+
+    /// make sure the filesystem of the log directory has at least `min_free_space` bytes.
+    pub fn ensure_free_space(&self, min_free_space: u64) -> Result<(), BootstrapError> {
+        let usage = crate::disk::disk_usage(Path::new(self.file_dir()))
+            .map_err(|e| BootstrapError::LogDirectoryCreationError(Box::new(e)))?;
+        if usage.available_bytes < min_free_space {
+            return Err(BootstrapError::InsufficientDiskSpaceError(format!(
+                "{} has {} bytes available, logging.min_free_space requires {}",
+                self.file_dir(),
+                usage.available_bytes,
+                min_free_space
+            )));
         }
         Ok(())
     }
@@ -214,12 +765,31 @@ impl FileAppenderConfig {
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(default, deny_unknown_fields)]
 pub struct ConsoleAppenderConfig {
+    /// `console` unless named.
+    name: Option<String>,
     enable: bool,
     write_level: Level,
     logger_names: Vec<String>,
+    fields: BTreeMap<String, String>,
+    format: LogFormat,
+    /// layout of the lines of a text appender, see [`PatternLayout`].
+    pattern: Option<PatternLayout>,
+    /// events written by the appender among the ones its loggers enable, see [`FilterExpr`].
+    filter: Option<FilterExpr>,
+    /// window collapsing identical consecutive events, see [`DedupFormat`](dedup::DedupFormat).
+    #[serde(deserialize_with = "duration_opt")]
+    dedup_window: Option<Duration>,
+    buffer_size: Option<usize>,
+    on_full: OnFull,
+    flush_on: FlushOn,
 }
 
 impl ConsoleAppenderConfig {
+    /// the name of the appender, `console` unless named.
+    pub fn name(&self) -> &str {
+        self.name.as_deref().unwrap_or(CONSOLE_APPENDER)
+    }
+
     pub fn write_level(&self) -> Level {
         self.write_level
     }
@@ -231,6 +801,40 @@ impl ConsoleAppenderConfig {
     pub fn logger_names(&self) -> Vec<&str> {
         self.logger_names.iter().map(|x| x.as_str()).collect()
     }
+
+    /// extra static fields added to every event written by this appender.
+    pub fn fields(&self) -> &BTreeMap<String, String> {
+        &self.fields
+    }
+
+    pub fn format(&self) -> LogFormat {
+        self.format
+    }
+
+    pub fn pattern(&self) -> Option<&PatternLayout> {
+        self.pattern.as_ref()
+    }
+
+    pub fn filter(&self) -> Option<&FilterExpr> {
+        self.filter.as_ref()
+    }
+
+    pub fn dedup_window(&self) -> Option<Duration> {
+        self.dedup_window
+    }
+
+    /// number of lines buffered before `on_full` applies.
+    pub fn buffer_size(&self) -> usize {
+        self.buffer_size.unwrap_or(DEFAULT_BUFFER_SIZE)
+    }
+
+    pub fn on_full(&self) -> OnFull {
+        self.on_full
+    }
+
+    pub fn flush_on(&self) -> FlushOn {
+        self.flush_on
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -239,15 +843,62 @@ pub struct LoggingConfig {
     all_logger: AllLogger,
     file_appenders: Vec<FileAppenderConfig>,
     console_appender: Option<ConsoleAppenderConfig>,
+    audit: Option<AuditAppenderConfig>,
+    /// signal reopening or rotating the file appenders.
+    reopen_signal: Option<ReopenSignalConfig>,
+    /// alerting on bursts of ERROR events.
+    error_monitor: Option<ErrorMonitorConfig>,
+    /// fields required on some events.
+    schema: Option<EventSchemaConfig>,
+    /// rotation, retention and flush scheduling of the appenders.
+    maintenance: Option<LogMaintenanceConfig>,
+    /// static fields added to every event of every appender.
+    #[serde(default)]
+    fields: BTreeMap<String, String>,
+    /// minimum free space required on the filesystem of every file appender.
+    #[serde(default, deserialize_with = "byte_size_opt")]
+    min_free_space: Option<u64>,
+    /// id of this process among the instances sharing a host, the `{instance_id}` of file
+    /// name templates.
+    #[serde(default)]
+    instance_id: Option<String>,
+    /// time given to the appenders to write their pending events on shutdown and on panic,
+    /// [`DEFAULT_FLUSH_TIMEOUT`] by default.
+    #[serde(default, deserialize_with = "duration_opt")]
+    flush_timeout: Option<Duration>,
+    /// where the appenders without `file_dir` write.
+    #[serde(default)]
+    default_folder: Option<LogFolderStrategy>,
+    /// directory the relative `file_dir` values were resolved against.
+    #[serde(skip)]
+    base_dir: Option<LogBaseDir>,
 }
 
 impl LoggingConfig {
     pub fn new(config: &Config) -> Result<Self, BootstrapError> {
-        let logging_config = config
+        Self::new_with_fs(config, &OsFs)
+    }
+
+    /// like [`LoggingConfig::new`], preparing log directories on `fs`.
+    pub fn new_with_fs(config: &Config, fs: &dyn Fs) -> Result<Self, BootstrapError> {
+        Self::new_with_folder(config, fs, &LogFolderStrategy::default())
+    }
+
+    /// like [`LoggingConfig::new_with_fs`], the appenders without `file_dir` writing to the
+    /// folder of `default_folder` unless `logging.default_folder` is set.
+    pub fn new_with_folder(
+        config: &Config,
+        fs: &dyn Fs,
+        default_folder: &LogFolderStrategy,
+    ) -> Result<Self, BootstrapError> {
+        let mut logging_config = config
             .get::<LoggingConfig>()
-            .map_err(|e| BootstrapError::LoggingConfigLoadError(e))?;
+            .map_err(BootstrapError::LoggingConfigLoadError)?;
+        logging_config.route_loggers()?;
+        logging_config.resolve_file_dirs(LogBaseDir::from_config(config)?, default_folder);
+        logging_config.resolve_file_names()?;
         // validate logging config
-        logging_config.validate()?;
+        logging_config.validate_with_fs(fs)?;
         Ok(logging_config)
     }
 
@@ -265,6 +916,282 @@ impl LoggingConfig {
         self.console_appender.as_ref()
     }
 
+    pub fn audit_config(&self) -> Option<&AuditAppenderConfig> {
+        self.audit.as_ref()
+    }
+
+    pub fn reopen_signal_config(&self) -> Option<&ReopenSignalConfig> {
+        self.reopen_signal.as_ref()
+    }
+
+    pub fn error_monitor_config(&self) -> Option<&ErrorMonitorConfig> {
+        self.error_monitor.as_ref()
+    }
+
+    pub fn schema_config(&self) -> Option<&EventSchemaConfig> {
+        self.schema.as_ref()
+    }
+
+    pub fn maintenance_config(&self) -> LogMaintenanceConfig {
+        self.maintenance.clone().unwrap_or_default()
+    }
+
+    pub fn fields(&self) -> &BTreeMap<String, String> {
+        &self.fields
+    }
+
+    pub fn instance_id(&self) -> Option<&str> {
+        self.instance_id.as_deref()
+    }
+
+    pub fn flush_timeout(&self) -> Duration {
+        self.flush_timeout.unwrap_or(DEFAULT_FLUSH_TIMEOUT)
+    }
+
+    /// the names of the appenders, file appenders first.
+    pub fn appender_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.file_appenders.iter().map(|x| x.name()).collect();
+        if let Some(console) = &self.console_appender {
+            names.push(console.name());
+        }
+        names
+    }
+
+    /// the `logger_names` of every appender by name.
+    fn appender_logger_names(&mut self) -> Vec<(String, &mut Vec<String>)> {
+        let mut appenders: Vec<(String, &mut Vec<String>)> = self
+            .file_appenders
+            .iter_mut()
+            .map(|x| (x.name.clone(), &mut x.logger_names))
+            .collect();
+        if let Some(console) = self.console_appender.as_mut() {
+            appenders.push((console.name().to_string(), &mut console.logger_names));
+        }
+        appenders
+    }
+
+    /// route the loggers declaring `appenders`, by name, to them, turning the logger to appenders
+    /// mapping into the `logger_names` of the appenders.
+    ///
+    /// An additive logger is also routed to every appender one of its ancestors writes to, so
+    /// its own level applies there. A logger which is not additive only writes to its own
+    /// appenders, [`logger_targets`](reload::logger_targets) turns its target off in the others.
+    ///
+    /// # Example
+    /// ```
+    /// use beaver_bootstrap::log::LoggingConfig;
+    /// let mut logging_config: LoggingConfig = serde_json::from_str(
+    ///     r#"{
+    ///         "all_logger": {
+    ///             "default_level": "info",
+    ///             "default_name": "root",
+    ///             "default_appenders": ["app.log"],
+    ///             "loggers": [
+    ///                 {
+    ///                     "name": "db", "target": "my_app.db", "level": "debug",
+    ///                     "appenders": ["console"]
+    ///                 },
+    ///                 {
+    ///                     "name": "audit", "target": "my_app.audit",
+    ///                     "appenders": ["audit"], "additive": false
+    ///                 }
+    ///             ]
+    ///         },
+    ///         "file_appenders": [
+    ///             {
+    ///                 "enable": true, "file_name": "app.log", "logger_names": [],
+    ///                 "file_max_size": 1000, "file_max_count": 1
+    ///             },
+    ///             {
+    ///                 "name": "audit", "enable": true, "file_name": "audit.log",
+    ///                 "logger_names": [],
+    ///                 "file_max_size": 1000, "file_max_count": 1
+    ///             }
+    ///         ],
+    ///         "console_appender": {}
+    ///     }"#,
+    /// )
+    /// .unwrap();
+    /// logging_config.route_loggers().unwrap();
+    /// let appenders = logging_config.file_appender_config();
+    /// assert_eq!(appenders[0].logger_names(), vec!["root", "db"]);
+    /// assert_eq!(appenders[1].logger_names(), vec!["audit"]);
+    /// let console = logging_config.console_appender_config().unwrap();
+    /// assert_eq!(console.logger_names(), vec!["db"]);
+    ///
+    /// let mut mistyped: LoggingConfig = serde_json::from_str(
+    ///     r#"{
+    ///         "all_logger": { "default_name": "root", "default_appenders": ["consol"] },
+    ///         "file_appenders": [],
+    ///         "console_appender": {}
+    ///     }"#,
+    /// )
+    /// .unwrap();
+    /// let error = mistyped.route_loggers().unwrap_err().to_string();
+    /// assert!(error.ends_with("unknown appender consol of logger root, did you mean console?"));
+    /// ```
+    pub fn route_loggers(&mut self) -> Result<(), BootstrapError> {
+        let mut loggers = self.all_logger.loggers.clone();
+        // ancestors first, so their routes are known when routing their descendants
+        loggers.sort_by_key(|x| x.target.len());
+        let mut appenders = self.appender_logger_names();
+        for logger in &loggers {
+            if let Some(unknown) = logger
+                .appenders
+                .iter()
+                .find(|x| !appenders.iter().any(|(name, _)| name == *x))
+            {
+                let names = appenders.iter().map(|(name, _)| name.as_str());
+                return Err(BootstrapError::InvalidConfigValueError(format!(
+                    "unknown appender {} of logger {}{}",
+                    unknown,
+                    logger.name,
+                    did_you_mean(unknown, names)
+                )));
+            }
+            if logger.appenders.is_empty() {
+                continue;
+            }
+            for (name, logger_names) in appenders.iter_mut() {
+                if logger_names.contains(&logger.name) {
+                    continue;
+                }
+                let routed = logger.appenders.contains(name)
+                    || (logger.additive && Self::ancestor_routed(&loggers, logger, logger_names));
+                if routed {
+                    logger_names.push(logger.name.clone());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// whether the events of the ancestors of `logger` reach the appender of `logger_names`,
+    /// through the closest ancestor listed there or not additive.
+    fn ancestor_routed(loggers: &[Logger], logger: &Logger, logger_names: &[String]) -> bool {
+        let mut ancestors: Vec<&Logger> = loggers
+            .iter()
+            .filter(|x| x.is_ancestor_of(&logger.target))
+            .collect();
+        ancestors.sort_by_key(|x| std::cmp::Reverse(x.target.len()));
+        ancestors
+            .into_iter()
+            .find(|x| !x.additive || logger_names.contains(&x.name))
+            .is_some_and(|x| logger_names.contains(&x.name))
+    }
+
+    /// resolve the directories of the appenders against `base_dir`, the appenders without
+    /// one writing to the folder of `default_folder` unless the config sets its own.
+    fn resolve_file_dirs(
+        &mut self,
+        base_dir: Option<LogBaseDir>,
+        default_folder: &LogFolderStrategy,
+    ) {
+        let strategy = self
+            .default_folder
+            .get_or_insert_with(|| default_folder.clone());
+        let folder = strategy.folder();
+        for appender in &mut self.file_appenders {
+            appender.resolve_file_dir(base_dir.as_ref(), &folder);
+        }
+        if let Some(audit) = &mut self.audit {
+            audit.resolve_file_dir(base_dir.as_ref(), &folder);
+        }
+        self.base_dir = base_dir;
+    }
+
+    /// where the appenders without `file_dir` write, see [`LogFolderStrategy`].
+    pub fn default_folder(&self) -> LogFolderStrategy {
+        self.default_folder.clone().unwrap_or_default()
+    }
+
+    /// directory the relative `file_dir` values were resolved against, see [`LogBaseDir`].
+    pub fn base_dir(&self) -> Option<&LogBaseDir> {
+        self.base_dir.as_ref()
+    }
+
+    /// where every file appender writes and why, e.g. `file_dir logs is relative to
+    /// app.data_dir=/var/lib/billing`.
+    pub fn file_dir_report(&self) -> Vec<String> {
+        self.file_appenders
+            .iter()
+            .map(|x| {
+                format!(
+                    "appender {} writes to {}: {}",
+                    x.name,
+                    x.file_path.display(),
+                    describe_dir(
+                        x.configured_dir.as_deref(),
+                        self.base_dir.as_ref(),
+                        &self.default_folder()
+                    )
+                )
+            })
+            .collect()
+    }
+
+    /// resolve the `file_name_template` of every file appender for this process.
+    fn resolve_file_names(&mut self) -> Result<(), BootstrapError> {
+        let vars = [
+            ("pid", Some(std::process::id().to_string())),
+            ("hostname", crate::env::hostname()),
+            ("instance_id", self.instance_id.clone()),
+            // resolved per event by the tenant files
+            ("tenant", Some(TENANT_PLACEHOLDER.to_string())),
+        ];
+        for appender in &mut self.file_appenders {
+            appender.resolve_file_name(&vars)?;
+        }
+        Ok(())
+    }
+
+    /// global fields merged with the fields of one appender, the appender wins on conflicts.
+    pub fn appender_fields(
+        &self,
+        appender_fields: &BTreeMap<String, String>,
+    ) -> Vec<(String, String)> {
+        let mut fields = self.fields.clone();
+        fields.extend(appender_fields.clone());
+        fields.into_iter().collect()
+    }
+
+    /// merge loggers declared by modules, routed to every appender of the default logger.
+    ///
+    /// Loggers of config.toml win: a module logger whose name or target is already configured
+    /// is skipped.
+    pub fn merge_loggers(&mut self, loggers: Vec<Logger>) {
+        let Some(default_name) = self
+            .all_logger
+            .loggers
+            .iter()
+            .find(|x| x.target.is_empty())
+            .map(|x| x.name.clone())
+        else {
+            return;
+        };
+        for logger in loggers {
+            let conflict = self
+                .all_logger
+                .loggers
+                .iter()
+                .any(|x| x.name == logger.name || x.target == logger.target);
+            if conflict {
+                continue;
+            }
+            for appender in self.file_appenders.iter_mut() {
+                if appender.logger_names.contains(&default_name) {
+                    appender.logger_names.push(logger.name.clone());
+                }
+            }
+            if let Some(appender) = self.console_appender.as_mut()
+                && appender.logger_names.contains(&default_name)
+            {
+                appender.logger_names.push(logger.name.clone());
+            }
+            self.all_logger.loggers.push(logger);
+        }
+    }
+
     fn all_logger_name(&self) -> Vec<&str> {
         self.logger_config()
             .loggers
@@ -277,10 +1204,10 @@ impl LoggingConfig {
         let logger_config = self.logger_config();
         let all_loggers = &logger_config.loggers;
 
-        let mut set: HashSet<&Logger> = HashSet::new();
+        let mut set: HashSet<&str> = HashSet::new();
 
         for logger in all_loggers {
-            if !set.insert(logger) {
+            if !set.insert(logger.name()) {
                 return Err(BootstrapError::DuplicateLoggerError(format!(
                     "{:?}",
                     logger
@@ -290,56 +1217,114 @@ impl LoggingConfig {
         Ok(())
     }
 
-    fn validate_file_appender(&self) -> Result<(), BootstrapError> {
+    /// names of appenders are unique, as loggers reference appenders by name.
+    fn validate_appender_names(&self) -> Result<(), BootstrapError> {
+        let mut set: HashSet<&str> = HashSet::new();
+        for name in self.appender_names() {
+            // appenders without name lack a file name, reported by validate_file_appender
+            if !name.is_empty() && !set.insert(name) {
+                return Err(BootstrapError::DuplicateAppenderError(name.to_string()));
+            }
+        }
+        Ok(())
+    }
+
+    /// the logger names of appender `appender` are configured loggers.
+    fn validate_logger_names(&self, appender: &str, names: &[&str]) -> Result<(), BootstrapError> {
         let all_logger_name = self.all_logger_name();
-        let all_logger_name_set: HashSet<&str> = all_logger_name.iter().cloned().collect();
+        match names.iter().find(|x| !all_logger_name.contains(x)) {
+            Some(logger) => Err(BootstrapError::InvalidConfigValueError(format!(
+                "unknown logger {} in appender {}{}",
+                logger,
+                appender,
+                did_you_mean(logger, all_logger_name.iter().copied())
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    fn validate_file_appender(&self, fs: &dyn Fs) -> Result<(), BootstrapError> {
         let file_appender_config = self.file_appender_config();
         let mut path_set: HashSet<&Path> = HashSet::new();
         for config in file_appender_config {
+            if config.file_name().is_empty() {
+                let name = if config.name().is_empty() {
+                    "?"
+                } else {
+                    config.name()
+                };
+                return Err(BootstrapError::MissingConfigValueError(format!(
+                    "logging.file_appenders[{}].file_name",
+                    name
+                )));
+            }
+            config.validate_rotation()?;
+            config.validate_batch()?;
             config
-                .ensure_log_directory()
+                .ensure_log_directory(fs)
                 .map_err(|e| BootstrapError::LogDirectoryCreationError(Box::new(e)))?;
-            // check log file path duplication
+            if config.enable() {
+                config.ensure_log_file_writable(fs)?;
+                if let Some(min_free_space) = self.min_free_space {
+                    config.ensure_free_space(min_free_space)?;
+                }
+            }
+            // check log file path duplication, templates are resolved already and collide in
+            // every process when they resolve to the same path here
             let log_file_path: &Path = config.file_path();
             if !path_set.insert(log_file_path) {
+                let path = log_file_path.to_str().unwrap_or("").to_string();
                 return Err(BootstrapError::DuplicateLogFilePathError(
-                    log_file_path.to_str().unwrap_or("").to_string(),
+                    match config.file_name_template() {
+                        Some(template) => format!("{} (file_name_template={})", path, template),
+                        None => path,
+                    },
                 ));
             }
-            let loggers = config.logger_names();
-            for logger in loggers {
-                if !all_logger_name_set.contains(logger) {
-                    return Err(BootstrapError::InvalidConfigValueError(format!(
-                        "wrong logger name {} in appender{:#?}",
-                        logger, config
-                    )));
-                }
+            self.validate_logger_names(config.name(), &config.logger_names())?;
+            validate_pattern(config.name(), config.format(), config.pattern())?;
+            validate_dedup(config.name(), config.format(), config.dedup_window())?;
+            if config.is_per_tenant() && config.retention().is_enabled() {
+                return Err(BootstrapError::InvalidConfigValueError(format!(
+                    "logging.file_appenders[{}]: max_age and max_total_size do not apply to \
+                     the files of tenants",
+                    config.name()
+                )));
             }
         }
         Ok(())
     }
     fn validate_console_appender(&self) -> Result<(), BootstrapError> {
-        let all_logger_name = self.all_logger_name();
-        let all_logger_name_set: HashSet<&str> = all_logger_name.iter().cloned().collect();
         let Some(config) = &self.console_appender else {
             return Ok(());
         };
-        let loggers = config.logger_names();
-        for logger in loggers {
-            if !all_logger_name_set.contains(logger) {
-                return Err(BootstrapError::InvalidConfigValueError(format!(
-                    "wrong logger name {} in console appender",
-                    logger
-                )));
-            }
-        }
-        Ok(())
+        validate_buffer_size("logging.console_appender", config.buffer_size)?;
+        self.validate_logger_names(config.name(), &config.logger_names())?;
+        validate_pattern(config.name(), config.format(), config.pattern())?;
+        validate_dedup(config.name(), config.format(), config.dedup_window())
     }
 
     pub fn validate(&self) -> Result<(), BootstrapError> {
+        self.validate_with_fs(&OsFs)
+    }
+
+    pub fn validate_with_fs(&self, fs: &dyn Fs) -> Result<(), BootstrapError> {
         self.validate_loggers()?;
-        self.validate_file_appender()?;
+        self.validate_appender_names()?;
+        self.validate_file_appender(fs)?;
         self.validate_console_appender()?;
+        if let Some(reopen_signal) = &self.reopen_signal {
+            reopen_signal.validate()?;
+        }
+        if let Some(error_monitor) = &self.error_monitor {
+            error_monitor.validate()?;
+        }
+        if let Some(schema) = &self.schema {
+            schema.validate()?;
+        }
+        if let Some(maintenance) = &self.maintenance {
+            maintenance.validate()?;
+        }
         Ok(())
     }
 }
@@ -347,93 +1332,74 @@ impl ConfigPrefix for LoggingConfig {
     const PREFIX: &'static str = "logging";
 }
 
-#[derive(Debug, Default, Copy, Clone, Serialize, PartialEq, Eq, Hash)]
-pub enum Level {
-    /// The "trace" level.
-    Trace,
-    /// The "debug" level.
-    Debug,
-    /// The "info" level.
-    #[default]
-    Info,
-    /// The "warn" level.
-    Warn,
-    /// The "error" level.
-    Error,
-    /// Off level.
-    Off,
-}
-
-impl<'de> Deserialize<'de> for Level {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        const VARIANTS: [&str; 6] = ["trace", "debug", "info", "warn", "error", "off"];
-
-        let s = String::deserialize(deserializer)?;
-        s.parse()
-            .map_err(|_| <D::Error as serde::de::Error>::unknown_variant(&s, &VARIANTS))
+/// a worker without room for a line would drop or block on every event.
+fn validate_buffer_size(appender: &str, buffer_size: Option<usize>) -> Result<(), BootstrapError> {
+    if buffer_size == Some(0) {
+        return Err(BootstrapError::InvalidLogBufferSizeError(format!(
+            "{}.buffer_size=0, expected at least 1 line",
+            appender
+        )));
     }
+    Ok(())
 }
 
-#[non_exhaustive]
-#[derive(Debug)]
-pub struct ParseLevelError;
-
-impl FromStr for Level {
-    type Err = ParseLevelError;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            s if s.eq_ignore_ascii_case("trace") => Ok(Level::Trace),
-            s if s.eq_ignore_ascii_case("debug") => Ok(Level::Debug),
-            s if s.eq_ignore_ascii_case("info") => Ok(Level::Info),
-            s if s.eq_ignore_ascii_case("warn") => Ok(Level::Warn),
-            s if s.eq_ignore_ascii_case("error") => Ok(Level::Error),
-            s if s.eq_ignore_ascii_case("off") => Ok(Level::Off),
-            _ => Err(ParseLevelError),
+/// a pattern only lays out text appenders.
+fn validate_pattern(
+    appender: &str,
+    format: LogFormat,
+    pattern: Option<&PatternLayout>,
+) -> Result<(), BootstrapError> {
+    match (format, pattern) {
+        (LogFormat::Json | LogFormat::FastJson, Some(pattern)) => {
+            Err(BootstrapError::InvalidConfigValueError(format!(
+                "pattern {} of appender {} requires format = \"text\"",
+                pattern, appender
+            )))
         }
+        _ => Ok(()),
     }
 }
 
-impl Level {
-    pub fn as_str(&self) -> &'static str {
-        match self {
-            Level::Trace => "Trace",
-            Level::Debug => "Debug",
-            Level::Info => "Info",
-            Level::Warn => "Warn",
-            Level::Error => "Error",
-            Level::Off => "Off",
-        }
-    }
-
-    pub fn as_tracing_level(&self) -> Option<tracing::Level> {
-        match self {
-            Level::Trace => Some(tracing::Level::TRACE),
-            Level::Debug => Some(tracing::Level::DEBUG),
-            Level::Info => Some(tracing::Level::INFO),
-            Level::Warn => Some(tracing::Level::WARN),
-            Level::Error => Some(tracing::Level::ERROR),
-            Level::Off => None,
-        }
+/// repeats are only collapsed by the serde formatters.
+fn validate_dedup(
+    appender: &str,
+    format: LogFormat,
+    dedup_window: Option<Duration>,
+) -> Result<(), BootstrapError> {
+    match (format, dedup_window) {
+        (LogFormat::FastJson, Some(_)) => Err(BootstrapError::InvalidConfigValueError(format!(
+            "dedup_window of appender {} requires format = \"text\" or \"json\"",
+            appender
+        ))),
+        _ => Ok(()),
     }
+}
 
-    pub fn as_tracing_level_filter(&self) -> tracing::level_filters::LevelFilter {
-        match self {
-            Level::Trace => tracing::level_filters::LevelFilter::TRACE,
-            Level::Debug => tracing::level_filters::LevelFilter::DEBUG,
-            Level::Info => tracing::level_filters::LevelFilter::INFO,
-            Level::Warn => tracing::level_filters::LevelFilter::WARN,
-            Level::Error => tracing::level_filters::LevelFilter::ERROR,
-            Level::Off => tracing::level_filters::LevelFilter::OFF,
-        }
-    }
+/// `, did you mean <candidate>?` for the candidate closest to the mistyped `name`, empty when
+/// none is within a third of its length in edit distance.
+fn did_you_mean<'a>(name: &str, candidates: impl IntoIterator<Item = &'a str>) -> String {
+    let max_distance = (name.chars().count() / 3).max(1);
+    candidates
+        .into_iter()
+        .map(|x| (edit_distance(name, x), x))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, x)| format!(", did you mean {}?", x))
+        .unwrap_or_default()
 }
 
-impl fmt::Display for Level {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.pad(self.as_str())
+/// the Levenshtein distance between `a` and `b`, in chars.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, x) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, y) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(x != *y);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
     }
+    row[b.len()]
 }
@@ -0,0 +1,263 @@
+use std::{
+    collections::BTreeMap,
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::error::BootstrapError;
+
+use super::{DEFAULT_LOG_FOLDER, location::LogBaseDir};
+
+/// hash used as `prev_hash` of the first record of an audit log.
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuditAppenderConfig {
+    enable: bool,
+    file_dir: Option<String>,
+    file_name: String,
+}
+
+impl Default for AuditAppenderConfig {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            file_dir: None,
+            file_name: "audit.log".to_string(),
+        }
+    }
+}
+
+impl AuditAppenderConfig {
+    pub fn enable(&self) -> bool {
+        self.enable
+    }
+
+    /// resolve a relative `file_dir` against `base_dir`, a missing one to the logs of
+    /// `base_dir` or to `default_folder`.
+    pub(crate) fn resolve_file_dir(
+        &mut self,
+        base_dir: Option<&LogBaseDir>,
+        default_folder: &Path,
+    ) {
+        let file_dir = match (&self.file_dir, base_dir) {
+            (Some(dir), Some(base_dir)) => base_dir.resolve(dir),
+            (Some(_), None) => return,
+            (None, Some(base_dir)) => base_dir.logs_dir(),
+            (None, None) => default_folder.to_path_buf(),
+        };
+        self.file_dir = Some(file_dir.to_string_lossy().into_owned());
+    }
+
+    pub fn file_path(&self) -> PathBuf {
+        match &self.file_dir {
+            Some(dir) => PathBuf::from(dir).join(&self.file_name),
+            None => DEFAULT_LOG_FOLDER.join(&self.file_name),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AuditRecord {
+    pub seq: u64,
+    pub ts_ms: u64,
+    pub action: String,
+    pub actor: String,
+    pub fields: BTreeMap<String, String>,
+    pub prev_hash: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct AuditLine {
+    #[serde(flatten)]
+    record: AuditRecord,
+    hash: String,
+}
+
+impl AuditRecord {
+    fn hash(&self) -> String {
+        // struct field order and BTreeMap keep the serialized form stable
+        let body = serde_json::to_string(self).unwrap_or_default();
+        format!("{:x}", Sha256::digest(body.as_bytes()))
+    }
+}
+
+struct AuditState {
+    file: File,
+    seq: u64,
+    last_hash: String,
+}
+
+/// AuditLogger writes audit events to their own file, separate from application logs.
+///
+/// Each event is one JSON line carrying the hash of the previous line, and its own hash over
+/// its content, so removing, reordering or editing a line breaks the chain; see
+/// [`verify_audit_log`]. The chain resumes from the last line when the file is reopened.
+///
+/// # Example
+/// ```no_run
+/// use beaver_bootstrap::log::audit::AuditLogger;
+/// let audit = AuditLogger::open("logs/audit.log").unwrap();
+/// audit.record("user.login", "alice", &[("ip", "10.0.0.1")]).unwrap();
+/// ```
+pub struct AuditLogger {
+    path: PathBuf,
+    state: Mutex<AuditState>,
+}
+
+impl AuditLogger {
+    pub fn open(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let (seq, last_hash) = match File::open(&path) {
+            Ok(file) => match last_line(file)? {
+                Some(line) => {
+                    let line: AuditLine = serde_json::from_str(&line)?;
+                    (line.record.seq + 1, line.hash)
+                }
+                None => (0, GENESIS_HASH.to_string()),
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => (0, GENESIS_HASH.to_string()),
+            Err(e) => return Err(e),
+        };
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        Ok(Self {
+            path,
+            state: Mutex::new(AuditState {
+                file,
+                seq,
+                last_hash,
+            }),
+        })
+    }
+
+    // called by the bootstrap only
+    #[cfg_attr(not(feature = "full"), allow(dead_code))]
+    pub(crate) fn from_config(config: &AuditAppenderConfig) -> Result<Self, BootstrapError> {
+        Self::open(config.file_path()).map_err(|e| BootstrapError::AuditLogOpenError(Box::new(e)))
+    }
+
+    pub fn path(&self) -> &Path {
+        self.path.as_path()
+    }
+
+    /// append an audit event, returning its sequence number.
+    pub fn record(
+        &self,
+        action: &str,
+        actor: &str,
+        fields: &[(&str, &str)],
+    ) -> std::io::Result<u64> {
+        let mut state = self
+            .state
+            .lock()
+            .map_err(|_| std::io::Error::other("audit logger is poisoned"))?;
+        let record = AuditRecord {
+            seq: state.seq,
+            ts_ms: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or_default(),
+            action: action.to_string(),
+            actor: actor.to_string(),
+            fields: fields
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            prev_hash: state.last_hash.clone(),
+        };
+        let hash = record.hash();
+        let seq = record.seq;
+        let mut line = serde_json::to_string(&AuditLine {
+            record,
+            hash: hash.clone(),
+        })?;
+        line.push('\n');
+        state.file.write_all(line.as_bytes())?;
+        state.file.flush()?;
+        state.seq = seq + 1;
+        state.last_hash = hash;
+        Ok(seq)
+    }
+}
+
+/// AuditVerifyError is the first break found in an audit log chain.
+#[derive(Debug)]
+pub enum AuditVerifyError {
+    Io(std::io::Error),
+    Malformed { line: usize, reason: String },
+    Tampered { line: usize, reason: String },
+}
+
+impl std::fmt::Display for AuditVerifyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AuditVerifyError::Io(e) => write!(f, "unable to read audit log: {}", e),
+            AuditVerifyError::Malformed { line, reason } => {
+                write!(f, "malformed audit record at line {}: {}", line, reason)
+            }
+            AuditVerifyError::Tampered { line, reason } => {
+                write!(f, "audit chain broken at line {}: {}", line, reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AuditVerifyError {}
+
+/// verify the hash chain of an audit log, returning the number of records.
+pub fn verify_audit_log(path: impl AsRef<Path>) -> Result<usize, AuditVerifyError> {
+    let file = File::open(path).map_err(AuditVerifyError::Io)?;
+    let mut prev_hash = GENESIS_HASH.to_string();
+    let mut count = 0;
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(AuditVerifyError::Io)?;
+        let number = index + 1;
+        let parsed: AuditLine =
+            serde_json::from_str(&line).map_err(|e| AuditVerifyError::Malformed {
+                line: number,
+                reason: e.to_string(),
+            })?;
+        if parsed.record.seq != count as u64 {
+            return Err(AuditVerifyError::Tampered {
+                line: number,
+                reason: format!("expected seq {}, found {}", count, parsed.record.seq),
+            });
+        }
+        if parsed.record.prev_hash != prev_hash {
+            return Err(AuditVerifyError::Tampered {
+                line: number,
+                reason: "prev_hash does not match the previous record".to_string(),
+            });
+        }
+        if parsed.record.hash() != parsed.hash {
+            return Err(AuditVerifyError::Tampered {
+                line: number,
+                reason: "record content does not match its hash".to_string(),
+            });
+        }
+        prev_hash = parsed.hash;
+        count += 1;
+    }
+    Ok(count)
+}
+
+fn last_line(file: File) -> std::io::Result<Option<String>> {
+    let mut last = None;
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        if !line.trim().is_empty() {
+            last = Some(line);
+        }
+    }
+    Ok(last)
+}
@@ -0,0 +1,278 @@
+use std::{
+    io::{self, Write},
+    sync::{
+        Arc, Mutex, MutexGuard, TryLockError, Weak,
+        mpsc::{self, Receiver, RecvTimeoutError, SyncSender},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::BootstrapError,
+    serde::{byte_size_opt, duration_opt},
+};
+
+/// bytes a batch holds before it is written unless its `size` is set.
+pub const DEFAULT_BATCH_SIZE: u64 = 64 * 1024;
+/// time the first event of a batch waits for it to be written unless its `latency` is set.
+pub const DEFAULT_BATCH_LATENCY: Duration = Duration::from_millis(100);
+/// largest `size` of a batch, which is held in memory.
+pub const MAX_BATCH_SIZE: u64 = 64 * 1024 * 1024;
+/// full batches waiting for the batch thread before the logging threads block.
+const QUEUED_BATCHES: usize = 4;
+
+/// BatchConfig coalesces the writes of a file appender into chunks, see the `batch` table of
+/// `[[logging.file_appenders]]`:
+///
+/// ```toml
+/// [[logging.file_appenders]]
+/// file_name = "app.log"
+/// batch = { size = "128KiB", latency = "50ms" }
+/// ```
+///
+/// Events are then copied to the batch on the thread logging them, and written to the file
+/// by a thread of the appender once `size` bytes are batched, once the oldest of them waited
+/// `latency`, after an ERROR event unless `flush_on_error` is false, and when the logging is
+/// flushed. A chatty service makes one write call per batch rather than per event, at the
+/// cost of losing the batch in a crash the panic hook can not flush.
+///
+/// The batch replaces the worker of the appender, so `buffer_size`, `on_full` and `flush_on`
+/// do not apply to it and are rejected next to it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BatchConfig {
+    /// bytes batched before they are written, [`DEFAULT_BATCH_SIZE`] by default.
+    #[serde(deserialize_with = "byte_size_opt")]
+    size: Option<u64>,
+    /// longest time an event waits in a batch, [`DEFAULT_BATCH_LATENCY`] by default.
+    #[serde(deserialize_with = "duration_opt")]
+    latency: Option<Duration>,
+    /// whether an ERROR event writes its batch at once, so the last error before a crash
+    /// reaches the file.
+    flush_on_error: bool,
+}
+
+impl Default for BatchConfig {
+    fn default() -> Self {
+        Self {
+            size: None,
+            latency: None,
+            flush_on_error: true,
+        }
+    }
+}
+
+impl BatchConfig {
+    pub fn size(&self) -> u64 {
+        self.size.unwrap_or(DEFAULT_BATCH_SIZE)
+    }
+
+    pub fn latency(&self) -> Duration {
+        self.latency.unwrap_or(DEFAULT_BATCH_LATENCY)
+    }
+
+    pub fn flush_on_error(&self) -> bool {
+        self.flush_on_error
+    }
+
+    pub(crate) fn validate(&self, appender: &str) -> Result<(), BootstrapError> {
+        if let Some(size) = self.size
+            && !(1..=MAX_BATCH_SIZE).contains(&size)
+        {
+            return Err(BootstrapError::InvalidConfigValueError(format!(
+                "logging.file_appenders[{}].batch.size={}, must be between 1 and {}",
+                appender, size, MAX_BATCH_SIZE
+            )));
+        }
+        if self.latency.is_some_and(|x| x.is_zero()) {
+            return Err(BootstrapError::InvalidConfigValueError(format!(
+                "logging.file_appenders[{}].batch.latency=0",
+                appender
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// a message to the batch thread, the only writer of the inner writer.
+enum Message {
+    /// a batch to write.
+    Batch(Vec<u8>),
+    /// flush the inner writer, answering with the first error since the last flush.
+    Flush(SyncSender<io::Result<()>>),
+}
+
+/// the batch being filled.
+struct Pending {
+    buf: Vec<u8>,
+    /// when its oldest byte was written.
+    since: Option<Instant>,
+    /// sent to under the lock, so the batches reach the thread in order.
+    batches: SyncSender<Message>,
+}
+
+impl Pending {
+    /// hand the batch to the thread, blocking while [`QUEUED_BATCHES`] wait for it.
+    fn send(&mut self) -> io::Result<()> {
+        self.since = None;
+        if self.buf.is_empty() {
+            return Ok(());
+        }
+        let batch = std::mem::take(&mut self.buf);
+        self.batches
+            .send(Message::Batch(batch))
+            .map_err(|_| io::Error::other("the batch thread stopped"))
+    }
+}
+
+struct BatchState {
+    pending: Mutex<Pending>,
+    size: usize,
+    latency: Duration,
+}
+
+impl BatchState {
+    fn lock(&self) -> MutexGuard<'_, Pending> {
+        self.pending.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// BatchWriter batches what is written to it and hands the batches to a thread writing them
+/// to its inner writer, once `size` bytes are batched, once the oldest waited `latency`, on
+/// a flush and when dropped.
+///
+/// A write only copies to the batch, it blocks while the thread is 4 batches behind. A flush
+/// waits for the thread to write and flush every batch before it, and reports the first
+/// write error since the previous flush. The thread, named after the appender, stops once
+/// the writer is dropped.
+///
+/// # Example
+/// ```
+/// use std::{io::Write, time::Duration};
+/// use beaver_bootstrap::log::batch::BatchWriter;
+/// let mut writer = BatchWriter::new(std::io::stdout(), "app.log", 64 * 1024, Duration::from_millis(50));
+/// writer.write_all(b"batched\n").unwrap();
+/// writer.flush().unwrap(); // written now rather than in 50ms
+/// ```
+pub struct BatchWriter {
+    state: Arc<BatchState>,
+}
+
+impl std::fmt::Debug for BatchWriter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BatchWriter")
+            .field("size", &self.state.size)
+            .field("latency", &self.state.latency)
+            .finish_non_exhaustive()
+    }
+}
+
+impl BatchWriter {
+    pub fn new<W: Write + Send + 'static>(
+        inner: W,
+        name: &str,
+        size: usize,
+        latency: Duration,
+    ) -> Self {
+        let (batches, rx) = mpsc::sync_channel(QUEUED_BATCHES);
+        let state = Arc::new(BatchState {
+            // grown as events come, a large size is not allocated up front
+            pending: Mutex::new(Pending {
+                buf: Vec::new(),
+                since: None,
+                batches,
+            }),
+            size,
+            latency,
+        });
+        let weak = Arc::downgrade(&state);
+        let _ = thread::Builder::new()
+            .name(format!("beaver-log-batch-{}", name))
+            .spawn(move || write_batches(inner, weak, latency, rx));
+        Self { state }
+    }
+}
+
+/// write the batches received on `rx` and the pending one of `state` once it waited
+/// `latency`, until the writer is dropped.
+fn write_batches<W: Write>(
+    mut inner: W,
+    state: Weak<BatchState>,
+    latency: Duration,
+    rx: Receiver<Message>,
+) {
+    let mut error = None;
+    let mut wait = latency;
+    loop {
+        match rx.recv_timeout(wait) {
+            Ok(Message::Batch(batch)) => {
+                if let Err(e) = inner.write_all(&batch) {
+                    error.get_or_insert(e);
+                }
+            }
+            Ok(Message::Flush(done)) => {
+                let flushed = inner.flush();
+                let _ = done.send(error.take().map_or(flushed, Err));
+            }
+            Err(RecvTimeoutError::Timeout) => {
+                let Some(state) = state.upgrade() else {
+                    continue;
+                };
+                // a logging thread holding the lock may be waiting for this one to receive
+                let mut pending = match state.pending.try_lock() {
+                    Ok(pending) => pending,
+                    Err(TryLockError::Poisoned(e)) => e.into_inner(),
+                    Err(TryLockError::WouldBlock) => continue,
+                };
+                wait = match pending.since.map(|x| x.elapsed()) {
+                    // queued behind the batches sent before, the channel keeps the order
+                    Some(waited) if waited >= latency => {
+                        let _ = pending.send();
+                        latency
+                    }
+                    Some(waited) => latency - waited,
+                    None => latency,
+                };
+            }
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+    }
+}
+
+impl Write for BatchWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut pending = self.state.lock();
+        if pending.since.is_none() {
+            pending.since = Some(Instant::now());
+        }
+        pending.buf.extend_from_slice(buf);
+        if pending.buf.len() >= self.state.size {
+            pending.send()?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let (done, flushed) = mpsc::sync_channel(1);
+        {
+            let mut pending = self.state.lock();
+            pending.send()?;
+            pending
+                .batches
+                .send(Message::Flush(done))
+                .map_err(|_| io::Error::other("the batch thread stopped"))?;
+        }
+        flushed
+            .recv()
+            .map_err(|_| io::Error::other("the batch thread stopped"))?
+    }
+}
+
+impl Drop for BatchWriter {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
@@ -0,0 +1,302 @@
+use std::{
+    io::{self, Write},
+    sync::{
+        Arc, Condvar, Mutex,
+        atomic::{AtomicU64, Ordering},
+        mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+use tracing_appender::non_blocking::{ErrorCounter, NonBlocking, NonBlockingBuilder, WorkerGuard};
+use tracing_subscriber::fmt::MakeWriter;
+
+/// default number of buffered lines of an appender, same as tracing-appender.
+pub const DEFAULT_BUFFER_SIZE: usize = 128_000;
+
+/// interval of the dropped events warning.
+const DROPPED_EVENTS_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// prefix of the markers sent through a non blocking writer to wait for its worker, followed
+/// by the number of the marker. Events never start with a NUL byte.
+const FLUSH_MARKER: &[u8] = b"\0beaver-flush:";
+
+/// OnFull is what an appender does when its buffer is full.
+#[derive(Debug, Default, Copy, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OnFull {
+    /// drop the event and count it, producers never wait.
+    #[default]
+    Drop,
+    /// block the producer until the writer catches up, no event is lost.
+    Block,
+}
+
+/// wrap `writer` in a non blocking writer with the given buffer settings, with the handle
+/// waiting for its worker to drain.
+pub fn non_blocking<W: Write + Send + 'static>(
+    writer: W,
+    name: &str,
+    buffer_size: usize,
+    on_full: OnFull,
+) -> (QueuedWriter, WorkerGuard, FlushHandle) {
+    let state = Arc::new(FlushState::default());
+    let writer = MarkedWriter {
+        inner: writer,
+        state: state.clone(),
+    };
+    let (writer, guard) = NonBlockingBuilder::default()
+        .buffered_lines_limit(buffer_size)
+        .lossy(on_full == OnFull::Drop)
+        .thread_name(&format!("beaver-log-{}", name))
+        .finish(writer);
+    let queued = QueuedWriter {
+        writer: writer.clone(),
+        state: state.clone(),
+    };
+    let handle = FlushHandle {
+        name: name.to_string(),
+        writer,
+        state,
+        sent: Arc::new(AtomicU64::new(0)),
+    };
+    (queued, guard, handle)
+}
+
+/// the last flush marker written by the worker of a non blocking writer, and the events
+/// sent to and written by it.
+#[derive(Default)]
+struct FlushState {
+    flushed: Mutex<u64>,
+    drained: Condvar,
+    events_sent: AtomicU64,
+    events_written: AtomicU64,
+}
+
+/// QueuedWriter is a non blocking writer counting the events it hands to its worker, see
+/// [`AppenderStats`].
+#[derive(Clone)]
+pub struct QueuedWriter {
+    writer: NonBlocking,
+    state: Arc<FlushState>,
+}
+
+impl std::fmt::Debug for QueuedWriter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("QueuedWriter").finish_non_exhaustive()
+    }
+}
+
+impl QueuedWriter {
+    /// counter of events dropped because the buffer was full.
+    pub fn error_counter(&self) -> ErrorCounter {
+        self.writer.error_counter()
+    }
+}
+
+impl Write for QueuedWriter {
+    /// hand one event to the worker, the formatting layers write an event at once.
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.state.events_sent.fetch_add(1, Ordering::Relaxed);
+        self.writer.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        self.write(buf).map(|_| ())
+    }
+}
+
+impl<'a> MakeWriter<'a> for QueuedWriter {
+    type Writer = QueuedWriter;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self.clone()
+    }
+}
+
+/// MarkedWriter is the writer of the worker, it flushes on a flush marker and reports it
+/// rather than writing it.
+struct MarkedWriter<W> {
+    inner: W,
+    state: Arc<FlushState>,
+}
+
+impl<W: Write> MarkedWriter<W> {
+    /// flush and report the marker `marker`.
+    fn reach(&mut self, marker: &[u8]) -> io::Result<()> {
+        self.inner.flush()?;
+        let marker = std::str::from_utf8(marker)
+            .ok()
+            .and_then(|x| x.parse::<u64>().ok())
+            .unwrap_or_default();
+        let mut flushed = self.state.flushed.lock().unwrap_or_else(|e| e.into_inner());
+        *flushed = (*flushed).max(marker);
+        self.state.drained.notify_all();
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for MarkedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let Some(marker) = buf.strip_prefix(FLUSH_MARKER) else {
+            return self.inner.write(buf);
+        };
+        self.reach(marker)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+
+    /// write one event or marker, the worker writes each at once.
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        if let Some(marker) = buf.strip_prefix(FLUSH_MARKER) {
+            return self.reach(marker);
+        }
+        // counted even when the write fails, the event left the queue
+        self.state.events_written.fetch_add(1, Ordering::Relaxed);
+        self.inner.write_all(buf)
+    }
+}
+
+/// FlushHandle waits for the worker of a non blocking writer to write and flush the events
+/// sent so far, without stopping it like dropping its guard does.
+#[derive(Clone)]
+pub struct FlushHandle {
+    name: String,
+    writer: NonBlocking,
+    state: Arc<FlushState>,
+    sent: Arc<AtomicU64>,
+}
+
+impl std::fmt::Debug for FlushHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FlushHandle")
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
+impl FlushHandle {
+    /// send a flush marker behind the pending events and wait up to `timeout` for the worker
+    /// to reach it, returning whether it did. A marker dropped by a full lossy buffer is not
+    /// reached.
+    pub fn flush(&self, timeout: Duration) -> bool {
+        let marker = self.sent.fetch_add(1, Ordering::AcqRel) + 1;
+        let mut line = FLUSH_MARKER.to_vec();
+        line.extend_from_slice(marker.to_string().as_bytes());
+        if self.writer.clone().write_all(&line).is_err() {
+            return false;
+        }
+        let deadline = Instant::now() + timeout;
+        let mut flushed = self.state.flushed.lock().unwrap_or_else(|e| e.into_inner());
+        while *flushed < marker {
+            let left = deadline.saturating_duration_since(Instant::now());
+            if left.is_zero() {
+                return false;
+            }
+            flushed = match self.state.drained.wait_timeout(flushed, left) {
+                Ok((flushed, _)) => flushed,
+                Err(e) => e.into_inner().0,
+            };
+        }
+        true
+    }
+
+    /// the counts of events of the appender since startup.
+    pub fn stats(&self) -> AppenderStats {
+        // read the written events first, so that none is counted written but not sent
+        let written = self.state.events_written.load(Ordering::Relaxed);
+        let dropped = self.writer.error_counter().dropped_lines() as u64;
+        let sent = self.state.events_sent.load(Ordering::Relaxed);
+        AppenderStats {
+            appender: self.name.clone(),
+            sent,
+            written,
+            queued: sent.saturating_sub(written + dropped),
+            dropped,
+        }
+    }
+}
+
+/// AppenderStats are the counts of events of a non blocking appender since startup, see
+/// [`LoggingManager::stats`](crate::log::LoggingManager::stats).
+///
+/// `sent` events are `written` by the worker, `queued` in its buffer or `dropped` because
+/// the buffer was full. A `queued` count near the `buffer_size` of the appender, or any
+/// `dropped` event, means the appender can not keep up with the events logged.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AppenderStats {
+    pub appender: String,
+    pub sent: u64,
+    pub written: u64,
+    pub queued: u64,
+    pub dropped: u64,
+}
+
+/// DroppedEvents counts the events each appender dropped because its buffer was full.
+#[derive(Clone, Default)]
+pub struct DroppedEvents {
+    counters: Vec<(String, ErrorCounter)>,
+}
+
+impl std::fmt::Debug for DroppedEvents {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map().entries(self.counts()).finish()
+    }
+}
+
+impl DroppedEvents {
+    pub fn add(&mut self, appender: &str, counter: ErrorCounter) {
+        self.counters.push((appender.to_string(), counter));
+    }
+
+    /// dropped events per appender since startup.
+    pub fn counts(&self) -> Vec<(String, usize)> {
+        self.counters
+            .iter()
+            .map(|(name, counter)| (name.clone(), counter.dropped_lines()))
+            .collect()
+    }
+
+    pub fn total(&self) -> usize {
+        self.counters.iter().map(|(_, c)| c.dropped_lines()).sum()
+    }
+
+    /// warn once a minute about appenders that dropped events since the last warning.
+    ///
+    /// The monitor stops when the returned sender is dropped.
+    pub(crate) fn spawn_monitor(&self) -> Sender<()> {
+        let (stop, rx) = mpsc::channel::<()>();
+        let dropped = self.clone();
+        let _ = thread::Builder::new()
+            .name("beaver-log-dropped".to_string())
+            .spawn(move || dropped.monitor(rx));
+        stop
+    }
+
+    fn monitor(&self, rx: Receiver<()>) {
+        let mut last = self.counts();
+        while let Err(RecvTimeoutError::Timeout) = rx.recv_timeout(DROPPED_EVENTS_CHECK_INTERVAL) {
+            let current = self.counts();
+            for ((name, now), (_, before)) in current.iter().zip(last.iter()) {
+                if now > before {
+                    tracing::warn!(
+                        appender = name.as_str(),
+                        dropped = now - before,
+                        total_dropped = now,
+                        "log appender buffer is full, events were dropped"
+                    );
+                }
+            }
+            last = current;
+        }
+    }
+}
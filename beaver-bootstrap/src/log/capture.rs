@@ -0,0 +1,83 @@
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::{Layer, layer::Context};
+
+use super::early::EarlyEvent;
+
+/// CapturedEvent is an event recorded by a [`LogCapture`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapturedEvent {
+    pub level: Level,
+    pub target: String,
+    /// the event as a line of text, `LEVEL target: message field=value`.
+    pub line: String,
+}
+
+/// LogCapture is a layer recording the events of its subscriber, to assert on the logs of the
+/// code under test. Set as the `log_capture` of the bootstrap builder, it records the events
+/// of every appender once logging is initialized.
+///
+/// # Example
+/// ```
+/// use beaver_bootstrap::log::capture::LogCapture;
+/// use tracing::Level;
+/// use tracing_subscriber::layer::SubscriberExt;
+/// let capture = LogCapture::default();
+/// let subscriber = tracing_subscriber::registry().with(capture.clone());
+/// tracing::subscriber::with_default(subscriber, || {
+///     tracing::warn!(target: "billing::invoice", id = 42, "invoice overdue");
+/// });
+/// assert!(capture.logs_contain("billing", Level::WARN, &["overdue", "id=42"]));
+/// assert!(!capture.logs_contain("billing", Level::ERROR, &["overdue"]));
+/// assert_eq!(capture.lines(), ["WARN billing::invoice: invoice overdue id=42"]);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct LogCapture {
+    events: Arc<Mutex<Vec<CapturedEvent>>>,
+}
+
+impl LogCapture {
+    /// the events recorded so far, the oldest first.
+    pub fn events(&self) -> Vec<CapturedEvent> {
+        self.lock().clone()
+    }
+
+    /// the lines of the events recorded so far, to compare with a golden file.
+    pub fn lines(&self) -> Vec<String> {
+        self.lock().iter().map(|x| x.line.clone()).collect()
+    }
+
+    /// whether an event of `level`, of `target` or of a module below it, holds every one of
+    /// `substrings`.
+    pub fn logs_contain(&self, target: &str, level: Level, substrings: &[&str]) -> bool {
+        self.lock().iter().any(|event| {
+            let below = event
+                .target
+                .strip_prefix(target)
+                .is_some_and(|x| x.is_empty() || x.starts_with("::"));
+            event.level == level && below && substrings.iter().all(|x| event.line.contains(x))
+        })
+    }
+
+    /// forget the events recorded so far.
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<CapturedEvent>> {
+        self.events.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl<S: Subscriber> Layer<S> for LogCapture {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        let captured = CapturedEvent {
+            level: *metadata.level(),
+            target: metadata.target().to_string(),
+            line: EarlyEvent::new(event).line(),
+        };
+        self.lock().push(captured);
+    }
+}
@@ -0,0 +1,146 @@
+use std::{
+    cell::RefCell,
+    future::Future,
+    marker::PhantomData,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+thread_local! {
+    static CONTEXT: RefCell<Vec<(String, String)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// LogContext holds contextual key-value pairs (request_id, tenant, ...) of the current thread.
+///
+/// Every event formatted by beaver's appenders includes the pairs that are in scope when it
+/// is emitted. When a key is pushed more than once, the innermost value is written.
+///
+/// # Example
+/// ```
+/// use beaver_bootstrap::log::context::LogContext;
+/// let _guard = LogContext::push("request_id", "42");
+/// LogContext::scope(&[("tenant", "acme")], || {
+///     assert_eq!(LogContext::get("tenant").as_deref(), Some("acme"));
+///     tracing::info!("handled"); // ... request_id=42 tenant=acme
+/// });
+/// assert_eq!(LogContext::get("tenant"), None);
+/// ```
+pub struct LogContext;
+
+impl LogContext {
+    /// push a pair, it is popped when the returned guard is dropped.
+    #[must_use = "the field is removed when the guard is dropped"]
+    pub fn push(key: &str, value: &str) -> LogContextGuard {
+        let depth = CONTEXT.with(|ctx| {
+            let mut ctx = ctx.borrow_mut();
+            ctx.push((key.to_string(), value.to_string()));
+            ctx.len() - 1
+        });
+        LogContextGuard {
+            depth,
+            _not_send: PhantomData,
+        }
+    }
+
+    /// run `f` with `fields` pushed.
+    pub fn scope<R>(fields: &[(&str, &str)], f: impl FnOnce() -> R) -> R {
+        let _guards: Vec<LogContextGuard> = fields.iter().map(|(k, v)| Self::push(k, v)).collect();
+        f()
+    }
+
+    /// innermost value of `key`.
+    pub fn get(key: &str) -> Option<String> {
+        CONTEXT.with(|ctx| {
+            ctx.borrow()
+                .iter()
+                .rev()
+                .find(|(k, _)| k == key)
+                .map(|(_, v)| v.clone())
+        })
+    }
+
+    /// all pairs in scope, outermost first, keeping only the innermost value of each key.
+    pub fn current() -> Vec<(String, String)> {
+        let mut pairs = Vec::new();
+        Self::for_each(|key, value| pairs.push((key.to_string(), value.to_string())));
+        pairs
+    }
+
+    /// visit the pairs of [`LogContext::current`] without copying them.
+    pub(crate) fn for_each(mut f: impl FnMut(&str, &str)) {
+        CONTEXT.with(|ctx| {
+            let ctx = ctx.borrow();
+            for (index, (key, value)) in ctx.iter().enumerate() {
+                if !ctx[index + 1..].iter().any(|(k, _)| k == key) {
+                    f(key, value);
+                }
+            }
+        })
+    }
+
+    fn replace(fields: Vec<(String, String)>) -> Vec<(String, String)> {
+        CONTEXT.with(|ctx| std::mem::replace(&mut *ctx.borrow_mut(), fields))
+    }
+}
+
+/// LogContextGuard pops its pair, and any pair pushed after it, when dropped.
+///
+/// The pairs live in a thread local, so the guard is not `Send`: dropped on another thread it
+/// would truncate the context of that thread.
+///
+/// ```compile_fail
+/// use beaver_bootstrap::log::context::LogContext;
+/// let guard = LogContext::push("request_id", "42");
+/// std::thread::spawn(move || drop(guard));
+/// ```
+pub struct LogContextGuard {
+    depth: usize,
+    _not_send: PhantomData<*const ()>,
+}
+
+impl Drop for LogContextGuard {
+    fn drop(&mut self) {
+        CONTEXT.with(|ctx| ctx.borrow_mut().truncate(self.depth));
+    }
+}
+
+/// WithLogContext carries a log context along with a future across threads and polls.
+pub struct WithLogContext<F> {
+    inner: Pin<Box<F>>,
+    fields: Vec<(String, String)>,
+}
+
+impl<F: Future> Future for WithLogContext<F> {
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+        let previous = LogContext::replace(std::mem::take(&mut this.fields));
+        let result = this.inner.as_mut().poll(cx);
+        // keep what the future pushed for its next poll
+        this.fields = LogContext::replace(previous);
+        result
+    }
+}
+
+/// LogContextExt attaches a log context to a future, the task-local flavour of [`LogContext`].
+///
+/// # Example
+/// ```
+/// use beaver_bootstrap::log::context::{LogContext, LogContextExt};
+/// let task = async { LogContext::get("request_id") }
+///     .with_log_context(&[("request_id", "42")]);
+/// ```
+pub trait LogContextExt: Future + Sized {
+    /// run the future with the current context plus `fields`.
+    fn with_log_context(self, fields: &[(&str, &str)]) -> WithLogContext<Self> {
+        let mut context = LogContext::current();
+        context.extend(fields.iter().map(|(k, v)| (k.to_string(), v.to_string())));
+        WithLogContext {
+            inner: Box::pin(self),
+            fields: context,
+        }
+    }
+}
+
+impl<F: Future> LogContextExt for F {}
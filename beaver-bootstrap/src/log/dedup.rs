@@ -0,0 +1,139 @@
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+
+use tracing::{Event, Subscriber};
+use tracing_subscriber::{
+    fmt::{FmtContext, FormatEvent, FormatFields, format::Writer},
+    registry::LookupSpan,
+};
+
+use super::{
+    context::LogContext,
+    format::{ClockTime, LogFormat},
+};
+use crate::clock::Clock;
+
+/// DedupFormat collapses identical consecutive events of an appender, so a tight error loop
+/// writes one line and a repeat count rather than filling the disk.
+///
+/// Events are identical when their level, target, fields and [`LogContext`] pairs are. The
+/// first event is written, the repeats within `window` of it are counted, and
+/// `last message repeated N times` is written before the next different event, or before the
+/// next repeat once the window is over. Without `window` every event is written.
+///
+/// # Example
+/// ```
+/// use std::{sync::Arc, time::Duration};
+/// use beaver_bootstrap::{clock::SystemClock, log::format::{LogFormat, fmt_layer_with_clock}};
+/// use tracing_subscriber::layer::SubscriberExt;
+/// let (window, clock) = (Some(Duration::from_secs(10)), Arc::new(SystemClock));
+/// let layer = fmt_layer_with_clock(LogFormat::Text, false, vec![], window, clock, std::io::stdout);
+/// let subscriber = tracing_subscriber::registry().with(layer);
+/// tracing::subscriber::with_default(subscriber, || {
+///     for _ in 0..3 {
+///         tracing::error!("connection refused");
+///     }
+///     tracing::info!("connected"); // after "last message repeated 2 times"
+/// });
+/// ```
+pub struct DedupFormat<F> {
+    inner: F,
+    window: Option<Duration>,
+    clock: Arc<dyn Clock>,
+    format: LogFormat,
+    last: Mutex<Option<LastEvent>>,
+}
+
+/// the last written event and the repeats counted since.
+struct LastEvent {
+    key: String,
+    level: tracing::Level,
+    target: String,
+    written_at: SystemTime,
+    repeated: usize,
+}
+
+impl<F> DedupFormat<F> {
+    pub fn new(
+        inner: F,
+        window: Option<Duration>,
+        clock: Arc<dyn Clock>,
+        format: LogFormat,
+    ) -> Self {
+        Self {
+            inner,
+            window,
+            clock,
+            format,
+            last: Mutex::new(None),
+        }
+    }
+
+    fn write_repeated(&self, writer: &mut Writer<'_>, last: &LastEvent) -> fmt::Result {
+        let message = format!("last message repeated {} times", last.repeated);
+        match self.format {
+            LogFormat::Text => writeln!(writer, "{}", message),
+            LogFormat::Json | LogFormat::FastJson => writeln!(
+                writer,
+                "{}",
+                serde_json::json!({
+                    "timestamp": ClockTime::new(self.clock.clone()).timestamp(),
+                    "level": last.level.as_str(),
+                    "target": last.target,
+                    "message": message,
+                    "repeated": last.repeated,
+                })
+            ),
+        }
+    }
+}
+
+impl<S, N, F> FormatEvent<S, N> for DedupFormat<F>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+    F: FormatEvent<S, N>,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let Some(window) = self.window else {
+            return self.inner.format_event(ctx, writer, event);
+        };
+        let metadata = event.metadata();
+        let mut key = format!("{} {} ", metadata.level(), metadata.target());
+        ctx.format_fields(Writer::new(&mut key), event)?;
+        for (k, v) in LogContext::current() {
+            key.push_str(&format!(" {}={}", k, v));
+        }
+        let now = self.clock.now();
+        // the lock is held while writing, so the count precedes the next event
+        let mut last = self.last.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(last) = last.as_mut()
+            && last.key == key
+            && now
+                .duration_since(last.written_at)
+                .is_ok_and(|x| x < window)
+        {
+            last.repeated += 1;
+            return Ok(());
+        }
+        if let Some(last) = last.as_ref().filter(|x| x.repeated > 0) {
+            self.write_repeated(&mut writer, last)?;
+        }
+        *last = Some(LastEvent {
+            key,
+            level: *metadata.level(),
+            target: metadata.target().to_string(),
+            written_at: now,
+            repeated: 0,
+        });
+        self.inner.format_event(ctx, writer, event)
+    }
+}
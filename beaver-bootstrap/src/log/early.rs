@@ -0,0 +1,233 @@
+use std::{
+    collections::VecDeque,
+    fmt,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use tracing::{
+    Dispatch, Event, Metadata, Subscriber,
+    dispatcher::DefaultGuard,
+    field::{Field, Value, Visit, display},
+};
+use tracing_subscriber::{Layer, layer::Context, prelude::*};
+
+#[cfg(feature = "full")]
+use crate::admin::{AdminResponse, AdminRoutes};
+
+/// number of events an [`EarlyEvents`] ring buffer keeps, the oldest are dropped first.
+pub const DEFAULT_EARLY_EVENTS: usize = 10_000;
+
+/// an event recorded before logging was initialized, its values kept as their text.
+pub(super) struct EarlyEvent {
+    metadata: &'static Metadata<'static>,
+    values: Vec<(Field, String)>,
+}
+
+impl EarlyEvent {
+    pub(super) fn new(event: &Event<'_>) -> Self {
+        let mut values = EarlyValues::default();
+        event.record(&mut values);
+        Self {
+            metadata: event.metadata(),
+            values: values.0,
+        }
+    }
+
+    /// dispatch the event again to `dispatch`, within its current span.
+    fn replay(&self, dispatch: &Dispatch) {
+        if !dispatch.enabled(self.metadata) {
+            return;
+        }
+        let fields = self.metadata.fields();
+        let Some(first) = fields.iter().next() else {
+            dispatch.event(&Event::new(self.metadata, &fields.value_set(&[])));
+            return;
+        };
+        let values: Vec<_> = self.values.iter().map(|(_, x)| display(x)).collect();
+        // an event has 32 fields at most, the slots left are unset
+        let mut slots: [(&Field, Option<&dyn Value>); 32] = [(&first, None); 32];
+        for (slot, ((field, _), value)) in slots.iter_mut().zip(self.values.iter().zip(&values)) {
+            *slot = (field, Some(value as &dyn Value));
+        }
+        dispatch.event(&Event::new(self.metadata, &fields.value_set(&slots)));
+    }
+
+    /// the event as a line of text, `LEVEL target: message field=value`.
+    pub(super) fn line(&self) -> String {
+        let mut line = format!("{} {}:", self.metadata.level(), self.metadata.target());
+        for (field, value) in &self.values {
+            match field.name() {
+                "message" => line.push_str(&format!(" {}", value)),
+                name => line.push_str(&format!(" {}={}", name, value)),
+            }
+        }
+        line
+    }
+}
+
+#[derive(Default)]
+struct EarlyValues(Vec<(Field, String)>);
+
+impl Visit for EarlyValues {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        let value = match field.name() {
+            "message" => value.to_string(),
+            _ => format!("{:?}", value),
+        };
+        self.0.push((field.clone(), value));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0.push((field.clone(), format!("{:?}", value)));
+    }
+}
+
+#[derive(Default)]
+struct EarlyBuffer {
+    events: VecDeque<EarlyEvent>,
+    /// number of the oldest events dropped to make room.
+    dropped: usize,
+}
+
+/// EarlyLayer records the events of the subscriber into its [`EarlyEvents`].
+struct EarlyLayer {
+    buffer: Arc<Mutex<EarlyBuffer>>,
+    capacity: usize,
+}
+
+impl<S: Subscriber> Layer<S> for EarlyLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        if self.capacity == 0 {
+            return;
+        }
+        let event = EarlyEvent::new(event);
+        let mut buffer = self.buffer.lock().unwrap_or_else(|e| e.into_inner());
+        if buffer.events.len() >= self.capacity {
+            buffer.events.pop_front();
+            buffer.dropped += 1;
+        }
+        buffer.events.push_back(event);
+    }
+}
+
+/// EarlyEvents captures the events of the current thread into a ring buffer until logging is
+/// initialized, then replays them into the subscriber installed meanwhile, so the events of
+/// the first steps of [`Bootstrap::initialize`](crate::bootstrap::Bootstrap::initialize),
+/// e.g. of config loading, reach the appenders too. Past its capacity, the oldest events are
+/// dropped.
+///
+/// Replayed events keep their level, target and fields, their values as text, and are
+/// timestamped by the replay. They are replayed within the span current at replay. Events of
+/// other threads are not captured. Dropped without [`EarlyEvents::replay`], e.g. when
+/// initialization fails, the events are replayed into the default subscriber then.
+///
+/// # Example
+/// ```
+/// use beaver_bootstrap::log::early::EarlyEvents;
+/// let early = EarlyEvents::capture(16);
+/// tracing::info!(target: "app", path = "etc/config.toml", "config loaded");
+/// assert_eq!(early.lines(), ["INFO app: config loaded path=\"etc/config.toml\""]);
+/// // install the subscriber of the application, then
+/// assert_eq!(early.replay(), 1);
+/// ```
+pub struct EarlyEvents {
+    buffer: Arc<Mutex<EarlyBuffer>>,
+    guard: Option<DefaultGuard>,
+}
+
+impl fmt::Debug for EarlyEvents {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EarlyEvents")
+            .field("events", &self.lock().events.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl EarlyEvents {
+    /// start capturing up to `capacity` events on the current thread.
+    pub fn capture(capacity: usize) -> Self {
+        let buffer = Arc::new(Mutex::new(EarlyBuffer::default()));
+        let layer = EarlyLayer {
+            buffer: buffer.clone(),
+            capacity,
+        };
+        let guard = tracing::subscriber::set_default(tracing_subscriber::registry().with(layer));
+        Self {
+            buffer,
+            guard: Some(guard),
+        }
+    }
+
+    /// the captured events as lines of text, the oldest first.
+    pub fn lines(&self) -> Vec<String> {
+        self.lock().events.iter().map(EarlyEvent::line).collect()
+    }
+
+    /// stop capturing and replay the captured events into the default subscriber, returning
+    /// their number.
+    pub fn replay(mut self) -> usize {
+        self.replay_captured()
+    }
+
+    fn replay_captured(&mut self) -> usize {
+        // the subscriber of the application is the default again
+        drop(self.guard.take());
+        let EarlyBuffer { events, dropped } = std::mem::take(&mut *self.lock());
+        tracing::dispatcher::get_default(|dispatch| {
+            for event in &events {
+                event.replay(dispatch);
+            }
+        });
+        if dropped > 0 {
+            tracing::warn!(
+                "the {} oldest events logged before logging was initialized are lost",
+                dropped
+            );
+        }
+        events.len()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, EarlyBuffer> {
+        self.buffer.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Drop for EarlyEvents {
+    fn drop(&mut self) {
+        if self.guard.is_some() {
+            self.replay_captured();
+        }
+    }
+}
+
+/// StartupLog keeps the lines of the events captured by [`EarlyEvents`] during startup, for
+/// the post-mortem of a startup which failed or misbehaved before its logs were written.
+#[derive(Debug, Clone, Default)]
+pub struct StartupLog {
+    lines: Arc<Mutex<Vec<String>>>,
+}
+
+impl StartupLog {
+    pub fn lines(&self) -> Vec<String> {
+        self.lock().clone()
+    }
+
+    /// keep the events captured by `early` so far.
+    pub fn record(&self, early: &EarlyEvents) {
+        *self.lock() = early.lines();
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<String>> {
+        self.lines.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// register `/debug/startup-log`, the lines of `log` as text.
+#[cfg(feature = "full")]
+pub fn register_routes(routes: &AdminRoutes, log: StartupLog) {
+    routes.route("/debug/startup-log", move |_| {
+        let mut body = log.lines().join("\n");
+        body.push('\n');
+        AdminResponse::text(200, &body)
+    });
+}
@@ -0,0 +1,110 @@
+use std::io::{self, BufRead, Write};
+
+use crate::{
+    config::secret::{ConfigCipher, ENCRYPTED_VALUE_PREFIX},
+    error::BootstrapError,
+};
+
+/// EncryptingWriter encrypts the lines of a file appender with AES-256-GCM, for deployments
+/// which must not keep readable application logs at rest:
+///
+/// ```toml
+/// [[logging.file_appenders]]
+/// file_name = "app.log"
+/// encrypt = true
+/// ```
+///
+/// Every line is written as its own record, `enc:` followed by the base64 encoded nonce and
+/// ciphertext like encrypted config values, so files stay line oriented, rotate as usual and
+/// a truncated file loses its last line only. The key is the config key of
+/// [`Bootstrap`](crate::bootstrap::Bootstrap), `BEAVER_CONFIG_KEY` by default. Lines are
+/// encrypted once their newline is written, [`logcat`] reads them back.
+///
+/// # Example
+/// ```
+/// use std::io::Write;
+/// use beaver_bootstrap::{
+///     config::secret::{ConfigCipher, decode_key, generate_key},
+///     log::encrypt::{EncryptingWriter, logcat},
+/// };
+/// let key = decode_key(&generate_key()).unwrap();
+/// let mut writer = EncryptingWriter::new(Vec::new(), ConfigCipher::new(key));
+/// writer.write_all(b"INFO login user=alice\n").unwrap();
+/// let encrypted = writer.into_inner();
+/// assert!(encrypted.starts_with(b"enc:"));
+/// let mut decrypted = Vec::new();
+/// logcat(&encrypted[..], &ConfigCipher::new(key), &mut decrypted).unwrap();
+/// assert_eq!(decrypted, b"INFO login user=alice\n");
+/// ```
+pub struct EncryptingWriter<W> {
+    inner: W,
+    cipher: ConfigCipher,
+    /// the start of a line not complete yet.
+    pending: Vec<u8>,
+}
+
+impl<W: Write> EncryptingWriter<W> {
+    pub fn new(inner: W, cipher: ConfigCipher) -> Self {
+        Self {
+            inner,
+            cipher,
+            pending: Vec::new(),
+        }
+    }
+
+    /// the inner writer, an incomplete last line is not written.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    fn write_record(&mut self, line: &[u8]) -> io::Result<()> {
+        let record = self
+            .cipher
+            .encrypt(&String::from_utf8_lossy(line))
+            .map_err(io::Error::other)?;
+        self.inner.write_all(format!("{}\n", record).as_bytes())
+    }
+}
+
+impl<W: Write> Write for EncryptingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+        while let Some(end) = self.pending.iter().position(|x| *x == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=end).collect();
+            self.write_record(&line[..end])?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// decrypt the log lines of `reader` to `out` with `cipher`, returning the number of
+/// decrypted lines. Lines which are not encrypted, e.g. written before `encrypt` was set, are
+/// copied as they are.
+///
+/// The `logcat` subcommand of the `cli` feature decrypts files with the config key.
+pub fn logcat(
+    reader: impl BufRead,
+    cipher: &ConfigCipher,
+    mut out: impl Write,
+) -> Result<usize, BootstrapError> {
+    let error = |line: usize, reason: String| {
+        BootstrapError::LogDecryptionError(format!("line {}: {}", line, reason))
+    };
+    let mut decrypted = 0;
+    for (index, line) in reader.lines().enumerate() {
+        let line = line.map_err(|e| error(index + 1, e.to_string()))?;
+        let plaintext = match line.starts_with(ENCRYPTED_VALUE_PREFIX) {
+            true => {
+                decrypted += 1;
+                cipher.decrypt(&line).map_err(|e| error(index + 1, e))?
+            }
+            false => line,
+        };
+        writeln!(out, "{}", plaintext).map_err(|e| error(index + 1, e.to_string()))?;
+    }
+    Ok(decrypted)
+}
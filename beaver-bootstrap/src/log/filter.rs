@@ -0,0 +1,394 @@
+use std::{fmt, str::FromStr};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tracing::{
+    Event, Metadata,
+    field::{Field, Visit},
+};
+use tracing_subscriber::layer::{Context, Filter};
+
+use super::context::LogContext;
+use crate::level::Level;
+
+/// FilterExpr is the `filter` of an appender, selecting events by their fields and message
+/// beyond the levels and targets of loggers, e.g. a dedicated file for one tenant or for
+/// timeouts:
+///
+/// ```toml
+/// filter = "field.tenant == 'acme' || message =~ 'timeout'"
+/// ```
+///
+/// A comparison is an operand, an operator and a value:
+///
+/// - operands are `field.<name>`, a field of the event or a [`LogContext`] pair, `message`,
+///   `target` and `level`,
+/// - `==` and `!=` compare strings, `=~` and `!~` match a regular expression, and `<`, `<=`,
+///   `>`, `>=` compare levels by severity, e.g. `level >= warn`,
+/// - values are quoted with `'` or `"`, or bare words such as `500` or `warn`.
+///
+/// `field.<name>` alone is true when the field is present. Comparisons combine with `&&`,
+/// `||`, `!` and parentheses, `&&` binding tighter than `||`. A comparison of a missing field
+/// is false whatever its operator, `field.tenant != 'acme'` included, negate the comparison
+/// instead to pass events without the field, e.g. `!(field.tenant == 'acme')`.
+///
+/// # Example
+/// ```
+/// use beaver_bootstrap::log::filter::FilterExpr;
+/// let filter: FilterExpr = "field.tenant == 'acme' || message =~ 'time(out)?'".parse().unwrap();
+/// assert_eq!(filter.to_string(), "field.tenant == 'acme' || message =~ 'time(out)?'");
+/// assert!("level >= loud".parse::<FilterExpr>().is_err());
+/// assert!("field.tenant ==".parse::<FilterExpr>().is_err());
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct FilterExpr {
+    source: String,
+    expr: Expr,
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Or(Box<Expr>, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Exists(String),
+    Compare(Operand, Comparison),
+}
+
+#[derive(Debug, Clone)]
+enum Operand {
+    Field(String),
+    Message,
+    Target,
+    Level,
+}
+
+#[derive(Debug, Clone)]
+enum Comparison {
+    Eq(String),
+    Ne(String),
+    Matches(Regex),
+    NotMatches(Regex),
+    /// the severity of the level of the event against a level, e.g. `Greater` for `>`.
+    Severity(std::cmp::Ordering, bool, Level),
+}
+
+#[derive(Debug)]
+pub struct ParseFilterError {
+    filter: String,
+    reason: String,
+}
+
+impl fmt::Display for ParseFilterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid filter {:?}: {}", self.filter, self.reason)
+    }
+}
+
+impl std::error::Error for ParseFilterError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Word(String),
+    Quoted(String),
+    Op(&'static str),
+}
+
+const OPERATORS: [&str; 13] = [
+    "==", "!=", "=~", "!~", "<=", ">=", "&&", "||", "<", ">", "!", "(", ")",
+];
+
+fn tokenize(s: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = s.trim_start();
+    while let Some(c) = rest.chars().next() {
+        if let Some(op) = OPERATORS.iter().find(|x| rest.starts_with(**x)) {
+            tokens.push(Token::Op(op));
+            rest = &rest[op.len()..];
+        } else if c == '\'' || c == '"' {
+            let mut value = String::new();
+            let mut chars = rest[1..].char_indices();
+            let end = loop {
+                match chars.next() {
+                    Some((i, x)) if x == c => break i + 2,
+                    Some((_, '\\')) => match chars.next() {
+                        Some((_, x)) => value.push(x),
+                        None => return Err("unterminated string".to_string()),
+                    },
+                    Some((_, x)) => value.push(x),
+                    None => return Err("unterminated string".to_string()),
+                }
+            };
+            tokens.push(Token::Quoted(value));
+            rest = &rest[end..];
+        } else if is_word_char(c) {
+            let end = rest.find(|x| !is_word_char(x)).unwrap_or(rest.len());
+            tokens.push(Token::Word(rest[..end].to_string()));
+            rest = &rest[end..];
+        } else {
+            return Err(format!("unexpected {:?}", c));
+        }
+        rest = rest.trim_start();
+    }
+    Ok(tokens)
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '.' | ':' | '-')
+}
+
+/// recursive descent over the tokens, `||` of `&&` of unary expressions.
+struct Parser {
+    tokens: std::iter::Peekable<std::vec::IntoIter<Token>>,
+}
+
+impl Parser {
+    fn next_if_op(&mut self, op: &'static str) -> bool {
+        self.tokens.next_if_eq(&Token::Op(op)).is_some()
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut expr = self.and()?;
+        while self.next_if_op("||") {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut expr = self.unary()?;
+        while self.next_if_op("&&") {
+            expr = Expr::And(Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.next_if_op("!") {
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        if self.next_if_op("(") {
+            let expr = self.or()?;
+            if !self.next_if_op(")") {
+                return Err("missing )".to_string());
+            }
+            return Ok(expr);
+        }
+        let operand = match self.tokens.next() {
+            Some(Token::Word(word)) => match word.as_str() {
+                "message" => Operand::Message,
+                "target" => Operand::Target,
+                "level" => Operand::Level,
+                word => match word.strip_prefix("field.") {
+                    Some(name) if !name.is_empty() => Operand::Field(name.to_string()),
+                    _ => return Err(format!("unknown operand {}", word)),
+                },
+            },
+            Some(token) => return Err(format!("expected an operand, found {}", token)),
+            None => return Err("expected an operand".to_string()),
+        };
+        let op = match self.tokens.peek() {
+            Some(Token::Op(op)) if !matches!(*op, "&&" | "||" | "(" | ")" | "!") => *op,
+            _ => {
+                return match operand {
+                    Operand::Field(name) => Ok(Expr::Exists(name)),
+                    _ => Err("expected an operator".to_string()),
+                };
+            }
+        };
+        self.tokens.next();
+        let value = match self.tokens.next() {
+            Some(Token::Word(x)) | Some(Token::Quoted(x)) => x,
+            Some(token) => return Err(format!("expected a value after {}, found {}", op, token)),
+            None => return Err(format!("expected a value after {}", op)),
+        };
+        let regex = |value: &str| Regex::new(value).map_err(|e| e.to_string());
+        let comparison = match op {
+            "==" => Comparison::Eq(value),
+            "!=" => Comparison::Ne(value),
+            "=~" => Comparison::Matches(regex(&value)?),
+            "!~" => Comparison::NotMatches(regex(&value)?),
+            op => {
+                if !matches!(operand, Operand::Level) {
+                    return Err(format!("{} compares levels only", op));
+                }
+                let level: Level = value
+                    .parse()
+                    .map_err(|_| format!("unknown level {}", value))?;
+                let (ordering, or_equal) = match op {
+                    "<" => (std::cmp::Ordering::Less, false),
+                    "<=" => (std::cmp::Ordering::Less, true),
+                    ">" => (std::cmp::Ordering::Greater, false),
+                    _ => (std::cmp::Ordering::Greater, true),
+                };
+                Comparison::Severity(ordering, or_equal, level)
+            }
+        };
+        Ok(Expr::Compare(operand, comparison))
+    }
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Word(x) => f.write_str(x),
+            Token::Quoted(x) => write!(f, "{:?}", x),
+            Token::Op(x) => f.write_str(x),
+        }
+    }
+}
+
+impl FromStr for FilterExpr {
+    type Err = ParseFilterError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = |reason: String| ParseFilterError {
+            filter: s.to_string(),
+            reason,
+        };
+        let mut parser = Parser {
+            tokens: tokenize(s).map_err(error)?.into_iter().peekable(),
+        };
+        let expr = parser.or().map_err(error)?;
+        if let Some(token) = parser.tokens.next() {
+            return Err(error(format!("unexpected {}", token)));
+        }
+        Ok(FilterExpr {
+            source: s.to_string(),
+            expr,
+        })
+    }
+}
+
+impl TryFrom<String> for FilterExpr {
+    type Error = ParseFilterError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<FilterExpr> for String {
+    fn from(value: FilterExpr) -> Self {
+        value.source
+    }
+}
+
+impl fmt::Display for FilterExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+/// the values of the fields of an event, formatted like the text appenders do.
+#[derive(Default)]
+struct FieldValues(Vec<(&'static str, String)>);
+
+impl Visit for FieldValues {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.push((field.name(), value.to_string()));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0.push((field.name(), format!("{:?}", value)));
+    }
+}
+
+/// the event an expression is evaluated against.
+struct EventValues<'a> {
+    metadata: &'a Metadata<'a>,
+    fields: FieldValues,
+    context: Vec<(String, String)>,
+}
+
+impl EventValues<'_> {
+    fn get(&self, operand: &Operand) -> Option<&str> {
+        match operand {
+            Operand::Field(name) => self.field(name),
+            Operand::Message => self.field("message"),
+            Operand::Target => Some(self.metadata.target()),
+            Operand::Level => Some(self.metadata.level().as_str()),
+        }
+    }
+
+    /// a field of the event, or the innermost context pair.
+    fn field(&self, name: &str) -> Option<&str> {
+        self.fields
+            .0
+            .iter()
+            .find(|(k, _)| *k == name)
+            .map(|(_, v)| v.as_str())
+            .or_else(|| {
+                self.context
+                    .iter()
+                    .find(|(k, _)| k == name)
+                    .map(|(_, v)| v.as_str())
+            })
+    }
+}
+
+/// severity of `level`, higher for more severe levels.
+fn severity(level: &tracing::Level) -> u8 {
+    match *level {
+        tracing::Level::TRACE => 0,
+        tracing::Level::DEBUG => 1,
+        tracing::Level::INFO => 2,
+        tracing::Level::WARN => 3,
+        tracing::Level::ERROR => 4,
+    }
+}
+
+impl Expr {
+    fn eval(&self, event: &EventValues<'_>) -> bool {
+        match self {
+            Expr::Or(a, b) => a.eval(event) || b.eval(event),
+            Expr::And(a, b) => a.eval(event) && b.eval(event),
+            Expr::Not(a) => !a.eval(event),
+            Expr::Exists(name) => event.field(name).is_some(),
+            Expr::Compare(Operand::Level, Comparison::Severity(ordering, or_equal, level)) => {
+                let Some(level) = level.as_tracing_level() else {
+                    // nothing is as severe as off
+                    return *ordering == std::cmp::Ordering::Less;
+                };
+                let actual = severity(event.metadata.level()).cmp(&severity(&level));
+                actual == *ordering || (*or_equal && actual.is_eq())
+            }
+            Expr::Compare(operand, comparison) => {
+                let value = event.get(operand);
+                match comparison {
+                    Comparison::Eq(x) => value == Some(x.as_str()),
+                    Comparison::Ne(x) => value.is_some_and(|v| v != x),
+                    Comparison::Matches(x) => value.is_some_and(|v| x.is_match(v)),
+                    Comparison::NotMatches(x) => value.is_some_and(|v| !x.is_match(v)),
+                    Comparison::Severity(..) => false,
+                }
+            }
+        }
+    }
+}
+
+impl FilterExpr {
+    /// whether `event` passes the expression.
+    pub fn matches(&self, event: &Event<'_>) -> bool {
+        let mut fields = FieldValues::default();
+        event.record(&mut fields);
+        let values = EventValues {
+            metadata: event.metadata(),
+            fields,
+            context: LogContext::current(),
+        };
+        self.expr.eval(&values)
+    }
+}
+
+/// the expression decides per event, as fields are unknown before the event is recorded.
+impl<S> Filter<S> for FilterExpr {
+    fn enabled(&self, _meta: &Metadata<'_>, _cx: &Context<'_, S>) -> bool {
+        true
+    }
+
+    fn event_enabled(&self, event: &Event<'_>, _cx: &Context<'_, S>) -> bool {
+        self.matches(event)
+    }
+}
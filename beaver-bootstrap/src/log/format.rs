@@ -0,0 +1,353 @@
+use std::{
+    collections::BTreeMap,
+    fmt,
+    sync::Arc,
+    time::{Duration, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::{
+    Layer,
+    fmt::{
+        FmtContext, FormatEvent, FormatFields, MakeWriter,
+        format::{JsonFields, Writer},
+        time::FormatTime,
+    },
+    registry::LookupSpan,
+};
+
+use super::{context::LogContext, dedup::DedupFormat, json::FastJsonLayer};
+use crate::clock::{Clock, ManualClock, SystemClock};
+
+/// timestamp of every line of the deterministic layers since the epoch,
+/// 2000-01-01T00:00:00Z.
+pub const DETERMINISTIC_TIME: Duration = Duration::from_secs(946_684_800);
+
+/// LogFormat is the output format of an appender.
+#[derive(Debug, Default, Copy, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LogFormat {
+    /// human readable text.
+    #[default]
+    Text,
+    /// one JSON object per line, event fields flattened to the top level.
+    Json,
+    /// the lines of `json` serialized without allocating per event, for high throughput,
+    /// but without the fields of spans nor collapsed repeats, see
+    /// [`FastJsonLayer`](super::json::FastJsonLayer).
+    FastJson,
+}
+
+/// FieldsFormat appends static `key=value` fields to every event rendered by `inner`.
+///
+/// It is used for `[logging.fields]` and the per-appender `fields` tables, so values such as
+/// service, env or region are present on each line without touching call sites. The pairs of
+/// the current [`LogContext`] are appended after the static fields. For JSON output they are
+/// added as top-level string members.
+pub struct FieldsFormat<F> {
+    inner: F,
+    fields: Vec<(String, String)>,
+    format: LogFormat,
+    /// whether the top-level members of JSON lines are sorted by key.
+    sort_keys: bool,
+}
+
+impl<F> FieldsFormat<F> {
+    pub fn new(inner: F, fields: Vec<(String, String)>, format: LogFormat) -> Self {
+        Self {
+            inner,
+            fields,
+            format,
+            sort_keys: false,
+        }
+    }
+
+    /// whether to sort the top-level members of JSON lines by key, rather than write them in
+    /// the order of the event, its fields then the static and context ones.
+    pub fn with_sorted_keys(mut self, sort_keys: bool) -> Self {
+        self.sort_keys = sort_keys;
+        self
+    }
+}
+
+impl<S, N, F> FormatEvent<S, N> for FieldsFormat<F>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+    F: FormatEvent<S, N>,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let context = LogContext::current();
+        if self.fields.is_empty() && context.is_empty() && !self.sort_keys {
+            return self.inner.format_event(ctx, writer, event);
+        }
+        let mut buf = String::new();
+        self.inner.format_event(ctx, Writer::new(&mut buf), event)?;
+        let line = buf.strip_suffix('\n').unwrap_or(&buf);
+        let extra = self.fields.iter().chain(context.iter());
+        match self.format {
+            LogFormat::Text => {
+                writer.write_str(line)?;
+                for (key, value) in extra {
+                    write!(writer, " {}={}", key, value)?;
+                }
+            }
+            LogFormat::Json | LogFormat::FastJson => {
+                let Some(object) = line.strip_suffix('}') else {
+                    return writer.write_str(&buf);
+                };
+                let mut object = object.to_string();
+                for (key, value) in extra {
+                    object.push_str(&format!(",{}:{}", json_string(key), json_string(value)));
+                }
+                object.push('}');
+                if self.sort_keys {
+                    object = sorted_json(&object);
+                }
+                writer.write_str(&object)?;
+            }
+        }
+        writeln!(writer)
+    }
+}
+
+fn json_string(value: &str) -> String {
+    serde_json::to_string(value).unwrap_or_else(|_| "\"\"".to_string())
+}
+
+/// the JSON object `object` with its top-level members sorted by key.
+fn sorted_json(object: &str) -> String {
+    serde_json::from_str::<BTreeMap<String, serde_json::Value>>(object)
+        .ok()
+        .and_then(|x| serde_json::to_string(&x).ok())
+        .unwrap_or_else(|| object.to_string())
+}
+
+/// a clock frozen at [`DETERMINISTIC_TIME`].
+pub fn deterministic_clock() -> Arc<dyn Clock> {
+    Arc::new(ManualClock::new(UNIX_EPOCH + DETERMINISTIC_TIME))
+}
+
+/// ClockTime writes the timestamps of log lines from a [`Clock`], as RFC 3339 UTC with
+/// microseconds like the default timer of `tracing_subscriber`.
+///
+/// A [`ManualClock`](crate::clock::ManualClock) makes the timestamps reproducible, so log
+/// output can be compared with golden files.
+///
+/// # Example
+/// ```
+/// use std::{sync::Arc, time::{Duration, UNIX_EPOCH}};
+/// use beaver_bootstrap::{clock::ManualClock, log::format::ClockTime};
+/// let clock = ManualClock::new(UNIX_EPOCH + Duration::from_micros(1_700_000_000_123_456));
+/// assert_eq!(ClockTime::new(Arc::new(clock)).timestamp(), "2023-11-14T22:13:20.123456Z");
+/// ```
+#[derive(Clone)]
+pub struct ClockTime {
+    clock: Arc<dyn Clock>,
+}
+
+impl fmt::Debug for ClockTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClockTime").finish_non_exhaustive()
+    }
+}
+
+impl ClockTime {
+    pub fn new(clock: Arc<dyn Clock>) -> Self {
+        Self { clock }
+    }
+
+    /// current time of the clock, formatted like in log lines.
+    pub fn timestamp(&self) -> String {
+        let mut timestamp = String::with_capacity(27);
+        // writing to a String cannot fail
+        let _ = self.write_timestamp(&mut timestamp);
+        timestamp
+    }
+
+    /// write the current time of the clock like [`ClockTime::timestamp`], without
+    /// allocating.
+    pub fn write_timestamp(&self, w: &mut impl fmt::Write) -> fmt::Result {
+        let since_epoch = self
+            .clock
+            .now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let secs = since_epoch.as_secs();
+        let (year, month, day) = civil_from_days((secs / 86400) as i64);
+        let time = secs % 86400;
+        write!(
+            w,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:06}Z",
+            year,
+            month,
+            day,
+            time / 3600,
+            time / 60 % 60,
+            time % 60,
+            since_epoch.subsec_micros()
+        )
+    }
+}
+
+impl FormatTime for ClockTime {
+    fn format_time(&self, w: &mut Writer<'_>) -> fmt::Result {
+        self.write_timestamp(w)
+    }
+}
+
+/// year, month and day of `days` since 1970-01-01 in the proleptic Gregorian calendar.
+pub(crate) fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// build the fmt layer of one appender.
+///
+/// Every appender gets its own formatter, so a single event can be rendered as text on the
+/// console and as JSON in a file at the same time.
+///
+/// # Example
+/// ```
+/// use beaver_bootstrap::log::format::{LogFormat, fmt_layer};
+/// use tracing_subscriber::layer::SubscriberExt;
+/// let subscriber = tracing_subscriber::registry()
+///     .with(fmt_layer(LogFormat::Text, true, vec![], std::io::stdout))
+///     .with(fmt_layer(LogFormat::Json, false, vec![], std::io::stderr));
+/// tracing::subscriber::with_default(subscriber, || tracing::info!(user = "alice", "login"));
+/// ```
+pub fn fmt_layer<S, W>(
+    format: LogFormat,
+    ansi: bool,
+    fields: Vec<(String, String)>,
+    writer: W,
+) -> Box<dyn Layer<S> + Send + Sync>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    fmt_layer_with_clock(format, ansi, fields, None, Arc::new(SystemClock), writer)
+}
+
+/// like [`fmt_layer`], with the timestamps read from `clock` and the repeats of an event
+/// within `dedup_window` collapsed, see [`DedupFormat`].
+pub fn fmt_layer_with_clock<S, W>(
+    format: LogFormat,
+    ansi: bool,
+    fields: Vec<(String, String)>,
+    dedup_window: Option<Duration>,
+    clock: Arc<dyn Clock>,
+    writer: W,
+) -> Box<dyn Layer<S> + Send + Sync>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    build_fmt_layer(format, ansi, fields, dedup_window, clock, false, writer)
+}
+
+/// like [`fmt_layer`], rendering the same lines on every run for golden-file tests: every
+/// timestamp is [`DETERMINISTIC_TIME`], without ANSI colors nor collapsed repeats, and the
+/// members of JSON lines are sorted by key, `fast_json` ones rendered like `json` ones.
+/// Thread ids are never written.
+///
+/// # Example
+/// ```
+/// use beaver_bootstrap::log::format::{LogFormat, deterministic_fmt_layer};
+/// use tracing_subscriber::layer::SubscriberExt;
+/// let layer = deterministic_fmt_layer(LogFormat::Json, vec![], std::io::stdout);
+/// let subscriber = tracing_subscriber::registry().with(layer);
+/// // {"fields":..,"level":"INFO","message":"login","target":..,"timestamp":"2000-01-01T..","user":"alice"}
+/// tracing::subscriber::with_default(subscriber, || tracing::info!(user = "alice", "login"));
+/// ```
+pub fn deterministic_fmt_layer<S, W>(
+    format: LogFormat,
+    fields: Vec<(String, String)>,
+    writer: W,
+) -> Box<dyn Layer<S> + Send + Sync>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    build_fmt_layer(
+        format,
+        false,
+        fields,
+        None,
+        deterministic_clock(),
+        true,
+        writer,
+    )
+}
+
+fn build_fmt_layer<S, W>(
+    format: LogFormat,
+    ansi: bool,
+    fields: Vec<(String, String)>,
+    dedup_window: Option<Duration>,
+    clock: Arc<dyn Clock>,
+    sort_keys: bool,
+    writer: W,
+) -> Box<dyn Layer<S> + Send + Sync>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    let timer = ClockTime::new(clock.clone());
+    match format {
+        // sorting members and collapsing repeats need the serde formatter
+        LogFormat::FastJson if !sort_keys && dedup_window.is_none() => {
+            FastJsonLayer::new(fields, clock, writer).boxed()
+        }
+        LogFormat::Text => tracing_subscriber::fmt::layer()
+            .with_ansi(ansi)
+            .event_format(DedupFormat::new(
+                FieldsFormat::new(
+                    tracing_subscriber::fmt::format()
+                        .with_ansi(ansi)
+                        .with_timer(timer),
+                    fields,
+                    format,
+                )
+                .with_sorted_keys(sort_keys),
+                dedup_window,
+                clock,
+                format,
+            ))
+            .with_writer(writer)
+            .boxed(),
+        LogFormat::Json | LogFormat::FastJson => tracing_subscriber::fmt::layer()
+            .with_ansi(false)
+            .fmt_fields(JsonFields::new())
+            .event_format(DedupFormat::new(
+                FieldsFormat::new(
+                    tracing_subscriber::fmt::format()
+                        .json()
+                        .flatten_event(true)
+                        .with_timer(timer),
+                    fields,
+                    format,
+                )
+                .with_sorted_keys(sort_keys),
+                dedup_window,
+                clock,
+                format,
+            ))
+            .with_writer(writer)
+            .boxed(),
+    }
+}
@@ -0,0 +1,237 @@
+use std::{
+    cell::RefCell,
+    fmt::{self, Write as _},
+    io::Write as _,
+    sync::Arc,
+};
+
+use tracing::{
+    Event, Subscriber,
+    field::{Field, Visit},
+};
+use tracing_subscriber::{Layer, fmt::MakeWriter, layer::Context};
+
+use super::{context::LogContext, format::ClockTime};
+use crate::clock::Clock;
+
+/// capacity beyond which the buffer of a thread is released after an event, so one huge
+/// event does not pin its memory.
+const MAX_RETAINED_CAPACITY: usize = 64 * 1024;
+
+thread_local! {
+    static BUFFER: RefCell<String> = const { RefCell::new(String::new()) };
+}
+
+/// FastJsonLayer writes events as JSON lines like the `json` format, for the `fast_json`
+/// one: the line is serialized into a buffer reused by every event of the thread rather than
+/// built from intermediate strings, so formatting an event allocates nothing once the buffer
+/// grew. A non blocking appender still copies the line to hand it to its worker.
+///
+/// The static fields are rendered once, when the layer is built, and the keys of the fields
+/// of events, static strings of their callsites, are escaped as written. The fields of spans
+/// are not written, nor repeats collapsed: [`LogContext`] pairs are, after the static fields.
+///
+/// # Example
+/// ```
+/// use std::sync::Arc;
+/// use beaver_bootstrap::{clock::SystemClock, log::json::FastJsonLayer};
+/// use tracing_subscriber::layer::SubscriberExt;
+/// let fields = vec![("service".to_string(), "billing".to_string())];
+/// let layer = FastJsonLayer::new(fields, Arc::new(SystemClock), std::io::stdout);
+/// let subscriber = tracing_subscriber::registry().with(layer);
+/// // {"timestamp":..,"level":"INFO","message":"login","user":"alice","target":..,"service":"billing"}
+/// tracing::subscriber::with_default(subscriber, || tracing::info!(user = "alice", "login"));
+/// ```
+pub struct FastJsonLayer<W> {
+    /// the static fields, as `,"key":"value"` members.
+    fields: String,
+    timer: ClockTime,
+    writer: W,
+}
+
+impl<W> fmt::Debug for FastJsonLayer<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FastJsonLayer")
+            .field("fields", &self.fields)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<W> FastJsonLayer<W> {
+    pub fn new(fields: Vec<(String, String)>, clock: Arc<dyn Clock>, writer: W) -> Self {
+        let mut members = String::new();
+        for (key, value) in &fields {
+            // writing to a String cannot fail
+            let _ = write_member(&mut members, key, value);
+        }
+        Self {
+            fields: members,
+            timer: ClockTime::new(clock),
+            writer,
+        }
+    }
+
+    /// serialize `event` into `buf` as one line.
+    fn format(&self, buf: &mut String, event: &Event<'_>) -> fmt::Result {
+        let metadata = event.metadata();
+        buf.push_str("{\"timestamp\":\"");
+        self.timer.write_timestamp(buf)?;
+        buf.push_str("\",\"level\":\"");
+        buf.push_str(metadata.level().as_str());
+        buf.push('"');
+        let mut visitor = JsonVisitor {
+            buf: &mut *buf,
+            result: Ok(()),
+        };
+        event.record(&mut visitor);
+        visitor.result?;
+        buf.push_str(",\"target\":");
+        write_string(buf, metadata.target())?;
+        buf.push_str(&self.fields);
+        let mut result = Ok(());
+        LogContext::for_each(|key, value| {
+            result = result.and_then(|_| write_member(buf, key, value));
+        });
+        result?;
+        buf.push_str("}\n");
+        Ok(())
+    }
+}
+
+impl<S, W> Layer<S> for FastJsonLayer<W>
+where
+    S: Subscriber,
+    W: for<'w> MakeWriter<'w> + 'static,
+{
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let write = |buf: &mut String| {
+            if self.format(buf, event).is_ok() {
+                let mut writer = self.writer.make_writer_for(event.metadata());
+                let _ = writer.write_all(buf.as_bytes());
+            }
+        };
+        BUFFER.with(|buffer| match buffer.try_borrow_mut() {
+            Ok(mut buf) => {
+                buf.clear();
+                write(&mut buf);
+                if buf.capacity() > MAX_RETAINED_CAPACITY {
+                    *buf = String::new();
+                }
+            }
+            // an event logged while writing one, e.g. by the writer
+            Err(_) => write(&mut String::new()),
+        });
+    }
+}
+
+/// JsonVisitor writes the fields of an event as `,"key":value` members.
+struct JsonVisitor<'a> {
+    buf: &'a mut String,
+    result: fmt::Result,
+}
+
+impl JsonVisitor<'_> {
+    fn member(&mut self, field: &Field, value: impl FnOnce(&mut String) -> fmt::Result) {
+        if self.result.is_err() {
+            return;
+        }
+        self.buf.push(',');
+        self.result = write_string(self.buf, field.name()).and_then(|_| {
+            self.buf.push(':');
+            value(self.buf)
+        });
+    }
+}
+
+impl Visit for JsonVisitor<'_> {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.member(field, |buf| {
+            if value.is_finite() {
+                // the shortest representation keeping the decimal point, like serde_json
+                write!(buf, "{:?}", value)
+            } else {
+                buf.push_str("null");
+                Ok(())
+            }
+        });
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.member(field, |buf| write!(buf, "{}", value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.member(field, |buf| write!(buf, "{}", value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.member(field, |buf| write!(buf, "{}", value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.member(field, |buf| write_string(buf, value));
+    }
+
+    fn record_error(&mut self, field: &Field, value: &(dyn std::error::Error + 'static)) {
+        self.member(field, |buf| {
+            buf.push('"');
+            write!(Escaped(buf), "{}", value)?;
+            buf.push('"');
+            Ok(())
+        });
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.member(field, |buf| {
+            buf.push('"');
+            write!(Escaped(buf), "{:?}", value)?;
+            buf.push('"');
+            Ok(())
+        });
+    }
+}
+
+/// `,"key":"value"`.
+fn write_member(buf: &mut String, key: &str, value: &str) -> fmt::Result {
+    buf.push(',');
+    write_string(buf, key)?;
+    buf.push(':');
+    write_string(buf, value)
+}
+
+/// `value` as a JSON string.
+fn write_string(buf: &mut String, value: &str) -> fmt::Result {
+    buf.push('"');
+    Escaped(buf).write_str(value)?;
+    buf.push('"');
+    Ok(())
+}
+
+/// Escaped escapes what is written to it as the content of a JSON string.
+struct Escaped<'a>(&'a mut String);
+
+impl fmt::Write for Escaped<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut start = 0;
+        for (index, byte) in s.bytes().enumerate() {
+            let escape = match byte {
+                b'"' => "\\\"",
+                b'\\' => "\\\\",
+                b'\n' => "\\n",
+                b'\r' => "\\r",
+                b'\t' => "\\t",
+                0..=0x1f => "",
+                _ => continue,
+            };
+            self.0.push_str(&s[start..index]);
+            if escape.is_empty() {
+                write!(self.0, "\\u{:04x}", byte)?;
+            } else {
+                self.0.push_str(escape);
+            }
+            start = index + 1;
+        }
+        self.0.push_str(&s[start..]);
+        Ok(())
+    }
+}
@@ -0,0 +1,189 @@
+use std::{
+    env, fmt,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{config::Config, error::BootstrapError};
+
+/// LogFolderStrategy is where the appenders without `file_dir` write, unless a
+/// [`LogBaseDir`] is set, chosen by `logging.default_folder` or by the `log_folder` of the
+/// bootstrap builder, the config winning:
+///
+/// ```toml
+/// [logging]
+/// default_folder = "xdg_state"
+/// # or default_folder = { custom = "/var/log/billing" }
+/// ```
+///
+/// # Example
+/// ```
+/// use std::path::Path;
+/// use beaver_bootstrap::log::location::LogFolderStrategy;
+/// let strategy = LogFolderStrategy::Custom("/var/log/billing".into());
+/// assert_eq!(strategy.folder(), Path::new("/var/log/billing"));
+/// assert!(LogFolderStrategy::Cwd.folder().ends_with("logs"));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogFolderStrategy {
+    /// `logs` next to the executable.
+    #[default]
+    ExeRelative,
+    /// `logs` in the working directory.
+    Cwd,
+    /// `$XDG_STATE_HOME/<executable>/logs`, `$XDG_STATE_HOME` being `~/.local/state` unless
+    /// set, for desktop and per-user services.
+    XdgState,
+    /// a folder of its own.
+    Custom(PathBuf),
+}
+
+impl LogFolderStrategy {
+    /// the folder of the strategy, `./logs` when the executable or the home directory it
+    /// needs is unknown.
+    pub fn folder(&self) -> PathBuf {
+        let folder = match self {
+            LogFolderStrategy::ExeRelative => env::current_exe()
+                .ok()
+                .and_then(|x| x.parent().map(|x| x.join("logs"))),
+            LogFolderStrategy::Cwd => env::current_dir().ok().map(|x| x.join("logs")),
+            LogFolderStrategy::XdgState => {
+                let state_home = env::var_os("XDG_STATE_HOME")
+                    .filter(|x| !x.is_empty())
+                    .map(PathBuf::from)
+                    .or_else(|| env::var_os("HOME").map(|x| PathBuf::from(x).join(".local/state")));
+                let app = env::current_exe()
+                    .ok()
+                    .and_then(|x| x.file_stem().map(|x| x.to_os_string()));
+                state_home.zip(app).map(|(x, app)| x.join(app).join("logs"))
+            }
+            LogFolderStrategy::Custom(folder) => Some(folder.clone()),
+        };
+        folder.unwrap_or_else(|| PathBuf::from("./logs"))
+    }
+}
+
+impl fmt::Display for LogFolderStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LogFolderStrategy::ExeRelative => f.write_str("exe_relative"),
+            LogFolderStrategy::Cwd => f.write_str("cwd"),
+            LogFolderStrategy::XdgState => f.write_str("xdg_state"),
+            LogFolderStrategy::Custom(folder) => write!(f, "custom {}", folder.display()),
+        }
+    }
+}
+
+/// key of the directory the logs are written under.
+pub const LOG_BASE_DIR_KEY: &str = "app.log_base_dir";
+/// key of the directory of the application data, its logs written to `logs` under it unless
+/// `app.log_base_dir` is set.
+pub const DATA_DIR_KEY: &str = "app.data_dir";
+
+/// LogBaseDir is the directory the relative `file_dir` values of the appenders resolve
+/// against, instead of the working directory, which is rarely the one expected in a
+/// container:
+///
+/// ```toml
+/// [app]
+/// data_dir = "/var/lib/billing"
+///
+/// [[logging.file_appenders]]
+/// file_dir = "audit"           # /var/lib/billing/audit
+/// ```
+///
+/// `app.log_base_dir` wins over `app.data_dir`. Appenders without `file_dir` write to
+/// `app.log_base_dir`, or to `logs` under `app.data_dir`. Both must be absolute.
+///
+/// # Example
+/// ```
+/// use std::path::Path;
+/// use beaver_bootstrap::{config::Config, log::location::LogBaseDir};
+/// let inner = config::Config::builder()
+///     .set_override("app.data_dir", "/var/lib/billing")
+///     .unwrap();
+/// let base_dir = LogBaseDir::from_config(&Config::new(inner.build().unwrap()))
+///     .unwrap()
+///     .unwrap();
+/// assert_eq!(base_dir.resolve("audit"), Path::new("/var/lib/billing/audit"));
+/// assert_eq!(base_dir.logs_dir(), Path::new("/var/lib/billing/logs"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogBaseDir {
+    /// the key the directory was read from.
+    key: &'static str,
+    path: PathBuf,
+}
+
+impl LogBaseDir {
+    /// the directory of `app.log_base_dir` or `app.data_dir`, `None` when neither is set.
+    pub fn from_config(config: &Config) -> Result<Option<Self>, BootstrapError> {
+        for key in [LOG_BASE_DIR_KEY, DATA_DIR_KEY] {
+            let Ok(path) = config.get_at::<String>(key) else {
+                continue;
+            };
+            let path = PathBuf::from(path);
+            if !path.is_absolute() {
+                return Err(BootstrapError::InvalidConfigValueError(format!(
+                    "{}={}, expected an absolute path",
+                    key,
+                    path.display()
+                )));
+            }
+            return Ok(Some(Self { key, path }));
+        }
+        Ok(None)
+    }
+
+    pub fn key(&self) -> &str {
+        self.key
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// the directory of appenders without `file_dir`.
+    pub fn logs_dir(&self) -> PathBuf {
+        match self.key {
+            DATA_DIR_KEY => self.path.join("logs"),
+            _ => self.path.clone(),
+        }
+    }
+
+    /// `dir` below the base directory, unchanged when absolute.
+    pub fn resolve(&self, dir: &str) -> PathBuf {
+        self.path.join(dir)
+    }
+}
+
+/// how the directory of an appender was found, reported at startup.
+pub(crate) fn describe_dir(
+    file_dir: Option<&str>,
+    base_dir: Option<&LogBaseDir>,
+    strategy: &LogFolderStrategy,
+) -> String {
+    match (file_dir, base_dir) {
+        (Some(dir), _) if Path::new(dir).is_absolute() => "file_dir is absolute".to_string(),
+        (Some(dir), Some(base)) => format!(
+            "file_dir {} is relative to {}={}",
+            dir,
+            base.key,
+            base.path.display()
+        ),
+        (Some(dir), None) => format!(
+            "file_dir {} is relative to the working directory {}, set {} or {} to resolve it \
+             elsewhere",
+            dir,
+            env::current_dir()
+                .map(|x| x.display().to_string())
+                .unwrap_or_else(|_| "?".to_string()),
+            LOG_BASE_DIR_KEY,
+            DATA_DIR_KEY
+        ),
+        (None, Some(base)) => format!("no file_dir, logs of {}={}", base.key, base.path.display()),
+        (None, None) => format!("no file_dir, default folder {}", strategy),
+    }
+}
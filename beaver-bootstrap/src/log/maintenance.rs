@@ -0,0 +1,230 @@
+use std::{
+    fs,
+    sync::{
+        Arc,
+        mpsc::{self, RecvTimeoutError, Sender},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{LoggingManager, reopen::ReopenableFile, retention::RetentionPolicy};
+use crate::{clock::Clock, error::BootstrapError, random::RngProvider, serde::duration_opt};
+
+/// time between two checks of the file appenders unless `interval` is set.
+pub const DEFAULT_MAINTENANCE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// RotationMode is where the size of a file appender is checked against `file_max_size`.
+#[derive(Debug, Default, Copy, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RotationMode {
+    /// by the write crossing the size, which renames the files before it returns.
+    #[default]
+    OnWrite,
+    /// by the maintenance thread every `interval`, files may grow past their size until then.
+    Scheduled,
+}
+
+/// LogMaintenanceConfig schedules the upkeep of the file appenders off their write path, see
+/// `[logging.maintenance]`:
+///
+/// ```toml
+/// [logging.maintenance]
+/// interval = "1m"
+/// jitter = 0.1
+/// rotation = "scheduled"
+/// flush_interval = "1s"
+/// ```
+///
+/// Every `interval`, spread by up to `jitter` of it either way so the instances of a host do
+/// not rotate at once, the maintenance thread rotates the files past their `file_max_size` in
+/// scheduled mode, then enforces their retention. With `flush_interval`, it also flushes every
+/// appender on its own period, bounding the events a crash loses.
+///
+/// Per-tenant appenders keep rotating on write, daily rollovers happen on the first write of
+/// a day.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogMaintenanceConfig {
+    /// time between two checks, [`DEFAULT_MAINTENANCE_INTERVAL`] by default.
+    #[serde(deserialize_with = "duration_opt")]
+    interval: Option<Duration>,
+    /// ratio of `interval` the checks are randomly spread by, in `[0, 1]`.
+    jitter: f64,
+    rotation: RotationMode,
+    /// time between two flushes of the appenders, not flushed periodically by default.
+    #[serde(deserialize_with = "duration_opt")]
+    flush_interval: Option<Duration>,
+}
+
+impl Default for LogMaintenanceConfig {
+    fn default() -> Self {
+        Self {
+            interval: None,
+            jitter: 0.1,
+            rotation: RotationMode::OnWrite,
+            flush_interval: None,
+        }
+    }
+}
+
+impl LogMaintenanceConfig {
+    pub fn interval(&self) -> Duration {
+        self.interval.unwrap_or(DEFAULT_MAINTENANCE_INTERVAL)
+    }
+
+    pub fn jitter(&self) -> f64 {
+        self.jitter
+    }
+
+    pub fn rotation(&self) -> RotationMode {
+        self.rotation
+    }
+
+    pub fn flush_interval(&self) -> Option<Duration> {
+        self.flush_interval
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), BootstrapError> {
+        if self.interval.is_some_and(|x| x.is_zero()) {
+            return Err(BootstrapError::InvalidConfigValueError(
+                "logging.maintenance.interval=0".to_string(),
+            ));
+        }
+        if self.flush_interval.is_some_and(|x| x.is_zero()) {
+            return Err(BootstrapError::InvalidConfigValueError(
+                "logging.maintenance.flush_interval=0".to_string(),
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.jitter) {
+            return Err(BootstrapError::InvalidConfigValueError(format!(
+                "logging.maintenance.jitter={}, expected a ratio in [0, 1]",
+                self.jitter
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// MaintainedFile is the active file of a file appender and its upkeep.
+#[derive(Clone)]
+pub struct MaintainedFile {
+    file: ReopenableFile,
+    retention: RetentionPolicy,
+    /// size rotating the file in scheduled mode.
+    max_size: Option<u64>,
+}
+
+impl MaintainedFile {
+    pub fn new(file: ReopenableFile, retention: RetentionPolicy) -> Self {
+        Self {
+            file,
+            retention,
+            max_size: None,
+        }
+    }
+
+    /// rotate the file once it reaches `max_size`, for a file opened without a size limit.
+    pub fn with_scheduled_rotation(mut self, max_size: u64) -> Self {
+        self.max_size = Some(max_size);
+        self
+    }
+
+    /// rotate the file when due, then enforce its retention.
+    pub fn maintain(&self, clock: &dyn Clock) {
+        let path = self.file.path();
+        if let Some(max_size) = self.max_size {
+            let size = fs::metadata(path).map(|x| x.len()).unwrap_or_default();
+            if size >= max_size
+                && let Err(e) = self.file.rotate()
+            {
+                tracing::warn!("unable to rotate {}: {}", path.display(), e);
+            }
+        }
+        if let Err(e) = self.retention.enforce_at(path, clock.now()) {
+            tracing::warn!(
+                "unable to enforce log retention of {}: {}",
+                path.display(),
+                e
+            );
+        }
+    }
+
+    fn is_idle(&self) -> bool {
+        self.max_size.is_none() && !self.retention.is_enabled()
+    }
+}
+
+/// LogMaintenance runs the checks of [`LogMaintenanceConfig`] on a background thread until
+/// dropped, so rotations, retention and flushes never run on the threads logging.
+pub struct LogMaintenance {
+    _stop: Sender<()>,
+}
+
+impl std::fmt::Debug for LogMaintenance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LogMaintenance").finish_non_exhaustive()
+    }
+}
+
+impl LogMaintenance {
+    /// start maintaining `files` and flushing the appenders of `manager`, `None` when there is
+    /// nothing to do.
+    pub fn start(
+        config: &LogMaintenanceConfig,
+        files: Vec<MaintainedFile>,
+        manager: LoggingManager,
+        clock: Arc<dyn Clock>,
+        rng: Arc<dyn RngProvider>,
+    ) -> Option<Self> {
+        let files: Vec<MaintainedFile> = files.into_iter().filter(|x| !x.is_idle()).collect();
+        let flush_interval = config.flush_interval();
+        if files.is_empty() && flush_interval.is_none() {
+            return None;
+        }
+        let interval = config.interval();
+        let jitter = config.jitter();
+        let (stop, rx) = mpsc::channel::<()>();
+        let spawned = thread::Builder::new()
+            .name("beaver-log-maintenance".to_string())
+            .spawn(move || {
+                let next_check = |now: Instant| now + rng.jitter(interval, jitter);
+                let start = clock.instant();
+                // retention is enforced once at startup
+                let mut check_at = match files.is_empty() {
+                    true => None,
+                    false => Some(start),
+                };
+                let mut flush_at = flush_interval.map(|x| start + x);
+                loop {
+                    let Some(due) = check_at.into_iter().chain(flush_at).min() else {
+                        return;
+                    };
+                    let timeout = due.saturating_duration_since(clock.instant());
+                    if let Ok(()) | Err(RecvTimeoutError::Disconnected) = clock.wait(&rx, timeout) {
+                        return;
+                    }
+                    let now = clock.instant();
+                    if check_at.is_some_and(|x| x <= now) {
+                        files.iter().for_each(|x| x.maintain(clock.as_ref()));
+                        check_at = Some(next_check(now));
+                    }
+                    if let (Some(at), Some(period)) =
+                        (flush_at.filter(|x| *x <= now), flush_interval)
+                    {
+                        manager.flush(period);
+                        flush_at = Some((at + period).max(now));
+                    }
+                }
+            });
+        match spawned {
+            Ok(_) => Some(Self { _stop: stop }),
+            Err(e) => {
+                tracing::warn!("unable to start the log maintenance thread: {}", e);
+                None
+            }
+        }
+    }
+}
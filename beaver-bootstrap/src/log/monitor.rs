@@ -0,0 +1,293 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{
+        Arc, Mutex, RwLock,
+        mpsc::{self, Sender},
+    },
+    thread,
+    time::{Duration, Instant, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::{Layer, layer::Context};
+
+use crate::{
+    clock::{Clock, SystemClock},
+    error::BootstrapError,
+    net::{HttpUrl, http_request},
+    serde::duration_opt,
+};
+
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// ErrorMonitorConfig configures error alerting, see `[logging.error_monitor]`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ErrorMonitorConfig {
+    enable: bool,
+    /// length of the sliding window, 60 seconds by default.
+    #[serde(deserialize_with = "duration_opt")]
+    window: Option<Duration>,
+    /// ERROR events of one target within the window firing an alert.
+    threshold: usize,
+    /// minimum time between two alerts of one target, the window by default.
+    #[serde(deserialize_with = "duration_opt")]
+    cooldown: Option<Duration>,
+    /// `http://` url receiving every alert as a JSON POST.
+    webhook: Option<String>,
+}
+
+impl Default for ErrorMonitorConfig {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            window: None,
+            threshold: 10,
+            cooldown: None,
+            webhook: None,
+        }
+    }
+}
+
+impl ErrorMonitorConfig {
+    pub fn enable(&self) -> bool {
+        self.enable
+    }
+
+    pub fn window(&self) -> Duration {
+        self.window.unwrap_or(Duration::from_secs(60))
+    }
+
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    pub fn cooldown(&self) -> Duration {
+        self.cooldown.unwrap_or_else(|| self.window())
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), BootstrapError> {
+        if self.threshold == 0 {
+            return Err(BootstrapError::InvalidConfigValueError(
+                "logging.error_monitor.threshold=0".to_string(),
+            ));
+        }
+        if let Some(webhook) = &self.webhook {
+            webhook.parse::<HttpUrl>().map_err(|e| {
+                BootstrapError::InvalidConfigValueError(format!(
+                    "logging.error_monitor.webhook: {}",
+                    e
+                ))
+            })?;
+        }
+        Ok(())
+    }
+}
+
+/// ErrorAlert is fired when a target logs too many ERROR events within the window.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct ErrorAlert {
+    pub target: String,
+    pub count: usize,
+    pub window_secs: u64,
+    pub ts_ms: u64,
+}
+
+pub type ErrorAlertListener = Arc<dyn Fn(&ErrorAlert) + Send + Sync>;
+
+#[derive(Default)]
+struct TargetWindow {
+    events: VecDeque<Instant>,
+    last_alert: Option<Instant>,
+}
+
+/// ErrorMonitor counts ERROR events per target in sliding windows and fires an
+/// [`ErrorAlert`] when a target reaches the threshold.
+///
+/// Alerts are delivered on a background thread: they are logged as warnings, passed to the
+/// listeners and posted to the webhook when one is configured.
+///
+/// # Example
+/// ```
+/// use std::sync::{Arc, atomic::{AtomicUsize, Ordering}};
+/// use beaver_bootstrap::log::monitor::ErrorMonitor;
+/// let monitor = ErrorMonitor::new(std::time::Duration::from_secs(60), 2);
+/// let alerts = Arc::new(AtomicUsize::new(0));
+/// let counter = alerts.clone();
+/// monitor.on_alert(move |_| {
+///     counter.fetch_add(1, Ordering::SeqCst);
+/// });
+/// assert!(monitor.record("db").is_none());
+/// assert!(monitor.record("db").is_some());
+/// ```
+pub struct ErrorMonitor {
+    clock: Arc<dyn Clock>,
+    window: Duration,
+    threshold: usize,
+    cooldown: Duration,
+    targets: Mutex<HashMap<String, TargetWindow>>,
+    listeners: Arc<RwLock<Vec<ErrorAlertListener>>>,
+    sender: Mutex<Sender<ErrorAlert>>,
+}
+
+impl std::fmt::Debug for ErrorMonitor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ErrorMonitor")
+            .field("window", &self.window)
+            .field("threshold", &self.threshold)
+            .field("cooldown", &self.cooldown)
+            .finish()
+    }
+}
+
+impl ErrorMonitor {
+    pub fn new(window: Duration, threshold: usize) -> Self {
+        Self::with_webhook(Arc::new(SystemClock), window, threshold, window, None)
+    }
+
+    fn with_webhook(
+        clock: Arc<dyn Clock>,
+        window: Duration,
+        threshold: usize,
+        cooldown: Duration,
+        webhook: Option<HttpUrl>,
+    ) -> Self {
+        let listeners: Arc<RwLock<Vec<ErrorAlertListener>>> = Arc::default();
+        let (sender, receiver) = mpsc::channel::<ErrorAlert>();
+        let thread_listeners = listeners.clone();
+        let _ = thread::Builder::new()
+            .name("beaver-error-monitor".to_string())
+            .spawn(move || {
+                while let Ok(alert) = receiver.recv() {
+                    tracing::warn!(
+                        "{} ERROR events from {} within {}s",
+                        alert.count,
+                        alert.target,
+                        alert.window_secs
+                    );
+                    let listeners = thread_listeners
+                        .read()
+                        .map(|x| x.clone())
+                        .unwrap_or_default();
+                    for listener in listeners {
+                        listener(&alert);
+                    }
+                    if let Some(webhook) = &webhook {
+                        let body = serde_json::to_vec(&alert).unwrap_or_default();
+                        if let Err(e) = http_request(
+                            "POST",
+                            webhook,
+                            "application/json",
+                            &body,
+                            WEBHOOK_TIMEOUT,
+                        ) {
+                            tracing::warn!("failed to post error alert to {}: {}", webhook, e);
+                        }
+                    }
+                }
+            });
+        Self {
+            clock,
+            window,
+            threshold,
+            cooldown,
+            targets: Mutex::default(),
+            listeners,
+            sender: Mutex::new(sender),
+        }
+    }
+
+    pub fn from_config(config: &ErrorMonitorConfig, clock: Arc<dyn Clock>) -> Self {
+        Self::with_webhook(
+            clock,
+            config.window(),
+            config.threshold(),
+            config.cooldown(),
+            config.webhook.as_deref().and_then(|x| x.parse().ok()),
+        )
+    }
+
+    /// call `listener` on every alert.
+    pub fn on_alert<F>(&self, listener: F)
+    where
+        F: Fn(&ErrorAlert) + Send + Sync + 'static,
+    {
+        if let Ok(mut listeners) = self.listeners.write() {
+            listeners.push(Arc::new(listener));
+        }
+    }
+
+    /// count one ERROR event of `target`, returning the alert it fired if any.
+    pub fn record(&self, target: &str) -> Option<ErrorAlert> {
+        let now = self.clock.instant();
+        let mut targets = self.targets.lock().unwrap_or_else(|e| e.into_inner());
+        let state = targets.entry(target.to_string()).or_default();
+        state.events.push_back(now);
+        while let Some(first) = state.events.front()
+            && now.duration_since(*first) > self.window
+        {
+            state.events.pop_front();
+        }
+        let count = state.events.len();
+        if count < self.threshold
+            || state
+                .last_alert
+                .is_some_and(|last| now.duration_since(last) < self.cooldown)
+        {
+            return None;
+        }
+        state.last_alert = Some(now);
+        let alert = ErrorAlert {
+            target: target.to_string(),
+            count,
+            window_secs: self.window.as_secs(),
+            ts_ms: self
+                .clock
+                .now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or_default(),
+        };
+        if let Ok(sender) = self.sender.lock() {
+            let _ = sender.send(alert.clone());
+        }
+        Some(alert)
+    }
+
+    /// ERROR events counted per target within the current window.
+    pub fn counts(&self) -> HashMap<String, usize> {
+        let now = self.clock.instant();
+        let targets = self.targets.lock().unwrap_or_else(|e| e.into_inner());
+        targets
+            .iter()
+            .map(|(target, state)| {
+                let count = state
+                    .events
+                    .iter()
+                    .filter(|x| now.duration_since(**x) <= self.window)
+                    .count();
+                (target.clone(), count)
+            })
+            .collect()
+    }
+}
+
+/// ErrorMonitorLayer feeds the ERROR events of the subscriber to an [`ErrorMonitor`].
+pub struct ErrorMonitorLayer {
+    monitor: Arc<ErrorMonitor>,
+}
+
+impl ErrorMonitorLayer {
+    pub fn new(monitor: Arc<ErrorMonitor>) -> Self {
+        Self { monitor }
+    }
+}
+
+impl<S: Subscriber> Layer<S> for ErrorMonitorLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        if *event.metadata().level() == tracing::Level::ERROR {
+            self.monitor.record(event.metadata().target());
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use std::str::FromStr;
+
+use tracing_subscriber::filter::Targets;
+
+use crate::error::BootstrapError;
+
+/// environment variable read for the [`TargetOverlay`] at startup.
+pub const DEFAULT_LOG_FILTER_ENV: &str = "BEAVER_LOG";
+
+/// TargetOverlay holds target levels given at startup, applied on top of the targets of the
+/// configured loggers of every appender.
+///
+/// It uses the `target=level` directives of `EnvFilter`, e.g. `BEAVER_LOG=info,my_app::db=trace`,
+/// which allows one-off debugging of packaged binaries without editing config.toml. Span and
+/// field filters are not supported, and events stay capped by the write level of each appender.
+///
+/// # Example
+/// ```
+/// use beaver_bootstrap::log::overlay::TargetOverlay;
+/// use tracing::{Level, level_filters::LevelFilter};
+/// use tracing_subscriber::filter::Targets;
+/// let configured = Targets::new()
+///     .with_default(LevelFilter::WARN)
+///     .with_target("my_app::db", LevelFilter::INFO);
+/// let targets = TargetOverlay::parse("BEAVER_LOG", "my_app::db=trace")
+///     .unwrap()
+///     .apply(configured);
+/// assert!(targets.would_enable("my_app::db", &Level::TRACE));
+/// assert!(!targets.would_enable("my_app::http", &Level::INFO));
+/// ```
+#[derive(Debug, Clone)]
+pub struct TargetOverlay {
+    targets: Targets,
+}
+
+impl TargetOverlay {
+    /// the overlay of environment variable `var`, `None` when it is unset or empty.
+    pub fn from_env(var: &str) -> Result<Option<Self>, BootstrapError> {
+        match std::env::var(var) {
+            Ok(value) if !value.trim().is_empty() => Self::parse(var, &value).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    /// parse the directives `value`, read from `var`.
+    pub fn parse(var: &str, value: &str) -> Result<Self, BootstrapError> {
+        Targets::from_str(value)
+            .map(|targets| Self { targets })
+            .map_err(|e| {
+                BootstrapError::InvalidConfigValueError(format!("{}={}: {}", var, value, e))
+            })
+    }
+
+    /// `targets` with the levels of the overlay replacing theirs.
+    pub fn apply(&self, targets: Targets) -> Targets {
+        let overlaid = targets.with_targets(
+            self.targets
+                .iter()
+                .map(|(target, level)| (target.to_string(), level)),
+        );
+        match self.targets.default_level() {
+            Some(level) => overlaid.with_default(level),
+            None => overlaid,
+        }
+    }
+}
@@ -0,0 +1,478 @@
+use std::{
+    fmt,
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::{
+    Layer,
+    fmt::{FmtContext, FormatEvent, FormatFields, MakeWriter, format::Writer},
+    registry::LookupSpan,
+};
+
+use super::{
+    context::LogContext,
+    dedup::DedupFormat,
+    format::{LogFormat, civil_from_days, deterministic_clock},
+};
+use crate::clock::Clock;
+
+/// date format of `%d` without option, the `ISO8601` of logback.
+const ISO8601: &str = "yyyy-MM-dd HH:mm:ss,SSS";
+
+/// PatternLayout is a logback style layout of text lines, e.g.
+/// `%d{ISO8601} %-5p [%t] %c - %m%n`, so services migrating from logback or log4rs keep the
+/// lines their log parsers expect.
+///
+/// Conversions are `%` followed by an optional width, a word and an optional `{option}`:
+///
+/// - `%d`, `%date`: the timestamp in UTC, the option is `ISO8601` (the default), `RFC3339`
+///   or a date pattern of `yyyy`, `MM`, `dd`, `HH`, `mm`, `ss`, `SSS`, `SSSSSS`, `X` (`Z`)
+///   and `'quoted'` literals,
+/// - `%p`, `%le`, `%level`: the level,
+/// - `%t`, `%thread`: the name of the emitting thread,
+/// - `%c`, `%lo`, `%logger`, `%target`: the target,
+/// - `%m`, `%msg`, `%message`: the message followed by the fields of the event,
+/// - `%M`, `%module`, `%F`, `%file`, `%L`, `%line`: the module, file and line of the callsite,
+/// - `%X{key}`, `%mdc{key}`: a static field of the appender or a [`LogContext`] pair, `%X`
+///   alone writes them all as `key=value`,
+/// - `%n`: a newline, lines are not terminated otherwise,
+/// - `%%`: a percent sign.
+///
+/// A width pads to a minimum, `%5p` on the left and `%-5p` on the right, and `.N` truncates to
+/// the last N chars, `.-N` to the first N. Patterns are plain text, without colors.
+///
+/// # Example
+/// ```
+/// use beaver_bootstrap::log::pattern::PatternLayout;
+/// let layout: PatternLayout = "%d{ISO8601} %-5p [%t] %c - %m%n".parse().unwrap();
+/// assert_eq!(layout.to_string(), "%d{ISO8601} %-5p [%t] %c - %m%n");
+/// assert!("%d %q".parse::<PatternLayout>().is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct PatternLayout {
+    pattern: String,
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    Conversion(Conversion, Padding),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Conversion {
+    Date(Vec<DatePart>),
+    Level,
+    Thread,
+    Target,
+    Message,
+    Module,
+    File,
+    Line,
+    Mdc(Option<String>),
+    Newline,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Padding {
+    min: usize,
+    left_align: bool,
+    max: Option<usize>,
+    /// truncate by keeping the first `max` chars rather than the last.
+    keep_start: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum DatePart {
+    Literal(String),
+    Year,
+    Month,
+    Day,
+    Hour,
+    Minute,
+    Second,
+    Millis,
+    Micros,
+    /// the offset of UTC, `Z` or `+0000`.
+    Offset(bool),
+}
+
+#[derive(Debug)]
+pub struct ParsePatternError {
+    pattern: String,
+    reason: String,
+}
+
+impl fmt::Display for ParsePatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid pattern {:?}: {}", self.pattern, self.reason)
+    }
+}
+
+impl std::error::Error for ParsePatternError {}
+
+impl FromStr for PatternLayout {
+    type Err = ParsePatternError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = |reason: String| ParsePatternError {
+            pattern: s.to_string(),
+            reason,
+        };
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut chars = s.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                literal.push(c);
+                continue;
+            }
+            if chars.next_if_eq(&'%').is_some() {
+                literal.push('%');
+                continue;
+            }
+            let mut padding = Padding {
+                left_align: chars.next_if_eq(&'-').is_some(),
+                ..Padding::default()
+            };
+            padding.min = take_number(&mut chars).unwrap_or(0);
+            if chars.next_if_eq(&'.').is_some() {
+                padding.keep_start = chars.next_if_eq(&'-').is_some();
+                padding.max = Some(
+                    take_number(&mut chars).ok_or_else(|| error("missing width".to_string()))?,
+                );
+            }
+            let mut word = String::new();
+            while let Some(c) = chars.next_if(|c| c.is_ascii_alphabetic()) {
+                word.push(c);
+            }
+            let option = match chars.next_if_eq(&'{') {
+                Some(_) => {
+                    let mut option = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => option.push(c),
+                            None => return Err(error(format!("unclosed option of %{}", word))),
+                        }
+                    }
+                    Some(option)
+                }
+                None => None,
+            };
+            let conversion = match word.as_str() {
+                "d" | "date" => Conversion::Date(parse_date(option.as_deref().unwrap_or(ISO8601))),
+                "p" | "le" | "level" => Conversion::Level,
+                "t" | "thread" => Conversion::Thread,
+                "c" | "lo" | "logger" | "target" => Conversion::Target,
+                "m" | "msg" | "message" => Conversion::Message,
+                "M" | "module" => Conversion::Module,
+                "F" | "file" => Conversion::File,
+                "L" | "line" => Conversion::Line,
+                "X" | "mdc" => Conversion::Mdc(option.clone()),
+                "n" => Conversion::Newline,
+                "" => return Err(error("missing conversion after %".to_string())),
+                word => return Err(error(format!("unknown conversion %{}", word))),
+            };
+            let takes_option = matches!(conversion, Conversion::Date(_) | Conversion::Mdc(_));
+            if option.is_some() && !takes_option {
+                return Err(error(format!("%{} takes no option", word)));
+            }
+            if !literal.is_empty() {
+                segments.push(Segment::Literal(std::mem::take(&mut literal)));
+            }
+            segments.push(Segment::Conversion(conversion, padding));
+        }
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+        Ok(PatternLayout {
+            pattern: s.to_string(),
+            segments,
+        })
+    }
+}
+
+impl TryFrom<String> for PatternLayout {
+    type Error = ParsePatternError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<PatternLayout> for String {
+    fn from(value: PatternLayout) -> Self {
+        value.pattern
+    }
+}
+
+impl fmt::Display for PatternLayout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.pattern)
+    }
+}
+
+fn take_number(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) -> Option<usize> {
+    let mut digits = String::new();
+    while let Some(c) = chars.next_if(|c| c.is_ascii_digit()) {
+        digits.push(c);
+    }
+    digits.parse().ok()
+}
+
+/// the parts of a logback date pattern, letters other than the known fields are literals.
+fn parse_date(format: &str) -> Vec<DatePart> {
+    let format = match format {
+        "ISO8601" => ISO8601,
+        "RFC3339" => "yyyy-MM-dd'T'HH:mm:ss.SSSSSSX",
+        format => format,
+    };
+    let tokens: [(&str, DatePart); 10] = [
+        ("yyyy", DatePart::Year),
+        ("SSSSSS", DatePart::Micros),
+        ("SSS", DatePart::Millis),
+        ("MM", DatePart::Month),
+        ("dd", DatePart::Day),
+        ("HH", DatePart::Hour),
+        ("mm", DatePart::Minute),
+        ("ss", DatePart::Second),
+        ("XXX", DatePart::Offset(true)),
+        ("X", DatePart::Offset(true)),
+    ];
+    let mut parts = Vec::new();
+    let mut rest = format;
+    while let Some(c) = rest.chars().next() {
+        if c == '\'' {
+            let quoted = &rest[1..];
+            let end = quoted.find('\'').unwrap_or(quoted.len());
+            parts.push(DatePart::Literal(quoted[..end].to_string()));
+            rest = quoted.get(end + 1..).unwrap_or("");
+        } else if c == 'Z' {
+            parts.push(DatePart::Offset(false));
+            rest = &rest[1..];
+        } else if let Some((token, part)) = tokens.iter().find(|(x, _)| rest.starts_with(x)) {
+            parts.push(part.clone());
+            rest = &rest[token.len()..];
+        } else {
+            parts.push(DatePart::Literal(c.to_string()));
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    parts
+}
+
+/// PatternFormat renders events with a [`PatternLayout`], the static fields of the appender
+/// are only written where the layout has `%X`.
+pub struct PatternFormat {
+    layout: PatternLayout,
+    fields: Vec<(String, String)>,
+    clock: Arc<dyn Clock>,
+    /// whether `%t` writes the id of unnamed threads, `-` otherwise.
+    thread_ids: bool,
+}
+
+impl PatternFormat {
+    pub fn new(
+        layout: PatternLayout,
+        fields: Vec<(String, String)>,
+        clock: Arc<dyn Clock>,
+    ) -> Self {
+        Self {
+            layout,
+            fields,
+            clock,
+            thread_ids: true,
+        }
+    }
+
+    /// whether `%t` writes the id of the threads without a name, which differs from run to
+    /// run, or `-`.
+    pub fn with_thread_ids(mut self, thread_ids: bool) -> Self {
+        self.thread_ids = thread_ids;
+        self
+    }
+
+    fn write_date(&self, parts: &[DatePart], buf: &mut String) -> fmt::Result {
+        use std::fmt::Write;
+        let since_epoch = self
+            .clock
+            .now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let secs = since_epoch.as_secs();
+        let (year, month, day) = civil_from_days((secs / 86400) as i64);
+        let time = secs % 86400;
+        for part in parts {
+            match part {
+                DatePart::Literal(x) => buf.push_str(x),
+                DatePart::Year => write!(buf, "{:04}", year)?,
+                DatePart::Month => write!(buf, "{:02}", month)?,
+                DatePart::Day => write!(buf, "{:02}", day)?,
+                DatePart::Hour => write!(buf, "{:02}", time / 3600)?,
+                DatePart::Minute => write!(buf, "{:02}", time / 60 % 60)?,
+                DatePart::Second => write!(buf, "{:02}", time % 60)?,
+                DatePart::Millis => write!(buf, "{:03}", since_epoch.subsec_millis())?,
+                DatePart::Micros => write!(buf, "{:06}", since_epoch.subsec_micros())?,
+                DatePart::Offset(true) => buf.push('Z'),
+                DatePart::Offset(false) => buf.push_str("+0000"),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<S, N> FormatEvent<S, N> for PatternFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        use std::fmt::Write;
+        let metadata = event.metadata();
+        let mut buf = String::new();
+        for segment in &self.layout.segments {
+            let (conversion, padding) = match segment {
+                Segment::Literal(x) => {
+                    writer.write_str(x)?;
+                    continue;
+                }
+                Segment::Conversion(conversion, padding) => (conversion, padding),
+            };
+            buf.clear();
+            match conversion {
+                Conversion::Date(parts) => self.write_date(parts, &mut buf)?,
+                Conversion::Level => write!(buf, "{}", metadata.level())?,
+                Conversion::Thread => match std::thread::current().name() {
+                    Some(name) => buf.push_str(name),
+                    None if self.thread_ids => write!(buf, "{:?}", std::thread::current().id())?,
+                    None => buf.push('-'),
+                },
+                Conversion::Target => buf.push_str(metadata.target()),
+                Conversion::Message => ctx.format_fields(Writer::new(&mut buf), event)?,
+                Conversion::Module => buf.push_str(metadata.module_path().unwrap_or_default()),
+                Conversion::File => buf.push_str(metadata.file().unwrap_or_default()),
+                Conversion::Line => {
+                    if let Some(line) = metadata.line() {
+                        write!(buf, "{}", line)?;
+                    }
+                }
+                Conversion::Mdc(key) => {
+                    let context = LogContext::current();
+                    let pairs = self.fields.iter().chain(context.iter());
+                    match key {
+                        // the context is pushed last, the innermost value wins
+                        Some(key) => {
+                            if let Some((_, value)) = pairs.rev().find(|(k, _)| k == key) {
+                                buf.push_str(value);
+                            }
+                        }
+                        None => {
+                            let pairs: Vec<String> =
+                                pairs.map(|(k, v)| format!("{}={}", k, v)).collect();
+                            buf.push_str(&pairs.join(" "));
+                        }
+                    }
+                }
+                Conversion::Newline => buf.push('\n'),
+            }
+            write_padded(&mut writer, &buf, padding)?;
+        }
+        Ok(())
+    }
+}
+
+fn write_padded(writer: &mut Writer<'_>, value: &str, padding: &Padding) -> fmt::Result {
+    let len = value.chars().count();
+    let value = match padding.max {
+        // `%.0m` writes nothing, rather than finding no char to cut at
+        Some(0) => "",
+        Some(max) if len > max && padding.keep_start => {
+            let end = value
+                .char_indices()
+                .nth(max)
+                .map_or(value.len(), |(i, _)| i);
+            &value[..end]
+        }
+        Some(max) if len > max => {
+            let start = value.char_indices().nth(len - max).map_or(0, |(i, _)| i);
+            &value[start..]
+        }
+        _ => value,
+    };
+    let pad = padding.min.saturating_sub(value.chars().count());
+    if padding.left_align {
+        write!(writer, "{}{:pad$}", value, "", pad = pad)
+    } else {
+        write!(writer, "{:pad$}{}", "", value, pad = pad)
+    }
+}
+
+/// build the fmt layer of a text appender with a pattern layout, with the timestamps read
+/// from `clock` and the repeats of an event within `dedup_window` collapsed.
+///
+/// # Example
+/// ```
+/// use std::sync::Arc;
+/// use beaver_bootstrap::{clock::SystemClock, log::pattern::pattern_layer_with_clock};
+/// use tracing_subscriber::layer::SubscriberExt;
+/// let layout = "%d %-5p %c - %m%n".parse().unwrap();
+/// let clock = Arc::new(SystemClock);
+/// let layer = pattern_layer_with_clock(layout, vec![], None, clock, std::io::stdout);
+/// let subscriber = tracing_subscriber::registry().with(layer);
+/// tracing::subscriber::with_default(subscriber, || tracing::info!(user = "alice", "login"));
+/// ```
+pub fn pattern_layer_with_clock<S, W>(
+    layout: PatternLayout,
+    fields: Vec<(String, String)>,
+    dedup_window: Option<Duration>,
+    clock: Arc<dyn Clock>,
+    writer: W,
+) -> Box<dyn Layer<S> + Send + Sync>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    tracing_subscriber::fmt::layer()
+        .with_ansi(false)
+        .event_format(DedupFormat::new(
+            PatternFormat::new(layout, fields, clock.clone()),
+            dedup_window,
+            clock,
+            LogFormat::Text,
+        ))
+        .with_writer(writer)
+        .boxed()
+}
+
+/// like [`pattern_layer_with_clock`], rendering the same lines on every run for golden-file
+/// tests: every date is [`DETERMINISTIC_TIME`](super::format::DETERMINISTIC_TIME), repeats
+/// are not collapsed and `%t` writes `-` for the threads without a name.
+pub fn deterministic_pattern_layer<S, W>(
+    layout: PatternLayout,
+    fields: Vec<(String, String)>,
+    writer: W,
+) -> Box<dyn Layer<S> + Send + Sync>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    tracing_subscriber::fmt::layer()
+        .with_ansi(false)
+        .event_format(
+            PatternFormat::new(layout, fields, deterministic_clock()).with_thread_ids(false),
+        )
+        .with_writer(writer)
+        .boxed()
+}
@@ -0,0 +1,245 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::{Registry, filter::Targets, reload};
+
+use crate::{
+    config::{Config, ConfigChange, ConfigDiff, ConfigPrefix, reload::ConfigSubscriber},
+    error::BootstrapError,
+    fs::Fs,
+    log::{
+        AllLogger, Level, Logger, LoggingConfig, location::LogFolderStrategy,
+        overlay::TargetOverlay,
+    },
+};
+
+/// name of the console appender unless named.
+pub const CONSOLE_APPENDER: &str = "console";
+
+/// keys of an appender applied by a reload, other keys of `[logging]` need a restart.
+const RELOADABLE_APPENDER_KEYS: [&str; 3] = ["enable", "write_level", "logger_names"];
+
+/// handle of the filter of a running appender.
+pub type FilterHandle = reload::Handle<Targets, Registry>;
+
+/// the targets enabled by the loggers `logger_names` of an appender at their effective
+/// levels, unknown names are skipped.
+///
+/// The targets of loggers which are not additive and not listed are off, so the events of
+/// their targets do not reach the appender through an ancestor.
+pub fn logger_targets(all_logger: &AllLogger, logger_names: &[&str]) -> Targets {
+    let logger_names: HashSet<&str> = logger_names.iter().copied().collect();
+    let targets = logger_names
+        .iter()
+        .filter_map(|name| all_logger.logger(name))
+        .fold(Targets::new(), |acc, logger| {
+            let level = all_logger.effective_level(logger).as_tracing_level_filter();
+            if logger.target().is_empty() {
+                acc.with_default(level)
+            } else {
+                acc.with_target(logger.target(), level)
+            }
+        });
+    all_logger
+        .loggers()
+        .into_iter()
+        .filter(|x| !x.is_additive() && !x.target().is_empty())
+        .filter(|x| !logger_names.contains(x.name()))
+        .fold(targets, |acc, logger| {
+            acc.with_target(logger.target(), LevelFilter::OFF)
+        })
+}
+
+/// the filter of an appender, `targets` overlaid by `overlay` and capped by the write level
+/// `cap`, so the overlay cannot raise an appender above its write level.
+pub fn appender_filter(
+    targets: Targets,
+    overlay: Option<&TargetOverlay>,
+    cap: LevelFilter,
+) -> Targets {
+    let targets = match overlay {
+        Some(overlay) => overlay.apply(targets),
+        None => targets,
+    };
+    let capped = Targets::new().with_targets(
+        targets
+            .iter()
+            .map(|(target, level)| (target.to_string(), level.min(cap))),
+    );
+    match targets.default_level() {
+        Some(level) => capped.with_default(level.min(cap)),
+        None => capped,
+    }
+}
+
+/// LoggingReloader applies the `[logging]` section of reloaded configs to the running
+/// appenders.
+///
+/// The targets and write levels of appenders are reloaded, an appender which is disabled or
+/// removed stops writing. Appenders added or enabled after startup, and changes of anything
+/// else, e.g. formats, file sizes or the audit log, are logged as requiring a restart.
+pub struct LoggingReloader {
+    /// filters of the appenders started at startup, by name.
+    filters: HashMap<String, FilterHandle>,
+    overlay: Option<TargetOverlay>,
+    /// loggers declared by modules, merged into every reloaded config.
+    module_loggers: Vec<Logger>,
+    fs: Arc<dyn Fs>,
+    /// folder of the appenders without `file_dir` at startup.
+    default_folder: LogFolderStrategy,
+}
+
+impl std::fmt::Debug for LoggingReloader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LoggingReloader")
+            .field("appenders", &self.filters.keys().collect::<Vec<_>>())
+            .field("overlay", &self.overlay)
+            .finish_non_exhaustive()
+    }
+}
+
+impl LoggingReloader {
+    pub fn new(
+        filters: HashMap<String, FilterHandle>,
+        overlay: Option<TargetOverlay>,
+        module_loggers: Vec<Logger>,
+        fs: Arc<dyn Fs>,
+    ) -> Self {
+        Self {
+            filters,
+            overlay,
+            module_loggers,
+            fs,
+            default_folder: LogFolderStrategy::default(),
+        }
+    }
+
+    /// resolve the appenders without `file_dir` like [`LoggingConfig::new_with_folder`], so
+    /// they are compared with the folders of startup.
+    pub fn with_default_folder(mut self, default_folder: LogFolderStrategy) -> Self {
+        self.default_folder = default_folder;
+        self
+    }
+
+    fn logging_config(&self, config: &Config) -> Result<LoggingConfig, BootstrapError> {
+        let mut logging_config =
+            LoggingConfig::new_with_folder(config, self.fs.as_ref(), &self.default_folder)?;
+        logging_config.merge_loggers(self.module_loggers.clone());
+        Ok(logging_config)
+    }
+
+    fn reload(&self, name: &str, handle: &FilterHandle, targets: Targets) -> bool {
+        match handle.reload(targets) {
+            Ok(()) => true,
+            Err(e) => {
+                tracing::warn!(
+                    "unable to reload the filter of log appender {}: {}",
+                    name,
+                    e
+                );
+                false
+            }
+        }
+    }
+}
+
+/// whether `change` of `[logging]` needs a restart to be applied.
+fn needs_restart(change: &ConfigChange) -> bool {
+    let Some(key) = change.key().strip_prefix("logging.") else {
+        return false;
+    };
+    if key.starts_with("all_logger.") {
+        return false;
+    }
+    let appender_key = key.strip_prefix("console_appender.").or_else(|| {
+        key.strip_prefix("file_appenders[")
+            .and_then(|x| x.split_once("].").map(|(_, key)| key))
+    });
+    match appender_key {
+        // `logger_names[0]` is the item of a list
+        Some(key) => !RELOADABLE_APPENDER_KEYS.contains(&key.split('[').next().unwrap_or(key)),
+        None => true,
+    }
+}
+
+impl ConfigSubscriber for LoggingReloader {
+    fn name(&self) -> String {
+        LoggingConfig::PREFIX.to_string()
+    }
+
+    fn validate(&self, proposed: &Config, diff: &ConfigDiff) -> Result<(), String> {
+        if !diff.touches(LoggingConfig::PREFIX) {
+            return Ok(());
+        }
+        self.logging_config(proposed)
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+
+    fn apply(&self, config: &Config, diff: &ConfigDiff) {
+        if !diff.touches(LoggingConfig::PREFIX) {
+            return;
+        }
+        let logging_config = match self.logging_config(config) {
+            Ok(logging_config) => logging_config,
+            Err(e) => {
+                tracing::warn!("logging config not applied: {}", e);
+                return;
+            }
+        };
+        let all_logger = logging_config.logger_config();
+        let mut appenders: Vec<(String, bool, Vec<&str>, Level)> = logging_config
+            .file_appender_config()
+            .into_iter()
+            .map(|x| {
+                let name = x.name().to_string();
+                (name, x.enable(), x.logger_names(), x.write_level())
+            })
+            .collect();
+        if let Some(console) = logging_config.console_appender_config() {
+            appenders.push((
+                console.name().to_string(),
+                console.enable(),
+                console.logger_names(),
+                console.write_level(),
+            ));
+        }
+        let mut configured = HashSet::new();
+        for (name, enable, logger_names, write_level) in &appenders {
+            configured.insert(name.as_str());
+            let Some(handle) = self.filters.get(name) else {
+                if *enable {
+                    tracing::info!("log appender {} starts after a restart", name);
+                }
+                continue;
+            };
+            let targets = if *enable {
+                let targets = logger_targets(all_logger, logger_names);
+                let cap = write_level.as_tracing_level_filter();
+                appender_filter(targets, self.overlay.as_ref(), cap)
+            } else {
+                Targets::new()
+            };
+            if self.reload(name, handle, targets) {
+                match enable {
+                    true => tracing::info!("log appender {} reloaded at {}", name, write_level),
+                    false => tracing::info!("log appender {} disabled", name),
+                }
+            }
+        }
+        for (name, handle) in &self.filters {
+            if !configured.contains(name.as_str()) && self.reload(name, handle, Targets::new()) {
+                tracing::info!("log appender {} removed, it stops writing", name);
+            }
+        }
+        for change in diff.changes().iter().filter(|x| needs_restart(x)) {
+            tracing::info!(
+                "logging config {} takes effect after a restart",
+                change.key()
+            );
+        }
+    }
+}
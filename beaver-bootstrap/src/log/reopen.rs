@@ -0,0 +1,220 @@
+use std::{
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard},
+    thread::{self, JoinHandle},
+};
+
+use serde::{Deserialize, Serialize};
+use tracing_rolling_file::RollingFileAppenderBase;
+
+use crate::{error::BootstrapError, signal::Signal};
+
+/// ReopenAction is what a reopen signal does to the file appenders.
+#[derive(Debug, Default, Copy, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReopenAction {
+    /// close and reopen the active files, for external tools (logrotate) which moved them.
+    #[default]
+    Reopen,
+    /// rotate the active files now, as if their rolling condition was met.
+    Rotate,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ReopenSignalConfig {
+    enable: bool,
+    signal: String,
+    action: ReopenAction,
+}
+
+impl Default for ReopenSignalConfig {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            signal: "SIGHUP".to_string(),
+            action: ReopenAction::Reopen,
+        }
+    }
+}
+
+impl ReopenSignalConfig {
+    pub fn enable(&self) -> bool {
+        self.enable
+    }
+
+    pub fn signal(&self) -> &str {
+        self.signal.as_str()
+    }
+
+    pub fn action(&self) -> ReopenAction {
+        self.action
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), BootstrapError> {
+        if self.enable && signal_number(&self.signal).is_none() {
+            return Err(BootstrapError::InvalidConfigValueError(format!(
+                "logging.reopen_signal.signal={}",
+                self.signal
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// number of a supported signal name, with or without the `SIG` prefix.
+#[cfg(unix)]
+fn signal_number(name: &str) -> Option<i32> {
+    Signal::from_name(name).map(|x| x.number())
+}
+
+#[cfg(not(unix))]
+fn signal_number(_name: &str) -> Option<i32> {
+    None
+}
+
+/// ReopenableFile is a rolling log file which can be reopened or rotated from another thread.
+#[derive(Clone)]
+pub struct ReopenableFile {
+    path: PathBuf,
+    max_count: usize,
+    max_size: u64,
+    inner: Arc<Mutex<RollingFileAppenderBase>>,
+}
+
+impl ReopenableFile {
+    pub fn open(path: &Path, max_count: usize, max_size: u64) -> Result<Self, &'static str> {
+        let appender = Self::build(path, max_count, max_size)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            max_count,
+            max_size,
+            inner: Arc::new(Mutex::new(appender)),
+        })
+    }
+
+    fn build(
+        path: &Path,
+        max_count: usize,
+        max_size: u64,
+    ) -> Result<RollingFileAppenderBase, &'static str> {
+        RollingFileAppenderBase::builder()
+            .filename(path.to_str().unwrap_or_default().to_string())
+            .max_filecount(max_count)
+            .condition_max_file_size(max_size)
+            .condition_daily()
+            .build()
+    }
+
+    pub fn path(&self) -> &Path {
+        self.path.as_path()
+    }
+
+    /// flush and close the active file, then open `path` again.
+    pub fn reopen(&self) -> io::Result<()> {
+        let appender =
+            Self::build(&self.path, self.max_count, self.max_size).map_err(io::Error::other)?;
+        let mut inner = self.lock();
+        inner.flush()?;
+        *inner = appender;
+        Ok(())
+    }
+
+    /// rotate the active file now.
+    pub fn rotate(&self) -> io::Result<()> {
+        self.lock().rollover()
+    }
+
+    pub fn apply(&self, action: ReopenAction) -> io::Result<()> {
+        match action {
+            ReopenAction::Reopen => self.reopen(),
+            ReopenAction::Rotate => self.rotate(),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, RollingFileAppenderBase> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Write for ReopenableFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.lock().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.lock().flush()
+    }
+}
+
+/// ReopenWatcher applies the reopen action to the files on every signal, until dropped.
+pub struct ReopenWatcher {
+    #[cfg(unix)]
+    handle: signal_hook::iterator::Handle,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl std::fmt::Debug for ReopenWatcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ReopenWatcher").finish_non_exhaustive()
+    }
+}
+
+impl ReopenWatcher {
+    #[cfg(unix)]
+    pub fn spawn(config: &ReopenSignalConfig, files: Vec<ReopenableFile>) -> io::Result<Self> {
+        let signal = signal_number(config.signal()).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, config.signal().to_string())
+        })?;
+        let mut signals = signal_hook::iterator::Signals::new([signal])?;
+        let handle = signals.handle();
+        let action = config.action();
+        let name = config.signal().to_string();
+        let thread = thread::Builder::new()
+            .name("beaver-log-reopen".to_string())
+            .spawn(move || {
+                for _ in signals.forever() {
+                    for file in &files {
+                        match file.apply(action) {
+                            Ok(()) => tracing::info!(
+                                "{:?} log file {} on {}",
+                                action,
+                                file.path().display(),
+                                name
+                            ),
+                            Err(e) => tracing::warn!(
+                                "failed to {:?} log file {} on {}: {}",
+                                action,
+                                file.path().display(),
+                                name,
+                                e
+                            ),
+                        }
+                    }
+                }
+            })?;
+        Ok(Self {
+            handle,
+            thread: Some(thread),
+        })
+    }
+
+    #[cfg(not(unix))]
+    pub fn spawn(config: &ReopenSignalConfig, _files: Vec<ReopenableFile>) -> io::Result<Self> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            config.signal().to_string(),
+        ))
+    }
+}
+
+impl Drop for ReopenWatcher {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        self.handle.close();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
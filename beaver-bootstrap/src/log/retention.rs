@@ -0,0 +1,103 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+/// RetentionPolicy bounds the rotated files kept next to an active log file.
+///
+/// Rotated files are named `<file_name>.<n>`, `n = 1` being the newest. The active file is
+/// never removed. [`LogMaintenance`](super::maintenance::LogMaintenance) enforces the policy of
+/// the file appenders every `logging.maintenance.interval`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    /// remove rotated files last modified longer ago than this.
+    pub max_age: Option<Duration>,
+    /// remove the oldest rotated files until the active and rotated files fit in this budget.
+    pub max_total_size: Option<u64>,
+}
+
+impl RetentionPolicy {
+    pub fn is_enabled(&self) -> bool {
+        self.max_age.is_some() || self.max_total_size.is_some()
+    }
+
+    /// remove the rotated files of `file_path` exceeding the policy, returning them.
+    pub fn enforce(&self, file_path: &Path) -> io::Result<Vec<PathBuf>> {
+        self.enforce_at(file_path, SystemTime::now())
+    }
+
+    /// same as [`RetentionPolicy::enforce`], computing file ages at `now`.
+    pub fn enforce_at(&self, file_path: &Path, now: SystemTime) -> io::Result<Vec<PathBuf>> {
+        let mut removed = Vec::new();
+        if !self.is_enabled() {
+            return Ok(removed);
+        }
+        let mut rotated = rotated_files(file_path)?;
+        // oldest first
+        rotated.sort_by_key(|file| std::cmp::Reverse(file.index));
+
+        if let Some(max_age) = self.max_age {
+            rotated.retain(|file| {
+                let expired = now
+                    .duration_since(file.modified)
+                    .is_ok_and(|age| age > max_age);
+                if expired && fs::remove_file(&file.path).is_ok() {
+                    removed.push(file.path.clone());
+                    return false;
+                }
+                true
+            });
+        }
+
+        if let Some(max_total_size) = self.max_total_size {
+            let active_size = fs::metadata(file_path).map(|m| m.len()).unwrap_or(0);
+            let mut total: u64 = active_size + rotated.iter().map(|f| f.size).sum::<u64>();
+            for file in &rotated {
+                if total <= max_total_size {
+                    break;
+                }
+                fs::remove_file(&file.path)?;
+                total -= file.size;
+                removed.push(file.path.clone());
+            }
+        }
+        Ok(removed)
+    }
+}
+
+struct RotatedFile {
+    path: PathBuf,
+    index: usize,
+    size: u64,
+    modified: SystemTime,
+}
+
+fn rotated_files(file_path: &Path) -> io::Result<Vec<RotatedFile>> {
+    let (Some(dir), Some(file_name)) = (file_path.parent(), file_path.file_name()) else {
+        return Ok(vec![]);
+    };
+    let prefix = format!("{}.", file_name.to_string_lossy());
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        let Some(index) = name
+            .strip_prefix(&prefix)
+            .and_then(|n| n.parse::<usize>().ok())
+        else {
+            continue;
+        };
+        let metadata = entry.metadata()?;
+        if !metadata.is_file() {
+            continue;
+        }
+        files.push(RotatedFile {
+            path: entry.path(),
+            index,
+            size: metadata.len(),
+            modified: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+        });
+    }
+    Ok(files)
+}
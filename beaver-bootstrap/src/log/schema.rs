@@ -0,0 +1,332 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use serde::{Deserialize, Serialize};
+use tracing::{
+    Event, Metadata, Subscriber,
+    field::{Field, Visit},
+};
+use tracing_subscriber::{Layer, layer::Context};
+
+use crate::error::BootstrapError;
+
+/// SchemaMode is what happens to the events which do not match their schema.
+#[derive(Debug, Default, Copy, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SchemaMode {
+    /// schemas are not checked.
+    #[default]
+    Off,
+    /// events are written and a warning names the mismatch, for development.
+    Warn,
+    /// events are dropped by every appender and a warning names the mismatch.
+    Strict,
+}
+
+/// FieldType is the type a field of an event must be recorded as.
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldType {
+    String,
+    /// a signed or unsigned integer.
+    Integer,
+    /// a floating point number or an integer.
+    Float,
+    Bool,
+    /// any value, only the presence of the field is checked.
+    Any,
+}
+
+impl fmt::Display for FieldType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            FieldType::String => "string",
+            FieldType::Integer => "integer",
+            FieldType::Float => "float",
+            FieldType::Bool => "bool",
+            FieldType::Any => "any",
+        };
+        f.write_str(name)
+    }
+}
+
+/// EventSchema declares the fields the events of a target or of a name must carry.
+///
+/// An event matches when its target is `target` or below it, e.g. `billing::invoice` for
+/// `billing`, and its name is `name`, as set by `tracing::info!(name: "invoice_paid", ...)`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EventSchema {
+    target: Option<String>,
+    name: Option<String>,
+    /// required fields and their types.
+    fields: BTreeMap<String, FieldType>,
+}
+
+impl EventSchema {
+    fn matches(&self, metadata: &Metadata<'_>) -> bool {
+        let target = self.target.as_deref().is_none_or(|target| {
+            metadata
+                .target()
+                .strip_prefix(target)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+        });
+        target && self.name.as_deref().is_none_or(|x| x == metadata.name())
+    }
+
+    /// the mismatches of `fields`, empty when they match.
+    fn mismatches(&self, fields: &RecordedFields) -> Vec<String> {
+        self.fields
+            .iter()
+            .filter_map(|(name, expected)| match fields.get(name) {
+                None => Some(format!("missing field {}", name)),
+                Some(actual) if !actual.is(*expected) => Some(format!(
+                    "field {} is {}, expected {}",
+                    name, actual, expected
+                )),
+                Some(_) => None,
+            })
+            .collect()
+    }
+}
+
+/// EventSchemaConfig enforces field contracts on events, see `[logging.schema]`, so
+/// pipelines relying on the fields of some events do not break silently when they change:
+///
+/// ```toml
+/// [logging.schema]
+/// mode = "strict"
+///
+/// [[logging.schema.events]]
+/// target = "billing"
+/// name = "invoice_paid"
+/// fields = { invoice_id = "string", amount = "integer" }
+/// ```
+///
+/// Every event matching a schema must carry its fields with their types, extra fields are
+/// allowed. Events matching no schema are not checked.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EventSchemaConfig {
+    mode: SchemaMode,
+    events: Vec<EventSchema>,
+}
+
+impl EventSchemaConfig {
+    pub fn mode(&self) -> SchemaMode {
+        self.mode
+    }
+
+    pub fn events(&self) -> &[EventSchema] {
+        &self.events
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), BootstrapError> {
+        for (index, schema) in self.events.iter().enumerate() {
+            if schema.target.is_none() && schema.name.is_none() {
+                return Err(BootstrapError::InvalidConfigValueError(format!(
+                    "logging.schema.events[{}]: target or name is required",
+                    index
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// the type a field was recorded as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RecordedType {
+    String,
+    Integer,
+    Float,
+    Bool,
+    Debug,
+}
+
+impl RecordedType {
+    fn is(&self, expected: FieldType) -> bool {
+        match expected {
+            FieldType::String => *self == RecordedType::String,
+            FieldType::Integer => *self == RecordedType::Integer,
+            FieldType::Float => matches!(self, RecordedType::Float | RecordedType::Integer),
+            FieldType::Bool => *self == RecordedType::Bool,
+            FieldType::Any => true,
+        }
+    }
+}
+
+impl fmt::Display for RecordedType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            RecordedType::String => "string",
+            RecordedType::Integer => "integer",
+            RecordedType::Float => "float",
+            RecordedType::Bool => "bool",
+            RecordedType::Debug => "a debug value",
+        };
+        f.write_str(name)
+    }
+}
+
+/// the types of the fields of an event.
+#[derive(Default)]
+struct RecordedFields(Vec<(&'static str, RecordedType)>);
+
+impl RecordedFields {
+    fn get(&self, name: &str) -> Option<RecordedType> {
+        self.0.iter().find(|(x, _)| *x == name).map(|(_, x)| *x)
+    }
+}
+
+impl Visit for RecordedFields {
+    fn record_f64(&mut self, field: &Field, _value: f64) {
+        self.0.push((field.name(), RecordedType::Float));
+    }
+
+    fn record_i64(&mut self, field: &Field, _value: i64) {
+        self.0.push((field.name(), RecordedType::Integer));
+    }
+
+    fn record_u64(&mut self, field: &Field, _value: u64) {
+        self.0.push((field.name(), RecordedType::Integer));
+    }
+
+    fn record_i128(&mut self, field: &Field, _value: i128) {
+        self.0.push((field.name(), RecordedType::Integer));
+    }
+
+    fn record_u128(&mut self, field: &Field, _value: u128) {
+        self.0.push((field.name(), RecordedType::Integer));
+    }
+
+    fn record_bool(&mut self, field: &Field, _value: bool) {
+        self.0.push((field.name(), RecordedType::Bool));
+    }
+
+    fn record_str(&mut self, field: &Field, _value: &str) {
+        self.0.push((field.name(), RecordedType::String));
+    }
+
+    fn record_debug(&mut self, field: &Field, _value: &dyn fmt::Debug) {
+        self.0.push((field.name(), RecordedType::Debug));
+    }
+}
+
+/// SchemaValidator checks events against the schemas of an [`EventSchemaConfig`], see
+/// [`SchemaLayer`].
+///
+/// A mismatch is warned about once, the count of mismatching events is kept.
+///
+/// # Example
+/// ```
+/// use std::sync::Arc;
+/// use beaver_bootstrap::log::schema::{EventSchemaConfig, SchemaLayer, SchemaValidator};
+/// use tracing_subscriber::layer::SubscriberExt;
+/// let config: EventSchemaConfig = toml::from_str(r#"
+///     mode = "strict"
+///     [[events]]
+///     name = "invoice_paid"
+///     fields = { amount = "integer" }
+/// "#).unwrap();
+/// let validator = Arc::new(SchemaValidator::new(&config));
+/// let subscriber = tracing_subscriber::registry().with(SchemaLayer::new(validator.clone()));
+/// tracing::subscriber::with_default(subscriber, || {
+///     tracing::info!(name: "invoice_paid", amount = 42, "paid");
+///     tracing::info!(name: "invoice_paid", amount = "42", "paid"); // dropped
+/// });
+/// assert_eq!(validator.mismatches(), 1);
+/// ```
+#[derive(Debug)]
+pub struct SchemaValidator {
+    mode: SchemaMode,
+    events: Vec<EventSchema>,
+    /// mismatches warned about already.
+    warned: Mutex<HashSet<String>>,
+    mismatches: AtomicU64,
+}
+
+impl SchemaValidator {
+    pub fn new(config: &EventSchemaConfig) -> Self {
+        Self {
+            mode: config.mode,
+            events: config.events.clone(),
+            warned: Mutex::default(),
+            mismatches: AtomicU64::new(0),
+        }
+    }
+
+    /// the mismatches of `event` with the schemas it matches, empty when it conforms.
+    pub fn check(&self, event: &Event<'_>) -> Vec<String> {
+        let metadata = event.metadata();
+        let mut schemas = self
+            .events
+            .iter()
+            .filter(|x| x.matches(metadata))
+            .peekable();
+        if schemas.peek().is_none() {
+            return Vec::new();
+        }
+        let mut fields = RecordedFields::default();
+        event.record(&mut fields);
+        schemas.flat_map(|x| x.mismatches(&fields)).collect()
+    }
+
+    /// whether `event` is written, warning about its mismatches in warn and strict modes.
+    pub fn enabled(&self, event: &Event<'_>) -> bool {
+        if self.mode == SchemaMode::Off {
+            return true;
+        }
+        let mismatches = self.check(event);
+        if mismatches.is_empty() {
+            return true;
+        }
+        self.mismatches.fetch_add(1, Ordering::Relaxed);
+        let metadata = event.metadata();
+        let message = format!(
+            "event {} of {} does not match its schema: {}",
+            metadata.name(),
+            metadata.target(),
+            mismatches.join(", ")
+        );
+        // the lock is released before warning, the warning is an event checked too
+        let first = self
+            .warned
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(message.clone());
+        if first {
+            tracing::warn!("{}", message);
+        }
+        self.mode != SchemaMode::Strict
+    }
+
+    /// number of events which did not match their schema.
+    pub fn mismatches(&self) -> u64 {
+        self.mismatches.load(Ordering::Relaxed)
+    }
+}
+
+/// SchemaLayer feeds the events of the subscriber to a [`SchemaValidator`], dropping the
+/// mismatching ones for every layer in strict mode.
+pub struct SchemaLayer {
+    validator: Arc<SchemaValidator>,
+}
+
+impl SchemaLayer {
+    pub fn new(validator: Arc<SchemaValidator>) -> Self {
+        Self { validator }
+    }
+}
+
+impl<S: Subscriber> Layer<S> for SchemaLayer {
+    fn event_enabled(&self, event: &Event<'_>, _ctx: Context<'_, S>) -> bool {
+        self.validator.enabled(event)
+    }
+}
@@ -0,0 +1,143 @@
+use std::{
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use super::{context::LogContext, reopen::ReopenableFile};
+
+/// placeholder of the tenant in the `file_name_template` of a file appender.
+pub const TENANT_PLACEHOLDER: &str = "{tenant}";
+
+/// tenant of the events logged outside the context of any tenant.
+pub const DEFAULT_TENANT: &str = "default";
+
+/// context key holding the tenant unless `tenant_field` is set.
+pub const DEFAULT_TENANT_FIELD: &str = "tenant";
+
+/// number of tenant files an appender keeps open unless `max_open_files` is set.
+pub const DEFAULT_MAX_OPEN_FILES: usize = 64;
+
+/// TenantFiles writes every event to the file of its tenant, the [`LogContext`] value of
+/// `tenant_field`, so the logs of the customers of a SaaS deployment are kept apart:
+///
+/// ```toml
+/// [[logging.file_appenders]]
+/// file_name_template = "app-{tenant}.log"
+/// tenant_field = "tenant"
+/// max_open_files = 64
+/// ```
+///
+/// Files are opened on the first event of their tenant and rotated like the other file
+/// appenders. Past `max_open_files`, the least recently written file is flushed and closed.
+/// Events without a tenant go to the [`DEFAULT_TENANT`] file. Tenants are sanitized to
+/// letters, digits, `-` and `_` in file names, so they cannot escape the log directory.
+///
+/// The tenant is read on the thread emitting the event, so tenant appenders write on it.
+///
+/// # Example
+/// ```no_run
+/// use std::io::Write;
+/// use beaver_bootstrap::log::{context::LogContext, tenant::TenantFiles};
+/// let mut files = TenantFiles::new("logs", "app-{tenant}.log", "tenant", 3, 1 << 20, 64);
+/// LogContext::scope(&[("tenant", "acme")], || {
+///     files.write_all(b"written to logs/app-acme.log\n").unwrap();
+/// });
+/// assert!(files.file_path("../etc").ends_with("app-___etc.log"));
+/// ```
+pub struct TenantFiles {
+    dir: PathBuf,
+    file_name: String,
+    tenant_field: String,
+    max_count: usize,
+    max_size: u64,
+    max_open: usize,
+    /// open files by tenant, the most recently written last.
+    open: Vec<(String, ReopenableFile)>,
+}
+
+impl std::fmt::Debug for TenantFiles {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TenantFiles")
+            .field("dir", &self.dir)
+            .field("file_name", &self.file_name)
+            .field("tenant_field", &self.tenant_field)
+            .field(
+                "open",
+                &self.open.iter().map(|(x, _)| x).collect::<Vec<_>>(),
+            )
+            .finish_non_exhaustive()
+    }
+}
+
+impl TenantFiles {
+    pub fn new(
+        dir: impl Into<PathBuf>,
+        file_name: &str,
+        tenant_field: &str,
+        max_count: usize,
+        max_size: u64,
+        max_open: usize,
+    ) -> Self {
+        Self {
+            dir: dir.into(),
+            file_name: file_name.to_string(),
+            tenant_field: tenant_field.to_string(),
+            max_count,
+            max_size,
+            max_open: max_open.max(1),
+            open: Vec::new(),
+        }
+    }
+
+    /// path of the file of `tenant`.
+    pub fn file_path(&self, tenant: &str) -> PathBuf {
+        let tenant: String = tenant
+            .chars()
+            .map(|x| match x.is_alphanumeric() || x == '-' || x == '_' {
+                true => x,
+                false => '_',
+            })
+            .collect();
+        self.dir
+            .join(self.file_name.replace(TENANT_PLACEHOLDER, &tenant))
+    }
+
+    /// the file of `tenant`, opened and made the most recently written one.
+    fn file(&mut self, tenant: &str) -> io::Result<&mut ReopenableFile> {
+        match self.open.iter().position(|(x, _)| x == tenant) {
+            Some(index) => {
+                let entry = self.open.remove(index);
+                self.open.push(entry);
+            }
+            None => {
+                if self.open.len() >= self.max_open {
+                    let (_, mut evicted) = self.open.remove(0);
+                    evicted.flush()?;
+                }
+                let file = open(&self.file_path(tenant), self.max_count, self.max_size)?;
+                self.open.push((tenant.to_string(), file));
+            }
+        }
+        Ok(&mut self.open.last_mut().expect("file was pushed").1)
+    }
+}
+
+fn open(path: &Path, max_count: usize, max_size: u64) -> io::Result<ReopenableFile> {
+    ReopenableFile::open(path, max_count, max_size)
+        .map_err(|e| io::Error::other(format!("{}: {}", path.display(), e)))
+}
+
+impl Write for TenantFiles {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let tenant = LogContext::get(&self.tenant_field);
+        self.file(tenant.as_deref().unwrap_or(DEFAULT_TENANT))?
+            .write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        for (_, file) in &mut self.open {
+            file.flush()?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,230 @@
+use std::{
+    io::{self, Write},
+    sync::{Arc, Mutex, MutexGuard},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+use tracing::Metadata;
+use tracing_appender::non_blocking::{ErrorCounter, WorkerGuard};
+use tracing_subscriber::fmt::{MakeWriter, writer::EitherWriter};
+
+use super::buffer::{self, AppenderStats, FlushHandle, OnFull, QueuedWriter};
+
+/// FlushOn controls when an appender flushes its output.
+#[derive(Debug, Default, Copy, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FlushOn {
+    /// write on the caller thread and flush after ERROR events, so the last error before a
+    /// crash reaches the file.
+    Error,
+    /// write on the caller thread and flush after every event, fully unbuffered.
+    EveryEvent,
+    /// hand events to a background worker thread, which flushes as it sees fit.
+    #[default]
+    Never,
+}
+
+type BoxedWriter = Box<dyn Write + Send>;
+
+/// SyncWriter writes events on the calling thread and flushes according to [`FlushOn`].
+#[derive(Clone)]
+pub struct SyncWriter {
+    inner: Arc<Mutex<BoxedWriter>>,
+    flush_on: FlushOn,
+}
+
+impl SyncWriter {
+    pub fn new<W: Write + Send + 'static>(writer: W, flush_on: FlushOn) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(Box::new(writer))),
+            flush_on,
+        }
+    }
+
+    pub fn flush(&self) -> io::Result<()> {
+        self.lock().flush()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BoxedWriter> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// SyncWriterGuard holds the writer for one event and flushes it when dropped if required.
+pub struct SyncWriterGuard<'a> {
+    inner: MutexGuard<'a, BoxedWriter>,
+    flush: bool,
+}
+
+impl Write for SyncWriterGuard<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl Drop for SyncWriterGuard<'_> {
+    fn drop(&mut self) {
+        if self.flush {
+            let _ = self.inner.flush();
+        }
+    }
+}
+
+impl<'a> MakeWriter<'a> for SyncWriter {
+    type Writer = SyncWriterGuard<'a>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        SyncWriterGuard {
+            inner: self.lock(),
+            flush: self.flush_on == FlushOn::EveryEvent,
+        }
+    }
+
+    fn make_writer_for(&'a self, meta: &Metadata<'_>) -> Self::Writer {
+        let flush = match self.flush_on {
+            FlushOn::EveryEvent => true,
+            FlushOn::Error => *meta.level() == tracing::Level::ERROR,
+            FlushOn::Never => false,
+        };
+        SyncWriterGuard {
+            inner: self.lock(),
+            flush,
+        }
+    }
+}
+
+/// AppenderWriter is the writer of one appender, buffered on a worker or synchronous.
+#[derive(Clone)]
+pub enum AppenderWriter {
+    NonBlocking(QueuedWriter),
+    Sync(SyncWriter),
+}
+
+impl AppenderWriter {
+    /// counter of events dropped because the buffer was full, only for non blocking writers.
+    pub fn error_counter(&self) -> Option<ErrorCounter> {
+        match self {
+            AppenderWriter::NonBlocking(writer) => Some(writer.error_counter()),
+            AppenderWriter::Sync(_) => None,
+        }
+    }
+}
+
+impl<'a> MakeWriter<'a> for AppenderWriter {
+    type Writer = EitherWriter<QueuedWriter, SyncWriterGuard<'a>>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        match self {
+            AppenderWriter::NonBlocking(writer) => EitherWriter::A(writer.make_writer()),
+            AppenderWriter::Sync(writer) => EitherWriter::B(writer.make_writer()),
+        }
+    }
+
+    fn make_writer_for(&'a self, meta: &Metadata<'_>) -> Self::Writer {
+        match self {
+            AppenderWriter::NonBlocking(writer) => EitherWriter::A(writer.make_writer_for(meta)),
+            AppenderWriter::Sync(writer) => EitherWriter::B(writer.make_writer_for(meta)),
+        }
+    }
+}
+
+/// AppenderWriterGuard flushes the output of an appender when dropped.
+pub enum AppenderWriterGuard {
+    Worker(WorkerGuard, FlushHandle),
+    Sync(SyncWriter),
+}
+
+impl std::fmt::Debug for AppenderWriterGuard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AppenderWriterGuard::Worker(guard, _) => f.debug_tuple("Worker").field(guard).finish(),
+            AppenderWriterGuard::Sync(_) => f.debug_tuple("Sync").finish(),
+        }
+    }
+}
+
+impl AppenderWriterGuard {
+    /// the flusher of the appender, usable while the guard lives.
+    pub fn flusher(&self) -> AppenderFlusher {
+        match self {
+            AppenderWriterGuard::Worker(_, handle) => AppenderFlusher::Worker(handle.clone()),
+            AppenderWriterGuard::Sync(writer) => AppenderFlusher::Sync(writer.clone()),
+        }
+    }
+}
+
+/// AppenderFlusher drains the output of an appender on demand, see
+/// [`LoggingManager`](crate::log::LoggingManager).
+#[derive(Clone)]
+pub enum AppenderFlusher {
+    Worker(FlushHandle),
+    Sync(SyncWriter),
+}
+
+impl std::fmt::Debug for AppenderFlusher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AppenderFlusher::Worker(_) => f.debug_tuple("Worker").finish(),
+            AppenderFlusher::Sync(_) => f.debug_tuple("Sync").finish(),
+        }
+    }
+}
+
+impl AppenderFlusher {
+    /// write out the events of the appender so far within `timeout`, returning whether they
+    /// were. Synchronous appenders flush on the calling thread.
+    pub fn flush(&self, timeout: Duration) -> bool {
+        match self {
+            AppenderFlusher::Worker(handle) => handle.flush(timeout),
+            AppenderFlusher::Sync(writer) => writer.flush().is_ok(),
+        }
+    }
+
+    /// the counts of events of the appender, `None` for a synchronous one, which writes on
+    /// the logging thread so never queues nor drops events.
+    pub fn stats(&self) -> Option<AppenderStats> {
+        match self {
+            AppenderFlusher::Worker(handle) => Some(handle.stats()),
+            AppenderFlusher::Sync(_) => None,
+        }
+    }
+}
+
+impl Drop for AppenderWriterGuard {
+    fn drop(&mut self) {
+        if let AppenderWriterGuard::Sync(writer) = self {
+            let _ = writer.flush();
+        }
+    }
+}
+
+/// build the writer of an appender from its buffering and flushing settings.
+pub fn appender_writer<W: Write + Send + 'static>(
+    writer: W,
+    name: &str,
+    buffer_size: usize,
+    on_full: OnFull,
+    flush_on: FlushOn,
+) -> (AppenderWriter, AppenderWriterGuard) {
+    match flush_on {
+        FlushOn::Never => {
+            let (writer, guard, handle) = buffer::non_blocking(writer, name, buffer_size, on_full);
+            (
+                AppenderWriter::NonBlocking(writer),
+                AppenderWriterGuard::Worker(guard, handle),
+            )
+        }
+        FlushOn::Error | FlushOn::EveryEvent => {
+            let writer = SyncWriter::new(writer, flush_on);
+            (
+                AppenderWriter::Sync(writer.clone()),
+                AppenderWriterGuard::Sync(writer),
+            )
+        }
+    }
+}
@@ -0,0 +1,212 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    sync::{
+        Arc, RwLock,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use crate::admin::{AdminResponse, AdminRoutes};
+
+/// Counter is a monotonically increasing metric.
+#[derive(Debug, Clone, Default)]
+pub struct Counter(Arc<AtomicU64>);
+
+impl Counter {
+    pub fn inc(&self) {
+        self.add(1);
+    }
+
+    pub fn add(&self, n: u64) {
+        self.0.fetch_add(n, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Gauge is a metric which can go up and down.
+#[derive(Debug, Clone, Default)]
+pub struct Gauge(Arc<AtomicU64>);
+
+impl Gauge {
+    pub fn set(&self, value: f64) {
+        self.0.store(value.to_bits(), Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> f64 {
+        f64::from_bits(self.0.load(Ordering::Relaxed))
+    }
+}
+
+type GaugeFn = Arc<dyn Fn() -> f64 + Send + Sync>;
+
+#[derive(Clone)]
+enum Sample {
+    Counter(Counter),
+    Gauge(Gauge),
+    GaugeFn(GaugeFn),
+}
+
+impl Sample {
+    fn value(&self) -> f64 {
+        match self {
+            Sample::Counter(counter) => counter.get() as f64,
+            Sample::Gauge(gauge) => gauge.get(),
+            Sample::GaugeFn(f) => f(),
+        }
+    }
+}
+
+struct Family {
+    help: String,
+    kind: &'static str,
+    /// samples by rendered label set, `""` without labels.
+    samples: BTreeMap<String, Sample>,
+}
+
+/// MetricsRegistry holds the counters and gauges of the process, rendered in the Prometheus
+/// text format.
+///
+/// # Example
+/// ```
+/// use beaver_bootstrap::metrics::MetricsRegistry;
+/// let metrics = MetricsRegistry::default();
+/// metrics.counter("jobs_total", "jobs run").add(2);
+/// metrics
+///     .gauge_with_labels("queue_size", "queued jobs", &[("queue", "high")])
+///     .set(3.0);
+/// let text = metrics.render();
+/// assert!(text.contains("jobs_total 2"));
+/// assert!(text.contains("queue_size{queue=\"high\"} 3"));
+/// ```
+#[derive(Clone, Default)]
+pub struct MetricsRegistry {
+    families: Arc<RwLock<BTreeMap<String, Family>>>,
+}
+
+impl std::fmt::Debug for MetricsRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let names: Vec<String> = self
+            .families
+            .read()
+            .map(|x| x.keys().cloned().collect())
+            .unwrap_or_default();
+        f.debug_struct("MetricsRegistry")
+            .field("metrics", &names)
+            .finish()
+    }
+}
+
+impl MetricsRegistry {
+    pub fn counter(&self, name: &str, help: &str) -> Counter {
+        self.counter_with_labels(name, help, &[])
+    }
+
+    /// the counter of `name` and `labels`, created on first use.
+    pub fn counter_with_labels(&self, name: &str, help: &str, labels: &[(&str, &str)]) -> Counter {
+        let sample = self.sample(name, help, "counter", labels, || {
+            Sample::Counter(Counter::default())
+        });
+        match sample {
+            Sample::Counter(counter) => counter,
+            _ => Counter::default(),
+        }
+    }
+
+    pub fn gauge(&self, name: &str, help: &str) -> Gauge {
+        self.gauge_with_labels(name, help, &[])
+    }
+
+    /// the gauge of `name` and `labels`, created on first use.
+    pub fn gauge_with_labels(&self, name: &str, help: &str, labels: &[(&str, &str)]) -> Gauge {
+        let sample = self.sample(name, help, "gauge", labels, || {
+            Sample::Gauge(Gauge::default())
+        });
+        match sample {
+            Sample::Gauge(gauge) => gauge,
+            _ => Gauge::default(),
+        }
+    }
+
+    /// register a gauge computed by `f` each time metrics are rendered.
+    pub fn gauge_fn<F>(&self, name: &str, help: &str, f: F)
+    where
+        F: Fn() -> f64 + Send + Sync + 'static,
+    {
+        let f: GaugeFn = Arc::new(f);
+        self.sample(name, help, "gauge", &[], || Sample::GaugeFn(f.clone()));
+    }
+
+    fn sample(
+        &self,
+        name: &str,
+        help: &str,
+        kind: &'static str,
+        labels: &[(&str, &str)],
+        create: impl FnOnce() -> Sample,
+    ) -> Sample {
+        let mut families = self.families.write().unwrap_or_else(|e| e.into_inner());
+        let family = families.entry(name.to_string()).or_insert_with(|| Family {
+            help: help.to_string(),
+            kind,
+            samples: BTreeMap::new(),
+        });
+        family
+            .samples
+            .entry(render_labels(labels))
+            .or_insert_with(create)
+            .clone()
+    }
+
+    /// current value of `name` without labels.
+    pub fn value(&self, name: &str) -> Option<f64> {
+        let families = self.families.read().ok()?;
+        families.get(name)?.samples.get("").map(Sample::value)
+    }
+
+    /// render every metric in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let families = self.families.read().unwrap_or_else(|e| e.into_inner());
+        for (name, family) in families.iter() {
+            let _ = writeln!(out, "# HELP {} {}", name, family.help);
+            let _ = writeln!(out, "# TYPE {} {}", name, family.kind);
+            for (labels, sample) in &family.samples {
+                let _ = writeln!(out, "{}{} {}", name, labels, sample.value());
+            }
+        }
+        out
+    }
+
+    /// register `/metrics` on the admin routes.
+    pub fn register_routes(&self, routes: &AdminRoutes) {
+        let metrics = self.clone();
+        routes.route("/metrics", move |_| {
+            AdminResponse::new(
+                200,
+                "text/plain; version=0.0.4",
+                metrics.render().into_bytes(),
+            )
+        });
+    }
+}
+
+fn render_labels(labels: &[(&str, &str)]) -> String {
+    if labels.is_empty() {
+        return String::new();
+    }
+    let labels: Vec<String> = labels
+        .iter()
+        .map(|(k, v)| {
+            let v = v
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n");
+            format!("{}=\"{}\"", k, v)
+        })
+        .collect();
+    format!("{{{}}}", labels.join(","))
+}
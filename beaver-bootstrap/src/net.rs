@@ -0,0 +1,129 @@
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    net::{SocketAddr, TcpStream, ToSocketAddrs},
+    time::Duration,
+};
+
+/// HttpUrl is a parsed `http://host[:port][/path]` url.
+///
+/// # Example
+/// ```
+/// use beaver_bootstrap::net::HttpUrl;
+/// let url: HttpUrl = "http://localhost:8080/hooks/alert".parse().unwrap();
+/// assert_eq!(url.host(), "localhost");
+/// assert_eq!(url.port(), 8080);
+/// assert_eq!(url.path(), "/hooks/alert");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpUrl {
+    host: String,
+    port: u16,
+    path: String,
+}
+
+impl std::str::FromStr for HttpUrl {
+    type Err = String;
+
+    fn from_str(url: &str) -> Result<Self, Self::Err> {
+        let Some(rest) = url.strip_prefix("http://") else {
+            return Err(format!("{}: only http:// urls are supported", url));
+        };
+        let (authority, path) = match rest.find('/') {
+            Some(i) => (&rest[..i], &rest[i..]),
+            None => (rest, "/"),
+        };
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => {
+                let port = port
+                    .parse::<u16>()
+                    .map_err(|e| format!("{}: invalid port: {}", url, e))?;
+                (host, port)
+            }
+            _ => (authority, 80),
+        };
+        if host.is_empty() {
+            return Err(format!("{}: missing host", url));
+        }
+        Ok(Self {
+            host: host.to_string(),
+            port,
+            path: path.to_string(),
+        })
+    }
+}
+
+impl std::fmt::Display for HttpUrl {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "http://{}:{}{}", self.host, self.port, self.path)
+    }
+}
+
+impl HttpUrl {
+    pub fn host(&self) -> &str {
+        self.host.as_str()
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    pub fn path(&self) -> &str {
+        self.path.as_str()
+    }
+}
+
+/// send a plain HTTP/1.1 request and return the response status code.
+///
+/// The connection is closed after the response head; the response body is not read.
+pub fn http_request(
+    method: &str,
+    url: &HttpUrl,
+    content_type: &str,
+    body: &[u8],
+    timeout: Duration,
+) -> io::Result<u16> {
+    let addr = resolve(url.host(), url.port())?;
+    http_request_to(addr, method, url, content_type, body, timeout)
+}
+
+/// the first address of `host`.
+pub fn resolve(host: &str, port: u16) -> io::Result<SocketAddr> {
+    (host, port)
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, host.to_string()))
+}
+
+/// like [`http_request`], connecting to `addr`, an address of the host of `url` resolved
+/// already.
+pub fn http_request_to(
+    addr: SocketAddr,
+    method: &str,
+    url: &HttpUrl,
+    content_type: &str,
+    body: &[u8],
+    timeout: Duration,
+) -> io::Result<u16> {
+    let mut stream = TcpStream::connect_timeout(&addr, timeout)?;
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+    write!(
+        stream,
+        "{} {} HTTP/1.1\r\nHost: {}:{}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        method,
+        url.path(),
+        url.host(),
+        url.port(),
+        content_type,
+        body.len()
+    )?;
+    stream.write_all(body)?;
+    stream.flush()?;
+    let mut status_line = String::new();
+    BufReader::new(stream).read_line(&mut status_line)?;
+    status_line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|x| x.parse::<u16>().ok())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, status_line.trim().to_string()))
+}
@@ -0,0 +1,378 @@
+use std::{
+    collections::BTreeMap,
+    env,
+    net::{IpAddr, SocketAddr, ToSocketAddrs},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    clock::Clock,
+    config::{Config, ConfigPrefix},
+    error::BootstrapError,
+    fs::Fs,
+};
+
+/// file of the kernel release, linux only.
+const KERNEL_RELEASE: &str = "/proc/sys/kernel/osrelease";
+
+/// earliest plausible wall clock, 2024-01-01T00:00:00Z.
+const MIN_PLAUSIBLE_TIME: Duration = Duration::from_secs(1_704_067_200);
+
+/// PreflightCheck is an environment requirement verified before modules are initialized.
+///
+/// # Example
+/// ```
+/// use beaver_bootstrap::preflight::PreflightCheck;
+/// struct HasCpus;
+/// impl PreflightCheck for HasCpus {
+///     fn name(&self) -> String {
+///         "cpus".to_string()
+///     }
+///     fn check(&self) -> Result<(), String> {
+///         std::thread::available_parallelism()
+///             .map(|_| ())
+///             .map_err(|e| e.to_string())
+///     }
+/// }
+/// ```
+pub trait PreflightCheck: Send + Sync {
+    /// name of the check, used as key of `preflight.severity`.
+    fn name(&self) -> String;
+    /// verify the requirement, describing what is missing on failure.
+    fn check(&self) -> Result<(), String>;
+}
+
+/// Severity is what a failed check does to the startup.
+#[derive(Debug, Default, Copy, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    /// abort the startup.
+    #[default]
+    Fail,
+    /// log a warning and continue.
+    Warn,
+    /// do not run the check.
+    Skip,
+}
+
+/// PreflightConfig declares built-in checks and per check severity, see `[preflight]`.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct PreflightConfig {
+    /// directories which must exist, or be creatable, and be writable.
+    writable_dirs: Vec<PathBuf>,
+    /// host names which must resolve, `host`, `host:port` or an ip address, `[::1]:53`.
+    dns: Vec<String>,
+    /// environment variables which must be set and not empty.
+    env: Vec<String>,
+    /// minimum kernel release, `major.minor`, linux only.
+    min_kernel: Option<String>,
+    /// minimum glibc version, `major.minor`, linux gnu only.
+    min_glibc: Option<String>,
+    /// check the wall clock is set.
+    clock: bool,
+    /// severity by check name, `fail` when absent.
+    severity: BTreeMap<String, Severity>,
+}
+
+impl ConfigPrefix for PreflightConfig {
+    const PREFIX: &'static str = "preflight";
+}
+
+impl PreflightConfig {
+    pub fn new(config: &Config) -> Result<Self, BootstrapError> {
+        config
+            .get::<PreflightConfig>()
+            .map_err(BootstrapError::ConfigLoadError)
+    }
+
+    pub fn severity(&self, name: &str) -> Severity {
+        self.severity.get(name).copied().unwrap_or_default()
+    }
+
+    /// the built-in checks declared by this config, reading files through `fs` and the time
+    /// of `clock`.
+    pub fn checks(&self, fs: &Arc<dyn Fs>, clock: &Arc<dyn Clock>) -> Vec<Box<dyn PreflightCheck>> {
+        let mut checks: Vec<Box<dyn PreflightCheck>> = Vec::new();
+        for dir in &self.writable_dirs {
+            checks.push(Box::new(WritableDirCheck::new(fs.clone(), dir.clone())));
+        }
+        for host in &self.dns {
+            checks.push(Box::new(DnsCheck(host.clone())));
+        }
+        if !self.env.is_empty() {
+            checks.push(Box::new(EnvVarCheck(self.env.clone())));
+        }
+        if let Some(version) = &self.min_kernel {
+            checks.push(Box::new(KernelVersionCheck::new(
+                fs.clone(),
+                version.clone(),
+            )));
+        }
+        if let Some(version) = &self.min_glibc {
+            checks.push(Box::new(GlibcVersionCheck(version.clone())));
+        }
+        if self.clock {
+            checks.push(Box::new(ClockCheck(clock.clone())));
+        }
+        checks
+    }
+}
+
+/// WritableDirCheck requires a directory to be creatable and writable.
+pub struct WritableDirCheck {
+    fs: Arc<dyn Fs>,
+    dir: PathBuf,
+}
+
+impl WritableDirCheck {
+    pub fn new(fs: Arc<dyn Fs>, dir: impl Into<PathBuf>) -> Self {
+        Self {
+            fs,
+            dir: dir.into(),
+        }
+    }
+}
+
+impl PreflightCheck for WritableDirCheck {
+    fn name(&self) -> String {
+        format!("writable_dir:{}", self.dir.display())
+    }
+
+    fn check(&self) -> Result<(), String> {
+        self.fs
+            .create_dir_all(&self.dir)
+            .map_err(|e| e.to_string())?;
+        let probe = self
+            .dir
+            .join(format!(".beaver-preflight-{}", std::process::id()));
+        self.fs.write(&probe, b"").map_err(|e| e.to_string())?;
+        let _ = self.fs.remove_file(&probe);
+        Ok(())
+    }
+}
+
+/// DnsCheck requires a host name to resolve.
+pub struct DnsCheck(pub String);
+
+impl PreflightCheck for DnsCheck {
+    fn name(&self) -> String {
+        format!("dns:{}", self.0)
+    }
+
+    fn check(&self) -> Result<(), String> {
+        // an address, `[::1]:53`, `::1` or `10.0.0.1`, needs no lookup
+        if self.0.parse::<SocketAddr>().is_ok()
+            || self.0.trim_matches(['[', ']']).parse::<IpAddr>().is_ok()
+        {
+            return Ok(());
+        }
+        let (host, port) = match self.0.rsplit_once(':') {
+            Some((host, port)) => {
+                let port = port
+                    .parse::<u16>()
+                    .map_err(|_| format!("invalid port {:?}", port))?;
+                (host, port)
+            }
+            None => (self.0.as_str(), 0),
+        };
+        match (host, port).to_socket_addrs().map(|mut addrs| addrs.next()) {
+            Ok(Some(_)) => Ok(()),
+            Ok(None) => Err("no address".to_string()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+}
+
+/// EnvVarCheck requires environment variables to be set and not empty.
+pub struct EnvVarCheck(pub Vec<String>);
+
+impl PreflightCheck for EnvVarCheck {
+    fn name(&self) -> String {
+        "env".to_string()
+    }
+
+    fn check(&self) -> Result<(), String> {
+        let missing: Vec<&str> = self
+            .0
+            .iter()
+            .filter(|name| env::var(name).map(|v| v.is_empty()).unwrap_or(true))
+            .map(String::as_str)
+            .collect();
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(format!("missing {}", missing.join(", ")))
+        }
+    }
+}
+
+/// ClockCheck requires the wall clock to be set to a plausible time.
+pub struct ClockCheck(pub Arc<dyn Clock>);
+
+impl PreflightCheck for ClockCheck {
+    fn name(&self) -> String {
+        "clock".to_string()
+    }
+
+    fn check(&self) -> Result<(), String> {
+        let now = self
+            .0
+            .now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|e| e.to_string())?;
+        if now < MIN_PLAUSIBLE_TIME {
+            return Err(format!("wall clock is {}s since epoch", now.as_secs()));
+        }
+        Ok(())
+    }
+}
+
+/// KernelVersionCheck requires a minimum kernel release.
+pub struct KernelVersionCheck {
+    fs: Arc<dyn Fs>,
+    minimum: String,
+}
+
+impl KernelVersionCheck {
+    pub fn new(fs: Arc<dyn Fs>, minimum: impl Into<String>) -> Self {
+        Self {
+            fs,
+            minimum: minimum.into(),
+        }
+    }
+}
+
+impl PreflightCheck for KernelVersionCheck {
+    fn name(&self) -> String {
+        "min_kernel".to_string()
+    }
+
+    fn check(&self) -> Result<(), String> {
+        let release = self
+            .fs
+            .read_to_string(Path::new(KERNEL_RELEASE))
+            .map_err(|e| format!("unable to read kernel release: {}", e))?;
+        require_version("kernel", release.trim(), &self.minimum)
+    }
+}
+
+/// GlibcVersionCheck requires a minimum glibc version.
+pub struct GlibcVersionCheck(pub String);
+
+impl PreflightCheck for GlibcVersionCheck {
+    fn name(&self) -> String {
+        "min_glibc".to_string()
+    }
+
+    #[cfg(all(target_os = "linux", target_env = "gnu"))]
+    fn check(&self) -> Result<(), String> {
+        // SAFETY: gnu_get_libc_version returns a static nul terminated string.
+        let version = unsafe { std::ffi::CStr::from_ptr(libc::gnu_get_libc_version()) };
+        require_version("glibc", &version.to_string_lossy(), &self.0)
+    }
+
+    #[cfg(not(all(target_os = "linux", target_env = "gnu")))]
+    fn check(&self) -> Result<(), String> {
+        Err("not linked against glibc".to_string())
+    }
+}
+
+/// leading numeric components of a version, `5.15.0-91-generic` is `[5, 15, 0]`.
+fn version_numbers(version: &str) -> Vec<u64> {
+    version
+        .split(['.', '-', '+'])
+        .map_while(|x| x.parse::<u64>().ok())
+        .collect()
+}
+
+/// fail when `actual` is older than `minimum`, or when `minimum` is not `major.minor...`.
+fn require_version(what: &str, actual: &str, minimum: &str) -> Result<(), String> {
+    let mut minimum_numbers = minimum
+        .split('.')
+        .map(|x| x.parse::<u64>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| format!("invalid minimum {} version {:?}", what, minimum))?;
+    let mut actual_numbers = version_numbers(actual);
+    // missing components are 0, so `2.31` satisfies `2.31.0`
+    let len = actual_numbers.len().max(minimum_numbers.len());
+    actual_numbers.resize(len, 0);
+    minimum_numbers.resize(len, 0);
+    if actual_numbers < minimum_numbers {
+        return Err(format!("{} {} is older than {}", what, actual, minimum));
+    }
+    Ok(())
+}
+
+/// PreflightResult is the outcome of one check.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreflightResult {
+    pub name: String,
+    pub severity: Severity,
+    pub error: Option<String>,
+}
+
+/// PreflightReport gathers the outcome of every check.
+#[derive(Debug, Clone, Default)]
+pub struct PreflightReport {
+    results: Vec<PreflightResult>,
+}
+
+impl PreflightReport {
+    /// run every check not skipped by its severity.
+    pub fn run<'a>(
+        checks: impl IntoIterator<Item = &'a dyn PreflightCheck>,
+        config: &PreflightConfig,
+    ) -> Self {
+        let results = checks
+            .into_iter()
+            .map(|check| (check.name(), check))
+            .map(|(name, check)| {
+                let severity = config.severity(&name);
+                let error = match severity {
+                    Severity::Skip => None,
+                    _ => check.check().err(),
+                };
+                PreflightResult {
+                    name,
+                    severity,
+                    error,
+                }
+            })
+            .collect();
+        Self { results }
+    }
+
+    pub fn results(&self) -> &[PreflightResult] {
+        &self.results
+    }
+
+    fn failed(&self, severity: Severity) -> impl Iterator<Item = &PreflightResult> {
+        self.results
+            .iter()
+            .filter(move |x| x.severity == severity && x.error.is_some())
+    }
+
+    /// log the warnings, then fail with every failed check at once.
+    pub fn into_result(self) -> Result<Self, BootstrapError> {
+        for result in self.failed(Severity::Warn) {
+            tracing::warn!(
+                "preflight check {} failed: {}",
+                result.name,
+                result.error.as_deref().unwrap_or_default()
+            );
+        }
+        let failures: Vec<String> = self
+            .failed(Severity::Fail)
+            .map(|x| format!("{}: {}", x.name, x.error.as_deref().unwrap_or_default()))
+            .collect();
+        if !failures.is_empty() {
+            return Err(BootstrapError::PreflightCheckError(failures.join("; ")));
+        }
+        Ok(self)
+    }
+}
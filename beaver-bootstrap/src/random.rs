@@ -0,0 +1,118 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use rand::{Rng, RngCore, SeedableRng, rngs::StdRng};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::{Config, ConfigPrefix},
+    error::BootstrapError,
+};
+
+/// RngProvider is the source of randomness of beaver's services, the random bits of ids and
+/// the jitter of backoffs.
+///
+/// [`ThreadRngProvider`] is used by default; [`SeededRngProvider`] replays the same sequence
+/// for a seed, making test runs reproducible.
+pub trait RngProvider: Send + Sync {
+    fn next_u64(&self) -> u64;
+
+    fn fill_bytes(&self, dest: &mut [u8]);
+
+    /// uniform value in `[0, 1)`.
+    fn next_f64(&self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// `base` randomly spread by up to `ratio` either way, used for jittered backoff.
+    fn jitter(&self, base: Duration, ratio: f64) -> Duration {
+        let factor = 1.0 + ratio.clamp(0.0, 1.0) * (self.next_f64() * 2.0 - 1.0);
+        base.mul_f64(factor)
+    }
+}
+
+/// ThreadRngProvider draws from the thread local generator of `rand`, seeded by the OS.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ThreadRngProvider;
+
+impl RngProvider for ThreadRngProvider {
+    fn next_u64(&self) -> u64 {
+        rand::rng().random()
+    }
+
+    fn fill_bytes(&self, dest: &mut [u8]) {
+        rand::rng().fill_bytes(dest)
+    }
+}
+
+/// SeededRngProvider is a deterministic generator shared by all callers.
+///
+/// # Example
+/// ```
+/// use beaver_bootstrap::random::{RngProvider, SeededRngProvider};
+/// let a = SeededRngProvider::new(42);
+/// let b = SeededRngProvider::new(42);
+/// assert_eq!(a.next_u64(), b.next_u64());
+/// ```
+#[derive(Debug)]
+pub struct SeededRngProvider {
+    rng: Mutex<StdRng>,
+}
+
+impl SeededRngProvider {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: Mutex::new(StdRng::seed_from_u64(seed)),
+        }
+    }
+}
+
+impl RngProvider for SeededRngProvider {
+    fn next_u64(&self) -> u64 {
+        self.rng
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .next_u64()
+    }
+
+    fn fill_bytes(&self, dest: &mut [u8]) {
+        self.rng
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .fill_bytes(dest)
+    }
+}
+
+/// RandomConfig selects the random source, see `[random]`.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct RandomConfig {
+    /// fixed seed for reproducible runs, OS seeded when absent.
+    seed: Option<u64>,
+}
+
+impl ConfigPrefix for RandomConfig {
+    const PREFIX: &'static str = "random";
+}
+
+impl RandomConfig {
+    pub fn new(config: &Config) -> Result<Self, BootstrapError> {
+        config
+            .get::<RandomConfig>()
+            .map_err(BootstrapError::ConfigLoadError)
+    }
+
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    /// the provider selected by this config.
+    pub fn provider(&self) -> Arc<dyn RngProvider> {
+        match self.seed {
+            Some(seed) => Arc::new(SeededRngProvider::new(seed)),
+            None => Arc::new(ThreadRngProvider),
+        }
+    }
+}
@@ -0,0 +1,168 @@
+use std::{
+    future::Future,
+    sync::{Mutex, RwLock},
+    time::{Duration, Instant},
+};
+
+use di::{Ref, ServiceCollection, ServiceProvider, scoped_factory};
+
+use crate::{id::REQUEST_ID_FIELD, log::context::LogContextExt};
+
+/// RequestContext holds the values of the request being handled, injectable as
+/// `Ref<RequestContext>` from a [`RequestScope`].
+#[derive(Debug, Clone, Default)]
+pub struct RequestContext {
+    request_id: String,
+    deadline: Option<Instant>,
+    principal: Option<String>,
+}
+
+impl RequestContext {
+    pub fn new(request_id: impl Into<String>) -> Self {
+        Self {
+            request_id: request_id.into(),
+            ..Default::default()
+        }
+    }
+
+    /// the request must complete within `timeout` from now.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.deadline = Instant::now().checked_add(timeout);
+        self
+    }
+
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// the authenticated caller.
+    pub fn with_principal(mut self, principal: impl Into<String>) -> Self {
+        self.principal = Some(principal.into());
+        self
+    }
+
+    pub fn request_id(&self) -> &str {
+        &self.request_id
+    }
+
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    /// time left before the deadline, zero once it passed.
+    pub fn remaining(&self) -> Option<Duration> {
+        self.deadline
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+    }
+
+    pub fn principal(&self) -> Option<&str> {
+        self.principal.as_deref()
+    }
+}
+
+/// the request context of one scope, filled when the scope begins.
+#[derive(Default)]
+struct RequestSlot(Mutex<Option<Ref<RequestContext>>>);
+
+/// RequestScope is a child DI scope living as long as one request.
+///
+/// Scoped services resolved from [`RequestScope::provider`] are created once per request and
+/// dropped with the scope, which middleware of HTTP or gRPC modules ties to the completion of
+/// the response, see [`RequestScope::run`].
+///
+/// # Example
+/// ```
+/// use std::sync::RwLock;
+/// use beaver_bootstrap::request::{RequestContext, RequestScope};
+/// use di::{Ref, ServiceCollection};
+/// let binder = RwLock::new(ServiceCollection::new());
+/// RequestScope::register(&binder);
+/// let root = binder.read().unwrap().build_provider().unwrap();
+/// let scope = RequestScope::begin(&root, RequestContext::new("42").with_principal("alice"));
+/// let context = scope.provider().get_required::<RequestContext>();
+/// assert_eq!(context.request_id(), "42");
+/// assert_eq!(context.principal(), Some("alice"));
+/// ```
+pub struct RequestScope {
+    provider: ServiceProvider,
+    context: Ref<RequestContext>,
+}
+
+impl RequestScope {
+    /// register the scoped [`RequestContext`], done by the bootstrap for its provider.
+    pub fn register(binder: &RwLock<ServiceCollection>) {
+        let mut service_collection = binder.write().unwrap_or_else(|e| e.into_inner());
+        service_collection
+            .add(scoped_factory::<RequestSlot, _>(|_| {
+                Ref::new(RequestSlot::default())
+            }))
+            .add(scoped_factory::<RequestContext, _>(|provider| {
+                provider
+                    .get::<RequestSlot>()
+                    .and_then(|slot| slot.0.lock().ok()?.clone())
+                    .unwrap_or_default()
+            }));
+    }
+
+    /// create the scope of a request from the root provider.
+    pub fn begin(root: &ServiceProvider, context: RequestContext) -> Self {
+        let provider = root.create_scope();
+        let context = Ref::new(context);
+        if let Some(slot) = provider.get::<RequestSlot>()
+            && let Ok(mut slot) = slot.0.lock()
+        {
+            let _ = slot.insert(context.clone());
+        }
+        Self { provider, context }
+    }
+
+    /// the log context pairs of the request, to attach to its handler with
+    /// [`with_log_context`](LogContextExt::with_log_context), or with
+    /// [`LogContext::scope`](crate::log::context::LogContext::scope) when it is synchronous.
+    pub fn log_fields(&self) -> [(&str, &str); 1] {
+        [(REQUEST_ID_FIELD, self.context.request_id())]
+    }
+
+    pub fn provider(&self) -> &ServiceProvider {
+        &self.provider
+    }
+
+    pub fn context(&self) -> Ref<RequestContext> {
+        self.context.clone()
+    }
+
+    /// handle a request in its own scope, dropped once the returned future completes.
+    ///
+    /// The request id is in the log context of the handler. The future is `Send` when the one
+    /// of the handler is, so it can be spawned on the runtime:
+    ///
+    /// ```
+    /// use std::sync::RwLock;
+    /// use beaver_bootstrap::request::{RequestContext, RequestScope};
+    /// use di::ServiceCollection;
+    /// fn assert_send<T: Send>(_: T) {}
+    /// let binder = RwLock::new(ServiceCollection::new());
+    /// RequestScope::register(&binder);
+    /// let root = binder.read().unwrap().build_provider().unwrap();
+    /// assert_send(async {
+    ///     RequestScope::run(&root, RequestContext::new("1"), |_provider| async { 1 }).await
+    /// });
+    /// ```
+    pub async fn run<F, Fut>(
+        root: &ServiceProvider,
+        context: RequestContext,
+        handler: F,
+    ) -> Fut::Output
+    where
+        F: FnOnce(ServiceProvider) -> Fut,
+        Fut: Future,
+    {
+        let scope = Self::begin(root, context);
+        let output = handler(scope.provider.clone())
+            .with_log_context(&scope.log_fields())
+            .await;
+        drop(scope);
+        output
+    }
+}
@@ -0,0 +1,195 @@
+use std::{collections::HashMap, future::Future, io, pin::Pin, sync::Arc, time::Duration};
+
+use serde::{Deserialize, Serialize};
+use tokio::{runtime::Handle, sync::Semaphore, task::JoinSet};
+use tracing::Instrument;
+
+use crate::{
+    config::{Config, ConfigPrefix},
+    env::RuntimeEnv,
+    error::BootstrapError,
+    serde::duration_opt,
+};
+
+/// future of an asynchronous initialization, see
+/// [`Module::initialize`](crate::bootstrap::Module::initialize).
+pub type InitFuture =
+    Pin<Box<dyn Future<Output = Result<(), Box<dyn std::error::Error + Send + Sync>>> + Send>>;
+
+/// RuntimeConfig configures the managed tokio runtime, see `[runtime]`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RuntimeConfig {
+    /// worker threads, the [`RuntimeEnv::parallelism`] by default.
+    worker_threads: Option<usize>,
+    /// modules initialized at the same time.
+    init_concurrency: usize,
+    /// time limit of the initialization of one module, 30 seconds by default.
+    #[serde(deserialize_with = "duration_opt")]
+    init_timeout: Option<Duration>,
+    /// time limit of the disposal of one service at shutdown, 10 seconds by default.
+    #[serde(deserialize_with = "duration_opt")]
+    dispose_timeout: Option<Duration>,
+    /// time the application is given to return once asked to shut down, 30 seconds by
+    /// default.
+    #[serde(deserialize_with = "duration_opt")]
+    shutdown_timeout: Option<Duration>,
+}
+
+impl Default for RuntimeConfig {
+    fn default() -> Self {
+        Self {
+            worker_threads: None,
+            init_concurrency: 4,
+            init_timeout: None,
+            dispose_timeout: None,
+            shutdown_timeout: None,
+        }
+    }
+}
+
+impl ConfigPrefix for RuntimeConfig {
+    const PREFIX: &'static str = "runtime";
+}
+
+impl RuntimeConfig {
+    pub fn new(config: &Config) -> Result<Self, BootstrapError> {
+        let runtime_config = config
+            .get::<RuntimeConfig>()
+            .map_err(BootstrapError::ConfigLoadError)?;
+        if runtime_config.worker_threads == Some(0) {
+            return Err(BootstrapError::InvalidConfigValueError(
+                "runtime.worker_threads=0".to_string(),
+            ));
+        }
+        if runtime_config.init_concurrency == 0 {
+            return Err(BootstrapError::InvalidConfigValueError(
+                "runtime.init_concurrency=0".to_string(),
+            ));
+        }
+        Ok(runtime_config)
+    }
+
+    pub fn init_concurrency(&self) -> usize {
+        self.init_concurrency
+    }
+
+    pub fn init_timeout(&self) -> Duration {
+        self.init_timeout.unwrap_or(Duration::from_secs(30))
+    }
+
+    pub fn dispose_timeout(&self) -> Duration {
+        self.dispose_timeout.unwrap_or(Duration::from_secs(10))
+    }
+
+    pub fn shutdown_timeout(&self) -> Duration {
+        self.shutdown_timeout.unwrap_or(Duration::from_secs(30))
+    }
+}
+
+/// ManagedRuntime is the tokio runtime owned by the bootstrap.
+///
+/// Dropping it shuts the runtime down in the background, so it may be dropped from within
+/// an asynchronous context.
+///
+/// # Example
+/// ```
+/// use beaver_bootstrap::runtime::{ManagedRuntime, RuntimeConfig};
+/// let runtime = ManagedRuntime::new(&RuntimeConfig::default()).unwrap();
+/// assert_eq!(runtime.run(async { 1 + 1 }), 2);
+/// ```
+pub struct ManagedRuntime {
+    runtime: Option<tokio::runtime::Runtime>,
+}
+
+impl std::fmt::Debug for ManagedRuntime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ManagedRuntime").finish()
+    }
+}
+
+impl ManagedRuntime {
+    pub fn new(config: &RuntimeConfig) -> io::Result<Self> {
+        Self::with_env(config, &RuntimeEnv::detect())
+    }
+
+    /// like [`ManagedRuntime::new`], sized after the limits of `env`.
+    pub fn with_env(config: &RuntimeConfig, env: &RuntimeEnv) -> io::Result<Self> {
+        let mut builder = tokio::runtime::Builder::new_multi_thread();
+        builder
+            .thread_name("beaver-runtime")
+            .enable_all()
+            .worker_threads(config.worker_threads.unwrap_or(env.parallelism()));
+        Ok(Self {
+            runtime: Some(builder.build()?),
+        })
+    }
+
+    pub fn handle(&self) -> &Handle {
+        match &self.runtime {
+            Some(runtime) => runtime.handle(),
+            None => unreachable!("runtime is only taken on drop"),
+        }
+    }
+
+    /// run `future` on the runtime and block the caller until it completes.
+    ///
+    /// Unlike `block_on`, the caller may itself be running inside another runtime.
+    pub fn run<F>(&self, future: F) -> F::Output
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        self.handle().spawn(async move {
+            let _ = sender.send(future.await);
+        });
+        match receiver.recv() {
+            Ok(output) => output,
+            Err(_) => panic!("task of the managed runtime panicked"),
+        }
+    }
+}
+
+impl Drop for ManagedRuntime {
+    fn drop(&mut self) {
+        if let Some(runtime) = self.runtime.take() {
+            runtime.shutdown_background();
+        }
+    }
+}
+
+/// run named initializations, at most `concurrency` at a time and each within `timeout`,
+/// returning the names and errors of the failed ones in completion order.
+pub async fn run_initializers(
+    initializers: Vec<(String, InitFuture)>,
+    concurrency: usize,
+    timeout: Duration,
+) -> Vec<(String, String)> {
+    let permits = Arc::new(Semaphore::new(concurrency.max(1)));
+    let mut tasks = JoinSet::new();
+    let mut names = HashMap::new();
+    for (name, future) in initializers {
+        let permits = permits.clone();
+        let span = tracing::info_span!("module", name = %name, step = "initialize");
+        let task = tasks.spawn(async move {
+            let _permit = permits.acquire_owned().await;
+            match tokio::time::timeout(timeout, future.instrument(span)).await {
+                Ok(Ok(())) => Ok(()),
+                Ok(Err(e)) => Err(e.to_string()),
+                Err(_) => Err(format!("timed out after {:?}", timeout)),
+            }
+        });
+        names.insert(task.id(), name);
+    }
+    let mut failures = vec![];
+    while let Some(joined) = tasks.join_next_with_id().await {
+        let (id, error) = match joined {
+            Ok((_, Ok(()))) => continue,
+            Ok((id, Err(e))) => (id, e),
+            Err(e) => (e.id(), e.to_string()),
+        };
+        failures.push((names.remove(&id).unwrap_or_default(), error));
+    }
+    failures
+}
@@ -0,0 +1,263 @@
+use std::sync::mpsc::Receiver;
+
+/// ServiceEvent is a control of the Windows service control manager, translated for the
+/// application.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ServiceEvent {
+    /// the service is stopped, or the system shuts down.
+    Stop,
+    Pause,
+    Continue,
+}
+
+/// ServiceState is a status reported to the service control manager.
+///
+/// `Stopped` is reported by `run` once the application returns.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ServiceState {
+    /// the application is starting, reported again to show progress of a long bootstrap.
+    StartPending,
+    Running,
+    PausePending,
+    Paused,
+    ContinuePending,
+    StopPending,
+}
+
+/// ServiceContext connects an application to the service control manager: it receives the
+/// controls and reports the status transitions.
+///
+/// # Example
+/// ```
+/// use std::{cell::RefCell, rc::Rc, sync::mpsc};
+/// use beaver_bootstrap::scm::{ServiceContext, ServiceEvent, ServiceState};
+/// let (sender, receiver) = mpsc::channel();
+/// let states = Rc::new(RefCell::new(vec![]));
+/// let reported = states.clone();
+/// let service = ServiceContext::new(receiver, move |x| reported.borrow_mut().push(x));
+/// for event in [ServiceEvent::Pause, ServiceEvent::Continue, ServiceEvent::Stop] {
+///     sender.send(event).unwrap();
+/// }
+/// service.run_until_stop(|_| {});
+/// assert_eq!(
+///     *states.borrow(),
+///     [
+///         ServiceState::Running,
+///         ServiceState::PausePending,
+///         ServiceState::Paused,
+///         ServiceState::ContinuePending,
+///         ServiceState::Running,
+///         ServiceState::StopPending,
+///     ]
+/// );
+/// ```
+pub struct ServiceContext {
+    events: Receiver<ServiceEvent>,
+    report: Box<dyn Fn(ServiceState)>,
+}
+
+impl std::fmt::Debug for ServiceContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ServiceContext").finish_non_exhaustive()
+    }
+}
+
+impl ServiceContext {
+    pub fn new<F>(events: Receiver<ServiceEvent>, report: F) -> Self
+    where
+        F: Fn(ServiceState) + 'static,
+    {
+        Self {
+            events,
+            report: Box::new(report),
+        }
+    }
+
+    pub fn report(&self, state: ServiceState) {
+        (self.report)(state);
+    }
+
+    /// wait for the next control, `None` once the control manager is gone.
+    pub fn next_event(&self) -> Option<ServiceEvent> {
+        self.events.recv().ok()
+    }
+
+    /// report `Running`, then follow the controls until a stop.
+    ///
+    /// `on_event` is called on every control, between the pending and the final state of
+    /// pauses and resumes. `StopPending` is reported on return, the application then shuts the
+    /// bootstrap down.
+    pub fn run_until_stop<F>(&self, mut on_event: F)
+    where
+        F: FnMut(ServiceEvent),
+    {
+        self.report(ServiceState::Running);
+        loop {
+            match self.next_event().unwrap_or(ServiceEvent::Stop) {
+                ServiceEvent::Pause => {
+                    self.report(ServiceState::PausePending);
+                    on_event(ServiceEvent::Pause);
+                    self.report(ServiceState::Paused);
+                }
+                ServiceEvent::Continue => {
+                    self.report(ServiceState::ContinuePending);
+                    on_event(ServiceEvent::Continue);
+                    self.report(ServiceState::Running);
+                }
+                ServiceEvent::Stop => break,
+            }
+        }
+        tracing::info!("windows service stopping");
+        on_event(ServiceEvent::Stop);
+        self.report(ServiceState::StopPending);
+    }
+}
+
+#[cfg(windows)]
+pub use self::dispatcher::run;
+
+#[cfg(windows)]
+mod dispatcher {
+    use std::{
+        cell::Cell,
+        ffi::OsString,
+        sync::{Mutex, mpsc},
+        time::Duration,
+    };
+
+    use windows_service::{
+        define_windows_service,
+        service::{
+            ServiceControl, ServiceControlAccept, ServiceExitCode, ServiceStatus, ServiceType,
+        },
+        service_control_handler::{self, ServiceControlHandlerResult, ServiceStatusHandle},
+        service_dispatcher,
+    };
+
+    use super::{ServiceContext, ServiceEvent, ServiceState};
+    use crate::error::BootstrapError;
+
+    /// time the control manager waits for the next report of a pending state.
+    const PENDING_WAIT_HINT: Duration = Duration::from_secs(30);
+
+    type App = Box<dyn FnOnce(ServiceContext) -> Result<(), Box<dyn std::error::Error>> + Send>;
+
+    /// the application started by the dispatcher, there is one service per process.
+    static SERVICE: Mutex<Option<(String, App)>> = Mutex::new(None);
+
+    define_windows_service!(ffi_service_main, service_main);
+
+    /// run `app` as the Windows service `name`, blocking until it stopped.
+    ///
+    /// `StartPending` is reported before `app` is called and `Stopped` after it returned, with
+    /// a service specific exit code of 1 on error.
+    pub fn run<F>(name: &str, app: F) -> Result<(), BootstrapError>
+    where
+        F: FnOnce(ServiceContext) -> Result<(), Box<dyn std::error::Error>> + Send + 'static,
+    {
+        *SERVICE.lock().unwrap_or_else(|e| e.into_inner()) =
+            Some((name.to_string(), Box::new(app)));
+        service_dispatcher::start(name, ffi_service_main)
+            .map_err(|e| BootstrapError::WindowsServiceError(Box::new(e)))
+    }
+
+    fn service_main(_arguments: Vec<OsString>) {
+        let Some((name, app)) = SERVICE.lock().unwrap_or_else(|e| e.into_inner()).take() else {
+            return;
+        };
+        let (sender, receiver) = mpsc::channel();
+        let handler = move |control| {
+            let event = match control {
+                ServiceControl::Stop | ServiceControl::Shutdown => ServiceEvent::Stop,
+                ServiceControl::Pause => ServiceEvent::Pause,
+                ServiceControl::Continue => ServiceEvent::Continue,
+                ServiceControl::Interrogate => return ServiceControlHandlerResult::NoError,
+                _ => return ServiceControlHandlerResult::NotImplemented,
+            };
+            let _ = sender.send(event);
+            ServiceControlHandlerResult::NoError
+        };
+        let status_handle = match service_control_handler::register(&name, handler) {
+            Ok(status_handle) => status_handle,
+            Err(e) => {
+                tracing::error!("failed to register windows service {}: {}", name, e);
+                return;
+            }
+        };
+        let checkpoint = Cell::new(0);
+        let service = ServiceContext::new(receiver, move |state| {
+            let current_state = match state {
+                ServiceState::StartPending => windows_service::service::ServiceState::StartPending,
+                ServiceState::Running => windows_service::service::ServiceState::Running,
+                ServiceState::PausePending => windows_service::service::ServiceState::PausePending,
+                ServiceState::Paused => windows_service::service::ServiceState::Paused,
+                ServiceState::ContinuePending => {
+                    windows_service::service::ServiceState::ContinuePending
+                }
+                ServiceState::StopPending => windows_service::service::ServiceState::StopPending,
+            };
+            let pending = matches!(
+                state,
+                ServiceState::StartPending
+                    | ServiceState::PausePending
+                    | ServiceState::ContinuePending
+                    | ServiceState::StopPending
+            );
+            checkpoint.set(if pending { checkpoint.get() + 1 } else { 0 });
+            let controls_accepted = if pending {
+                ServiceControlAccept::empty()
+            } else {
+                ServiceControlAccept::STOP
+                    | ServiceControlAccept::SHUTDOWN
+                    | ServiceControlAccept::PAUSE_CONTINUE
+            };
+            set_status(
+                status_handle,
+                current_state,
+                controls_accepted,
+                ServiceExitCode::NO_ERROR,
+                checkpoint.get(),
+            );
+        });
+        service.report(ServiceState::StartPending);
+        let exit_code = match app(service) {
+            Ok(()) => ServiceExitCode::NO_ERROR,
+            Err(e) => {
+                tracing::error!("windows service {} failed: {}", name, e);
+                ServiceExitCode::ServiceSpecific(1)
+            }
+        };
+        set_status(
+            status_handle,
+            windows_service::service::ServiceState::Stopped,
+            ServiceControlAccept::empty(),
+            exit_code,
+            0,
+        );
+    }
+
+    fn set_status(
+        status_handle: ServiceStatusHandle,
+        current_state: windows_service::service::ServiceState,
+        controls_accepted: ServiceControlAccept,
+        exit_code: ServiceExitCode,
+        checkpoint: u32,
+    ) {
+        let status = ServiceStatus {
+            service_type: ServiceType::OWN_PROCESS,
+            current_state,
+            controls_accepted,
+            exit_code,
+            checkpoint,
+            wait_hint: if checkpoint > 0 {
+                PENDING_WAIT_HINT
+            } else {
+                Duration::ZERO
+            },
+            process_id: None,
+        };
+        if let Err(e) = status_handle.set_service_status(status) {
+            tracing::warn!("failed to report windows service status: {}", e);
+        }
+    }
+}
@@ -11,3 +11,90 @@ where
     }
     Ok(s)
 }
+
+/// parse a duration such as `500ms`, `30s`, `5m`, `12h` or `7d`; a bare number is seconds.
+///
+/// # Example
+/// ```
+/// use std::time::Duration;
+/// use beaver_bootstrap::serde::parse_duration;
+/// assert_eq!(parse_duration("2h"), Ok(Duration::from_secs(7200)));
+/// assert!(parse_duration("999999999999999999d").is_err());
+/// ```
+pub fn parse_duration(value: &str) -> Result<std::time::Duration, String> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("invalid duration: {:?}", value))?;
+    let multiplier: u64 = match unit.trim() {
+        "ms" => return Ok(std::time::Duration::from_millis(number)),
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 60 * 60 * 24,
+        "w" => 60 * 60 * 24 * 7,
+        _ => return Err(format!("invalid duration unit: {:?}", value)),
+    };
+    number
+        .checked_mul(multiplier)
+        .map(std::time::Duration::from_secs)
+        .ok_or_else(|| format!("duration overflows: {:?}", value))
+}
+
+/// parse a byte size such as `512`, `64KB`, `100MB` or `2GB` (binary multiples).
+pub fn parse_byte_size(value: &str) -> Result<u64, String> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("invalid byte size: {:?}", value))?;
+    let multiplier: u64 = match unit.trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" | "KIB" => 1 << 10,
+        "M" | "MB" | "MIB" => 1 << 20,
+        "G" | "GB" | "GIB" => 1 << 30,
+        "T" | "TB" | "TIB" => 1 << 40,
+        _ => return Err(format!("invalid byte size unit: {:?}", value)),
+    };
+    number
+        .checked_mul(multiplier)
+        .ok_or_else(|| format!("byte size overflows: {:?}", value))
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum NumberOrString {
+    Number(u64),
+    String(String),
+}
+
+/// deserialize an optional duration written as `"7d"` or as a number of seconds.
+pub fn duration_opt<'de, D>(des: D) -> Result<Option<std::time::Duration>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Option::<NumberOrString>::deserialize(des)? {
+        None => Ok(None),
+        Some(NumberOrString::Number(seconds)) => Ok(Some(std::time::Duration::from_secs(seconds))),
+        Some(NumberOrString::String(s)) => parse_duration(&s).map(Some).map_err(D::Error::custom),
+    }
+}
+
+/// deserialize an optional byte size written as `"2GB"` or as a number of bytes.
+pub fn byte_size_opt<'de, D>(des: D) -> Result<Option<u64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Option::<NumberOrString>::deserialize(des)? {
+        None => Ok(None),
+        Some(NumberOrString::Number(bytes)) => Ok(Some(bytes)),
+        Some(NumberOrString::String(s)) => parse_byte_size(&s).map(Some).map_err(D::Error::custom),
+    }
+}
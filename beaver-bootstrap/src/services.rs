@@ -0,0 +1,94 @@
+use std::sync::RwLock;
+
+use di::{Ref, ServiceCollection, ServiceProvider, Type, singleton_factory};
+
+use crate::dispose::{Disposable, Disposer};
+
+/// ServiceCollectionExt adds explicit overrides to the binder handed to
+/// [`Module::configure`](crate::bootstrap::Module::configure).
+///
+/// Resolving a service returns its last registration, but every registration stays visible to
+/// `get_all`. `replace` drops the earlier ones, so a test module or the host application can
+/// swap a default provided by beaver, e.g. the [`Clock`](crate::clock::Clock).
+///
+/// # Example
+/// ```
+/// use std::sync::RwLock;
+/// use beaver_bootstrap::{
+///     clock::{Clock, ManualClock, SystemClock},
+///     services::ServiceCollectionExt,
+/// };
+/// use di::{Ref, ServiceCollection};
+/// let binder = RwLock::new(ServiceCollection::new());
+/// binder.replace_instance::<dyn Clock>(Ref::new(SystemClock));
+/// binder.replace::<dyn Clock, _>(|_| Ref::new(ManualClock::default()));
+/// assert_eq!(binder.count::<dyn Clock>(), 1);
+/// let provider = binder.read().unwrap().build_provider().unwrap();
+/// assert_eq!(provider.get_all::<dyn Clock>().count(), 1);
+/// ```
+pub trait ServiceCollectionExt {
+    /// register `factory` as the only singleton `T`, removing earlier registrations.
+    fn replace<T, F>(&self, factory: F)
+    where
+        T: ?Sized + Send + Sync + 'static,
+        F: Fn(&ServiceProvider) -> Ref<T> + Send + Sync + 'static;
+
+    /// register `service` as the only `T`, removing earlier registrations.
+    fn replace_instance<T>(&self, service: Ref<T>)
+    where
+        T: ?Sized + Send + Sync + 'static,
+    {
+        self.replace::<T, _>(move |_| service.clone());
+    }
+
+    /// register the singleton `T` created by `factory`, disposed by the bootstrap at shutdown,
+    /// see [`Disposable`].
+    fn singleton_disposable<T, F>(&self, factory: F)
+    where
+        T: Disposable + 'static,
+        F: Fn(&ServiceProvider) -> Ref<T> + Send + Sync + 'static;
+
+    /// number of registrations of `T`.
+    fn count<T: ?Sized + 'static>(&self) -> usize;
+
+    fn contains<T: ?Sized + 'static>(&self) -> bool {
+        self.count::<T>() > 0
+    }
+}
+
+impl ServiceCollectionExt for RwLock<ServiceCollection> {
+    fn replace<T, F>(&self, factory: F)
+    where
+        T: ?Sized + Send + Sync + 'static,
+        F: Fn(&ServiceProvider) -> Ref<T> + Send + Sync + 'static,
+    {
+        let mut service_collection = self.write().unwrap_or_else(|e| e.into_inner());
+        service_collection
+            .remove_all::<T>()
+            .add(singleton_factory::<T, _>(factory));
+    }
+
+    fn singleton_disposable<T, F>(&self, factory: F)
+    where
+        T: Disposable + 'static,
+        F: Fn(&ServiceProvider) -> Ref<T> + Send + Sync + 'static,
+    {
+        let mut service_collection = self.write().unwrap_or_else(|e| e.into_inner());
+        service_collection.add(singleton_factory::<T, _>(move |provider| {
+            let service = factory(provider);
+            if let Some(disposer) = provider.get::<Disposer>() {
+                disposer.track(std::any::type_name::<T>(), service.clone());
+            }
+            service
+        }));
+    }
+
+    fn count<T: ?Sized + 'static>(&self) -> usize {
+        let service_type = Type::of::<T>();
+        self.read()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .filter(|x| *x.service_type() == service_type)
+            .count()
+    }
+}
@@ -0,0 +1,282 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::{Config, ConfigPrefix},
+    error::BootstrapError,
+};
+
+/// Signal is a signal applications may handle, SIGINT and SIGTERM are left to the shutdown.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Signal {
+    Hup,
+    Usr1,
+    Usr2,
+}
+
+impl Signal {
+    /// signal of a name, with or without the `SIG` prefix and in any case.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.trim().to_ascii_uppercase();
+        match name.strip_prefix("SIG").unwrap_or(&name) {
+            "HUP" => Some(Signal::Hup),
+            "USR1" => Some(Signal::Usr1),
+            "USR2" => Some(Signal::Usr2),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Signal::Hup => "SIGHUP",
+            Signal::Usr1 => "SIGUSR1",
+            Signal::Usr2 => "SIGUSR2",
+        }
+    }
+
+    #[cfg(unix)]
+    pub(crate) fn number(&self) -> i32 {
+        use signal_hook::consts::signal::{SIGHUP, SIGUSR1, SIGUSR2};
+
+        match self {
+            Signal::Hup => SIGHUP,
+            Signal::Usr1 => SIGUSR1,
+            Signal::Usr2 => SIGUSR2,
+        }
+    }
+
+    #[cfg(unix)]
+    fn from_number(number: i32) -> Option<Self> {
+        [Signal::Hup, Signal::Usr1, Signal::Usr2]
+            .into_iter()
+            .find(|x| x.number() == number)
+    }
+}
+
+impl fmt::Display for Signal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// SignalConfig configures the signal bus, see `[signal]`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SignalConfig {
+    /// signals applications may not handle, kept for beaver features. The signal of
+    /// `logging.reopen_signal` is reserved once enabled.
+    reserved: Vec<String>,
+}
+
+impl ConfigPrefix for SignalConfig {
+    const PREFIX: &'static str = "signal";
+}
+
+impl SignalConfig {
+    pub fn new(config: &Config) -> Result<Self, BootstrapError> {
+        let signal_config = config
+            .get::<SignalConfig>()
+            .map_err(BootstrapError::ConfigLoadError)?;
+        signal_config.validate()?;
+        Ok(signal_config)
+    }
+
+    fn validate(&self) -> Result<(), BootstrapError> {
+        for (i, name) in self.reserved.iter().enumerate() {
+            if Signal::from_name(name).is_none() {
+                return Err(BootstrapError::InvalidConfigValueError(format!(
+                    "signal.reserved[{}]={}",
+                    i, name
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn reserved(&self) -> HashSet<Signal> {
+        self.reserved
+            .iter()
+            .filter_map(|x| Signal::from_name(x))
+            .collect()
+    }
+}
+
+/// wait for SIGINT or SIGTERM, returning the name of the received signal.
+#[cfg(feature = "full")]
+pub(crate) async fn shutdown_signal() -> std::io::Result<&'static str> {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{SignalKind, signal};
+
+        let mut terminate = signal(SignalKind::terminate())?;
+        tokio::select! {
+            result = tokio::signal::ctrl_c() => result.map(|_| "SIGINT"),
+            _ = terminate.recv() => Ok("SIGTERM"),
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        tokio::signal::ctrl_c().await.map(|_| "SIGINT")
+    }
+}
+
+type SignalHandler = Arc<dyn Fn(Signal) + Send + Sync>;
+
+type Handlers = Mutex<HashMap<Signal, Vec<SignalHandler>>>;
+
+/// SignalBus calls the handlers registered by applications when the process receives a
+/// [`Signal`].
+///
+/// A signal keeps its default behavior until a handler is subscribed to it. On platforms
+/// without these signals, handlers are accepted but only run on [`SignalBus::dispatch`].
+///
+/// # Example
+/// ```
+/// use std::sync::{Arc, atomic::{AtomicUsize, Ordering}};
+/// use beaver_bootstrap::signal::{Signal, SignalBus};
+/// let bus = SignalBus::new([Signal::Hup].into());
+/// assert!(bus.subscribe(Signal::Hup, |_| {}).is_err());
+/// let count = Arc::new(AtomicUsize::new(0));
+/// let counter = count.clone();
+/// bus.subscribe(Signal::Usr1, move |_| {
+///     counter.fetch_add(1, Ordering::Relaxed);
+/// })
+/// .unwrap();
+/// bus.dispatch(Signal::Usr1);
+/// assert_eq!(count.load(Ordering::Relaxed), 1);
+/// ```
+pub struct SignalBus {
+    reserved: HashSet<Signal>,
+    handlers: Arc<Handlers>,
+    #[cfg(unix)]
+    watcher: Mutex<Option<SignalWatcher>>,
+}
+
+impl fmt::Debug for SignalBus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SignalBus")
+            .field("reserved", &self.reserved)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SignalBus {
+    pub fn new(reserved: HashSet<Signal>) -> Self {
+        Self {
+            reserved,
+            handlers: Arc::default(),
+            #[cfg(unix)]
+            watcher: Mutex::new(None),
+        }
+    }
+
+    pub fn is_reserved(&self, signal: Signal) -> bool {
+        self.reserved.contains(&signal)
+    }
+
+    /// call `handler` on every `signal`, from a thread of the bus.
+    ///
+    /// Fails when the signal is reserved or cannot be caught.
+    pub fn subscribe<F>(&self, signal: Signal, handler: F) -> Result<(), BootstrapError>
+    where
+        F: Fn(Signal) + Send + Sync + 'static,
+    {
+        if self.is_reserved(signal) {
+            return Err(BootstrapError::SignalHandlerError(format!(
+                "{} is reserved",
+                signal
+            )));
+        }
+        let mut handlers = self.handlers.lock().unwrap_or_else(|e| e.into_inner());
+        let signal_handlers = handlers.entry(signal).or_default();
+        #[cfg(unix)]
+        if signal_handlers.is_empty() {
+            self.watch(signal)?;
+        }
+        signal_handlers.push(Arc::new(handler));
+        Ok(())
+    }
+
+    /// call the handlers of `signal` in order of subscription, as if it was received.
+    pub fn dispatch(&self, signal: Signal) {
+        dispatch(&self.handlers, signal);
+    }
+
+    /// catch `signal`, starting the watching thread on first use.
+    #[cfg(unix)]
+    fn watch(&self, signal: Signal) -> Result<(), BootstrapError> {
+        let mut watcher = self.watcher.lock().unwrap_or_else(|e| e.into_inner());
+        let watcher = match &mut *watcher {
+            Some(watcher) => watcher,
+            None => watcher.insert(
+                SignalWatcher::spawn(self.handlers.clone())
+                    .map_err(|e| BootstrapError::SignalHandlerError(e.to_string()))?,
+            ),
+        };
+        watcher
+            .handle
+            .add_signal(signal.number())
+            .map_err(|e| BootstrapError::SignalHandlerError(format!("{}: {}", signal, e)))
+    }
+}
+
+fn dispatch(handlers: &Handlers, signal: Signal) {
+    // handlers may subscribe again, so they are called without the lock
+    let signal_handlers = handlers
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(&signal)
+        .cloned()
+        .unwrap_or_default();
+    tracing::debug!(
+        "{} dispatched to {} handlers",
+        signal,
+        signal_handlers.len()
+    );
+    for handler in signal_handlers {
+        handler(signal);
+    }
+}
+
+/// the thread receiving the subscribed signals, stopped on drop.
+#[cfg(unix)]
+struct SignalWatcher {
+    handle: signal_hook::iterator::Handle,
+    thread: Option<std::thread::JoinHandle<()>>,
+}
+
+#[cfg(unix)]
+impl SignalWatcher {
+    fn spawn(handlers: Arc<Handlers>) -> std::io::Result<Self> {
+        let mut signals = signal_hook::iterator::Signals::new::<[i32; 0], i32>([])?;
+        let handle = signals.handle();
+        let thread = std::thread::Builder::new()
+            .name("beaver-signal".to_string())
+            .spawn(move || {
+                for number in signals.forever() {
+                    if let Some(signal) = Signal::from_number(number) {
+                        dispatch(&handlers, signal);
+                    }
+                }
+            })?;
+        Ok(Self {
+            handle,
+            thread: Some(thread),
+        })
+    }
+}
+
+#[cfg(unix)]
+impl Drop for SignalWatcher {
+    fn drop(&mut self) {
+        self.handle.close();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
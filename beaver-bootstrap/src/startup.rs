@@ -0,0 +1,169 @@
+use std::{sync::Arc, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    clock::Clock,
+    config::{Config, ConfigPrefix},
+    error::BootstrapError,
+    health::dependency::{DEFAULT_PROBE_TIMEOUT, ProbeTarget},
+    random::RngProvider,
+    serde::duration_opt,
+};
+
+/// time the startup waits for its dependencies unless `startup.wait_for.timeout` is set.
+pub const DEFAULT_WAIT_TIMEOUT: Duration = Duration::from_secs(60);
+/// first delay between two rounds of probes unless `startup.wait_for.backoff` is set.
+pub const DEFAULT_WAIT_BACKOFF: Duration = Duration::from_millis(250);
+/// longest delay between two rounds of probes unless `startup.wait_for.max_backoff` is set.
+pub const DEFAULT_WAIT_MAX_BACKOFF: Duration = Duration::from_secs(5);
+/// shortest time of a probe, for the last round at the deadline.
+const MIN_PROBE_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// StartupConfig tunes the startup of the process, see `[startup]`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StartupConfig {
+    /// dependencies which must be reachable before modules are initialized.
+    wait_for: WaitForConfig,
+}
+
+impl ConfigPrefix for StartupConfig {
+    const PREFIX: &'static str = "startup";
+}
+
+impl StartupConfig {
+    pub fn new(config: &Config) -> Result<Self, BootstrapError> {
+        let startup_config = config
+            .get::<StartupConfig>()
+            .map_err(BootstrapError::ConfigLoadError)?;
+        startup_config.wait_for.validate()?;
+        Ok(startup_config)
+    }
+
+    pub fn wait_for(&self) -> &WaitForConfig {
+        &self.wait_for
+    }
+}
+
+/// WaitForConfig lists the dependencies the startup blocks on, like the `wait-for-it.sh` of
+/// a container entrypoint, see `[startup.wait_for]`:
+///
+/// ```toml
+/// [startup.wait_for]
+/// targets = ["postgres://db:5432/billing", "http://api:8080/health", "tcp://redis:6379"]
+/// timeout = "2m"
+/// ```
+///
+/// Every target is probed like a `health.dependencies` one, the unreachable ones again after
+/// a backoff doubling from `backoff` up to `max_backoff`, until all are reachable or
+/// `timeout` elapsed, which fails the startup.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WaitForConfig {
+    /// urls of the dependencies.
+    targets: Vec<String>,
+    /// time the startup waits for all of them, [`DEFAULT_WAIT_TIMEOUT`] by default.
+    #[serde(deserialize_with = "duration_opt")]
+    timeout: Option<Duration>,
+    /// first delay between two rounds of probes, [`DEFAULT_WAIT_BACKOFF`] by default.
+    #[serde(deserialize_with = "duration_opt")]
+    backoff: Option<Duration>,
+    /// longest delay between two rounds of probes, [`DEFAULT_WAIT_MAX_BACKOFF`] by default.
+    #[serde(deserialize_with = "duration_opt")]
+    max_backoff: Option<Duration>,
+}
+
+impl WaitForConfig {
+    pub fn timeout(&self) -> Duration {
+        self.timeout.unwrap_or(DEFAULT_WAIT_TIMEOUT)
+    }
+
+    pub fn backoff(&self) -> Duration {
+        self.backoff.unwrap_or(DEFAULT_WAIT_BACKOFF)
+    }
+
+    pub fn max_backoff(&self) -> Duration {
+        self.max_backoff.unwrap_or(DEFAULT_WAIT_MAX_BACKOFF)
+    }
+
+    fn validate(&self) -> Result<(), BootstrapError> {
+        for (key, duration) in [
+            ("timeout", self.timeout),
+            ("backoff", self.backoff),
+            ("max_backoff", self.max_backoff),
+        ] {
+            if duration.is_some_and(|x| x.is_zero()) {
+                return Err(BootstrapError::InvalidConfigValueError(format!(
+                    "startup.wait_for.{}=0",
+                    key
+                )));
+            }
+        }
+        self.targets().map(|_| ())
+    }
+
+    fn targets(&self) -> Result<Vec<ProbeTarget>, BootstrapError> {
+        self.targets
+            .iter()
+            .map(|url| {
+                url.parse().map_err(|e| {
+                    BootstrapError::InvalidConfigValueError(format!(
+                        "startup.wait_for.targets: {}: {}",
+                        url, e
+                    ))
+                })
+            })
+            .collect()
+    }
+
+    /// block until every target is reachable, failing with the unreachable ones once the
+    /// timeout elapsed.
+    pub fn wait(
+        &self,
+        clock: Arc<dyn Clock>,
+        rng: Arc<dyn RngProvider>,
+    ) -> Result<(), BootstrapError> {
+        let mut pending = self.targets()?;
+        if pending.is_empty() {
+            return Ok(());
+        }
+        let start = clock.instant();
+        let timeout = self.timeout();
+        let mut backoff = self.backoff();
+        let mut first = true;
+        loop {
+            let left = timeout.saturating_sub(clock.instant() - start);
+            let probe_timeout = DEFAULT_PROBE_TIMEOUT.min(left).max(MIN_PROBE_TIMEOUT);
+            let mut failures = Vec::new();
+            pending.retain(|target| match target.probe(probe_timeout) {
+                Ok(()) => {
+                    tracing::info!(target: "startup", "{} is reachable", target);
+                    false
+                }
+                Err(e) => {
+                    if first {
+                        tracing::info!(target: "startup", "waiting for {}: {}", target, e);
+                    }
+                    failures.push(e);
+                    true
+                }
+            });
+            first = false;
+            if pending.is_empty() {
+                return Ok(());
+            }
+            let left = timeout.saturating_sub(clock.instant() - start);
+            if left.is_zero() {
+                return Err(BootstrapError::DependencyWaitTimeoutError(format!(
+                    "{} still unreachable after {:?}: {}",
+                    pending.len(),
+                    timeout,
+                    failures.join(", ")
+                )));
+            }
+            clock.sleep(rng.jitter(backoff, 0.2).min(left));
+            backoff = (backoff * 2).min(self.max_backoff());
+        }
+    }
+}
@@ -0,0 +1,75 @@
+use std::{
+    io::{Read, Write},
+    net::TcpStream,
+    time::Duration,
+};
+
+use beaver_bootstrap::{
+    admin::{AdminConfig, AdminResponse, AdminRoutes, AdminServer},
+    config::Config,
+};
+
+fn start() -> AdminServer {
+    let inner = config::Config::builder()
+        .set_override("admin.enable", true)
+        .unwrap()
+        .set_override("admin.bind", "127.0.0.1:0")
+        .unwrap()
+        .build()
+        .unwrap();
+    let config = AdminConfig::new(&Config::new(inner)).unwrap();
+    let routes = AdminRoutes::default();
+    routes.route("/ping", |_| AdminResponse::text(200, "pong"));
+    AdminServer::start(&config, routes).unwrap()
+}
+
+fn connect(server: &AdminServer) -> TcpStream {
+    let stream = TcpStream::connect(server.local_addr()).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(10)))
+        .unwrap();
+    stream
+}
+
+/// the status line of the response to `request`.
+fn status_line(mut stream: TcpStream, request: &[u8]) -> String {
+    // the server may answer and close before reading the whole request
+    let _ = stream.write_all(request);
+    let mut response = String::new();
+    let _ = stream.read_to_string(&mut response);
+    response.lines().next().unwrap_or_default().to_string()
+}
+
+#[test]
+fn request_is_served() {
+    let server = start();
+    let status = status_line(connect(&server), b"GET /ping HTTP/1.1\r\n\r\n");
+    assert_eq!(status, "HTTP/1.1 200 OK");
+}
+
+#[test]
+fn request_line_without_newline_is_bounded() {
+    let server = start();
+    // the whole limit of the head, read before the answer so the close does not reset
+    let status = status_line(connect(&server), &vec![b'a'; 16 * 1024]);
+    assert_eq!(status, "HTTP/1.1 431 Request Header Fields Too Large");
+}
+
+#[test]
+fn connections_beyond_the_limit_are_rejected() {
+    let server = start();
+    // 32 connections sending nothing hold their threads
+    let idle: Vec<TcpStream> = (0..32).map(|_| connect(&server)).collect();
+    let status = status_line(connect(&server), b"GET /ping HTTP/1.1\r\n\r\n");
+    assert_eq!(status, "HTTP/1.1 503 Service Unavailable");
+    drop(idle);
+    let mut status = String::new();
+    for _ in 0..100 {
+        status = status_line(connect(&server), b"GET /ping HTTP/1.1\r\n\r\n");
+        if status.contains("200") {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    assert_eq!(status, "HTTP/1.1 200 OK");
+}
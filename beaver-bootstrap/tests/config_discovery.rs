@@ -0,0 +1,143 @@
+use std::{path::Path, process::Command, sync::Arc};
+
+use beaver_bootstrap::{
+    bootstrap::Bootstrap,
+    config::{
+        CONFIG_FOLDER_ENV, Config, ConfigFilePolicy, default_config_folder,
+        export::ConfigExportFormat,
+    },
+    fs::MemoryFs,
+};
+
+/// set in the child process to the folder it expects [`default_config_folder`] to be.
+const EXPECTED_FOLDER_ENV: &str = "BEAVER_DISCOVERY_TEST_FOLDER";
+
+fn bootstrap(fs: MemoryFs, folder: Option<&str>) -> Bootstrap {
+    let builder = Bootstrap::builder()
+        .initialize_logging(false)
+        .env_config_prefix(None)
+        .fs(Arc::new(fs));
+    match folder {
+        Some(folder) => builder.config_folder(folder).build(),
+        None => builder.build(),
+    }
+}
+
+fn effective_config(bootstrap: &Bootstrap) -> String {
+    bootstrap
+        .effective_config(ConfigExportFormat::Toml)
+        .unwrap()
+}
+
+#[test]
+fn builder_folder_is_used() {
+    let fs = MemoryFs::default()
+        .with_file(
+            "/srv/app/etc/config.toml",
+            "[app]\nsource = \"from-builder\"\n",
+        )
+        .with_file(
+            concat!(env!("CARGO_MANIFEST_DIR"), "/etc/config.toml"),
+            "[app]\nsource = \"from-manifest\"\n",
+        );
+    let config = effective_config(&bootstrap(fs, Some("/srv/app/etc")));
+    assert!(config.contains("from-builder"), "{}", config);
+}
+
+#[test]
+fn manifest_folder_is_the_default_under_cargo() {
+    if std::env::var_os(CONFIG_FOLDER_ENV).is_some() {
+        return;
+    }
+    assert_eq!(
+        default_config_folder(),
+        Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/etc"))
+    );
+    let fs = MemoryFs::default().with_file(
+        concat!(env!("CARGO_MANIFEST_DIR"), "/etc/config.toml"),
+        "[app]\nsource = \"from-manifest\"\n",
+    );
+    let config = effective_config(&bootstrap(fs, None));
+    assert!(config.contains("from-manifest"), "{}", config);
+}
+
+#[test]
+fn env_folder_wins_over_the_manifest_folder() {
+    let folder = "/srv/beaver/etc";
+    let output = Command::new(std::env::current_exe().unwrap())
+        .args(["--exact", "child_reads_env_folder", "--test-threads=1"])
+        .env(CONFIG_FOLDER_ENV, folder)
+        .env(EXPECTED_FOLDER_ENV, folder)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+}
+
+#[test]
+fn child_reads_env_folder() {
+    let Ok(folder) = std::env::var(EXPECTED_FOLDER_ENV) else {
+        return;
+    };
+    assert_eq!(default_config_folder(), Path::new(&folder));
+    let fs = MemoryFs::default()
+        .with_file(
+            format!("{}/config.toml", folder),
+            "[app]\nsource = \"from-env\"\n",
+        )
+        .with_file(
+            concat!(env!("CARGO_MANIFEST_DIR"), "/etc/config.toml"),
+            "[app]\nsource = \"from-manifest\"\n",
+        );
+    let config = effective_config(&bootstrap(fs, None));
+    assert!(config.contains("from-env"), "{}", config);
+}
+
+#[test]
+fn missing_config_file_names_its_path() {
+    let error = bootstrap(MemoryFs::default(), Some("/srv/missing/etc"))
+        .effective_config(ConfigExportFormat::Toml)
+        .unwrap_err();
+    assert!(error.to_string().contains("/srv/missing/etc"), "{}", error);
+}
+
+fn optional_bootstrap(policy: ConfigFilePolicy) -> Bootstrap {
+    Bootstrap::builder()
+        .initialize_logging(false)
+        .env_config_prefix(None)
+        .fs(Arc::new(MemoryFs::default()))
+        .config_folder("/srv/missing/etc")
+        .config_file(policy)
+        .build()
+}
+
+#[test]
+fn missing_config_file_is_empty_when_optional() {
+    let bootstrap = optional_bootstrap(ConfigFilePolicy::Optional);
+    let config = effective_config(&bootstrap);
+    assert!(!config.contains("from-"), "{}", config);
+}
+
+#[test]
+fn missing_config_file_is_warned_about_when_optional_with_warning() {
+    effective_config(&optional_bootstrap(ConfigFilePolicy::OptionalWithWarning));
+    let (_, warnings) = Config::from_folder_with_policy(
+        &MemoryFs::default(),
+        Path::new("/srv/missing/etc"),
+        ConfigFilePolicy::OptionalWithWarning,
+        None,
+        "_",
+        vec![],
+    )
+    .unwrap();
+    assert_eq!(
+        warnings,
+        [
+            "configuration file /srv/missing/etc/config.toml not found, using environment \
+          variables and defaults"
+        ]
+    );
+}
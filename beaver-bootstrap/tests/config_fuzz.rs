@@ -0,0 +1,160 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use beaver_bootstrap::{
+    config::{
+        ConfigPrefix,
+        fuzz::{ConfigShape, arbitrary_tree, deserialize, load},
+    },
+    health::HealthConfig,
+    runtime::RuntimeConfig,
+    serde::duration_opt,
+    startup::StartupConfig,
+};
+use proptest::prelude::*;
+use serde::{Deserialize, Serialize};
+use toml::{Table, Value};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct PoolConfig {
+    size: u16,
+    ratio: f64,
+    lazy: bool,
+    name: String,
+    hosts: Vec<String>,
+    #[serde(deserialize_with = "duration_opt")]
+    idle: Option<Duration>,
+    retry: RetryConfig,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        Self {
+            size: 16,
+            ratio: 0.5,
+            lazy: false,
+            name: "primary".to_string(),
+            hosts: vec!["db-1".to_string()],
+            idle: None,
+            retry: RetryConfig::default(),
+        }
+    }
+}
+
+impl ConfigPrefix for PoolConfig {
+    const PREFIX: &'static str = "db.pool";
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct RetryConfig {
+    attempts: u8,
+}
+
+/// the properties `tree` flattens to, arrays split.
+fn leaves(prefix: &str, table: &Table, properties: &mut BTreeMap<String, Value>) {
+    for (key, value) in table {
+        let key = match prefix {
+            "" => key.clone(),
+            prefix => format!("{}.{}", prefix, key),
+        };
+        match value {
+            Value::Table(table) => leaves(&key, table, properties),
+            Value::Array(items) => {
+                for (index, item) in items.iter().enumerate() {
+                    properties.insert(format!("{}[{}]", key, index), item.clone());
+                }
+            }
+            value => {
+                properties.insert(key, value.clone());
+            }
+        }
+    }
+}
+
+#[test]
+fn shape_of_a_section_lists_the_leaves_of_its_default() {
+    let shape = ConfigShape::<PoolConfig>::infer().unwrap();
+    assert_eq!(
+        shape.keys(),
+        [
+            "db.pool.hosts",
+            "db.pool.lazy",
+            "db.pool.name",
+            "db.pool.ratio",
+            "db.pool.retry.attempts",
+            "db.pool.size",
+        ]
+    );
+    assert_eq!(
+        deserialize::<PoolConfig>(shape.base()).unwrap(),
+        PoolConfig::default()
+    );
+}
+
+proptest! {
+    #[test]
+    fn valid_trees_deserialize_and_round_trip(tree in ConfigShape::<PoolConfig>::infer().unwrap().valid()) {
+        let pool = deserialize::<PoolConfig>(&tree).unwrap();
+        let mut again = Table::new();
+        again.insert(
+            "db".to_string(),
+            Value::Table(Table::from_iter([(
+                "pool".to_string(),
+                Value::try_from(&pool).unwrap(),
+            )])),
+        );
+        prop_assert_eq!(deserialize::<PoolConfig>(&again).unwrap(), pool);
+    }
+
+    #[test]
+    fn invalid_trees_fail_to_deserialize(tree in ConfigShape::<PoolConfig>::infer().unwrap().invalid().unwrap()) {
+        prop_assert!(deserialize::<PoolConfig>(&tree).is_err());
+    }
+
+    #[test]
+    fn arbitrary_trees_flatten_to_their_leaves(tree in arbitrary_tree(3)) {
+        let properties = load(&tree).unwrap().to_properties().unwrap();
+        let mut expected = BTreeMap::new();
+        leaves("", &tree, &mut expected);
+        let keys: Vec<&String> = properties.get_properties().keys().collect();
+        prop_assert_eq!(keys, expected.keys().collect::<Vec<_>>());
+        for (key, value) in expected {
+            let text = match value {
+                Value::String(x) => x,
+                Value::Integer(x) => x.to_string(),
+                Value::Boolean(x) => x.to_string(),
+                _ => continue,
+            };
+            prop_assert_eq!(&properties.get_properties()[&key], &text);
+        }
+    }
+}
+
+#[test]
+fn builtin_sections_have_shapes() {
+    fn check<T>()
+    where
+        T: ConfigPrefix + Default + Serialize + serde::de::DeserializeOwned + 'static,
+    {
+        let shape = ConfigShape::<T>::infer().unwrap();
+        let mut runner = proptest::test_runner::TestRunner::new(ProptestConfig::with_cases(16));
+        runner
+            .run(&shape.valid(), |tree| {
+                prop_assert!(deserialize::<T>(&tree).is_ok());
+                Ok(())
+            })
+            .unwrap();
+        if let Some(invalid) = shape.invalid() {
+            runner
+                .run(&invalid, |tree| {
+                    prop_assert!(deserialize::<T>(&tree).is_err());
+                    Ok(())
+                })
+                .unwrap();
+        }
+    }
+    check::<RuntimeConfig>();
+    check::<HealthConfig>();
+    check::<StartupConfig>();
+}
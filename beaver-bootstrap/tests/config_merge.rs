@@ -0,0 +1,85 @@
+use std::{path::Path, sync::Arc};
+
+use beaver_bootstrap::{
+    config::{
+        Config,
+        source::{ArrayMerge, ConfigSource, MergePolicy, TableMerge, plugin_sources},
+    },
+    fs::MemoryFs,
+};
+use config::{ConfigError, Map, Value};
+
+const CONFIG: &str = r#"
+[logging.console_appender]
+enable = true
+level = "info"
+
+[[logging.file_appenders]]
+file_name = "app.log"
+"#;
+
+/// a profile adding an appender and raising the console level.
+struct Profile(MergePolicy);
+
+impl ConfigSource for Profile {
+    fn name(&self) -> String {
+        "profile".to_string()
+    }
+
+    fn load(&self) -> Result<Map<String, Value>, ConfigError> {
+        let appender = Map::from([("file_name".to_string(), Value::from("debug.log"))]);
+        let console = Map::from([("level".to_string(), Value::from("debug"))]);
+        Ok(Map::from([
+            (
+                "logging.file_appenders".to_string(),
+                Value::from(vec![Value::from(appender)]),
+            ),
+            ("logging.console_appender".to_string(), Value::from(console)),
+        ]))
+    }
+
+    fn merge_policy(&self) -> MergePolicy {
+        self.0
+    }
+}
+
+fn load(policy: MergePolicy) -> Config {
+    let fs = MemoryFs::default().with_file("/srv/app/etc/config.toml", CONFIG);
+    let sources = plugin_sources(&[Arc::new(Profile(policy))]);
+    Config::from_folder_with_fs(&fs, Path::new("/srv/app/etc"), Some("MERGE"), "__", sources)
+        .ok()
+        .unwrap()
+}
+
+fn file_names(config: &Config) -> Vec<String> {
+    let appenders: Vec<toml::Table> = config.get_at("logging.file_appenders").unwrap();
+    appenders
+        .iter()
+        .map(|x| x["file_name"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[test]
+fn arrays_are_replaced_and_tables_deep_merged_by_default() {
+    let config = load(MergePolicy::default());
+    assert_eq!(file_names(&config), ["debug.log"]);
+    let console: toml::Table = config.get_at("logging.console_appender").unwrap();
+    assert_eq!(console["level"].as_str(), Some("debug"));
+    assert_eq!(console["enable"].as_bool(), Some(true));
+}
+
+#[test]
+fn arrays_are_appended() {
+    let config = load(MergePolicy::default().with_arrays(ArrayMerge::Append));
+    assert_eq!(file_names(&config), ["app.log", "debug.log"]);
+}
+
+#[test]
+fn tables_are_replaced() {
+    let config = load(MergePolicy::default().with_tables(TableMerge::Replace));
+    let console: toml::Table = config.get_at("logging.console_appender").unwrap();
+    assert_eq!(console["level"].as_str(), Some("debug"));
+    assert!(!console.contains_key("enable"));
+    // the tables above the keys of the source are still merged
+    assert_eq!(file_names(&config), ["debug.log"]);
+}
@@ -0,0 +1,63 @@
+use std::{path::Path, sync::Arc};
+
+use beaver_bootstrap::{
+    bootstrap::Bootstrap,
+    fs::{Fs, MemoryFs},
+};
+
+const CONFIG_FOLDER: &str = "/srv/app/etc";
+const CONFIG: &str = "/srv/app/etc/config.toml";
+
+#[test]
+fn applied_reloads_are_counted() {
+    let fs = Arc::new(MemoryFs::default().with_file(CONFIG, "[pool]\nsize = 8\n"));
+    let bootstrap = Bootstrap::builder()
+        .initialize_logging(false)
+        .env_config_prefix(None)
+        .fs(fs.clone())
+        .config_folder(CONFIG_FOLDER)
+        .build();
+    bootstrap.initialize().unwrap();
+    let reloads = || {
+        bootstrap
+            .metrics()
+            .counter(
+                "config_reload_count",
+                "config reloads applied since startup",
+            )
+            .get()
+    };
+    assert_eq!(reloads(), 0);
+
+    fs.write(Path::new(CONFIG), b"[pool]\nsize = 16\n").unwrap();
+    assert!(!bootstrap.reload_config().unwrap().is_empty());
+    assert_eq!(reloads(), 1);
+
+    // an unchanged config is not applied
+    assert!(bootstrap.reload_config().unwrap().is_empty());
+    assert_eq!(reloads(), 1);
+}
+
+#[test]
+fn reloads_use_the_secrets_backend_of_the_new_config() {
+    let fs = Arc::new(MemoryFs::default().with_file(
+        CONFIG,
+        "[secrets]\nkey_env = \"BEAVER_RELOAD_TEST_KEY_A\"\n",
+    ));
+    let bootstrap = Bootstrap::builder()
+        .initialize_logging(false)
+        .env_config_prefix(None)
+        .fs(fs.clone())
+        .config_folder(CONFIG_FOLDER)
+        .build();
+    // nothing is encrypted, the key is not needed
+    bootstrap.initialize().unwrap();
+
+    fs.write(
+        Path::new(CONFIG),
+        b"[secrets]\nkey_env = \"BEAVER_RELOAD_TEST_KEY_B\"\n[db]\npassword = \"enc:AAAA\"\n",
+    )
+    .unwrap();
+    let error = bootstrap.reload_config().unwrap_err().to_string();
+    assert!(error.contains("BEAVER_RELOAD_TEST_KEY_B"), "{}", error);
+}
@@ -0,0 +1,102 @@
+use std::path::Path;
+
+use beaver_bootstrap::{
+    config::{
+        Config,
+        environment::{EnvNaming, EnvironmentSource},
+    },
+    fs::MemoryFs,
+};
+
+const CONFIG: &str = r#"
+[[logging.file_appenders]]
+file_name = "app.log"
+logger_names = ["root"]
+
+[[logging.file_appenders]]
+file_name = "audit.log"
+
+[http.status_pages]
+404 = "not_found.html"
+"#;
+
+/// the config of [`CONFIG`] overridden by `vars`, prefixed by `prefix`, read from a map
+/// instead of the process environment.
+fn load(prefix: &str, vars: &[(&str, &str)]) -> Result<Config, String> {
+    let vars = vars
+        .iter()
+        .map(|(var, value)| (format!("{}__{}", prefix, var), value.to_string()))
+        .collect();
+    let source = EnvironmentSource::new(EnvNaming::new(Some(prefix), "__")).source(Some(vars));
+    let fs = MemoryFs::default().with_file("/srv/app/etc/config.toml", CONFIG);
+    Config::from_folder_with_fs(
+        &fs,
+        Path::new("/srv/app/etc"),
+        Some(prefix),
+        "__",
+        vec![Box::new(source)],
+    )
+    .map_err(|e| e.to_string())
+}
+
+fn appenders(config: &Config) -> Vec<toml::Value> {
+    config.get_at("logging.file_appenders").unwrap()
+}
+
+#[test]
+fn element_of_array_of_tables_is_merged() {
+    let config = load(
+        "ENV_MERGE",
+        &[("LOGGING__FILE_APPENDERS__0__FILE_NAME", "server.log")],
+    )
+    .unwrap();
+    let appenders = appenders(&config);
+    assert_eq!(appenders.len(), 2);
+    assert_eq!(appenders[0]["file_name"].as_str(), Some("server.log"));
+    assert_eq!(appenders[0]["logger_names"][0].as_str(), Some("root"));
+    assert_eq!(appenders[1]["file_name"].as_str(), Some("audit.log"));
+}
+
+#[test]
+fn element_after_the_last_is_appended() {
+    let config = load(
+        "ENV_APPEND",
+        &[
+            ("LOGGING__FILE_APPENDERS__2__FILE_NAME", "access.log"),
+            ("LOGGING__FILE_APPENDERS__0__LOGGER_NAMES__1", "http"),
+        ],
+    )
+    .unwrap();
+    let appenders = appenders(&config);
+    assert_eq!(appenders.len(), 3);
+    assert_eq!(appenders[2]["file_name"].as_str(), Some("access.log"));
+    let names = appenders[0]["logger_names"].as_array().unwrap();
+    assert_eq!(names.len(), 2);
+    assert_eq!(names[1].as_str(), Some("http"));
+}
+
+#[test]
+fn numeric_key_of_table_stays_a_key() {
+    let config = load("ENV_TABLE", &[("HTTP__STATUS_PAGES__500", "error.html")]).unwrap();
+    let pages: toml::Table = config.get_at("http.status_pages").unwrap();
+    assert_eq!(pages["404"].as_str(), Some("not_found.html"));
+    assert_eq!(pages["500"].as_str(), Some("error.html"));
+}
+
+#[test]
+fn element_past_the_end_fails_naming_the_variable() {
+    let error = load(
+        "ENV_HOLE",
+        &[("LOGGING__FILE_APPENDERS__4__FILE_NAME", "lost.log")],
+    )
+    .err()
+    .unwrap();
+    assert!(
+        error.contains(
+            "env variable ENV_HOLE__LOGGING__FILE_APPENDERS__4__FILE_NAME sets \
+             logging.file_appenders[4], leaving logging.file_appenders[2] unset"
+        ),
+        "{}",
+        error
+    );
+}
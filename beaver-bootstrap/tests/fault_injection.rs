@@ -0,0 +1,154 @@
+use std::sync::{Arc, Mutex, RwLock};
+
+use beaver_bootstrap::{
+    bootstrap::{Bootstrap, Module},
+    dispose::{Disposable, DisposeFuture},
+    error::{BootstrapError, EX_SOFTWARE},
+    fault::FaultPlan,
+    fs::MemoryFs,
+    runtime::InitFuture,
+    services::ServiceCollectionExt,
+};
+use di::*;
+
+const CONFIG_FOLDER: &str = "/srv/app/etc";
+const CONFIG: &str = "/srv/app/etc/config.toml";
+
+/// what the modules and services of a test did, in order.
+type Journal = Arc<Mutex<Vec<String>>>;
+
+/// a service recording its disposal.
+struct Pool(&'static str, Journal);
+
+impl Disposable for Pool {
+    fn dispose(&self) -> DisposeFuture {
+        self.1.lock().unwrap().push(format!("dispose {}", self.0));
+        Box::pin(async { Ok(()) })
+    }
+}
+
+/// the pool of its own name, constructed when the module is initialized.
+struct PoolModule(&'static str, Journal);
+
+impl Module for PoolModule {
+    fn configure(&self, binder: &RwLock<ServiceCollection>) {
+        let (name, journal) = (self.0, self.1.clone());
+        match name {
+            "primary" => {
+                binder.singleton_disposable(move |_| Ref::new(Primary(Pool(name, journal.clone()))))
+            }
+            _ => {
+                binder.singleton_disposable(move |_| Ref::new(Replica(Pool(name, journal.clone()))))
+            }
+        }
+    }
+
+    fn name(&self) -> String {
+        self.0.to_string()
+    }
+
+    fn initialize(&self, provider: &ServiceProvider) -> Option<InitFuture> {
+        match self.0 {
+            "primary" => drop(provider.get_required::<Primary>()),
+            _ => drop(provider.get_required::<Replica>()),
+        }
+        self.1
+            .lock()
+            .unwrap()
+            .push(format!("initialize {}", self.0));
+        None
+    }
+}
+
+/// two services, the disposer telling services apart by type.
+struct Primary(Pool);
+struct Replica(Pool);
+
+impl Disposable for Primary {
+    fn dispose(&self) -> DisposeFuture {
+        self.0.dispose()
+    }
+}
+
+impl Disposable for Replica {
+    fn dispose(&self) -> DisposeFuture {
+        self.0.dispose()
+    }
+}
+
+fn bootstrap(journal: &Journal) -> Bootstrap {
+    let fs = MemoryFs::default().with_file(CONFIG, "[node]\n");
+    Bootstrap::builder()
+        .initialize_logging(false)
+        .env_config_prefix(None)
+        .fs(Arc::new(fs))
+        .config_folder(CONFIG_FOLDER)
+        .modules(vec![
+            Box::new(PoolModule("primary", journal.clone())) as Box<dyn Module>,
+            Box::new(PoolModule("replica", journal.clone())),
+        ])
+        .build()
+}
+
+#[test]
+fn injected_phase_fails_before_running() {
+    let journal = Journal::default();
+    let _faults = FaultPlan::default().fail_phase("modules").install();
+    let error = bootstrap(&journal).initialize().unwrap_err();
+    assert!(
+        matches!(error, BootstrapError::InjectedFaultError(_)),
+        "{}",
+        error
+    );
+    assert_eq!(error.to_string(), "injected fault: phase modules");
+    assert_eq!(error.exit_code(), EX_SOFTWARE);
+    assert!(journal.lock().unwrap().is_empty());
+}
+
+#[test]
+fn injected_module_fails_like_a_module() {
+    let journal = Journal::default();
+    let _faults = FaultPlan::default().fail_module(1).install();
+    let error = bootstrap(&journal).initialize().unwrap_err();
+    assert!(
+        matches!(error, BootstrapError::ModuleInitError(_)),
+        "{}",
+        error
+    );
+    assert!(
+        error
+            .to_string()
+            .contains("replica: injected fault at module 1"),
+        "{}",
+        error
+    );
+    // the bootstrap is dropped, so shut down, by now
+    assert_eq!(
+        *journal.lock().unwrap(),
+        ["initialize primary", "dispose primary"]
+    );
+}
+
+#[test]
+fn injected_shutdown_hook_does_not_stop_the_others() {
+    let journal = Journal::default();
+    let bootstrap = bootstrap(&journal);
+    bootstrap.initialize().unwrap();
+    let _faults = FaultPlan::default().fail_shutdown_hook(1).install();
+    bootstrap.shutdown();
+    assert_eq!(
+        *journal.lock().unwrap(),
+        [
+            "initialize primary",
+            "initialize replica",
+            "dispose primary"
+        ]
+    );
+}
+
+#[test]
+fn faults_are_removed_with_their_guard() {
+    let journal = Journal::default();
+    drop(FaultPlan::default().fail_phase("config").install());
+    bootstrap(&journal).initialize().unwrap();
+}
@@ -0,0 +1,327 @@
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::TcpListener,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU8, AtomicUsize, Ordering},
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use beaver_bootstrap::{
+    admin::AdminRequest,
+    bootstrap::Bootstrap,
+    clock::ManualClock,
+    error::BootstrapError,
+    event::EventBus,
+    fs::MemoryFs,
+    health::{
+        HealthCheck, HealthCheckResult, HealthRegistry, HealthStatus, HealthTransition,
+        dependency::{DependencyCheck, DependencyConfig},
+    },
+};
+
+const CONFIG_FOLDER: &str = "/srv/app/etc";
+const CONFIG: &str = "/srv/app/etc/config.toml";
+
+/// a certificate expiring on 2030-01-01.
+const CERT_2030: &str = "-----BEGIN CERTIFICATE-----
+MIIBdjCCAR2gAwIBAgIUOXptnbVOBSvLJ26xlBUQ/uom6pgwCgYIKoZIzj0EAwIw
+ETEPMA0GA1UEAwwGYmVhdmVyMB4XDTI1MDEwMTAwMDAwMFoXDTMwMDEwMTAwMDAw
+MFowETEPMA0GA1UEAwwGYmVhdmVyMFkwEwYHKoZIzj0CAQYIKoZIzj0DAQcDQgAE
+5t7htzDUkPmYzWlb1UsLozxmXVGxbHFtrCBFjg34dSeXZ+035/BLb6c27NAnN6Hr
+Q8C3tWwS8f/c8NJBHt+TgKNTMFEwHQYDVR0OBBYEFF37U324K1gb776tlT1R+nP1
+jhLJMB8GA1UdIwQYMBaAFF37U324K1gb776tlT1R+nP1jhLJMA8GA1UdEwEB/wQF
+MAMBAf8wCgYIKoZIzj0EAwIDRwAwRAIgPR/B0hHHEQPkYUrreCRw6Nncwi8s+Yqq
+lRZ2/hlfRBwCIFUPgnnhFsVOjM8bQI9mOJPtNmq5Sx290JjfWcySuDp/
+-----END CERTIFICATE-----";
+
+/// a certificate expiring on 2060-01-01, its expiry a GeneralizedTime.
+const CERT_2060: &str = "-----BEGIN CERTIFICATE-----
+MIIBfzCCASWgAwIBAgIUVjgC8je9EJiUZJoG67mthGkC0HQwCgYIKoZIzj0EAwIw
+FDESMBAGA1UEAwwJYmVhdmVyLWNhMCAXDTI1MDEwMTAwMDAwMFoYDzIwNjAwMTAx
+MDAwMDAwWjAUMRIwEAYDVQQDDAliZWF2ZXItY2EwWTATBgcqhkjOPQIBBggqhkjO
+PQMBBwNCAAT6q+yy8N74ZESU1+G1z1xOySJD9PUA/Kq0rVg8G8jPMdQASTisdEnn
+LhMZ75JzBBVgK62B+U5wpERKihXyZ0qWo1MwUTAdBgNVHQ4EFgQUF06q8m7mN2Ty
+ZI2cy0TLqaU/eJEwHwYDVR0jBBgwFoAUF06q8m7mN2TyZI2cy0TLqaU/eJEwDwYD
+VR0TAQH/BAUwAwEB/zAKBggqhkjOPQQDAgNIADBFAiEAlDJhi3ukEhM/AT77X700
+At15LsblyZE5ThHVyYK73soCIA6u454uoax0yAe6FmbB9EjbHRAwTB1N5q91no6k
+FImr
+-----END CERTIFICATE-----";
+
+/// 2029-12-25, a week before the expiry of [`CERT_2030`].
+const DEC_25_2029: u64 = 1_893_456_000 - 7 * 86400;
+
+/// a check whose status is set by the test, 0 healthy, 1 degraded, 2 unhealthy.
+#[derive(Clone, Default)]
+struct SwitchCheck(Arc<AtomicU8>);
+
+impl SwitchCheck {
+    fn set(&self, status: u8) {
+        self.0.store(status, Ordering::Relaxed);
+    }
+}
+
+impl HealthCheck for SwitchCheck {
+    fn name(&self) -> String {
+        "switch".to_string()
+    }
+
+    fn check(&self) -> HealthCheckResult {
+        match self.0.load(Ordering::Relaxed) {
+            0 => HealthCheckResult::healthy(),
+            1 => HealthCheckResult::degraded("half open"),
+            _ => HealthCheckResult::unhealthy("open"),
+        }
+    }
+}
+
+#[test]
+fn transitions_are_published_once_per_change() {
+    let events = Arc::new(EventBus::default());
+    let transitions = Arc::new(Mutex::new(vec![]));
+    let received = transitions.clone();
+    events.subscribe(move |x: &HealthTransition| received.lock().unwrap().push(x.clone()));
+    let registry = HealthRegistry::default().with_events(events);
+    let check = SwitchCheck::default();
+    registry.register(Arc::new(check.clone()));
+
+    for status in [0, 1, 1, 2, 0] {
+        check.set(status);
+        registry.evaluate();
+    }
+    let transitions = transitions.lock().unwrap();
+    let changes: Vec<_> = transitions.iter().map(|x| (x.from, x.to)).collect();
+    assert_eq!(
+        changes,
+        [
+            (HealthStatus::Healthy, HealthStatus::Degraded),
+            (HealthStatus::Degraded, HealthStatus::Unhealthy),
+            (HealthStatus::Unhealthy, HealthStatus::Healthy),
+        ]
+    );
+    assert_eq!(transitions[1].failing.len(), 1);
+    assert_eq!(transitions[1].failing[0].result.detail(), Some("open"));
+    assert!(transitions[2].failing.is_empty());
+}
+
+#[test]
+fn health_is_served_by_the_admin_endpoint() {
+    let fs = MemoryFs::default().with_file(CONFIG, "[health]\nenable = false\n");
+    let check = SwitchCheck::default();
+    let bootstrap = Bootstrap::builder()
+        .initialize_logging(false)
+        .env_config_prefix(None)
+        .fs(Arc::new(fs))
+        .config_folder(CONFIG_FOLDER)
+        .health_checks(vec![Arc::new(check.clone())])
+        .build();
+    bootstrap.initialize().unwrap();
+    check.set(2);
+    bootstrap.health().evaluate();
+
+    let response = bootstrap
+        .admin_routes()
+        .handle(&AdminRequest::new("GET", "/health"), None);
+    assert_eq!(response.status(), 503);
+    let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+    assert_eq!(
+        body,
+        serde_json::json!({
+            "status": "unhealthy",
+            "checks": [{"name": "switch", "status": "unhealthy", "detail": "open"}]
+        })
+    );
+}
+
+/// the report of the checks of a bootstrap initialized with `config`.
+fn initialized_report(config: &str) -> Result<serde_json::Value, BootstrapError> {
+    initialized_report_at(config, &[], SystemTime::now())
+}
+
+/// like [`initialized_report`], `files` next to the config and the clock reading `now`.
+fn initialized_report_at(
+    config: &str,
+    files: &[(&str, &str)],
+    now: SystemTime,
+) -> Result<serde_json::Value, BootstrapError> {
+    let fs = files.iter().fold(
+        MemoryFs::default().with_file(CONFIG, config),
+        |fs, (path, contents)| fs.with_file(path, contents),
+    );
+    let bootstrap = Bootstrap::builder()
+        .initialize_logging(false)
+        .env_config_prefix(None)
+        .fs(Arc::new(fs))
+        .config_folder(CONFIG_FOLDER)
+        .clock(Arc::new(ManualClock::new(now)))
+        .build();
+    bootstrap.initialize()?;
+    Ok(serde_json::to_value(bootstrap.health().evaluate()).unwrap())
+}
+
+#[cfg(unix)]
+#[test]
+fn disk_check_reports_directories_under_their_thresholds() {
+    let report = initialized_report(&format!(
+        "[health]\nenable = false\n\n[health.disk]\npaths = [\"{}\"]\ndegraded_free_ratio = 1.0\n",
+        env!("CARGO_MANIFEST_DIR")
+    ))
+    .unwrap();
+    assert_eq!(report["status"], "degraded");
+    assert_eq!(report["checks"][0]["name"], "disk");
+    let detail = report["checks"][0]["detail"].as_str().unwrap();
+    assert!(detail.starts_with(env!("CARGO_MANIFEST_DIR")), "{}", detail);
+}
+
+#[test]
+fn disk_check_without_paths_is_not_registered() {
+    let report = initialized_report("[health]\nenable = false\n").unwrap();
+    assert_eq!(report["checks"], serde_json::json!([]));
+}
+
+#[test]
+fn disk_thresholds_out_of_order_are_rejected() {
+    let error = initialized_report(
+        "[health.disk]\ndegraded_free_ratio = 0.05\nunhealthy_free_ratio = 0.1\n",
+    )
+    .unwrap_err();
+    assert!(
+        error
+            .to_string()
+            .contains("health.disk.unhealthy_free_ratio=0.1"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn chain_expires_with_its_first_certificate() {
+    let chain = format!("{}\n{}\n", CERT_2060, CERT_2030);
+    let config = "[health]\nenable = false\n\n[health.certs]\npaths = [\"/etc/tls/chain.pem\"]\n";
+    let files = [("/etc/tls/chain.pem", chain.as_str())];
+    let now = UNIX_EPOCH + Duration::from_secs(DEC_25_2029);
+    let report = initialized_report_at(config, &files, now).unwrap();
+    assert_eq!(
+        report["checks"],
+        serde_json::json!([{
+            "name": "certs",
+            "status": "degraded",
+            "detail": "/etc/tls/chain.pem: expires in 7d 0h"
+        }])
+    );
+
+    let report =
+        initialized_report_at(config, &files, now - Duration::from_secs(86400 * 365)).unwrap();
+    assert_eq!(report["status"], "healthy");
+}
+
+#[test]
+fn expired_or_unreadable_certificates_are_unhealthy() {
+    let config = "[health]\nenable = false\n\n[health.certs]\npaths = [\"/etc/tls/server.pem\", \
+                  \"/etc/tls/missing.pem\"]\nwindow = \"14d\"\n";
+    let files = [("/etc/tls/server.pem", CERT_2030)];
+    let now = UNIX_EPOCH + Duration::from_secs(1_893_456_000 + 3600);
+    let report = initialized_report_at(config, &files, now).unwrap();
+    assert_eq!(report["status"], "unhealthy");
+    let detail = report["checks"][0]["detail"].as_str().unwrap();
+    assert!(
+        detail.starts_with("/etc/tls/server.pem: expired 3600s ago, /etc/tls/missing.pem: "),
+        "{}",
+        detail
+    );
+}
+
+/// an HTTP server answering `status` to every request, counting the connections.
+fn http_server(status: u16) -> (String, Arc<AtomicUsize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/health", listener.local_addr().unwrap());
+    let connections = Arc::new(AtomicUsize::new(0));
+    let counted = connections.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            counted.fetch_add(1, Ordering::SeqCst);
+            // the whole head, closing with unread bytes would reset the probe
+            let mut reader = BufReader::new(&stream);
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap_or(0) > 2 {
+                line.clear();
+            }
+            write!(stream, "HTTP/1.1 {} X\r\nContent-Length: 0\r\n\r\n", status).unwrap();
+        }
+    });
+    (url, connections)
+}
+
+/// the url of a port nothing listens on.
+fn closed_url() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    format!("tcp://{}", listener.local_addr().unwrap())
+}
+
+#[test]
+fn dependency_probes_are_cached() {
+    let (url, connections) = http_server(200);
+    let clock = Arc::new(ManualClock::default());
+    let check = DependencyCheck::new("api", DependencyConfig::new(url), clock.clone()).unwrap();
+    assert_eq!(check.check(), HealthCheckResult::healthy());
+    assert_eq!(check.check(), HealthCheckResult::healthy());
+    assert_eq!(connections.load(Ordering::SeqCst), 1);
+
+    clock.advance(Duration::from_secs(11));
+    check.check();
+    assert_eq!(connections.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn failing_http_dependency_is_unhealthy() {
+    let (url, _) = http_server(503);
+    let check = DependencyCheck::new(
+        "api",
+        DependencyConfig::new(&url),
+        Arc::new(ManualClock::default()),
+    )
+    .unwrap();
+    assert_eq!(
+        check.check(),
+        HealthCheckResult::unhealthy(format!("{} answered 503", url))
+    );
+}
+
+#[test]
+fn unreachable_dependencies_fail_by_criticality() {
+    let config = format!(
+        "[health]\nenable = false\n\n[health.dependencies]\napi = {{ url = \"{}\" }}\ncache = \
+         {{ url = \"{}\", critical = false, timeout = \"500ms\" }}\n",
+        closed_url(),
+        closed_url()
+    );
+    let report = initialized_report(&config).unwrap();
+    assert_eq!(report["status"], "unhealthy");
+    let checks = report["checks"].as_array().unwrap();
+    let statuses: Vec<_> = checks
+        .iter()
+        .map(|x| (x["name"].as_str().unwrap(), x["status"].as_str().unwrap()))
+        .collect();
+    assert_eq!(
+        statuses,
+        [
+            ("dependencies.api", "unhealthy"),
+            ("dependencies.cache", "degraded")
+        ]
+    );
+}
+
+#[test]
+fn dependency_with_an_invalid_url_is_rejected() {
+    let error = initialized_report("[health.dependencies]\ndb = { url = \"tcp://db.internal\" }\n")
+        .unwrap_err();
+    assert!(
+        error
+            .to_string()
+            .contains("health.dependencies.db.url=tcp://db.internal"),
+        "{}",
+        error
+    );
+}
@@ -0,0 +1,79 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver},
+    time::Duration,
+};
+
+use beaver_bootstrap::config::{
+    k8s::{KeyPerFileSource, KubernetesConfig, VolumeWatcher},
+    source::SourceChanged,
+};
+use config::Source;
+
+/// a fresh folder of the test `name`.
+fn volume_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("beaver-k8s-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// a watcher of the config map at `dir`, checking it every 20ms, and its notifications.
+fn watch(dir: &Path) -> (VolumeWatcher, Receiver<()>) {
+    let (sender, receiver) = mpsc::channel();
+    let watcher = KubernetesConfig::default()
+        .config_map_dir(dir)
+        .watch_interval(Duration::from_millis(20))
+        .watch(SourceChanged::new(move || {
+            let _ = sender.send(());
+        }))
+        .unwrap()
+        .unwrap();
+    (watcher, receiver)
+}
+
+#[test]
+fn updated_file_is_reported() {
+    let dir = volume_dir("file");
+    std::fs::write(dir.join("pool.size"), "8").unwrap();
+    let (_watcher, changes) = watch(&dir);
+    std::fs::write(dir.join("pool.size"), "16").unwrap();
+    let changed = changes.recv_timeout(Duration::from_secs(5));
+    let _ = std::fs::remove_dir_all(&dir);
+    assert!(changed.is_ok());
+}
+
+#[test]
+fn unchanged_volume_is_not_reported() {
+    let dir = volume_dir("unchanged");
+    std::fs::write(dir.join("pool.size"), "8").unwrap();
+    let (_watcher, changes) = watch(&dir);
+    let changed = changes.recv_timeout(Duration::from_millis(200));
+    let _ = std::fs::remove_dir_all(&dir);
+    assert!(changed.is_err());
+}
+
+#[cfg(unix)]
+#[test]
+fn swapped_data_link_is_reported() {
+    use std::os::unix::fs::symlink;
+
+    // the layout of the kubelet, keys linking through `..data` to a timestamped folder
+    let dir = volume_dir("data-link");
+    for (folder, size) in [("..2026_01_01", "8"), ("..2026_01_02", "16")] {
+        std::fs::create_dir_all(dir.join(folder)).unwrap();
+        std::fs::write(dir.join(folder).join("pool.size"), size).unwrap();
+    }
+    symlink("..2026_01_01", dir.join("..data")).unwrap();
+    symlink("..data/pool.size", dir.join("pool.size")).unwrap();
+    let (_watcher, changes) = watch(&dir);
+
+    symlink("..2026_01_02", dir.join("..data_tmp")).unwrap();
+    std::fs::rename(dir.join("..data_tmp"), dir.join("..data")).unwrap();
+    let changed = changes.recv_timeout(Duration::from_secs(5));
+    let values = KeyPerFileSource::new(&dir).collect().unwrap();
+    let _ = std::fs::remove_dir_all(&dir);
+    assert!(changed.is_ok());
+    assert_eq!(values["pool.size"].to_string(), "16");
+    assert_eq!(values.len(), 1);
+}
@@ -0,0 +1,84 @@
+use std::{
+    io::{self, Write},
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use beaver_bootstrap::log::batch::BatchWriter;
+
+/// Writes records the size of every write call.
+#[derive(Clone, Default)]
+struct Writes(Arc<Mutex<Vec<usize>>>);
+
+impl Writes {
+    fn sizes(&self) -> Vec<usize> {
+        self.0.lock().unwrap().clone()
+    }
+}
+
+impl Write for Writes {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().push(buf.len());
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+const EVENT: &[u8] = b"2000-01-01T00:00:00Z INFO billing: invoice sent\n";
+
+#[test]
+fn writes_are_coalesced_into_batches_of_the_size() {
+    let writes = Writes::default();
+    let mut writer = BatchWriter::new(writes.clone(), "size", 4096, Duration::from_secs(3600));
+    for _ in 0..1000 {
+        writer.write_all(EVENT).unwrap();
+    }
+    writer.flush().unwrap();
+    let sizes = writes.sizes();
+    let (rest, full) = sizes.split_last().unwrap();
+    assert_eq!(full.len(), 1000 * EVENT.len() / 4096, "{:?}", sizes);
+    assert!(full.iter().all(|x| *x >= 4096), "{:?}", sizes);
+    assert!(*rest < 4096, "the flush writes the rest");
+    assert_eq!(sizes.iter().sum::<usize>(), 1000 * EVENT.len());
+}
+
+#[test]
+fn full_batches_are_written_off_the_writing_thread() {
+    let writes = Writes::default();
+    let mut writer = BatchWriter::new(writes.clone(), "thread", 4096, Duration::from_secs(3600));
+    for _ in 0..1000 {
+        writer.write_all(EVENT).unwrap();
+    }
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while writes.sizes().len() < 1000 * EVENT.len() / 4096 && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(writes.sizes().len(), 1000 * EVENT.len() / 4096);
+}
+
+#[test]
+fn batch_is_written_once_it_waited_the_latency() {
+    let writes = Writes::default();
+    let mut writer = BatchWriter::new(writes.clone(), "latency", 4096, Duration::from_millis(50));
+    writer.write_all(EVENT).unwrap();
+    writer.write_all(EVENT).unwrap();
+    assert!(writes.sizes().is_empty());
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while writes.sizes().is_empty() && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(writes.sizes(), [2 * EVENT.len()]);
+}
+
+#[test]
+fn dropped_writer_writes_its_batch() {
+    let writes = Writes::default();
+    let mut writer = BatchWriter::new(writes.clone(), "drop", 4096, Duration::from_secs(3600));
+    writer.write_all(EVENT).unwrap();
+    drop(writer);
+    assert_eq!(writes.sizes(), [EVENT.len()]);
+}
@@ -0,0 +1,19 @@
+use beaver_bootstrap::log::context::{LogContext, LogContextExt};
+
+#[test]
+fn fields_pushed_by_a_future_survive_its_yields() {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    let seen = runtime.block_on(
+        async {
+            let _user = LogContext::push("user", "alice");
+            tokio::task::yield_now().await;
+            (LogContext::get("request_id"), LogContext::get("user"))
+        }
+        .with_log_context(&[("request_id", "42")]),
+    );
+    assert_eq!(seen, (Some("42".to_string()), Some("alice".to_string())));
+    // nothing leaks to the thread polling it
+    assert_eq!(LogContext::current(), vec![]);
+}
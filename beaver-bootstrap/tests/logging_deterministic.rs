@@ -0,0 +1,59 @@
+use std::sync::Arc;
+
+use beaver_bootstrap::{bootstrap::Bootstrap, fs::MemoryFs, log::capture::LogCapture};
+use tracing::Level;
+
+const CONFIG_FOLDER: &str = "/srv/app/etc";
+const CONFIG: &str = "/srv/app/etc/config.toml";
+
+#[test]
+fn deterministic_logs_match_a_golden_file() {
+    let log_dir = std::env::temp_dir().join(format!("beaver-golden-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&log_dir);
+    std::fs::create_dir_all(&log_dir).unwrap();
+    let config = format!(
+        r#"
+[logging.all_logger]
+default_level = "info"
+default_name = "root"
+
+[logging.fields]
+service = "billing"
+
+[[logging.file_appenders]]
+logger_names = ["root"]
+enable = true
+format = "json"
+file_dir = "{}"
+file_name = "golden.log"
+file_max_size = 100_000_000
+file_max_count = 3
+flush_on = "every_event"
+"#,
+        log_dir.display()
+    );
+    let fs = MemoryFs::default().with_file(CONFIG, config);
+    let capture = LogCapture::default();
+    let bootstrap = Bootstrap::builder()
+        .env_config_prefix(None)
+        .fs(Arc::new(fs))
+        .config_folder(CONFIG_FOLDER)
+        .deterministic_logs(true)
+        .log_capture(capture.clone())
+        .build();
+    bootstrap.initialize().unwrap();
+    tracing::info!(target: "billing", user = "alice", amount = 42, "invoice sent");
+
+    let log = std::fs::read_to_string(log_dir.join("golden.log")).unwrap_or_default();
+    let _ = std::fs::remove_dir_all(&log_dir);
+    let line = log
+        .lines()
+        .find(|x| x.contains("invoice sent"))
+        .unwrap_or_else(|| panic!("{}", log));
+    assert_eq!(
+        line,
+        r#"{"amount":42,"level":"INFO","message":"invoice sent","service":"billing","target":"billing","timestamp":"2000-01-01T00:00:00.000000Z","user":"alice"}"#
+    );
+    assert!(capture.logs_contain("billing", Level::INFO, &["invoice sent", "user=\"alice\""]));
+    assert!(!capture.logs_contain("billing", Level::WARN, &["invoice sent"]));
+}
@@ -0,0 +1,130 @@
+use std::sync::{Arc, RwLock};
+
+use beaver_bootstrap::{
+    admin::AdminRequest,
+    bootstrap::{Bootstrap, Module},
+    config::source::ConfigSource,
+    fs::MemoryFs,
+};
+use config::{ConfigError, Map, Value};
+use di::ServiceCollection;
+
+const CONFIG_FOLDER: &str = "/srv/app/etc";
+const CONFIG: &str = "/srv/app/etc/config.toml";
+
+/// a source logging while the config loads, before logging is initialized.
+struct LoggingSource;
+
+impl ConfigSource for LoggingSource {
+    fn name(&self) -> String {
+        "logging".to_string()
+    }
+
+    fn load(&self) -> Result<Map<String, Value>, ConfigError> {
+        tracing::info!(source = "logging", "early event from config loading");
+        Ok(Map::new())
+    }
+}
+
+/// a source logging, then failing the startup.
+struct FailingSource;
+
+impl ConfigSource for FailingSource {
+    fn name(&self) -> String {
+        "failing".to_string()
+    }
+
+    fn load(&self) -> Result<Map<String, Value>, ConfigError> {
+        tracing::warn!(vault = "unreachable", "retrying the secrets vault");
+        Err(ConfigError::Message(
+            "secrets vault unreachable".to_string(),
+        ))
+    }
+}
+
+struct LoggingModule;
+
+impl Module for LoggingModule {
+    fn configure(&self, _binder: &RwLock<ServiceCollection>) {
+        tracing::info!("configuring the logging module");
+    }
+
+    fn name(&self) -> String {
+        "logging_module".to_string()
+    }
+}
+
+#[test]
+fn events_before_logging_reach_the_appenders() {
+    let log_dir = std::env::temp_dir().join(format!("beaver-early-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&log_dir);
+    std::fs::create_dir_all(&log_dir).unwrap();
+    let config = format!(
+        r#"
+[logging.all_logger]
+default_level = "info"
+default_name = "root"
+
+[[logging.file_appenders]]
+logger_names = ["root"]
+enable = true
+file_dir = "{}"
+file_name = "beaver.log"
+file_max_size = 100_000_000
+file_max_count = 3
+flush_on = "every_event"
+"#,
+        log_dir.display()
+    );
+    let fs = MemoryFs::default().with_file(CONFIG, config);
+    let bootstrap = Bootstrap::builder()
+        .env_config_prefix(None)
+        .fs(Arc::new(fs))
+        .config_folder(CONFIG_FOLDER)
+        .config_source(Box::new(LoggingSource))
+        .modules(vec![Box::new(LoggingModule)])
+        .build();
+    bootstrap.initialize().unwrap();
+    let log = std::fs::read_to_string(log_dir.join("beaver.log")).unwrap_or_default();
+    let _ = std::fs::remove_dir_all(&log_dir);
+    assert!(log.contains("early event from config loading"), "{}", log);
+    assert!(log.contains("source=\"logging\""), "{}", log);
+    assert!(log.contains("logging_module"), "{}", log);
+}
+
+fn bootstrap_without_logging(source: Box<dyn ConfigSource>) -> Bootstrap {
+    let fs = MemoryFs::default().with_file(CONFIG, "[node]\n");
+    Bootstrap::builder()
+        .initialize_logging(false)
+        .env_config_prefix(None)
+        .fs(Arc::new(fs))
+        .config_folder(CONFIG_FOLDER)
+        .config_source(source)
+        .build()
+}
+
+#[test]
+fn failed_startup_keeps_its_startup_log() {
+    let bootstrap = bootstrap_without_logging(Box::new(FailingSource));
+    assert!(bootstrap.initialize().is_err());
+    assert_eq!(
+        bootstrap.startup_log(),
+        ["WARN logging_early: retrying the secrets vault vault=\"unreachable\""]
+    );
+}
+
+#[test]
+fn startup_log_is_served_by_the_admin_endpoint() {
+    let bootstrap = bootstrap_without_logging(Box::new(LoggingSource));
+    bootstrap.initialize().unwrap();
+    let response = bootstrap
+        .admin_routes()
+        .handle(&AdminRequest::new("GET", "/debug/startup-log"), None);
+    let body = String::from_utf8_lossy(response.body());
+    assert_eq!(response.status(), 200);
+    assert!(
+        body.contains("INFO logging_early: early event from config loading"),
+        "{}",
+        body
+    );
+}
@@ -0,0 +1,70 @@
+use std::sync::{Arc, Mutex};
+
+use beaver_bootstrap::log::filter::FilterExpr;
+use tracing::{Event, Subscriber};
+use tracing_subscriber::{Layer, layer::Context, prelude::*};
+
+/// records whether each event passes its filter.
+struct Recorder {
+    filter: FilterExpr,
+    results: Arc<Mutex<Vec<bool>>>,
+}
+
+impl<S: Subscriber> Layer<S> for Recorder {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        self.results
+            .lock()
+            .unwrap()
+            .push(self.filter.matches(event));
+    }
+}
+
+/// whether the events logged by `log` pass `filter`, in order.
+fn matches(filter: &str, log: impl FnOnce()) -> Vec<bool> {
+    let results = Arc::default();
+    let recorder = Recorder {
+        filter: filter.parse().unwrap(),
+        results: Arc::clone(&results),
+    };
+    tracing::subscriber::with_default(tracing_subscriber::registry().with(recorder), log);
+    Arc::try_unwrap(results).unwrap().into_inner().unwrap()
+}
+
+fn log_with_and_without_tenant() {
+    tracing::info!(tenant = "acme", "with tenant");
+    tracing::info!(tenant = "globex", "other tenant");
+    tracing::info!("without tenant");
+}
+
+#[test]
+fn missing_field_fails_every_comparison() {
+    for filter in [
+        "field.tenant == 'acme'",
+        "field.tenant != 'acme'",
+        "field.tenant =~ 'ac'",
+        "field.tenant !~ 'ac'",
+    ] {
+        let results = matches(filter, log_with_and_without_tenant);
+        assert!(!results[2], "{}", filter);
+    }
+}
+
+#[test]
+fn negative_comparisons_match_other_values() {
+    assert_eq!(
+        matches("field.tenant != 'acme'", log_with_and_without_tenant),
+        [false, true, false]
+    );
+    assert_eq!(
+        matches("field.tenant !~ '^ac'", log_with_and_without_tenant),
+        [false, true, false]
+    );
+}
+
+#[test]
+fn negated_comparison_passes_missing_fields() {
+    assert_eq!(
+        matches("!(field.tenant == 'acme')", log_with_and_without_tenant),
+        [false, true, true]
+    );
+}
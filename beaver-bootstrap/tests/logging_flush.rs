@@ -0,0 +1,129 @@
+use std::process::Command;
+
+use beaver_bootstrap::{bootstrap::Bootstrap, fs::MemoryFs};
+
+const CONFIG_FOLDER: &str = "/srv/app/etc";
+const CONFIG: &str = "/srv/app/etc/config.toml";
+
+/// set in the child process to the folder of its log file.
+const LOG_DIR_ENV: &str = "BEAVER_FLUSH_TEST_LOG_DIR";
+
+/// the bootstrap of the child process, writing the root logger to `beaver.log` in `log_dir`
+/// through a non blocking appender.
+fn child_bootstrap(log_dir: &str) -> Bootstrap {
+    let config = format!(
+        r#"
+[logging.all_logger]
+default_level = "info"
+default_name = "root"
+
+[[logging.file_appenders]]
+logger_names = ["root"]
+enable = true
+file_dir = "{}"
+file_name = "beaver.log"
+file_max_size = 100_000_000
+file_max_count = 3
+flush_on = "never"
+"#,
+        log_dir
+    );
+    let fs = MemoryFs::default().with_file(CONFIG, config);
+    Bootstrap::builder()
+        .env_config_prefix(None)
+        .fs(std::sync::Arc::new(fs))
+        .config_folder(CONFIG_FOLDER)
+        .build()
+}
+
+/// run the test `name` of this binary in a child process logging to a fresh folder, returning
+/// the content of its log file once it exited.
+fn run_child(name: &str) -> String {
+    let log_dir = std::env::temp_dir().join(format!("beaver-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&log_dir);
+    std::fs::create_dir_all(&log_dir).unwrap();
+    // the output of the child is captured, its panic is expected
+    let output = Command::new(std::env::current_exe().unwrap())
+        .args(["--exact", name, "--nocapture", "--test-threads=1"])
+        .env(LOG_DIR_ENV, &log_dir)
+        .output()
+        .unwrap();
+    let log = std::fs::read_to_string(log_dir.join("beaver.log")).unwrap_or_default();
+    let _ = std::fs::remove_dir_all(&log_dir);
+    assert!(output.status.code().is_some(), "child {} was killed", name);
+    log
+}
+
+fn log_dir() -> Option<String> {
+    std::env::var(LOG_DIR_ENV).ok()
+}
+
+#[test]
+fn last_line_before_exit_is_written() {
+    let log = run_child("child_logs_then_exits");
+    assert!(log.contains("last line before exit"), "log: {}", log);
+}
+
+#[test]
+fn last_line_before_panic_is_written() {
+    let log = run_child("child_logs_then_panics");
+    assert!(log.contains("last line before panic"), "log: {}", log);
+    assert!(log.contains("boom"), "log: {}", log);
+}
+
+#[test]
+fn child_logs_then_exits() {
+    let Some(log_dir) = log_dir() else {
+        return;
+    };
+    let bootstrap = child_bootstrap(&log_dir);
+    bootstrap.initialize().unwrap();
+    for i in 0..1000 {
+        tracing::info!("line {}", i);
+    }
+    tracing::info!("last line before exit");
+    bootstrap.shutdown();
+    // exit skips the drop of the bootstrap and of its appender guards
+    std::process::exit(0);
+}
+
+#[test]
+fn child_logs_then_panics() {
+    let Some(log_dir) = log_dir() else {
+        return;
+    };
+    let bootstrap = child_bootstrap(&log_dir);
+    bootstrap.initialize().unwrap();
+    tracing::info!("last line before panic");
+    // the appender guards are never dropped, only the panic hook flushes
+    std::mem::forget(bootstrap);
+    panic!("boom");
+}
+
+#[test]
+fn stats_count_the_events_written() {
+    let log = run_child("child_checks_stats");
+    assert!(log.contains("stats checked"), "log: {}", log);
+}
+
+#[test]
+fn child_checks_stats() {
+    let Some(log_dir) = log_dir() else {
+        return;
+    };
+    let bootstrap = child_bootstrap(&log_dir);
+    bootstrap.initialize().unwrap();
+    for i in 0..1000 {
+        tracing::info!("line {}", i);
+    }
+    let manager = bootstrap.logging_manager().unwrap();
+    assert!(manager.flush(std::time::Duration::from_secs(5)));
+    let stats = manager.stats();
+    assert_eq!(stats.len(), 1, "{:?}", stats);
+    assert!(stats[0].sent >= 1000, "{:?}", stats);
+    assert_eq!(stats[0].written + stats[0].dropped, stats[0].sent);
+    assert_eq!(stats[0].queued, 0);
+    tracing::info!("stats checked");
+    bootstrap.shutdown();
+    std::process::exit(0);
+}
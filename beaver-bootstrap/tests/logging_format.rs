@@ -0,0 +1,162 @@
+use std::{
+    io::Write,
+    sync::{Arc, Mutex},
+};
+
+use beaver_bootstrap::{
+    clock::ManualClock,
+    log::{
+        context::LogContext,
+        format::{LogFormat, fmt_layer, fmt_layer_with_clock},
+        pattern::pattern_layer_with_clock,
+    },
+};
+use tracing_subscriber::{fmt::MakeWriter, layer::SubscriberExt};
+
+#[derive(Clone, Default)]
+struct CaptureWriter {
+    buf: Arc<Mutex<Vec<u8>>>,
+}
+
+impl CaptureWriter {
+    fn output(&self) -> String {
+        String::from_utf8(self.buf.lock().unwrap().clone()).unwrap()
+    }
+}
+
+impl Write for CaptureWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.buf.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> MakeWriter<'a> for CaptureWriter {
+    type Writer = CaptureWriter;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self.clone()
+    }
+}
+
+#[test]
+fn one_event_renders_as_text_and_json() {
+    let console = CaptureWriter::default();
+    let file = CaptureWriter::default();
+    let fields = vec![("service".to_string(), "demo".to_string())];
+    let subscriber = tracing_subscriber::registry()
+        .with(fmt_layer(
+            LogFormat::Text,
+            false,
+            fields.clone(),
+            console.clone(),
+        ))
+        .with(fmt_layer(LogFormat::Json, false, fields, file.clone()));
+
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info!(user = "alice", attempt = 2, "login");
+    });
+
+    let text = console.output();
+    assert_eq!(text.lines().count(), 1);
+    assert!(text.contains("INFO"));
+    assert!(text.contains("login"));
+    assert!(text.contains("user=\"alice\""));
+    assert!(text.contains("attempt=2"));
+    assert!(text.contains("service=demo"));
+
+    let json: serde_json::Value = serde_json::from_str(file.output().trim()).unwrap();
+    assert_eq!(json["level"], "INFO");
+    assert_eq!(json["message"], "login");
+    assert_eq!(json["user"], "alice");
+    assert_eq!(json["attempt"], 2);
+    assert_eq!(json["service"], "demo");
+}
+
+#[test]
+fn fast_json_renders_like_json() {
+    let json = CaptureWriter::default();
+    let fast_json = CaptureWriter::default();
+    let fields = vec![("service".to_string(), "de\"mo".to_string())];
+    let clock = Arc::new(ManualClock::new(
+        std::time::UNIX_EPOCH + std::time::Duration::from_micros(1_700_000_000_123_456),
+    ));
+    let subscriber = tracing_subscriber::registry()
+        .with(fmt_layer_with_clock(
+            LogFormat::Json,
+            false,
+            fields.clone(),
+            None,
+            clock.clone(),
+            json.clone(),
+        ))
+        .with(fmt_layer_with_clock(
+            LogFormat::FastJson,
+            false,
+            fields,
+            None,
+            clock,
+            fast_json.clone(),
+        ));
+
+    tracing::subscriber::with_default(subscriber, || {
+        let _request = LogContext::push("request_id", "42");
+        tracing::warn!(
+            target: "billing::invoice",
+            user = "alice\n\u{1}",
+            attempt = 2,
+            delta = -3,
+            ratio = 0.5,
+            whole = 2.0,
+            paid = false,
+            items = ?vec!["a", "b"],
+            "login \"failed\" {}",
+            7
+        );
+        tracing::info!(nan = f64::NAN, "no value");
+    });
+
+    let lines = |writer: &CaptureWriter| {
+        writer
+            .output()
+            .lines()
+            .map(|x| serde_json::from_str::<serde_json::Value>(x).unwrap())
+            .collect::<Vec<_>>()
+    };
+    let fast = lines(&fast_json);
+    assert_eq!(fast, lines(&json));
+    assert_eq!(fast.len(), 2);
+    assert_eq!(fast[0]["user"], "alice\n\u{1}");
+    assert_eq!(fast[0]["timestamp"], "2023-11-14T22:13:20.123456Z");
+    assert_eq!(fast[0]["request_id"], "42");
+}
+
+/// the line of `tracing::info!(target: "billing", "invoice sent")` laid out by `pattern`.
+fn pattern_line(pattern: &str) -> String {
+    let writer = CaptureWriter::default();
+    let clock = Arc::new(ManualClock::new(std::time::UNIX_EPOCH));
+    let layer = pattern_layer_with_clock(
+        pattern.parse().unwrap(),
+        vec![],
+        None,
+        clock,
+        writer.clone(),
+    );
+    let subscriber = tracing_subscriber::registry().with(layer);
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info!(target: "billing", "invoice sent");
+    });
+    writer.output()
+}
+
+#[test]
+fn pattern_truncates_to_max_width() {
+    assert_eq!(pattern_line("[%.3c]"), "[ing]");
+    assert_eq!(pattern_line("[%.-3c]"), "[bil]");
+    assert_eq!(pattern_line("[%.0c] %m"), "[] invoice sent");
+    assert_eq!(pattern_line("[%.-0c]"), "[]");
+}
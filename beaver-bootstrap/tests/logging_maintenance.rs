@@ -0,0 +1,62 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use beaver_bootstrap::{bootstrap::Bootstrap, fs::MemoryFs};
+
+const CONFIG_FOLDER: &str = "/srv/app/etc";
+const CONFIG: &str = "/srv/app/etc/config.toml";
+
+#[test]
+fn scheduled_rotation_runs_off_the_write_path() {
+    let log_dir = std::env::temp_dir().join(format!("beaver-maintenance-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&log_dir);
+    std::fs::create_dir_all(&log_dir).unwrap();
+    let config = format!(
+        r#"
+[logging.all_logger]
+default_level = "info"
+default_name = "root"
+
+[[logging.file_appenders]]
+logger_names = ["root"]
+enable = true
+file_dir = "{}"
+file_name = "beaver.log"
+file_max_size = 1000
+file_max_count = 3
+flush_on = "every_event"
+
+[logging.maintenance]
+interval = "50ms"
+jitter = 0
+rotation = "scheduled"
+"#,
+        log_dir.display()
+    );
+    let fs = MemoryFs::default().with_file(CONFIG, config);
+    let bootstrap = Bootstrap::builder()
+        .env_config_prefix(None)
+        .fs(Arc::new(fs))
+        .config_folder(CONFIG_FOLDER)
+        .build();
+    bootstrap.initialize().unwrap();
+    for i in 0..100 {
+        tracing::info!("line {}", i);
+    }
+    // writes do not rotate, the file grows past its size until the next check
+    let active = log_dir.join("beaver.log");
+    let rotated = log_dir.join("beaver.log.1");
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !rotated.exists() && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    let rotated_size = std::fs::metadata(&rotated).map(|x| x.len()).unwrap_or(0);
+    tracing::info!("after rotation");
+    let active_log = std::fs::read_to_string(&active).unwrap_or_default();
+    let _ = std::fs::remove_dir_all(&log_dir);
+    assert!(rotated_size > 1000, "rotated {} bytes", rotated_size);
+    assert!(active_log.contains("after rotation"), "{}", active_log);
+    assert!(!active_log.contains("line 99"), "{}", active_log);
+}
@@ -0,0 +1,232 @@
+use std::path::Path;
+
+use beaver_bootstrap::{
+    config::Config,
+    error::{BootstrapError, EX_CONFIG},
+    fs::MemoryFs,
+    log::LoggingConfig,
+};
+use config::{File, FileFormat};
+
+/// the logging config of one valid file appender, its keys replaced by the ones of `appender`.
+fn logging_config(appender: &str) -> Result<LoggingConfig, BootstrapError> {
+    logging_config_with("", &format!("file_dir = \"/var/log/beaver\"\n{}", appender))
+}
+
+/// like [`logging_config`], `prelude` the tables before the ones of `[logging]`.
+fn logging_config_with(prelude: &str, appender: &str) -> Result<LoggingConfig, BootstrapError> {
+    let defaults = [
+        ("file_name", "\"app.log\""),
+        ("file_max_size", "1024"),
+        ("file_max_count", "3"),
+    ];
+    let mut appender = appender.to_string();
+    for (key, value) in defaults {
+        if !appender.contains(key) {
+            appender.push_str(&format!("\n{} = {}", key, value));
+        }
+    }
+    let toml = format!(
+        r#"
+{}
+
+[logging.all_logger]
+default_level = "info"
+default_name = "root"
+
+[[logging.file_appenders]]
+name = "app"
+logger_names = ["root"]
+enable = true
+{}
+"#,
+        prelude, appender
+    );
+    let inner = config::Config::builder()
+        .add_source(File::from_str(&toml, FileFormat::Toml))
+        .build()
+        .unwrap();
+    LoggingConfig::new_with_fs(&Config::new(inner), &MemoryFs::default())
+}
+
+#[test]
+fn valid_file_appender_is_accepted() {
+    let config = logging_config("");
+    assert!(config.is_ok(), "{:?}", config.err());
+}
+
+#[test]
+fn zero_file_max_size_is_rejected() {
+    let error = logging_config("file_max_size = 0").unwrap_err();
+    assert!(
+        matches!(error, BootstrapError::InvalidLogFileSizeError(_)),
+        "{}",
+        error
+    );
+    assert!(
+        error.to_string().contains("[app].file_max_size=0"),
+        "{}",
+        error
+    );
+    assert_eq!(error.exit_code(), EX_CONFIG);
+}
+
+#[test]
+fn zero_file_max_count_is_rejected() {
+    let error = logging_config("file_max_count = 0").unwrap_err();
+    assert!(
+        matches!(error, BootstrapError::InvalidLogFileCountError(_)),
+        "{}",
+        error
+    );
+    assert_eq!(error.exit_code(), EX_CONFIG);
+}
+
+#[test]
+fn zero_buffer_size_is_rejected() {
+    let error = logging_config("buffer_size = 0").unwrap_err();
+    assert!(
+        matches!(error, BootstrapError::InvalidLogBufferSizeError(_)),
+        "{}",
+        error
+    );
+    assert!(
+        error.to_string().contains("[app].buffer_size=0"),
+        "{}",
+        error
+    );
+    assert_eq!(error.exit_code(), EX_CONFIG);
+}
+
+#[test]
+fn zero_console_buffer_size_is_rejected() {
+    let error = logging_config_with("[logging.console_appender]\nbuffer_size = 0", "").unwrap_err();
+    assert!(
+        matches!(error, BootstrapError::InvalidLogBufferSizeError(_)),
+        "{}",
+        error
+    );
+    assert!(
+        error
+            .to_string()
+            .contains("logging.console_appender.buffer_size=0"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn file_name_with_a_separator_is_rejected() {
+    let error = logging_config("file_name = \"logs/app.log\"").unwrap_err();
+    assert!(
+        matches!(error, BootstrapError::InvalidLogFileNameError(_)),
+        "{}",
+        error
+    );
+    assert!(
+        error.to_string().contains("file_name=logs/app.log"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn relative_file_dir_resolves_against_the_data_dir() {
+    let config = logging_config_with(
+        "[app]\ndata_dir = \"/var/lib/billing\"",
+        "file_dir = \"audit\"",
+    )
+    .unwrap();
+    let appender = config.file_appender_config()[0];
+    assert_eq!(appender.file_dir(), "/var/lib/billing/audit");
+    assert_eq!(
+        config.file_dir_report(),
+        [
+            "appender app writes to /var/lib/billing/audit/app.log: file_dir audit is relative to \
+          app.data_dir=/var/lib/billing"
+        ]
+    );
+}
+
+#[test]
+fn log_base_dir_wins_and_holds_the_appenders_without_file_dir() {
+    let config = logging_config_with(
+        "[app]\ndata_dir = \"/var/lib/billing\"\nlog_base_dir = \"/var/log/billing\"",
+        "",
+    )
+    .unwrap();
+    let appender = config.file_appender_config()[0];
+    assert_eq!(appender.file_path(), Path::new("/var/log/billing/app.log"));
+}
+
+#[test]
+fn default_folder_of_the_config_holds_the_appenders_without_file_dir() {
+    let config =
+        logging_config_with("[logging]\ndefault_folder = { custom = \"/srv/logs\" }", "").unwrap();
+    let appender = config.file_appender_config()[0];
+    assert_eq!(appender.file_path(), Path::new("/srv/logs/app.log"));
+    assert_eq!(
+        config.file_dir_report(),
+        ["appender app writes to /srv/logs/app.log: no file_dir, default folder custom /srv/logs"]
+    );
+}
+
+#[test]
+fn relative_base_dir_is_rejected() {
+    let error = logging_config_with("[app]\nlog_base_dir = \"logs\"", "").unwrap_err();
+    assert!(
+        error.to_string().contains("app.log_base_dir=logs"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn batch_of_a_file_appender_is_accepted() {
+    let config = logging_config("batch = { size = \"128KiB\", latency = \"50ms\" }").unwrap();
+    let batch = config.file_appender_config()[0].batch().unwrap();
+    assert_eq!(batch.size(), 128 * 1024);
+    assert_eq!(batch.latency(), std::time::Duration::from_millis(50));
+    assert!(batch.flush_on_error());
+}
+
+#[test]
+fn zero_batch_size_is_rejected() {
+    let error = logging_config("batch = { size = 0 }").unwrap_err();
+    assert!(
+        error
+            .to_string()
+            .contains("logging.file_appenders[app].batch.size=0"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn batch_size_beyond_the_limit_is_rejected() {
+    let error = logging_config("batch = { size = \"8GiB\" }").unwrap_err();
+    assert!(
+        error.to_string().contains("batch.size=8589934592"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn worker_settings_next_to_a_batch_are_rejected() {
+    for (setting, key) in [
+        ("flush_on = \"every_event\"", "flush_on"),
+        ("flush_on = \"error\"", "flush_on"),
+        ("buffer_size = 1024", "buffer_size"),
+        ("on_full = \"block\"", "on_full"),
+    ] {
+        let error = logging_config(&format!("batch = {{}}\n{}", setting)).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains(&format!("{} does not apply to a batch", key)),
+            "{}",
+            error
+        );
+    }
+}
@@ -0,0 +1,179 @@
+use std::sync::{Arc, Mutex, RwLock};
+
+use beaver_bootstrap::{
+    bootstrap::{Bootstrap, Module},
+    error::BootstrapError,
+    fs::MemoryFs,
+    runtime::InitFuture,
+};
+use di::*;
+
+const CONFIG_FOLDER: &str = "/srv/app/etc";
+const CONFIG: &str = "/srv/app/etc/config.toml";
+
+#[injectable]
+pub struct Greeter;
+
+impl Greeter {
+    fn greet(&self) -> &'static str {
+        "hello"
+    }
+}
+
+/// a module registering [`Greeter`] and recording when it is configured.
+struct RecordingModule {
+    name: &'static str,
+    dependencies: Vec<&'static str>,
+    configured: Arc<Mutex<Vec<String>>>,
+}
+
+impl RecordingModule {
+    fn boxed(
+        name: &'static str,
+        dependencies: &[&'static str],
+        configured: &Arc<Mutex<Vec<String>>>,
+    ) -> Box<dyn Module> {
+        Box::new(Self {
+            name,
+            dependencies: dependencies.to_vec(),
+            configured: configured.clone(),
+        })
+    }
+}
+
+impl Module for RecordingModule {
+    fn configure(&self, binder: &RwLock<ServiceCollection>) {
+        self.configured.lock().unwrap().push(self.name.to_string());
+        if self.name == "greeter" {
+            binder.write().unwrap().add(Greeter::singleton());
+        }
+    }
+
+    fn name(&self) -> String {
+        self.name.to_string()
+    }
+
+    fn dependencies(&self) -> Vec<String> {
+        self.dependencies.iter().map(|x| x.to_string()).collect()
+    }
+}
+
+/// a module recording when its asynchronous initialization is started.
+struct InitializingModule {
+    name: &'static str,
+    dependencies: Vec<&'static str>,
+    started: Arc<Mutex<Vec<String>>>,
+}
+
+impl InitializingModule {
+    fn boxed(
+        name: &'static str,
+        dependencies: &[&'static str],
+        started: &Arc<Mutex<Vec<String>>>,
+    ) -> Box<dyn Module> {
+        Box::new(Self {
+            name,
+            dependencies: dependencies.to_vec(),
+            started: started.clone(),
+        })
+    }
+}
+
+impl Module for InitializingModule {
+    fn configure(&self, _binder: &RwLock<ServiceCollection>) {}
+
+    fn initialize(&self, _provider: &ServiceProvider) -> Option<InitFuture> {
+        self.started.lock().unwrap().push(self.name.to_string());
+        Some(Box::pin(async { Ok(()) }))
+    }
+
+    fn name(&self) -> String {
+        self.name.to_string()
+    }
+
+    fn dependencies(&self) -> Vec<String> {
+        self.dependencies.iter().map(|x| x.to_string()).collect()
+    }
+}
+
+fn bootstrap(modules: Vec<Box<dyn Module>>) -> Bootstrap {
+    // the global subscriber is set once per process, so the tests run without logging
+    let fs = MemoryFs::default().with_file(CONFIG, "[node]\n");
+    Bootstrap::builder()
+        .initialize_logging(false)
+        .env_config_prefix(None)
+        .fs(Arc::new(fs))
+        .config_folder(CONFIG_FOLDER)
+        .modules(modules)
+        .build()
+}
+
+#[test]
+fn initialize_registers_the_services_of_modules() {
+    let configured = Arc::default();
+    let bootstrap = bootstrap(vec![RecordingModule::boxed("greeter", &[], &configured)]);
+    bootstrap.initialize().unwrap();
+    let provider = bootstrap.service_provider().unwrap();
+    assert_eq!(provider.get_required::<Greeter>().greet(), "hello");
+    assert_eq!(*configured.lock().unwrap(), ["greeter"]);
+}
+
+#[test]
+fn modules_are_configured_after_their_dependencies() {
+    let configured = Arc::default();
+    let bootstrap = bootstrap(vec![
+        RecordingModule::boxed("web", &["cache", "greeter"], &configured),
+        RecordingModule::boxed("cache", &["greeter"], &configured),
+        RecordingModule::boxed("metrics", &[], &configured),
+        RecordingModule::boxed("greeter", &[], &configured),
+    ]);
+    bootstrap.initialize().unwrap();
+    assert_eq!(
+        *configured.lock().unwrap(),
+        ["metrics", "greeter", "cache", "web"]
+    );
+}
+
+#[test]
+fn modules_are_initialized_after_their_dependencies() {
+    let started = Arc::default();
+    let bootstrap = bootstrap(vec![
+        InitializingModule::boxed("web", &["cache"], &started),
+        InitializingModule::boxed("cache", &[], &started),
+    ]);
+    bootstrap.initialize().unwrap();
+    assert_eq!(*started.lock().unwrap(), ["cache", "web"]);
+}
+
+#[test]
+fn unknown_dependency_fails_the_startup() {
+    let configured = Arc::default();
+    let bootstrap = bootstrap(vec![RecordingModule::boxed("web", &["cahce"], &configured)]);
+    let error = bootstrap.initialize().unwrap_err();
+    assert!(matches!(error, BootstrapError::ModuleInitError(_)));
+    assert!(
+        error
+            .to_string()
+            .contains("web depends on unknown module cahce")
+    );
+    assert!(configured.lock().unwrap().is_empty());
+}
+
+#[test]
+fn dependency_cycle_fails_the_startup() {
+    let configured = Arc::default();
+    let bootstrap = bootstrap(vec![
+        RecordingModule::boxed("a", &["b"], &configured),
+        RecordingModule::boxed("b", &["c"], &configured),
+        RecordingModule::boxed("c", &["a"], &configured),
+    ]);
+    let error = bootstrap.initialize().unwrap_err();
+    assert!(
+        error
+            .to_string()
+            .contains("module dependency cycle: a -> b -> c -> a"),
+        "{}",
+        error
+    );
+    assert!(configured.lock().unwrap().is_empty());
+}
@@ -0,0 +1,74 @@
+use std::{
+    path::Path,
+    sync::Arc,
+    time::{Duration, UNIX_EPOCH},
+};
+
+use beaver_bootstrap::{
+    clock::ManualClock,
+    fs::{Fs, MemoryFs},
+    preflight::{ClockCheck, DnsCheck, KernelVersionCheck, PreflightCheck, WritableDirCheck},
+};
+
+fn kernel(release: &str, minimum: &str) -> Result<(), String> {
+    let fs = MemoryFs::default().with_file("/proc/sys/kernel/osrelease", release);
+    KernelVersionCheck::new(Arc::new(fs), minimum).check()
+}
+
+#[test]
+fn writable_dir_is_created_and_left_empty() {
+    let fs = MemoryFs::default();
+    let check = WritableDirCheck::new(Arc::new(fs.clone()), "/srv/app/data");
+    assert_eq!(check.check(), Ok(()));
+    assert!(fs.exists(Path::new("/srv/app/data")));
+    let probe = format!("/srv/app/data/.beaver-preflight-{}", std::process::id());
+    assert_eq!(fs.file(probe), None);
+
+    // a file in the way of the directory
+    let fs = MemoryFs::default().with_file("/srv/app/data", "");
+    let check = WritableDirCheck::new(Arc::new(fs), "/srv/app/data");
+    assert!(check.check().is_err());
+}
+
+#[test]
+fn clock_must_be_past_2024() {
+    // 2020-01-01T00:00:00Z
+    let clock = ManualClock::new(UNIX_EPOCH + Duration::from_secs(1_577_836_800));
+    assert!(ClockCheck(Arc::new(clock)).check().is_err());
+    // 2025-01-01T00:00:00Z
+    let clock = ManualClock::new(UNIX_EPOCH + Duration::from_secs(1_735_689_600));
+    assert_eq!(ClockCheck(Arc::new(clock)).check(), Ok(()));
+}
+
+#[test]
+fn kernel_release_is_compared_to_the_minimum() {
+    assert_eq!(kernel("5.15.0-91-generic\n", "5.10"), Ok(()));
+    assert_eq!(kernel("5.15.0-91-generic\n", "5.15.0"), Ok(()));
+    assert_eq!(
+        kernel("5.15.0-91-generic\n", "6.1"),
+        Err("kernel 5.15.0-91-generic is older than 6.1".to_string())
+    );
+    assert_eq!(
+        kernel("5.15.0-91-generic\n", "six"),
+        Err("invalid minimum kernel version \"six\"".to_string())
+    );
+    assert!(kernel("5.15.0-91-generic\n", "5.").is_err());
+}
+
+#[test]
+fn dns_accepts_addresses_and_resolves_host_names() {
+    for host in [
+        "[::1]:53",
+        "[::1]",
+        "::1",
+        "127.0.0.1:53",
+        "127.0.0.1",
+        "localhost",
+    ] {
+        assert_eq!(DnsCheck(host.to_string()).check(), Ok(()), "{}", host);
+    }
+    assert_eq!(
+        DnsCheck("localhost:dns".to_string()).check(),
+        Err("invalid port \"dns\"".to_string())
+    );
+}
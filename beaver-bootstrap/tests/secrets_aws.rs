@@ -0,0 +1,121 @@
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::TcpListener,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use beaver_bootstrap::config::{
+    Config,
+    secret::{
+        CachedKeyProvider, SecretKeyProvider, SecretsConfig,
+        aws::{AwsSecretsManagerKeyProvider, Credentials},
+        decode_key, generate_key,
+    },
+};
+
+/// a Secrets Manager answering every request with `status` and `body`, and the requests it
+/// received, head and body.
+fn secrets_manager(status: u16, body: String) -> (String, Arc<Mutex<Vec<String>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/", listener.local_addr().unwrap());
+    let requests = Arc::new(Mutex::new(vec![]));
+    let received = requests.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(&stream);
+            let (mut request, mut line, mut length) = (String::new(), String::new(), 0);
+            while reader.read_line(&mut line).unwrap_or(0) > 2 {
+                if let Some(value) = line.to_lowercase().strip_prefix("content-length:") {
+                    length = value.trim().parse().unwrap();
+                }
+                request.push_str(&line);
+                line.clear();
+            }
+            let mut content = vec![0; length];
+            reader.read_exact(&mut content).unwrap();
+            request.push_str(&String::from_utf8(content).unwrap());
+            received.lock().unwrap().push(request);
+            write!(
+                stream,
+                "HTTP/1.1 {} X\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            )
+            .unwrap();
+        }
+    });
+    (url, requests)
+}
+
+fn provider(endpoint: &str) -> AwsSecretsManagerKeyProvider {
+    let inner = config::Config::builder()
+        .set_override("secrets.aws.secret_id", "billing/config-key")
+        .unwrap()
+        .set_override("secrets.aws.region", "eu-west-1")
+        .unwrap()
+        .set_override("secrets.aws.endpoint", endpoint)
+        .unwrap();
+    let secrets = SecretsConfig::new(&Config::new(inner.build().unwrap())).unwrap();
+    AwsSecretsManagerKeyProvider::new(secrets.aws().clone()).with_credentials(Credentials::new(
+        "AKIDEXAMPLE",
+        "secret",
+        None,
+        None,
+        "tests",
+    ))
+}
+
+#[test]
+fn key_is_read_from_a_signed_get_secret_value() {
+    let key = generate_key();
+    let (url, requests) = secrets_manager(200, format!(r#"{{"SecretString":"{}"}}"#, key));
+    assert_eq!(provider(&url).key().unwrap(), decode_key(&key).unwrap());
+
+    let requests = requests.lock().unwrap();
+    let request = requests[0].to_lowercase();
+    assert!(request.starts_with("post / "));
+    assert!(request.contains("x-amz-target: secretsmanager.getsecretvalue"));
+    assert!(request.contains("authorization: aws4-hmac-sha256 credential=akidexample/"));
+    assert!(request.contains("/eu-west-1/secretsmanager/aws4_request"));
+    assert!(requests[0].ends_with(r#"{"SecretId":"billing/config-key"}"#));
+}
+
+#[test]
+fn key_is_read_from_a_binary_secret() {
+    let body = format!(r#"{{"SecretBinary":"{}"}}"#, STANDARD.encode([7; 32]));
+    let (url, _) = secrets_manager(200, body);
+    assert_eq!(provider(&url).key().unwrap(), [7; 32]);
+}
+
+#[test]
+fn service_errors_are_reported() {
+    let body = r#"{"__type":"ResourceNotFoundException","Message":"no such secret"}"#;
+    let (url, _) = secrets_manager(400, body.to_string());
+    let error = provider(&url).key().unwrap_err();
+    assert!(error.contains("billing/config-key"), "{}", error);
+    assert!(
+        error.contains("ResourceNotFoundException no such secret"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn cached_key_is_fetched_once_from_async_callers() {
+    let key = generate_key();
+    let (url, requests) = secrets_manager(200, format!(r#"{{"SecretString":"{}"}}"#, key));
+    let provider = CachedKeyProvider::new(
+        Box::new(provider(&url)),
+        Duration::from_secs(300),
+        Duration::from_secs(3600),
+    );
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let keys = runtime.block_on(async { (provider.key(), provider.key()) });
+    assert_eq!(keys.0.unwrap(), decode_key(&key).unwrap());
+    assert_eq!(keys.1.unwrap(), decode_key(&key).unwrap());
+    assert_eq!(requests.lock().unwrap().len(), 1);
+}
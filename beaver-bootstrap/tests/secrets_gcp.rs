@@ -0,0 +1,77 @@
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::TcpListener,
+    sync::{Arc, Mutex},
+};
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use beaver_bootstrap::config::{
+    Config,
+    secret::{
+        SecretKeyProvider, SecretsConfig, decode_key, gcp::GcpSecretManagerKeyProvider,
+        generate_key,
+    },
+};
+
+const VERSION: &str = "projects/billing/secrets/config-key/versions/latest";
+
+/// a Secret Manager answering every request with `status` and `body`, and the heads of the
+/// requests it received.
+fn secret_manager(status: u16, body: String) -> (String, Arc<Mutex<Vec<String>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let requests = Arc::new(Mutex::new(vec![]));
+    let received = requests.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(&stream);
+            let (mut request, mut line) = (String::new(), String::new());
+            while reader.read_line(&mut line).unwrap_or(0) > 2 {
+                request.push_str(&line);
+                line.clear();
+            }
+            received.lock().unwrap().push(request);
+            write!(
+                stream,
+                "HTTP/1.1 {} X\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            )
+            .unwrap();
+        }
+    });
+    (url, requests)
+}
+
+fn provider(endpoint: &str) -> GcpSecretManagerKeyProvider {
+    let inner = config::Config::builder()
+        .set_override("secrets.gcp.name", VERSION)
+        .unwrap()
+        .set_override("secrets.gcp.endpoint", endpoint)
+        .unwrap();
+    let secrets = SecretsConfig::new(&Config::new(inner.build().unwrap())).unwrap();
+    GcpSecretManagerKeyProvider::new(secrets.gcp().clone()).with_access_token("ya29.token")
+}
+
+#[test]
+fn key_is_read_from_the_accessed_version() {
+    let key = generate_key();
+    let body = format!(r#"{{"payload":{{"data":"{}"}}}}"#, STANDARD.encode(&key));
+    let (url, requests) = secret_manager(200, body);
+    assert_eq!(provider(&url).key().unwrap(), decode_key(&key).unwrap());
+
+    let request = requests.lock().unwrap()[0].to_lowercase();
+    assert!(request.starts_with(&format!("get /v1/{}:access ", VERSION)));
+    assert!(request.contains("authorization: bearer ya29.token"));
+}
+
+#[test]
+fn service_errors_are_reported() {
+    let (url, _) = secret_manager(404, "secret not found".to_string());
+    let error = provider(&url).key().unwrap_err();
+    assert!(error.contains(VERSION), "{}", error);
+    assert!(error.contains("404"), "{}", error);
+    assert!(error.contains("secret not found"), "{}", error);
+}
@@ -1,12 +1,23 @@
-use beaver_bootstrap::{bootstrap::Bootstrap, error::BootstrapError};
+use std::process::ExitCode;
 
-fn main() -> Result<(), BootstrapError> {
+use beaver_bootstrap::{bootstrap::Bootstrap, cli::App, context::AppInfo};
+
+fn main() -> ExitCode {
     let bootstrap = Bootstrap::builder()
         .initialize_logging(true)
         .show_config(true)
         .modules(vec![])
+        .app_info(AppInfo::new(
+            env!("CARGO_PKG_NAME"),
+            env!("CARGO_PKG_VERSION"),
+        ))
         .build();
-    bootstrap.initialize()?;
-    tracing::info!("bootstrap initialized");
-    Ok(())
+    App::new(bootstrap).run(|ctx| async move {
+        tracing::info!(
+            "{} {} initialized",
+            ctx.app_info().name(),
+            ctx.app_info().version()
+        );
+        Ok(())
+    })
 }